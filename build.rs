@@ -1,4 +1,8 @@
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    tonic_build::compile_protos("grpc/race.proto")?;
+    let out_dir = std::path::PathBuf::from(std::env::var("OUT_DIR")?);
+    tonic_build::configure()
+        // Served by gRPC reflection
+        .file_descriptor_set_path(out_dir.join("raceboard_descriptor.bin"))
+        .compile(&["grpc/race.proto"], &["grpc"])?;
     Ok(())
 }
@@ -62,6 +62,23 @@ pub struct Event {
     pub data: Option<serde_json::Value>,
 }
 
+/// Server ETA estimate from `POST /predict`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EtaPrediction {
+    pub expected_seconds: i64,
+    pub confidence: f64,
+    /// Fallback stage that produced it: cluster, source_average, bootstrap, global_default
+    #[serde(default)]
+    pub method: Option<String>,
+}
+
+#[derive(Serialize)]
+struct PredictRequest<'a> {
+    source: &'a str,
+    title: &'a str,
+    metadata: &'a HashMap<String, String>,
+}
+
 // ============================================================================
 // Configuration Base Types
 // ============================================================================
@@ -72,8 +89,8 @@ pub struct ServerConfig {
     pub url: String,
     #[serde(default = "default_timeout")]
     pub timeout_seconds: u64,
-    #[serde(default = "default_retry_count")]
-    pub max_retries: u32,
+    #[serde(default)]
+    pub retry: RetryPolicy,
 }
 
 fn default_server_url() -> String {
@@ -84,20 +101,75 @@ fn default_timeout() -> u64 {
     30
 }
 
-fn default_retry_count() -> u32 {
-    3
-}
-
 impl Default for ServerConfig {
     fn default() -> Self {
         Self {
             url: default_server_url(),
             timeout_seconds: default_timeout(),
-            max_retries: default_retry_count(),
+            retry: RetryPolicy::default(),
         }
     }
 }
 
+/// Retry behaviour for transient failures (connection errors, timeouts,
+/// 5xx and 429). Other 4xx responses are never retried.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// Total attempts including the first one
+    pub max_attempts: u32,
+    /// Backoff before the first retry; doubles on each subsequent retry
+    pub base_backoff_ms: u64,
+    /// Upper bound for a single backoff, including Retry-After hints
+    pub max_backoff_ms: u64,
+    /// Random spread applied to each backoff, as a fraction (0.2 = ±20%)
+    pub jitter: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            base_backoff_ms: 500,
+            max_backoff_ms: 30_000,
+            jitter: 0.2,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Backoff before retry number `retry` (0-based), ignoring server hints
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let base = self
+            .base_backoff_ms
+            .saturating_mul(1u64 << retry.min(20))
+            .min(self.max_backoff_ms);
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        let factor = if jitter > 0.0 {
+            1.0 + rand::random::<f64>() * 2.0 * jitter - jitter
+        } else {
+            1.0
+        };
+        Duration::from_millis((base as f64 * factor).round() as u64)
+    }
+
+    fn retry_after(&self, response: &Response) -> Option<Duration> {
+        let value = response.headers().get(reqwest::header::RETRY_AFTER)?.to_str().ok()?;
+        let delay = match value.trim().parse::<u64>() {
+            Ok(secs) => Duration::from_secs(secs),
+            Err(_) => {
+                let at = DateTime::parse_from_rfc2822(value.trim()).ok()?;
+                (at.with_timezone(&Utc) - Utc::now()).to_std().unwrap_or_default()
+            }
+        };
+        Some(delay.min(Duration::from_millis(self.max_backoff_ms)))
+    }
+}
+
+fn is_retryable_status(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+}
+
 // ============================================================================
 // Raceboard API Client
 // ============================================================================
@@ -107,7 +179,7 @@ pub struct RaceboardClient {
     client: Client,
     server_url: String,
     timeout: Duration,
-    max_retries: u32,
+    retry: RetryPolicy,
 }
 
 impl RaceboardClient {
@@ -124,7 +196,7 @@ impl RaceboardClient {
             client,
             server_url: config.url,
             timeout: Duration::from_secs(config.timeout_seconds),
-            max_retries: config.max_retries,
+            retry: config.retry,
         })
     }
 
@@ -223,65 +295,95 @@ impl RaceboardClient {
         }
     }
 
+    /// Ask the server what ETA it would give such a race. Single attempt with a
+    /// short timeout: callers fall back to their own estimate on failure.
+    pub async fn predict(
+        &self,
+        source: &str,
+        title: &str,
+        metadata: &HashMap<String, String>,
+    ) -> Result<EtaPrediction> {
+        let url = format!("{}/predict", self.server_url);
+        let request = self.client.post(&url).json(&PredictRequest {
+            source,
+            title,
+            metadata,
+        });
+
+        let response = timeout(Duration::from_secs(5), request.send())
+            .await
+            .context("Prediction request timed out")?
+            .context("Failed to request prediction")?
+            .error_for_status()
+            .context("Prediction request rejected")?;
+
+        response
+            .json()
+            .await
+            .context("Failed to parse prediction response")
+    }
+
     async fn execute_with_retry<F, Fut>(&self, f: F) -> Result<Response>
     where
         F: Fn() -> Fut,
         Fut: std::future::Future<Output = reqwest::Result<Response>>,
     {
-        let mut retries = 0;
-        let mut last_error = None;
+        let max_attempts = self.retry.max_attempts.max(1);
+        let mut attempt = 0;
+
+        loop {
+            attempt += 1;
+            let can_retry = attempt < max_attempts;
 
-        while retries <= self.max_retries {
-            match timeout(self.timeout, f()).await {
+            let (error, delay) = match timeout(self.timeout, f()).await {
                 Ok(Ok(response)) => {
-                    if response.status().is_success() {
+                    let status = response.status();
+                    if status.is_success() {
                         return Ok(response);
-                    } else if response.status().is_server_error() && retries < self.max_retries {
-                        warn!(
-                            "Server error ({}), retrying... ({}/{})",
-                            response.status(),
-                            retries + 1,
-                            self.max_retries
-                        );
-                    } else {
+                    }
+                    if !is_retryable_status(status) || !can_retry {
                         return Err(anyhow::anyhow!(
-                            "Request failed with status: {}",
-                            response.status()
+                            "Request failed with status: {} (attempt {}/{})",
+                            status,
+                            attempt,
+                            max_attempts
                         ));
                     }
+                    let delay = self
+                        .retry
+                        .retry_after(&response)
+                        .unwrap_or_else(|| self.retry.backoff(attempt - 1));
+                    (format!("status {}", status), delay)
                 }
                 Ok(Err(e)) => {
-                    last_error = Some(e.to_string());
-                    if retries < self.max_retries {
-                        let backoff = Duration::from_secs(2u64.pow(retries));
-                        warn!(
-                            "Request failed: {}. Retrying in {:?}... ({}/{})",
+                    if !(e.is_connect() || e.is_timeout()) || !can_retry {
+                        return Err(anyhow::anyhow!(
+                            "Request failed: {} (attempt {}/{})",
                             e,
-                            backoff,
-                            retries + 1,
-                            self.max_retries
-                        );
-                        sleep(backoff).await;
+                            attempt,
+                            max_attempts
+                        ));
                     }
+                    (e.to_string(), self.retry.backoff(attempt - 1))
                 }
                 Err(_) => {
-                    last_error = Some("Request timed out".to_string());
-                    if retries < self.max_retries {
-                        warn!(
-                            "Request timed out. Retrying... ({}/{})",
-                            retries + 1,
-                            self.max_retries
-                        );
+                    if !can_retry {
+                        return Err(anyhow::anyhow!(
+                            "Request timed out (attempt {}/{})",
+                            attempt,
+                            max_attempts
+                        ));
                     }
+                    ("request timed out".to_string(), self.retry.backoff(attempt - 1))
                 }
-            }
-            retries += 1;
-        }
+            };
 
-        Err(anyhow::anyhow!(
-            "All retries exhausted. Last error: {}",
-            last_error.unwrap_or_else(|| "Unknown error".to_string())
-        ))
+            warn!(
+                "Request failed: {}. Retrying in {:?}... ({}/{})",
+                error, delay, attempt, max_attempts
+            );
+            sleep(delay).await;
+        }
     }
 }
 
@@ -737,4 +839,103 @@ pub mod test_utils {
             metadata: None,
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::mock;
+
+    fn fast_client() -> RaceboardClient {
+        RaceboardClient::new(ServerConfig {
+            url: test_utils::mock_server_url(),
+            timeout_seconds: 5,
+            retry: RetryPolicy {
+                max_attempts: 4,
+                base_backoff_ms: 1,
+                max_backoff_ms: 10,
+                jitter: 0.0,
+            },
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_retries_transient_failures_until_success() {
+        let flaky = mock("DELETE", "/race/flaky")
+            .with_status(503)
+            .expect(2)
+            .create();
+        let throttled = mock("DELETE", "/race/flaky")
+            .with_status(429)
+            .with_header("retry-after", "0")
+            .expect(1)
+            .create();
+        let ok = mock("DELETE", "/race/flaky")
+            .with_status(200)
+            .expect(1)
+            .create();
+
+        fast_client().delete_race("flaky").await.unwrap();
+
+        flaky.assert();
+        throttled.assert();
+        ok.assert();
+    }
+
+    #[tokio::test]
+    async fn test_client_errors_fail_fast() {
+        let bad_request = mock("DELETE", "/race/bad")
+            .with_status(400)
+            .expect(1)
+            .create();
+
+        assert!(fast_client().delete_race("bad").await.is_err());
+        bad_request.assert();
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_attempts() {
+        let down = mock("DELETE", "/race/down")
+            .with_status(500)
+            .expect(4)
+            .create();
+
+        assert!(fast_client().delete_race("down").await.is_err());
+        down.assert();
+    }
+
+    #[tokio::test]
+    async fn test_predict_parses_server_estimate() {
+        let predict = mock("POST", "/predict")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({
+                "source": "codex-session",
+                "title": "Codex: refactor parser",
+            })))
+            .with_status(200)
+            .with_body(r#"{"expected_seconds":240,"confidence":0.8,"lower_bound":120,"upper_bound":480,"method":"cluster"}"#)
+            .expect(1)
+            .create();
+
+        let prediction = fast_client()
+            .predict("codex-session", "Codex: refactor parser", &HashMap::new())
+            .await
+            .unwrap();
+        assert_eq!(prediction.expected_seconds, 240);
+        assert_eq!(prediction.method.as_deref(), Some("cluster"));
+        predict.assert();
+    }
+
+    #[test]
+    fn test_backoff_is_exponential_and_capped() {
+        let policy = RetryPolicy {
+            max_attempts: 5,
+            base_backoff_ms: 100,
+            max_backoff_ms: 350,
+            jitter: 0.0,
+        };
+        assert_eq!(policy.backoff(0), Duration::from_millis(100));
+        assert_eq!(policy.backoff(1), Duration::from_millis(200));
+        assert_eq!(policy.backoff(2), Duration::from_millis(350));
+        assert_eq!(policy.backoff(40), Duration::from_millis(350));
+    }
+}
@@ -1,11 +1,18 @@
 use crate::adapter_status::AdapterRegistry;
+use crate::config::{
+    is_hot_reloadable, LiveSettings, Settings, SharedPersistencePolicies, SourceEtaPolicy,
+};
+use crate::legacy_json::LegacyJsonWriter;
 use crate::monitoring::{AlertSystem, DataLayerMetrics, MonitoringSystem};
 use crate::persistence::PersistenceLayer;
 use crate::prediction::PredictionEngine;
 use crate::processing::ProcessingEngine;
+use crate::rate_limit::RateLimiter;
 use crate::rebuild::DoubleBufferClusters;
 use crate::rebuild_trigger::RebuildTrigger;
 use crate::storage::Storage;
+use crate::webhook::WebhookDispatcher;
+use std::collections::HashMap;
 use std::sync::Arc;
 
 #[derive(Clone)]
@@ -20,6 +27,97 @@ pub struct AppState {
     pub alert_system: Arc<AlertSystem>,
     pub data_layer_metrics: Option<Arc<DataLayerMetrics>>, 
     pub adapter_registry: Arc<AdapterRegistry>,
+    pub rate_limiter: Arc<RateLimiter>,
     pub read_only: bool,
+    /// Lets token-bearing clients (trusted adapters) write while read_only is set
+    pub write_token: Option<String>,
+    /// Required by admin-only endpoints; `None` disables them
+    pub admin_token: Option<String>,
+    /// Default for `GET /races?include_completed`
+    pub races_include_completed: bool,
+    /// Titles are truncated to this many characters on create/update
+    pub max_title_len: usize,
+    pub settings: Arc<LiveSettings>,
     pub legacy_json_fallback_enabled: bool,
+    /// Debounced races.json writer; present only when the legacy fallback is enabled
+    pub legacy_json_writer: Option<Arc<LegacyJsonWriter>>,
+    /// Outbound webhook for completed races; present only when `webhooks.url` is set
+    pub race_webhooks: Option<Arc<WebhookDispatcher>>,
+    pub source_aliases: HashMap<String, String>,
+    pub source_eta_policies: HashMap<String, SourceEtaPolicy>,
+    pub source_persistence: SharedPersistencePolicies,
+}
+
+/// Outcome of `AppState::reload_settings`, as dotted setting keys
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct ConfigReload {
+    /// Changed and already in effect
+    pub applied: Vec<String>,
+    /// Changed, but the running server keeps the old value until restarted
+    pub requires_restart: Vec<String>,
+}
+
+/// Header (HTTP) / metadata key (gRPC) carrying the write token
+pub const WRITE_TOKEN_HEADER: &str = "x-raceboard-token";
+
+/// Header carrying the admin token
+pub const ADMIN_TOKEN_HEADER: &str = "x-raceboard-admin-token";
+
+impl AppState {
+    /// Writes are allowed unless read-only, where only the write token bypasses
+    pub fn allows_write(&self, presented_token: Option<&str>) -> bool {
+        !self.read_only || write_token_matches(self.write_token.as_deref(), presented_token)
+    }
+
+    pub fn is_admin(&self, presented_token: Option<&str>) -> bool {
+        write_token_matches(self.admin_token.as_deref(), presented_token)
+    }
+
+    /// Swap in freshly loaded settings, applying the hot-reloadable subset
+    /// (`config::HOT_RELOADABLE_SETTINGS`) to the running server. The rest is
+    /// recorded but only takes effect after a restart.
+    pub fn reload_settings(&self, settings: Settings) -> ConfigReload {
+        let current = self.settings.current();
+        // env_logger's own filter is fixed at startup; only a plain level is adjustable
+        let log_level = current
+            .logging
+            .runtime_level()
+            .and(settings.logging.runtime_level());
+        let takes_effect = |key: &String| {
+            is_hot_reloadable(key) && (key != "logging.level" || log_level.is_some())
+        };
+        let (applied, requires_restart) = current
+            .changed_keys(&settings)
+            .into_iter()
+            .partition(takes_effect);
+
+        if let Some(level) = log_level {
+            log::set_max_level(level);
+        }
+        self.alert_system
+            .set_webhook_url(settings.alerts.webhook_url.clone());
+        self.rate_limiter.set_options(settings.limits.options());
+        *self.source_persistence.write().unwrap() = settings.sources.persistence.clone();
+        self.settings.replace(settings);
+
+        ConfigReload {
+            applied,
+            requires_restart,
+        }
+    }
+}
+
+/// Constant-time comparison; a missing or empty configured token never matches
+pub fn write_token_matches(expected: Option<&str>, presented: Option<&str>) -> bool {
+    match (expected, presented) {
+        (Some(expected), Some(presented)) if !expected.is_empty() => {
+            expected.len() == presented.len()
+                && expected
+                    .bytes()
+                    .zip(presented.bytes())
+                    .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+                    == 0
+        }
+        _ => false,
+    }
 }
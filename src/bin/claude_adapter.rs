@@ -1,7 +1,7 @@
 use anyhow::Result;
 use chrono::Utc;
 use clap::{Parser, Subcommand};
-use RaceboardServer::adapter_common::{RaceboardClient, Race, RaceState, RaceUpdate, Event, ServerConfig};
+use RaceboardServer::adapter_common::{RaceboardClient, Race, RaceState, RaceUpdate, Event, RetryPolicy, ServerConfig};
 use serde_json::json;
 use std::collections::HashMap;
 use std::env;
@@ -153,7 +153,7 @@ impl ClaudeAdapter {
         let config = ServerConfig {
             url: server_url,
             timeout_seconds: 30,
-            max_retries: 3,
+            retry: RetryPolicy::default(),
         };
         let client = RaceboardClient::new(config)?;
         Ok(Self { client })
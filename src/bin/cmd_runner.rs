@@ -12,7 +12,7 @@ use uuid::Uuid;
 
 // Use shared library for common functionality
 use RaceboardServer::adapter_common::{
-    Event, Race, RaceState, RaceUpdate, RaceboardClient, ServerConfig,
+    Event, Race, RaceState, RaceUpdate, RaceboardClient, RetryPolicy, ServerConfig,
 };
 
 #[derive(Parser, Debug)]
@@ -70,7 +70,7 @@ impl CommandRunner {
         let config = ServerConfig {
             url: args.server.clone(),
             timeout_seconds: 30,
-            max_retries: 3,
+            retry: RetryPolicy::default(),
         };
         
         let client = RaceboardClient::new(config)?;
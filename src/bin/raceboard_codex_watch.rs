@@ -8,7 +8,7 @@ use regex::Regex;
 use reqwest::Client;
 use RaceboardServer::adapter_common::{
     RaceboardClient, Race, RaceState, RaceUpdate, Event, ServerConfig,
-    AdapterType, AdapterHealthMonitor
+    AdapterType, AdapterHealthMonitor, EtaPrediction
 };
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -34,9 +34,14 @@ struct Args {
     server: String,
 
     /// Path to Codex log file (overrides default)
-    #[arg(long)]
+    #[arg(long, conflicts_with = "log_glob")]
     log_path: Option<PathBuf>,
 
+    /// Glob for rotated/dated Codex logs, e.g. "~/.codex/log/codex-*.log";
+    /// the newest match is watched and a newer one is picked up when it appears
+    #[arg(long)]
+    log_glob: Option<String>,
+
     // follow/new-only behavior is the default: we always tail and start at EOF
     /// Enable debug output
     #[arg(short = 'd', long)]
@@ -139,13 +144,14 @@ struct SessionTracker {
 }
 
 impl SessionTracker {
-    fn new(prompt: &str, race_id: String) -> Self {
+    /// `seeded_eta` (from the server's cluster history) wins over the prompt heuristic
+    fn new(prompt: &str, race_id: String, seeded_eta: Option<i64>) -> Self {
         let complexity = Self::analyze_prompt(prompt);
-        let eta = match complexity {
+        let eta = seeded_eta.unwrap_or(match complexity {
             PromptComplexity::Simple => 10,
             PromptComplexity::Moderate => 30,
             PromptComplexity::Complex => 120,
-        };
+        });
 
         Self {
             plan_progress: None,
@@ -198,7 +204,16 @@ impl SessionTracker {
     }
 }
 
+/// Below this the server's prediction is no better than the prompt heuristic
+const MIN_SEED_CONFIDENCE: f64 = 0.5;
+
 trait RaceboardApi: Send + Sync {
+    fn predict_eta(
+        &self,
+        source: String,
+        title: String,
+        metadata: HashMap<String, String>,
+    ) -> BoxFuture<'static, Result<EtaPrediction>>;
     fn create_race(&self, race: Race) -> BoxFuture<'static, Result<Race>>;
     fn update_race_progress(
         &self,
@@ -221,6 +236,16 @@ impl RealRaceboardApi {
 }
 
 impl RaceboardApi for RealRaceboardApi {
+    fn predict_eta(
+        &self,
+        source: String,
+        title: String,
+        metadata: HashMap<String, String>,
+    ) -> BoxFuture<'static, Result<EtaPrediction>> {
+        let client = self.client.clone();
+        Box::pin(async move { client.predict(&source, &title, &metadata).await })
+    }
+
     fn create_race(&self, race: Race) -> BoxFuture<'static, Result<Race>> {
         let client = self.client.clone();
         Box::pin(async move {
@@ -264,9 +289,85 @@ impl RaceboardApi for RealRaceboardApi {
     }
 }
 
+/// File-name glob (`*`, `?`) within a fixed directory
+#[derive(Debug, Clone)]
+struct LogGlob {
+    dir: PathBuf,
+    pattern: String,
+}
+
+impl LogGlob {
+    fn parse(glob: &str) -> Result<Self> {
+        let expanded = match glob.strip_prefix("~/") {
+            Some(rest) => dirs::home_dir()
+                .context("Could not find home directory")?
+                .join(rest),
+            None => PathBuf::from(glob),
+        };
+        let pattern = expanded
+            .file_name()
+            .and_then(|n| n.to_str())
+            .context("log glob must end in a file name pattern")?
+            .to_string();
+        let dir = expanded
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("."));
+        if dir.to_string_lossy().contains(['*', '?']) {
+            anyhow::bail!("wildcards are only supported in the file name: {}", glob);
+        }
+        Ok(Self { dir, pattern })
+    }
+
+    /// Most recently modified matching file (ties broken by name)
+    fn newest(&self) -> Option<PathBuf> {
+        std::fs::read_dir(&self.dir)
+            .ok()?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                entry
+                    .file_name()
+                    .to_str()
+                    .is_some_and(|name| wildcard_match(&self.pattern, name))
+            })
+            .filter_map(|entry| {
+                let meta = entry.metadata().ok().filter(|m| m.is_file())?;
+                Some((meta.modified().ok()?, entry.path()))
+            })
+            .max()
+            .map(|(_, path)| path)
+    }
+}
+
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let (p, n): (Vec<char>, Vec<char>) = (pattern.chars().collect(), name.chars().collect());
+    let (mut pi, mut ni) = (0, 0);
+    // Position of the last '*' and the name index it is currently absorbing up to
+    let mut backtrack: Option<(usize, usize)> = None;
+    while ni < n.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == n[ni]) {
+            pi += 1;
+            ni += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            backtrack = Some((pi, ni));
+            pi += 1;
+        } else if let Some((star, matched)) = backtrack {
+            pi = star + 1;
+            ni = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|&c| c == '*')
+}
+
 pub struct CodexLogWatcher {
     api: Box<dyn RaceboardApi>,
     log_path: PathBuf,
+    /// When set, `log_path` follows the newest file matching it
+    log_glob: Option<LogGlob>,
     last_position: u64,
     current_session: Option<SessionTracker>,
     last_completion: Option<Instant>,
@@ -281,6 +382,7 @@ impl CodexLogWatcher {
         server_config: ServerConfig,
         debug: bool,
         log_path_override: Option<PathBuf>,
+        log_glob: Option<String>,
         only_submission_starts: bool,
         min_turn_secs: u64,
     ) -> Result<Self> {
@@ -288,11 +390,19 @@ impl CodexLogWatcher {
             .expect("Could not find home directory")
             .join(".codex/log/codex-tui.log");
 
-        let log_path = log_path_override.unwrap_or(default_path);
+        let log_glob = log_glob.as_deref().map(LogGlob::parse).transpose()?;
+        let log_path = match &log_glob {
+            // No match yet: point at the pattern so the "not found" message names it
+            Some(glob) => glob
+                .newest()
+                .unwrap_or_else(|| glob.dir.join(&glob.pattern)),
+            None => log_path_override.unwrap_or(default_path),
+        };
 
         Ok(Self {
             api: Box::new(RealRaceboardApi::new(server_config)?),
             log_path,
+            log_glob,
             last_position: 0,
             current_session: None,
             last_completion: None,
@@ -342,8 +452,13 @@ impl CodexLogWatcher {
             match Watcher::new(tx, Duration::from_millis(100)) {
                 Ok(w) => {
                     let mut w: RecommendedWatcher = w;
+                    // With a glob, watch the directory so new log files raise events too
+                    let watch_path = match &self.log_glob {
+                        Some(glob) => glob.dir.clone(),
+                        None => self.log_path.clone(),
+                    };
                     if let Err(e) =
-                        Watcher::watch(&mut w, &self.log_path, RecursiveMode::NonRecursive)
+                        Watcher::watch(&mut w, &watch_path, RecursiveMode::NonRecursive)
                     {
                         warn!("Failed to watch file: {} — falling back to polling only", e);
                     } else {
@@ -362,6 +477,10 @@ impl CodexLogWatcher {
                     debug!("Filesystem event: write to {:?}", path);
                     self.process_new_lines().await?;
                 }
+                Ok(DebouncedEvent::Create(path)) if self.log_glob.is_some() => {
+                    debug!("Filesystem event: created {:?}", path);
+                    self.process_new_lines().await?;
+                }
                 Ok(other) => {
                     debug!("Filesystem event ignored: {:?}", other);
                 }
@@ -392,6 +511,33 @@ impl CodexLogWatcher {
     }
 
     async fn process_new_lines(&mut self) -> Result<()> {
+        let newer = self
+            .log_glob
+            .as_ref()
+            .and_then(LogGlob::newest)
+            .filter(|newest| *newest != self.log_path);
+
+        // Under a glob the previous file may have been deleted by rotation
+        if self.log_glob.is_none() || self.log_path.exists() {
+            self.read_from_last_position().await?;
+        }
+
+        // A newer file appeared (rotation into a new dated file): finish the
+        // old one above, then switch and read the new one from the start
+        if let Some(newest) = newer {
+            info!(
+                "Newer log file {} appeared; switching from {}",
+                newest.display(),
+                self.log_path.display()
+            );
+            self.log_path = newest;
+            self.last_position = 0;
+            self.read_from_last_position().await?;
+        }
+        Ok(())
+    }
+
+    async fn read_from_last_position(&mut self) -> Result<()> {
         let mut file = File::open(&self.log_path)?;
         let file_len = file.metadata()?.len();
         if file_len < self.last_position {
@@ -471,13 +617,13 @@ impl CodexLogWatcher {
             let title = format!("Codex: {}", self.truncate_title(&prompt, 50));
             debug!("Detected new submission; title='{}'", title);
 
-            let race = Race {
+            let mut race = Race {
                 id: Uuid::new_v4().to_string(),
                 source: "codex-session".to_string(),
                 title: title.clone(),
                 state: RaceState::Running,
                 started_at: Utc::now(),
-                eta_sec: None, // Will be updated based on complexity
+                eta_sec: None, // Seeded from cluster history below when available
                 progress: Some(0),
                 deeplink: None,
                 metadata: Some({
@@ -520,8 +666,17 @@ impl CodexLogWatcher {
                 }),
             };
 
+            // Without a confident prediction the server picks its own ETA and
+            // progress estimation falls back to the prompt heuristic
+            let seeded_eta = self.seed_eta(&race).await;
+            race.eta_sec = seeded_eta;
+
             let created = self.api.create_race(race).await?;
-            self.current_session = Some(SessionTracker::new(&prompt, created.id.clone()));
+            self.current_session = Some(SessionTracker::new(
+                &prompt,
+                created.id.clone(),
+                seeded_eta,
+            ));
 
             println!("🏁 Started: {}", title);
             debug!("Race created with id={}", created.id);
@@ -620,7 +775,8 @@ impl CodexLogWatcher {
                 };
 
                 let created = self.api.create_race(race).await?;
-                self.current_session = Some(SessionTracker::new("auto", created.id.clone()));
+                self.current_session =
+                    Some(SessionTracker::new("auto", created.id.clone(), None));
                 println!("🏁 Started: {}", title);
                 debug!(
                     "Auto-started session on FunctionCall; race id={}",
@@ -635,6 +791,38 @@ impl CodexLogWatcher {
 
     // Removed idle-based completion; rely on explicit Codex completion marker
 
+    /// Cluster-backed ETA for `race` from the server, if it is confident enough
+    async fn seed_eta(&self, race: &Race) -> Option<i64> {
+        let prediction = self
+            .api
+            .predict_eta(
+                race.source.clone(),
+                race.title.clone(),
+                race.metadata.clone().unwrap_or_default(),
+            )
+            .await;
+        match prediction {
+            Ok(p) if p.confidence >= MIN_SEED_CONFIDENCE && p.expected_seconds > 0 => {
+                debug!(
+                    "Seeding ETA {}s from server prediction (confidence {:.2}, method {:?})",
+                    p.expected_seconds, p.confidence, p.method
+                );
+                Some(p.expected_seconds)
+            }
+            Ok(p) => {
+                debug!(
+                    "Server prediction too uncertain to seed ETA (confidence {:.2})",
+                    p.confidence
+                );
+                None
+            }
+            Err(e) => {
+                debug!("Prediction unavailable, using prompt heuristic: {}", e);
+                None
+            }
+        }
+    }
+
     fn extract_user_input(&self, line: &str) -> String {
         // Extract text from: UserInput { items: [Text { text: "..." }] }
         // Be forgiving about whitespace and formatting
@@ -737,6 +925,8 @@ mod tests {
 
     #[derive(Default, Clone)]
     struct MockApiState {
+        /// Served by predict_eta; None behaves like an unreachable server
+        prediction: Option<EtaPrediction>,
         created: Vec<Race>,
         progress: Vec<(String, i32)>,
         completed: Vec<String>,
@@ -759,6 +949,16 @@ mod tests {
     }
 
     impl RaceboardApi for MockApi {
+        fn predict_eta(
+            &self,
+            _source: String,
+            _title: String,
+            _metadata: HashMap<String, String>,
+        ) -> BoxFuture<'static, Result<EtaPrediction>> {
+            let prediction = self.state.lock().unwrap().prediction.clone();
+            Box::pin(async move { prediction.context("server unreachable") })
+        }
+
         fn create_race(&self, race: Race) -> BoxFuture<'static, Result<Race>> {
             let st = self.state.clone();
             Box::pin(async move {
@@ -793,6 +993,7 @@ mod tests {
         CodexLogWatcher {
             api,
             log_path: PathBuf::from("/dev/null"),
+            log_glob: None,
             last_position: 0,
             current_session: None,
             last_completion: None,
@@ -845,6 +1046,94 @@ mod tests {
         // (already implied by created.len()==1)
         Ok(())
     }
+
+    const SUBMISSION: &str = r#"Submission { id: "1", op: UserInput { items: [Text { text: "refactor the parser module" }] } }"#;
+
+    #[tokio::test]
+    async fn test_confident_prediction_seeds_race_eta() -> Result<()> {
+        let (mock, state) = MockApi::new();
+        state.lock().unwrap().prediction = Some(EtaPrediction {
+            expected_seconds: 240,
+            confidence: 0.8,
+            method: Some("cluster".to_string()),
+        });
+        let mut watcher = make_watcher_with_api(Box::new(mock));
+
+        watcher.parse_log_line(SUBMISSION).await?;
+
+        assert_eq!(state.lock().unwrap().created[0].eta_sec, Some(240));
+        let session = watcher.current_session.as_ref().unwrap();
+        assert_eq!(session.activity_tracker.initial_eta, 240);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_uncertain_or_missing_prediction_uses_heuristic() -> Result<()> {
+        for prediction in [
+            Some(EtaPrediction {
+                expected_seconds: 240,
+                confidence: 0.2,
+                method: Some("bootstrap".to_string()),
+            }),
+            None,
+        ] {
+            let (mock, state) = MockApi::new();
+            state.lock().unwrap().prediction = prediction;
+            let mut watcher = make_watcher_with_api(Box::new(mock));
+
+            watcher.parse_log_line(SUBMISSION).await?;
+
+            assert_eq!(state.lock().unwrap().created[0].eta_sec, None);
+            // "refactor" makes the prompt Complex
+            let session = watcher.current_session.as_ref().unwrap();
+            assert_eq!(session.activity_tracker.initial_eta, 120);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("codex-*.log", "codex-2025-08-29.log"));
+        assert!(wildcard_match("codex-*.log", "codex-.log"));
+        assert!(wildcard_match("codex-?.log", "codex-1.log"));
+        assert!(!wildcard_match("codex-*.log", "codex-tui.log.1"));
+        assert!(!wildcard_match("codex-?.log", "codex-12.log"));
+        assert!(wildcard_match("*", "anything"));
+    }
+
+    #[tokio::test]
+    async fn test_newer_matching_log_becomes_active() -> Result<()> {
+        use std::time::SystemTime;
+
+        let dir = tempfile::TempDir::new()?;
+        let older = dir.path().join("codex-2025-08-28.log");
+        let newer = dir.path().join("codex-2025-08-29.log");
+        std::fs::write(&older, "first line\n")?;
+        File::options()
+            .write(true)
+            .open(&older)?
+            .set_modified(SystemTime::now() - Duration::from_secs(60))?;
+        std::fs::write(dir.path().join("unrelated.txt"), "ignored\n")?;
+
+        let (mock, _state) = MockApi::new();
+        let mut watcher = make_watcher_with_api(Box::new(mock));
+        let glob = LogGlob::parse(&dir.path().join("codex-*.log").to_string_lossy())?;
+        watcher.log_path = glob.newest().unwrap();
+        watcher.log_glob = Some(glob);
+        assert_eq!(watcher.log_path, older);
+
+        watcher.process_new_lines().await?;
+        assert_eq!(watcher.last_position, "first line\n".len() as u64);
+
+        std::fs::write(&newer, "rotated line one\nrotated line two\n")?;
+        watcher.process_new_lines().await?;
+        assert_eq!(watcher.log_path, newer);
+        assert_eq!(
+            watcher.last_position,
+            std::fs::metadata(&newer)?.len()
+        );
+        Ok(())
+    }
 }
 
 #[tokio::main]
@@ -856,9 +1145,10 @@ async fn main() -> Result<()> {
     info!(
         "server={} mode=follow+new_only log_path={}",
         args.server,
-        args.log_path
+        args.log_glob
             .as_ref()
-            .map(|p| p.display().to_string())
+            .map(|g| format!("<newest of> {}", g))
+            .or_else(|| args.log_path.as_ref().map(|p| p.display().to_string()))
             .unwrap_or_else(|| "<default> ~/.codex/log/codex-tui.log".to_string())
     );
 
@@ -909,6 +1199,7 @@ async fn main() -> Result<()> {
         server_config,
         args.debug,
         args.log_path,
+        args.log_glob,
         args.only_submission_starts,
         args.min_turn_secs,
     )?;
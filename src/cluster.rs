@@ -1,10 +1,15 @@
-use crate::stats::{EtaPrediction, ExecutionStats};
+use crate::rebuild::compared_title;
+use crate::stats::{EtaPrediction, ExecutionStats, OutlierFilter, PercentileSet, TrendAdjustment};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+/// Distinct titles and member metadata entries a cluster keeps
+pub const MAX_MEMBER_HISTORY: usize = 50;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RaceCluster {
     pub cluster_id: String,
@@ -13,16 +18,161 @@ pub struct RaceCluster {
     pub representative_metadata: HashMap<String, String>,
     pub stats: ExecutionStats,
     pub member_race_ids: Vec<String>,
-    pub member_titles: Vec<String>, // Store titles for centroid computation
-    pub member_metadata_history: Vec<HashMap<String, String>>, // Store metadata history
+    /// Distinct member titles with how many members carried each, for centroid computation
+    #[serde(deserialize_with = "deserialize_member_titles")]
+    pub member_titles: HashMap<String, u32>,
+    /// Metadata of the last `MAX_MEMBER_HISTORY` members, in `member_race_ids` order
+    pub member_metadata_history: Vec<HashMap<String, String>>,
+    /// Trustworthiness in [0,1], computed when the cluster is rebuilt from members
+    #[serde(default)]
+    pub quality_score: Option<f64>,
+    /// How far the cluster's ETA has been from the races it then saw complete
+    #[serde(default)]
+    pub prediction_accuracy: PredictionAccuracy,
+    /// `started_at` of the newest member race; `None` for clusters persisted before it was tracked
+    #[serde(default)]
+    pub latest_member_at: Option<DateTime<Utc>>,
+    pub last_updated: DateTime<Utc>,
+    pub last_accessed: DateTime<Utc>,
+}
+
+/// Running mean absolute error between the cluster median at completion time
+/// and the actual duration
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PredictionAccuracy {
+    pub mae: f64,
+    pub sample_size: usize,
+}
+
+impl PredictionAccuracy {
+    pub fn record(&mut self, predicted: f64, actual: i64) {
+        self.sample_size += 1;
+        let error = (actual as f64 - predicted).abs();
+        self.mae += (error - self.mae) / self.sample_size as f64;
+    }
+}
+
+/// The fields cluster listings need, copied out so serialization runs
+/// without holding the clusters lock
+#[derive(Debug, Clone)]
+pub struct ClusterSummary {
+    pub cluster_id: String,
+    pub source: String,
+    pub representative_title: String,
+    pub member_count: usize,
+    pub quality_score: Option<f64>,
+    pub prediction_accuracy: PredictionAccuracy,
+    pub latest_member_at: Option<DateTime<Utc>>,
+    pub stats: ExecutionStats,
     pub last_updated: DateTime<Utc>,
     pub last_accessed: DateTime<Utc>,
 }
 
+impl ClusterSummary {
+    /// Last sign of activity: the newest member, else the last stats update
+    pub fn active_at(&self) -> DateTime<Utc> {
+        self.latest_member_at.unwrap_or(self.last_updated)
+    }
+}
+
+impl From<&RaceCluster> for ClusterSummary {
+    fn from(cluster: &RaceCluster) -> Self {
+        Self {
+            cluster_id: cluster.cluster_id.clone(),
+            source: cluster.source.clone(),
+            representative_title: cluster.representative_title.clone(),
+            member_count: cluster.member_race_ids.len(),
+            quality_score: cluster.quality_score,
+            prediction_accuracy: cluster.prediction_accuracy.clone(),
+            latest_member_at: cluster.latest_member_at,
+            stats: cluster.stats.clone(),
+            last_updated: cluster.last_updated,
+            last_accessed: cluster.last_accessed,
+        }
+    }
+}
+
+/// Tally titles into the per-title member counts `RaceCluster` stores
+pub fn count_titles(titles: impl IntoIterator<Item = String>) -> HashMap<String, u32> {
+    let mut counts = HashMap::new();
+    for title in titles {
+        let count: &mut u32 = counts.entry(title).or_insert(0);
+        *count = count.saturating_add(1);
+    }
+    counts
+}
+
+/// Accepts the title -> count map, or the plain list of titles clusters were
+/// persisted with before schema version 4
+fn deserialize_member_titles<'de, D>(deserializer: D) -> Result<HashMap<String, u32>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum MemberTitles {
+        Counts(HashMap<String, u32>),
+        List(Vec<String>),
+    }
+
+    Ok(match MemberTitles::deserialize(deserializer)? {
+        MemberTitles::Counts(counts) => counts,
+        MemberTitles::List(titles) => count_titles(titles),
+    })
+}
+
+impl RaceCluster {
+    pub fn add_member_title(&mut self, title: &str) {
+        let count = self.member_titles.entry(title.to_string()).or_insert(0);
+        *count = count.saturating_add(1);
+    }
+
+    /// Record the metadata of the newest member, keeping the last `MAX_MEMBER_HISTORY`
+    pub fn add_member_metadata(&mut self, metadata: HashMap<String, String>) {
+        self.member_metadata_history.push(metadata);
+        if self.member_metadata_history.len() > MAX_MEMBER_HISTORY {
+            let excess = self.member_metadata_history.len() - MAX_MEMBER_HISTORY;
+            self.member_metadata_history.drain(..excess);
+        }
+    }
+
+    /// Members behind `member_titles`, counting repeated titles
+    pub fn member_title_count(&self) -> usize {
+        self.member_titles.values().map(|&c| c as usize).sum()
+    }
+
+    /// Hash of the cluster's content, ignoring bookkeeping timestamps, so a
+    /// rebuild that reproduces a cluster hashes the same as the stored copy
+    pub fn content_hash(&self) -> u64 {
+        let mut value = match serde_json::to_value(self) {
+            Ok(value) => value,
+            Err(_) => return 0,
+        };
+        if let Some(fields) = value.as_object_mut() {
+            fields.remove("last_updated");
+            fields.remove("last_accessed");
+            if let Some(stats) = fields.get_mut("stats").and_then(|s| s.as_object_mut()) {
+                stats.remove("last_updated");
+            }
+        }
+        // serde_json maps are key-ordered, so metadata hashes deterministically
+        seahash::hash(value.to_string().as_bytes())
+    }
+}
+
 pub struct ClusteringEngine {
     pub clusters: Arc<RwLock<HashMap<String, RaceCluster>>>,
     pub similarity_threshold: f64,
+    /// Clusters retained across all sources; creating one past this evicts the
+    /// cluster with the oldest `last_accessed` (LRU)
     pub max_clusters: usize,
+    pub trend_adjustment: TrendAdjustment,
+    /// Reported percentiles and the pair bounding cluster ETAs
+    pub percentiles: PercentileSet,
+    /// Applied to completed durations before they update cluster and source stats
+    pub outlier_filter: OutlierFilter,
+    /// Clusters dropped by LRU eviction since startup
+    evictions: AtomicU64,
 }
 
 impl ClusteringEngine {
@@ -31,9 +181,23 @@ impl ClusteringEngine {
             clusters: Arc::new(RwLock::new(HashMap::new())),
             similarity_threshold: 0.7,
             max_clusters,
+            trend_adjustment: TrendAdjustment::default(),
+            percentiles: PercentileSet::default(),
+            outlier_filter: OutlierFilter::default(),
+            evictions: AtomicU64::new(0),
         }
     }
 
+    pub fn evictions(&self) -> u64 {
+        self.evictions.load(Ordering::Relaxed)
+    }
+
+    /// Snapshot every cluster's summary; the read lock is held only for the copy
+    pub async fn summaries(&self) -> Vec<ClusterSummary> {
+        let clusters = self.clusters.read().await;
+        clusters.values().map(ClusterSummary::from).collect()
+    }
+
     pub fn calculate_similarity(
         race_title: &str,
         race_source: &str,
@@ -44,9 +208,11 @@ impl ClusteringEngine {
             return 0.0;
         }
 
+        let race_title = compared_title(race_title);
+        let representative_title = compared_title(&cluster.representative_title);
         let title_similarity = 1.0
-            - (levenshtein(race_title, &cluster.representative_title) as f64
-                / race_title.len().max(cluster.representative_title.len()) as f64);
+            - (levenshtein(race_title, representative_title) as f64
+                / race_title.len().max(representative_title.len()) as f64);
 
         let metadata_similarity =
             jaccard_similarity(race_metadata, &cluster.representative_metadata);
@@ -140,8 +306,11 @@ impl ClusteringEngine {
             representative_metadata: race_metadata.clone(),
             stats: ExecutionStats::new(),
             member_race_ids: vec![race_id.to_string()],
-            member_titles: vec![race_title.to_string()],
+            member_titles: count_titles([race_title.to_string()]),
             member_metadata_history: vec![race_metadata.clone()],
+            quality_score: None,
+            prediction_accuracy: PredictionAccuracy::default(),
+            latest_member_at: None,
             last_updated: Utc::now(),
             last_accessed: Utc::now(),
         };
@@ -157,6 +326,12 @@ impl ClusteringEngine {
             .map(|(id, _)| id.clone())
         {
             clusters.remove(&lru_id);
+            self.evictions.fetch_add(1, Ordering::Relaxed);
+            log::info!(
+                "Evicted least-recently-used cluster {} (capacity {})",
+                lru_id,
+                self.max_clusters
+            );
         }
     }
 
@@ -167,17 +342,21 @@ impl ClusteringEngine {
         new_metadata: &HashMap<String, String>,
     ) {
         // Add the new title and metadata to history
-        cluster.member_titles.push(new_title.to_string());
-        cluster.member_metadata_history.push(new_metadata.clone());
-
-        // Keep only recent history (last 50 members)
-        const MAX_HISTORY: usize = 50;
-        if cluster.member_titles.len() > MAX_HISTORY {
-            cluster.member_titles =
-                cluster.member_titles[cluster.member_titles.len() - MAX_HISTORY..].to_vec();
-            cluster.member_metadata_history = cluster.member_metadata_history
-                [cluster.member_metadata_history.len() - MAX_HISTORY..]
-                .to_vec();
+        cluster.add_member_title(new_title);
+        cluster.add_member_metadata(new_metadata.clone());
+
+        // Keep at most MAX_MEMBER_HISTORY distinct titles
+        if cluster.member_titles.len() > MAX_MEMBER_HISTORY {
+            // Titles carry no order, so make room by dropping the rarest other one
+            let rarest = cluster
+                .member_titles
+                .iter()
+                .filter(|(title, _)| title.as_str() != new_title)
+                .min_by(|a, b| a.1.cmp(b.1).then_with(|| b.0.cmp(a.0)))
+                .map(|(title, _)| title.clone());
+            if let Some(rarest) = rarest {
+                cluster.member_titles.remove(&rarest);
+            }
         }
 
         // Update representative every 10 members or if cluster is small
@@ -196,26 +375,34 @@ impl ClusteringEngine {
         cluster.last_updated = Utc::now();
     }
 
-    fn compute_centroid_title(&self, titles: &[String]) -> String {
-        if titles.is_empty() {
+    fn compute_centroid_title(&self, titles: &HashMap<String, u32>) -> String {
+        // Sorted so ties resolve the same way on every call
+        let mut distinct: Vec<(&String, u32)> = titles.iter().map(|(t, &c)| (t, c)).collect();
+        distinct.sort();
+
+        if distinct.is_empty() {
             return String::new();
         }
 
-        if titles.len() == 1 {
-            return titles[0].clone();
+        if distinct.len() == 1 {
+            return distinct[0].0.clone();
         }
 
-        // Find title with minimum average Levenshtein distance to all others
+        // Find title with minimum average Levenshtein distance to all other members
+        let member_count: u64 = distinct.iter().map(|&(_, c)| c as u64).sum();
         let mut min_avg_distance = f64::MAX;
-        let mut centroid_title = titles[0].clone();
+        let mut centroid_title = distinct[0].0.clone();
 
-        for candidate in titles {
-            let total_distance: usize = titles
+        for &(candidate, _) in &distinct {
+            let total_distance: u64 = distinct
                 .iter()
-                .map(|other| levenshtein(candidate, other))
+                .map(|&(other, count)| {
+                    levenshtein(compared_title(candidate), compared_title(other)) as u64
+                        * count as u64
+                })
                 .sum();
 
-            let avg_distance = total_distance as f64 / titles.len() as f64;
+            let avg_distance = total_distance as f64 / member_count as f64;
 
             if avg_distance < min_avg_distance {
                 min_avg_distance = avg_distance;
@@ -268,15 +455,42 @@ impl ClusteringEngine {
     pub async fn update_cluster_stats(&self, cluster_id: &str, duration: i64) {
         let mut clusters = self.clusters.write().await;
         if let Some(cluster) = clusters.get_mut(cluster_id) {
-            cluster.stats.update_with_duration(duration);
+            // Score what the cluster would have predicted before it learns this run
+            if !cluster.stats.recent_times.is_empty() {
+                cluster
+                    .prediction_accuracy
+                    .record(cluster.stats.median, duration);
+            }
+            cluster
+                .stats
+                .update_with_duration_filtered(duration, &self.outlier_filter);
+            // The race completed just now, so it started `duration` ago
+            let started_at = Utc::now() - chrono::Duration::seconds(duration);
+            cluster.latest_member_at = cluster.latest_member_at.max(Some(started_at));
             cluster.last_updated = Utc::now();
             cluster.last_accessed = Utc::now();
         }
     }
 
-    pub async fn get_cluster_eta(&self, cluster_id: &str) -> Option<EtaPrediction> {
-        let clusters = self.clusters.read().await;
-        clusters.get(cluster_id).map(|c| c.stats.calculate_eta())
+    /// Cluster ETA, or `None` while it has fewer than `min_samples` completed runs;
+    /// marks the cluster as used so LRU eviction keeps clusters that are predicting
+    pub async fn predict_from_cluster(
+        &self,
+        cluster_id: &str,
+        min_samples: usize,
+    ) -> Option<EtaPrediction> {
+        let mut clusters = self.clusters.write().await;
+        let cluster = clusters.get_mut(cluster_id)?;
+        cluster.last_accessed = Utc::now();
+        // A cluster with no (or too few) completed runs has nothing reliable to predict from
+        if cluster.stats.recent_times.len() < min_samples.max(1) {
+            return None;
+        }
+        Some(
+            cluster
+                .stats
+                .calculate_eta_with(&self.trend_adjustment, &self.percentiles),
+        )
     }
 }
 
@@ -362,6 +576,31 @@ pub fn extract_operation_type(
     }
 }
 
+#[cfg(test)]
+pub mod test_utils {
+    use super::*;
+
+    /// Cluster with the given members and no history; the source is the id's
+    /// prefix before `:`
+    pub fn test_cluster(cluster_id: &str, members: &[&str]) -> RaceCluster {
+        RaceCluster {
+            cluster_id: cluster_id.to_string(),
+            source: cluster_id.split(':').next().unwrap_or_default().to_string(),
+            representative_title: cluster_id.to_string(),
+            representative_metadata: HashMap::new(),
+            stats: ExecutionStats::new(),
+            member_race_ids: members.iter().map(|m| m.to_string()).collect(),
+            member_titles: HashMap::new(),
+            member_metadata_history: vec![],
+            quality_score: None,
+            prediction_accuracy: PredictionAccuracy::default(),
+            latest_member_at: None,
+            last_updated: Utc::now(),
+            last_accessed: Utc::now(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -404,4 +643,84 @@ mod tests {
             "test_suite"
         );
     }
+
+    #[tokio::test]
+    async fn test_prediction_accuracy_tracks_error_against_prior_median() {
+        let engine = ClusteringEngine::new(10);
+        let cluster_id = engine
+            .assign_race_to_cluster("r1", "cargo build", "cargo", &HashMap::new())
+            .await;
+
+        // The first completion has nothing to be scored against
+        for duration in [100, 100, 130, 70] {
+            engine.update_cluster_stats(&cluster_id, duration).await;
+        }
+
+        // Medians before each scored run: 100, 100, 100 -> errors 0, 30, 30
+        let clusters = engine.clusters.read().await;
+        let accuracy = &clusters[&cluster_id].prediction_accuracy;
+        assert_eq!(accuracy.sample_size, 3);
+        assert!((accuracy.mae - 20.0).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_capacity_evicts_least_recently_accessed_cluster() {
+        let engine = ClusteringEngine::new(2);
+        let build = engine
+            .assign_race_to_cluster("r1", "cargo build", "cargo", &HashMap::new())
+            .await;
+        let test = engine
+            .assign_race_to_cluster("r2", "go test", "go", &HashMap::new())
+            .await;
+        assert_ne!(build, test);
+
+        // Touch the older cluster so the newer one becomes least recently used
+        engine.update_cluster_stats(&build, 10).await;
+        let npm = engine
+            .assign_race_to_cluster("r3", "npm install", "npm", &HashMap::new())
+            .await;
+
+        let clusters = engine.clusters.read().await;
+        assert_eq!(clusters.len(), 2);
+        assert!(clusters.contains_key(&build));
+        assert!(clusters.contains_key(&npm));
+        assert!(!clusters.contains_key(&test));
+        assert_eq!(engine.evictions(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_summaries_release_lock_before_serialization() {
+        let engine = ClusteringEngine::new(10);
+        engine
+            .assign_race_to_cluster("r1", "cargo build", "cargo", &HashMap::new())
+            .await;
+
+        let summaries = engine.summaries().await;
+        let (started_tx, started_rx) = tokio::sync::oneshot::channel();
+        let (release_tx, release_rx) = std::sync::mpsc::channel::<()>();
+        // Serialize the way get_clusters does, parking halfway through
+        let serializer = tokio::task::spawn_blocking(move || {
+            let ids: Vec<_> = summaries.iter().map(|s| s.cluster_id.clone()).collect();
+            let _ = started_tx.send(());
+            release_rx.recv().unwrap();
+            let counts: Vec<_> = summaries.iter().map(|s| s.member_count).collect();
+            serde_json::json!({ "cluster_ids": ids, "member_counts": counts }).to_string()
+        });
+
+        started_rx.await.unwrap();
+        // A rebuild writer on another task gets in while serialization is parked
+        let writer_clusters = engine.clusters.clone();
+        let writer = tokio::spawn(async move {
+            writer_clusters.write().await.clear();
+        });
+        tokio::time::timeout(std::time::Duration::from_secs(5), writer)
+            .await
+            .expect("writer blocked by the summaries snapshot")
+            .unwrap();
+        release_tx.send(()).unwrap();
+
+        let json = serializer.await.unwrap();
+        assert!(json.contains("\"member_counts\":[1]"));
+        assert!(engine.clusters.read().await.is_empty());
+    }
 }
@@ -1,11 +1,31 @@
 use config::{Config, ConfigError, Environment, File};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+
+/// Config file read at startup and by `/admin/reload-config` (any supported extension)
+pub const DEFAULT_CONFIG_FILE: &str = "config";
+
+/// Default in-memory race cap; only live and recently completed races need to fit
+pub const DEFAULT_MAX_RACES: usize = 1000;
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Settings {
     pub server: ServerConfig,
     pub logging: LoggingConfig,
     pub storage: StorageConfig,
+    pub prediction: PredictionConfig,
+    pub processing: ProcessingConfig,
+    pub limits: LimitsConfig,
+    #[serde(default)]
+    pub sources: SourcesConfig,
+    #[serde(default)]
+    pub cors: CorsConfig,
+    #[serde(default)]
+    pub alerts: AlertsConfig,
+    #[serde(default)]
+    pub webhooks: WebhooksConfig,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -16,6 +36,34 @@ pub struct ServerConfig {
     pub grpc_host: String,
     pub read_only: bool,
     pub legacy_json_fallback_enabled: bool,
+    /// How often the legacy fallback rewrites races.json with queued completions
+    pub legacy_json_flush_interval_secs: u64,
+    /// Token (x-raceboard-token) that bypasses read_only, for trusted adapters
+    #[serde(default)]
+    pub write_token: Option<String>,
+    /// Token (x-raceboard-admin-token) required by admin-only endpoints such as
+    /// /admin/reload-config; unset disables them
+    #[serde(default)]
+    pub admin_token: Option<String>,
+    /// Serve gRPC server reflection so grpcurl etc. can discover the schema
+    pub grpc_reflection: bool,
+    /// Shared budget for HTTP, gRPC and background tasks to stop on Ctrl-C
+    pub shutdown_timeout_secs: u64,
+    /// `GET /races` also lists completed races still held in memory when the
+    /// request has no `include_completed` parameter
+    pub races_include_completed: bool,
+    /// Longer race titles are truncated on ingress (REST and gRPC)
+    pub max_title_len: usize,
+}
+
+impl ServerConfig {
+    pub fn legacy_json_flush_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.legacy_json_flush_interval_secs.max(1))
+    }
+
+    pub fn shutdown_timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.shutdown_timeout_secs.max(1))
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -23,14 +71,386 @@ pub struct LoggingConfig {
     pub level: String,
 }
 
+impl LoggingConfig {
+    /// The level as one filter applied through `log::set_max_level`, so it can
+    /// be reloaded; `None` when RUST_LOG is set or `level` is a directive list
+    /// (e.g. "info,sled=warn"), which env_logger keeps fixed until restart
+    pub fn runtime_level(&self) -> Option<log::LevelFilter> {
+        if std::env::var_os("RUST_LOG").is_some() {
+            return None;
+        }
+        self.level.trim().parse().ok()
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct AlertsConfig {
+    /// Critical alerts are POSTed here as JSON in addition to the alert log
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+}
+
+/// Outbound webhook POSTed when a race reaches passed/failed/canceled
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct WebhooksConfig {
+    /// Receiver URL; unset disables delivery
+    pub url: Option<String>,
+    /// HMAC-SHA256 key; the hex digest of the body is sent as X-Raceboard-Signature
+    pub secret: Option<String>,
+    /// Sources whose races are delivered; empty delivers every source
+    pub sources: Vec<String>,
+    /// Events waiting for delivery; past this they go straight to the dead-letter log
+    pub queue_size: usize,
+    pub timeout_secs: u64,
+    /// Total attempts per event, including the first
+    pub max_attempts: u32,
+    /// Backoff before the first retry; doubles on each later retry
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+    /// Events delivered (or retrying) at the same time
+    pub max_in_flight: usize,
+}
+
+impl Default for WebhooksConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            secret: None,
+            sources: Vec::new(),
+            queue_size: 1000,
+            timeout_secs: 10,
+            max_attempts: 5,
+            initial_backoff_ms: 1000,
+            max_backoff_ms: 60_000,
+            max_in_flight: 8,
+        }
+    }
+}
+
+impl WebhooksConfig {
+    pub fn delivers(&self, source: &str) -> bool {
+        self.sources.is_empty()
+            || self
+                .sources
+                .iter()
+                .any(|s| s.trim().eq_ignore_ascii_case(source))
+    }
+
+    /// Backoff before retry number `retry` (0-based)
+    pub fn backoff(&self, retry: u32) -> std::time::Duration {
+        std::time::Duration::from_millis(
+            self.initial_backoff_ms
+                .saturating_mul(1u64 << retry.min(20))
+                .min(self.max_backoff_ms),
+        )
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct StorageConfig {
+    /// Races held in memory (live races plus recently completed ones); the
+    /// clustering corpus is read from persistence and is not bounded by this
     pub max_races: usize,
     pub max_events_per_race: usize,
+    /// Log per-operation persistence diagnostics at trace level
+    pub persistence_trace: bool,
+    /// Move records failing checksum verification into the `corrupt` tree
+    pub quarantine_corrupt: bool,
+    /// Root for the sled DB, snapshots and legacy JSON files (default ~/.raceboard)
+    #[serde(default)]
+    pub data_dir: Option<PathBuf>,
+    /// Defer race fsyncs and flush in batches (see flush_interval_ms / flush_max_pending)
+    pub write_batching_enabled: bool,
+    pub flush_interval_ms: u64,
+    pub flush_max_pending: usize,
+    /// Largest batch a single persistence scan may return, whatever the caller requests
+    pub max_scan_batch: usize,
+    /// Stream evictions as EVICTED updates rather than plain deletes
+    pub eviction_events: bool,
+}
+
+impl StorageConfig {
+    pub fn data_dir(&self) -> PathBuf {
+        match &self.data_dir {
+            Some(dir) => match dir.strip_prefix("~") {
+                Ok(rest) => dirs::home_dir()
+                    .unwrap_or_else(|| PathBuf::from("."))
+                    .join(rest),
+                Err(_) => dir.clone(),
+            },
+            None => default_data_dir(),
+        }
+    }
+
+    /// In-memory race cap, as configured (at least one)
+    pub fn active_race_capacity(&self) -> usize {
+        self.max_races.max(1)
+    }
+
+    pub fn write_batching(&self) -> crate::persistence::WriteBatching {
+        crate::persistence::WriteBatching {
+            enabled: self.write_batching_enabled,
+            interval_ms: self.flush_interval_ms,
+            max_pending: self.flush_max_pending,
+        }
+    }
+}
+
+/// ~/.raceboard, or ./.raceboard when no home directory is available
+pub fn default_data_dir() -> PathBuf {
+    let mut path = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+    path.push(".raceboard");
+    path
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct PredictionConfig {
+    /// Clusters the online clustering engine retains (LRU-evicted past this)
+    pub max_clusters: usize,
+    pub trend_adjustment_enabled: bool,
+    pub trend_horizon: f64,
+    pub trend_max_adjustment: f64,
+    /// Percentiles reported beyond p10/p25/p50/p75/p90/p95, e.g. [99.0]
+    #[serde(default)]
+    pub extra_percentiles: Vec<f64>,
+    /// Percentiles bounding ETA ranges
+    pub eta_lower_percentile: f64,
+    pub eta_upper_percentile: f64,
+    /// Reject completed durations more than this many MADs from the median
+    /// (unset = off)
+    #[serde(default)]
+    pub outlier_mad_multiplier: Option<f64>,
+    /// Samples a cluster needs before the outlier filter applies
+    pub outlier_min_samples: usize,
+    /// ETA given when no cluster, source history or bootstrap default applies
+    pub default_eta_sec: i64,
+    /// Races read per persistence scan while a rebuild loads history
+    pub rebuild_scan_batch_size: usize,
+    /// How rebuilt clusters derive their representative title
+    pub centroid_title_mode: crate::rebuild::CentroidTitleMode,
+    /// Compute rebuild distances on all cores (same clusters as a serial run)
+    pub rebuild_parallel: bool,
+    /// Only source the phased rollout enables in Phase 1; should have races
+    pub rollout_pilot_source: String,
+    /// Passing rebuilds before a shadow source is promoted to canary
+    pub rollout_shadow_promotion_successes: u32,
+    /// Passing rebuilds before a canary source is promoted to production
+    pub rollout_canary_promotion_successes: u32,
+    /// Minimum share of passing rebuilds for either promotion
+    pub rollout_promotion_success_rate: f64,
+    /// Minutes a rollout rollback lasts before sources return to shadow mode
+    pub rollout_rollback_recovery_minutes: u64,
+    /// Per-source order of prediction stages, e.g. cargo = ["cluster", "bootstrap"]
+    #[serde(default)]
+    pub fallback_chains: HashMap<String, Vec<crate::stats::PredictionMethod>>,
+}
+
+impl PredictionConfig {
+    pub fn trend_adjustment(&self) -> crate::stats::TrendAdjustment {
+        crate::stats::TrendAdjustment {
+            enabled: self.trend_adjustment_enabled,
+            horizon: self.trend_horizon,
+            max_fraction: self.trend_max_adjustment,
+        }
+    }
+
+    pub fn percentile_set(&self) -> crate::stats::PercentileSet {
+        crate::stats::PercentileSet {
+            extra: self.extra_percentiles.clone(),
+            lower_bound: self.eta_lower_percentile.clamp(0.0, 100.0),
+            upper_bound: self.eta_upper_percentile.clamp(0.0, 100.0),
+        }
+    }
+
+    pub fn outlier_filter(&self) -> crate::stats::OutlierFilter {
+        crate::stats::OutlierFilter {
+            mad_multiplier: self.outlier_mad_multiplier,
+            min_samples: self.outlier_min_samples,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ProcessingConfig {
+    pub queue_capacity: usize,
+    pub workers: usize,
+    pub drop_when_full: bool,
+    pub degraded_queue_depth: usize,
+    pub stall_after_secs: u64,
+    pub eta_refresh_enabled: bool,
+    pub eta_refresh_interval_secs: u64,
+    pub eta_refresh_min_change: f64,
+}
+
+impl ProcessingConfig {
+    pub fn options(&self) -> crate::processing::ProcessingOptions {
+        crate::processing::ProcessingOptions {
+            queue_capacity: self.queue_capacity,
+            workers: self.workers,
+            drop_when_full: self.drop_when_full,
+            degraded_queue_depth: self.degraded_queue_depth,
+            stall_after: std::time::Duration::from_secs(self.stall_after_secs.max(1)),
+            eta_refresh: crate::processing::EtaRefreshOptions {
+                enabled: self.eta_refresh_enabled,
+                interval: std::time::Duration::from_secs(self.eta_refresh_interval_secs.max(1)),
+                min_change_ratio: self.eta_refresh_min_change,
+            },
+        }
+    }
+}
+
+/// Token-bucket limits on the race write endpoints (reads are never throttled)
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct LimitsConfig {
+    pub enabled: bool,
+    pub key_by: crate::rate_limit::LimitKey,
+    pub burst: u32,
+    pub per_second: f64,
+}
+
+impl LimitsConfig {
+    pub fn options(&self) -> crate::rate_limit::RateLimitOptions {
+        crate::rate_limit::RateLimitOptions {
+            enabled: self.enabled,
+            key_by: self.key_by,
+            burst: self.burst,
+            per_second: self.per_second,
+        }
+    }
+}
+
+/// CORS policy for the HTTP API. The defaults reproduce the historical
+/// wildcard policy; credentialed requests require explicit origins.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct CorsConfig {
+    /// Allowed origins, or "*" for any
+    pub allowed_origins: Vec<String>,
+    pub allowed_methods: Vec<String>,
+    /// Allowed request headers, or "*" for any
+    pub allowed_headers: Vec<String>,
+    pub allow_credentials: bool,
+    pub max_age_secs: Option<usize>,
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            allowed_origins: vec!["*".to_string()],
+            allowed_methods: ["GET", "POST", "PATCH", "DELETE", "OPTIONS"]
+                .iter()
+                .map(|m| m.to_string())
+                .collect(),
+            // Browser clients send the write/admin tokens as custom headers
+            allowed_headers: vec![
+                "Content-Type".to_string(),
+                crate::app_state::WRITE_TOKEN_HEADER.to_string(),
+                crate::app_state::ADMIN_TOKEN_HEADER.to_string(),
+            ],
+            allow_credentials: false,
+            max_age_secs: None,
+        }
+    }
+}
+
+impl CorsConfig {
+    pub fn middleware(&self) -> actix_cors::Cors {
+        let mut cors = actix_cors::Cors::default()
+            .allowed_methods(self.allowed_methods.iter().map(String::as_str));
+
+        // `Settings::validate` rules out "*" together with credentials
+        if self.allowed_origins.iter().any(|o| o == "*") {
+            cors = cors.allow_any_origin().send_wildcard();
+        } else {
+            for origin in &self.allowed_origins {
+                cors = cors.allowed_origin(origin);
+            }
+        }
+
+        if self.allowed_headers.iter().any(|h| h == "*") {
+            cors = cors.allow_any_header();
+        } else {
+            cors = cors.allowed_headers(self.allowed_headers.iter().map(String::as_str));
+        }
+
+        if self.allow_credentials {
+            cors = cors.supports_credentials();
+        }
+        cors.max_age(self.max_age_secs)
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct SourcesConfig {
+    /// Alternate source names mapped to their canonical name, e.g. gh = "github"
+    #[serde(default)]
+    pub aliases: HashMap<String, String>,
+    /// Per-source ETA handling, keyed by canonical source name
+    #[serde(default)]
+    pub eta: HashMap<String, SourceEtaPolicy>,
+    /// Per-source retention of completed races in the historical store
+    #[serde(default)]
+    pub persistence: HashMap<String, SourcePersistencePolicy>,
+    /// Clustering config for sources the rebuild has no tuned entry for;
+    /// unset fields keep the built-in catch-all values
+    #[serde(default)]
+    pub default_source_config: Option<crate::rebuild::SourceConfig>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct SourceEtaPolicy {
+    /// Replace adapter-supplied ETAs with the cluster prediction when it is
+    /// confident enough and more confident than the adapter
+    pub prefer_cluster_eta: bool,
+    pub min_cluster_confidence: f64,
+}
+
+impl Default for SourceEtaPolicy {
+    fn default() -> Self {
+        Self {
+            prefer_cluster_eta: false,
+            min_cluster_confidence: 0.6,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct SourcePersistencePolicy {
+    /// Store completed races in sled (and the legacy JSON files); when false
+    /// they still feed cluster and source stats but leave no history
+    pub persist_completed: bool,
+}
+
+impl Default for SourcePersistencePolicy {
+    fn default() -> Self {
+        Self {
+            persist_completed: true,
+        }
+    }
+}
+
+/// Per-source persistence policies shared by the HTTP and gRPC services, so a
+/// config reload reaches both
+pub type SharedPersistencePolicies = Arc<RwLock<HashMap<String, SourcePersistencePolicy>>>;
+
+/// Whether completed races of `source` go to the historical store (default true)
+pub fn persists_completed(
+    policies: &HashMap<String, SourcePersistencePolicy>,
+    source: &str,
+) -> bool {
+    policies.get(source).is_none_or(|p| p.persist_completed)
 }
 
 impl Settings {
     pub fn new() -> Result<Self, ConfigError> {
+        Self::load(DEFAULT_CONFIG_FILE)
+    }
+
+    /// Defaults, then `file` (if present), then RACEBOARD_* environment variables
+    pub fn load(file: &str) -> Result<Self, ConfigError> {
         let config = Config::builder()
             // Start with default values
             .set_default("server.http_port", 7777)?
@@ -38,18 +458,113 @@ impl Settings {
             .set_default("server.grpc_port", 50051)?
             .set_default("server.grpc_host", "127.0.0.1")?
             .set_default("server.read_only", false)?
+            .set_default("server.grpc_reflection", false)?
+            .set_default("server.shutdown_timeout_secs", 5)?
+            .set_default("server.races_include_completed", false)?
+            .set_default("server.max_title_len", 1024)?
             .set_default("server.legacy_json_fallback_enabled", true)?
+            .set_default("server.legacy_json_flush_interval_secs", 5)?
             .set_default("logging.level", "info")?
-            .set_default("storage.max_races", 1000)?
+            .set_default("storage.max_races", DEFAULT_MAX_RACES as i64)?
             .set_default("storage.max_events_per_race", 100)?
+            .set_default("storage.persistence_trace", false)?
+            .set_default("storage.quarantine_corrupt", true)?
+            .set_default("storage.write_batching_enabled", true)?
+            .set_default("storage.flush_interval_ms", 500)?
+            .set_default("storage.flush_max_pending", 100)?
+            .set_default("storage.max_scan_batch", 10000)?
+            .set_default("storage.eviction_events", true)?
+            .set_default("prediction.max_clusters", 1000)?
+            .set_default("prediction.trend_adjustment_enabled", true)?
+            .set_default("prediction.trend_horizon", 1.0)?
+            .set_default("prediction.trend_max_adjustment", 0.2)?
+            .set_default("prediction.eta_lower_percentile", 25.0)?
+            .set_default("prediction.eta_upper_percentile", 75.0)?
+            .set_default("prediction.outlier_min_samples", 5)?
+            .set_default("prediction.rebuild_scan_batch_size", 10000)?
+            .set_default("prediction.centroid_title_mode", "medoid")?
+            .set_default("prediction.rebuild_parallel", true)?
+            .set_default("prediction.rollout_pilot_source", "cargo")?
+            .set_default("prediction.rollout_shadow_promotion_successes", 5)?
+            .set_default("prediction.rollout_canary_promotion_successes", 10)?
+            .set_default("prediction.rollout_promotion_success_rate", 0.95)?
+            .set_default("prediction.rollout_rollback_recovery_minutes", 60)?
+            .set_default("prediction.default_eta_sec", 30)?
+            .set_default("processing.queue_capacity", 100)?
+            .set_default("processing.workers", 4)?
+            .set_default("processing.drop_when_full", false)?
+            .set_default("processing.degraded_queue_depth", 80)?
+            .set_default("processing.stall_after_secs", 60)?
+            .set_default("processing.eta_refresh_enabled", false)?
+            .set_default("processing.eta_refresh_interval_secs", 30)?
+            .set_default("processing.eta_refresh_min_change", 0.1)?
+            .set_default("limits.enabled", false)?
+            .set_default("limits.key_by", "source")?
+            .set_default("limits.burst", 60)?
+            .set_default("limits.per_second", 10.0)?
             // Add config file if it exists
-            .add_source(File::with_name("config").required(false))
+            .add_source(File::with_name(file).required(false))
             // Add environment variables with prefix RACEBOARD_
             // e.g., RACEBOARD_SERVER__HTTP_PORT=8080
             .add_source(Environment::with_prefix("RACEBOARD").separator("__"))
             .build()?;
 
-        config.try_deserialize()
+        let settings: Settings = config.try_deserialize()?;
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    /// Reject combinations that deserialize fine but can't be served safely
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.cors.allow_credentials && self.cors.allowed_origins.iter().any(|o| o == "*") {
+            return Err(ConfigError::Message(
+                "cors.allow_credentials requires explicit cors.allowed_origins, not \"*\"".into(),
+            ));
+        }
+        if self.server.max_title_len == 0 {
+            return Err(ConfigError::Message(
+                "server.max_title_len must be at least 1".into(),
+            ));
+        }
+        let (lower, upper) = (
+            self.prediction.eta_lower_percentile,
+            self.prediction.eta_upper_percentile,
+        );
+        if !(0.0..=100.0).contains(&lower) || !(0.0..=100.0).contains(&upper) || lower > upper {
+            return Err(ConfigError::Message(format!(
+                "prediction.eta_lower_percentile ({}) must not exceed eta_upper_percentile ({}), \
+                 and both must be within [0, 100]",
+                lower, upper
+            )));
+        }
+        if self.prediction.rollout_rollback_recovery_minutes == 0 {
+            return Err(ConfigError::Message(
+                "prediction.rollout_rollback_recovery_minutes must be at least 1".into(),
+            ));
+        }
+        if let Some(source_config) = &self.sources.default_source_config {
+            source_config.validate().map_err(|e| {
+                ConfigError::Message(format!("sources.default_source_config: {}", e))
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Dotted keys (e.g. `limits.burst`) whose values differ from `other`
+    pub fn changed_keys(&self, other: &Settings) -> Vec<String> {
+        let (mut old, mut new) = (BTreeMap::new(), BTreeMap::new());
+        let (old_value, new_value) = (serde_json::to_value(self), serde_json::to_value(other));
+        flatten_json("", &old_value.unwrap_or_default(), &mut old);
+        flatten_json("", &new_value.unwrap_or_default(), &mut new);
+        let mut keys: Vec<String> = old
+            .keys()
+            .chain(new.keys())
+            .filter(|key| old.get(*key) != new.get(*key))
+            .cloned()
+            .collect();
+        keys.sort();
+        keys.dedup();
+        keys
     }
 
     pub fn http_addr(&self) -> String {
@@ -66,3 +581,235 @@ impl Default for Settings {
         Self::new().expect("Failed to load default settings")
     }
 }
+
+fn flatten_json(
+    prefix: &str,
+    value: &serde_json::Value,
+    out: &mut BTreeMap<String, serde_json::Value>,
+) {
+    match value {
+        serde_json::Value::Object(map) if !map.is_empty() => {
+            for (key, value) in map {
+                let key = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten_json(&key, value, out);
+            }
+        }
+        _ => {
+            out.insert(prefix.to_string(), value.clone());
+        }
+    }
+}
+
+/// Settings `/admin/reload-config` applies to the running server; a key matches
+/// an entry ending in `.` by prefix. Everything else needs a restart.
+pub const HOT_RELOADABLE_SETTINGS: &[&str] = &[
+    "logging.level",
+    "alerts.webhook_url",
+    "limits.",
+    "sources.persistence.",
+];
+
+pub fn is_hot_reloadable(key: &str) -> bool {
+    HOT_RELOADABLE_SETTINGS
+        .iter()
+        .any(|hot| match hot.strip_suffix('.') {
+            Some(section) => key == section || key.starts_with(hot),
+            None => key == *hot,
+        })
+}
+
+/// The settings the server is running with and the file they came from
+pub struct LiveSettings {
+    file: String,
+    current: RwLock<Settings>,
+}
+
+impl LiveSettings {
+    pub fn new(file: &str, settings: Settings) -> Self {
+        Self {
+            file: file.to_string(),
+            current: RwLock::new(settings),
+        }
+    }
+
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    pub fn current(&self) -> Settings {
+        self.current.read().unwrap().clone()
+    }
+
+    pub fn replace(&self, settings: Settings) {
+        *self.current.write().unwrap() = settings;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{http::header, test, web, App, HttpResponse};
+
+    async fn allow_origin_for(cors: &CorsConfig, origin: &str) -> Option<String> {
+        let app = test::init_service(
+            App::new()
+                .wrap(cors.middleware())
+                .route("/health", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+        let request = test::TestRequest::get()
+            .uri("/health")
+            .insert_header((header::ORIGIN, origin))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        response
+            .headers()
+            .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+            .map(|v| v.to_str().unwrap().to_string())
+    }
+
+    fn load_with(toml: &str) -> Result<Settings, ConfigError> {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let file = temp_dir.path().join("config.toml");
+        std::fs::write(&file, toml).unwrap();
+        Settings::load(file.to_str().unwrap())
+    }
+
+    #[actix_web::test]
+    async fn test_wildcard_origin_with_credentials_is_rejected() {
+        let err =
+            load_with("[cors]\nallowed_origins = [\"*\"]\nallow_credentials = true\n").unwrap_err();
+        assert!(err.to_string().contains("allow_credentials"), "{}", err);
+
+        let settings = load_with(
+            "[cors]\nallowed_origins = [\"https://dashboard.example\"]\nallow_credentials = true\n",
+        )
+        .unwrap();
+        assert!(settings.cors.allow_credentials);
+    }
+
+    #[actix_web::test]
+    async fn test_centroid_title_mode_defaults_to_medoid() {
+        use crate::rebuild::CentroidTitleMode;
+
+        let settings = load_with("").unwrap();
+        assert_eq!(
+            settings.prediction.centroid_title_mode,
+            CentroidTitleMode::Medoid
+        );
+        let settings = load_with("[prediction]\ncentroid_title_mode = \"template\"\n").unwrap();
+        assert_eq!(
+            settings.prediction.centroid_title_mode,
+            CentroidTitleMode::Template
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_zero_max_title_len_is_rejected() {
+        let err = load_with("[server]\nmax_title_len = 0\n").unwrap_err();
+        assert!(err.to_string().contains("max_title_len"), "{}", err);
+
+        let settings = load_with("[server]\nmax_title_len = 64\n").unwrap();
+        assert_eq!(settings.server.max_title_len, 64);
+    }
+
+    #[actix_web::test]
+    async fn test_inverted_eta_percentiles_are_rejected() {
+        let err =
+            load_with("[prediction]\neta_lower_percentile = 90.0\neta_upper_percentile = 10.0\n")
+                .unwrap_err();
+        assert!(err.to_string().contains("eta_lower_percentile"), "{}", err);
+        assert!(load_with("[prediction]\neta_upper_percentile = 140.0\n").is_err());
+
+        let settings =
+            load_with("[prediction]\neta_lower_percentile = 10.0\neta_upper_percentile = 95.0\n")
+                .unwrap();
+        assert_eq!(settings.prediction.eta_upper_percentile, 95.0);
+    }
+
+    #[actix_web::test]
+    async fn test_zero_rollback_recovery_is_rejected() {
+        let err = load_with("[prediction]\nrollout_rollback_recovery_minutes = 0\n").unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("rollout_rollback_recovery_minutes"),
+            "{}",
+            err
+        );
+
+        let settings = load_with("[prediction]\nrollout_rollback_recovery_minutes = 5\n").unwrap();
+        assert_eq!(settings.prediction.rollout_rollback_recovery_minutes, 5);
+    }
+
+    #[actix_web::test]
+    async fn test_invalid_default_source_config_is_rejected() {
+        let err = load_with(
+            "[sources.default_source_config]\nstats_window = 4\nmin_samples_for_prediction = 10\n",
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("default_source_config"), "{}", err);
+
+        let settings =
+            load_with("[sources.default_source_config]\nmin_samples_for_prediction = 3\n").unwrap();
+        let source_config = settings.sources.default_source_config.unwrap();
+        assert_eq!(source_config.min_samples_for_prediction, 3);
+    }
+
+    #[actix_web::test]
+    async fn test_cors_default_is_wildcard() {
+        let cors = CorsConfig::default();
+        assert_eq!(
+            allow_origin_for(&cors, "https://any.example").await.as_deref(),
+            Some("*")
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_cors_disallowed_origin_gets_no_allow_origin_header() {
+        let cors = CorsConfig {
+            allowed_origins: vec!["https://dashboard.example".to_string()],
+            allow_credentials: true,
+            ..CorsConfig::default()
+        };
+        assert_eq!(
+            allow_origin_for(&cors, "https://dashboard.example").await.as_deref(),
+            Some("https://dashboard.example")
+        );
+        assert_eq!(allow_origin_for(&cors, "https://evil.example").await, None);
+    }
+
+    #[actix_web::test]
+    async fn test_cors_default_allows_token_headers_in_preflight() {
+        let app = test::init_service(
+            App::new()
+                .wrap(CorsConfig::default().middleware())
+                .route("/race/{id}", web::patch().to(HttpResponse::Ok)),
+        )
+        .await;
+        let request = test::TestRequest::default()
+            .method(actix_web::http::Method::OPTIONS)
+            .uri("/race/r1")
+            .insert_header((header::ORIGIN, "https://dashboard.example"))
+            .insert_header((header::ACCESS_CONTROL_REQUEST_METHOD, "PATCH"))
+            .insert_header((
+                header::ACCESS_CONTROL_REQUEST_HEADERS,
+                "content-type, x-raceboard-token, x-raceboard-admin-token",
+            ))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert!(response.status().is_success());
+        let allowed = response
+            .headers()
+            .get(header::ACCESS_CONTROL_ALLOW_HEADERS)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_ascii_lowercase();
+        assert!(allowed.contains("x-raceboard-token"));
+        assert!(allowed.contains("x-raceboard-admin-token"));
+    }
+}
@@ -1,13 +1,26 @@
 use crate::adapter_status::AdapterRegistry;
+use crate::app_state::{write_token_matches, WRITE_TOKEN_HEADER};
+use crate::config::SharedPersistencePolicies;
+use crate::monitoring::MonitoringSystem;
 use crate::persistence::PersistenceLayer;
-use crate::storage::{Storage, StorageEvent};
+use crate::storage::{CreateOutcome, Storage, StorageEvent};
+use crate::webhook::WebhookDispatcher;
+use std::collections::HashMap;
 use std::sync::Arc;
 use tonic::{Request, Response, Status};
+use tonic_health::server::HealthReporter;
+use tonic_health::ServingStatus;
 
 pub mod raceboard {
     tonic::include_proto!("raceboard");
 }
 
+/// Encoded descriptors for `raceboard.proto`, served by gRPC reflection when enabled
+pub const FILE_DESCRIPTOR_SET: &[u8] = tonic::include_file_descriptor_set!("raceboard_descriptor");
+
+/// How often the gRPC health status is re-derived from storage health
+const HEALTH_REPORT_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
 use raceboard::race_service_server::RaceService;
 use raceboard::{
     race_update, AddEventRequest, DeleteRaceRequest, Event as ProtoEvent, GetRaceRequest,
@@ -21,6 +34,11 @@ pub struct RaceServiceImpl {
     persistence: Arc<PersistenceLayer>,
     adapter_registry: Arc<AdapterRegistry>,
     read_only: bool,
+    write_token: Option<String>,
+    source_aliases: HashMap<String, String>,
+    max_title_len: usize,
+    source_persistence: SharedPersistencePolicies,
+    race_webhooks: Option<Arc<WebhookDispatcher>>,
 }
 
 impl RaceServiceImpl {
@@ -29,14 +47,68 @@ impl RaceServiceImpl {
         persistence: Arc<PersistenceLayer>,
         adapter_registry: Arc<AdapterRegistry>,
         read_only: bool,
+        source_aliases: HashMap<String, String>,
     ) -> Self {
         Self {
             storage,
             persistence,
             adapter_registry,
             read_only,
+            write_token: None,
+            source_aliases,
+            max_title_len: usize::MAX,
+            source_persistence: SharedPersistencePolicies::default(),
+            race_webhooks: None,
         }
     }
+
+    /// Let requests carrying this token in `x-raceboard-token` metadata write while read-only
+    pub fn with_write_token(mut self, write_token: Option<String>) -> Self {
+        self.write_token = write_token;
+        self
+    }
+
+    /// Truncate longer race titles in CreateRace/UpdateRace (unbounded by default)
+    pub fn with_max_title_len(mut self, max_title_len: usize) -> Self {
+        self.max_title_len = max_title_len;
+        self
+    }
+
+    /// Per-source opt-outs from storing completed races in history
+    pub fn with_source_persistence(
+        mut self,
+        source_persistence: SharedPersistencePolicies,
+    ) -> Self {
+        self.source_persistence = source_persistence;
+        self
+    }
+
+    /// Announce races completed through UpdateRace, as the REST endpoint does
+    pub fn with_webhooks(mut self, race_webhooks: Option<Arc<WebhookDispatcher>>) -> Self {
+        self.race_webhooks = race_webhooks;
+        self
+    }
+
+    fn persists_completed(&self, race: &crate::models::Race) -> bool {
+        race.state.is_terminal()
+            && crate::config::persists_completed(
+                &self.source_persistence.read().unwrap(),
+                &race.source,
+            )
+    }
+
+    /// False in read-only mode unless the request carries the write token
+    fn is_writable<T>(&self, request: &Request<T>) -> bool {
+        let token = request
+            .metadata()
+            .get(WRITE_TOKEN_HEADER)
+            .and_then(|v| v.to_str().ok());
+        !self.read_only || write_token_matches(self.write_token.as_deref(), token)
+    }
+}
+
+fn read_only_status() -> Status {
+    Status::permission_denied("Server is in read-only mode")
 }
 
 // Convert our internal Race to proto Race
@@ -83,7 +155,7 @@ fn race_to_proto(race: &crate::models::Race) -> ProtoRace {
             seconds: dt.timestamp(),
             nanos: dt.timestamp_subsec_nanos() as i32,
         }),
-        eta_source: race.eta_source.map(|v| v as i32),
+        eta_source: race.eta_source.map(i32::from),
         eta_confidence: race.eta_confidence,
         update_interval_hint: race.update_interval_hint,
         eta_history: race
@@ -97,7 +169,7 @@ fn race_to_proto(race: &crate::models::Race) -> ProtoRace {
                             seconds: rev.timestamp.timestamp(),
                             nanos: rev.timestamp.timestamp_subsec_nanos() as i32,
                         }),
-                        source: rev.source,
+                        source: rev.source.into(),
                         confidence: rev.confidence,
                     })
                     .collect()
@@ -143,6 +215,7 @@ fn proto_to_race(proto: ProtoRace) -> crate::models::Race {
                 .into_iter()
                 .map(|e| crate::models::Event {
                     event_type: e.r#type,
+                    kind: crate::models::EventKind::default(),
                     data: e.data.map(|s| serde_json::Value::String(s)),
                     timestamp: e
                         .timestamp
@@ -160,7 +233,7 @@ fn proto_to_race(proto: ProtoRace) -> crate::models::Race {
         last_eta_update: proto.last_eta_update.and_then(|ts| {
             chrono::DateTime::from_timestamp(ts.seconds, ts.nanos as u32)
         }),
-        eta_source: proto.eta_source,
+        eta_source: proto.eta_source.map(crate::models::EtaSource::from),
         eta_confidence: proto.eta_confidence,
         update_interval_hint: proto.update_interval_hint,
         completed_at: None,
@@ -179,7 +252,7 @@ fn proto_to_race(proto: ProtoRace) -> crate::models::Race {
                         ts.map(|timestamp| crate::models::EtaRevision {
                             eta_sec: rev.eta_sec,
                             timestamp,
-                            source: rev.source,
+                            source: crate::models::EtaSource::from(rev.source),
                             confidence: rev.confidence,
                         })
                     })
@@ -215,10 +288,12 @@ impl RaceService for RaceServiceImpl {
                     StorageEvent::Created(race) => RaceUpdate {
                         r#type: race_update::UpdateType::Created as i32,
                         race: Some(race_to_proto(&race)),
+                        ..Default::default()
                     },
                     StorageEvent::Updated(race) => RaceUpdate {
                         r#type: race_update::UpdateType::Updated as i32,
                         race: Some(race_to_proto(&race)),
+                        ..Default::default()
                     },
                     StorageEvent::Deleted(id) => RaceUpdate {
                         r#type: race_update::UpdateType::Deleted as i32,
@@ -226,6 +301,15 @@ impl RaceService for RaceServiceImpl {
                             id,
                             ..Default::default()
                         }),
+                        ..Default::default()
+                    },
+                    StorageEvent::Evicted { id, reason } => RaceUpdate {
+                        r#type: race_update::UpdateType::Evicted as i32,
+                        race: Some(ProtoRace {
+                            id,
+                            ..Default::default()
+                        }),
+                        eviction_reason: reason.as_str().to_string(),
                     },
                 };
                 
@@ -259,10 +343,14 @@ impl RaceService for RaceServiceImpl {
     }
 
     async fn create_race(&self, request: Request<ProtoRace>) -> Result<Response<ProtoRace>, Status> {
-        if self.read_only {
-            return Err(Status::permission_denied("Server is in read-only mode"));
+        if !self.is_writable(&request) {
+            return Err(read_only_status());
         }
-        let proto_race = request.into_inner();
+        let mut proto_race = request.into_inner();
+        proto_race.source =
+            crate::models::normalize_source(&proto_race.source, &self.source_aliases);
+        let origin = format!("new race from {}", proto_race.source);
+        crate::models::truncate_title(&mut proto_race.title, self.max_title_len, &origin);
         let race = proto_to_race(proto_race);
         
         // Reject adapter registrations - use REST adapter endpoints instead
@@ -272,20 +360,25 @@ impl RaceService for RaceServiceImpl {
             ));
         }
         
-        // Store the race
-        self.storage.create_or_update_race(race.clone()).await;
-        
+        // Store the race; a replayed create for a completed race returns the
+        // stored race untouched and is flagged via response metadata
+        let race = match self.storage.create_race_idempotent(race).await {
+            CreateOutcome::Stored(race) => race,
+            CreateOutcome::AlreadyCompleted(existing) => {
+                let mut response = Response::new(race_to_proto(&existing));
+                response
+                    .metadata_mut()
+                    .insert("x-raceboard-existing", "1".parse().unwrap());
+                return Ok(response);
+            }
+        };
+
         // Persist if this is a completed race
-        if matches!(
-            race.state,
-            crate::models::RaceState::Passed
-                | crate::models::RaceState::Failed
-                | crate::models::RaceState::Canceled
-        ) {
+        if self.persists_completed(&race) {
             use crate::persistence::RaceStore;
             let _ = self.persistence.store_race(&race).await;
         }
-        
+
         Ok(Response::new(race_to_proto(&race)))
     }
 
@@ -293,15 +386,20 @@ impl RaceService for RaceServiceImpl {
         &self,
         request: Request<UpdateRaceRequest>,
     ) -> Result<Response<ProtoRace>, Status> {
-        if self.read_only {
-            return Err(Status::permission_denied("Server is in read-only mode"));
+        if !self.is_writable(&request) {
+            return Err(read_only_status());
         }
-        let update_req = request.into_inner();
+        let mut update_req = request.into_inner();
         let id = update_req.id.clone();
+        if let Some(ref mut title) = update_req.title {
+            crate::models::truncate_title(title, self.max_title_len, &format!("race {}", id));
+        }
         
         // Build update from request
         let update = crate::models::RaceUpdate {
-            source: update_req.source,
+            source: update_req
+                .source
+                .map(|s| crate::models::normalize_source(&s, &self.source_aliases)),
             title: update_req.title,
             state: update_req.state.map(|s| match s {
                 x if x == ProtoRaceState::Queued as i32 => crate::models::RaceState::Queued,
@@ -322,7 +420,7 @@ impl RaceService for RaceServiceImpl {
             } else {
                 Some(update_req.metadata)
             },
-            eta_source: update_req.eta_source,
+            eta_source: update_req.eta_source.map(crate::models::EtaSource::from),
             eta_confidence: update_req.eta_confidence,
             update_interval_hint: update_req.update_interval_hint,
         };
@@ -331,18 +429,19 @@ impl RaceService for RaceServiceImpl {
         // NOTE: Adapter health/registration must NOT be inferred from race updates.
         // Adapter lifecycle is handled via dedicated RPCs (RegisterAdapter, ReportAdapterHealth, DeregisterAdapter)
         // or REST endpoints; race updates are unrelated.
-        match self.storage.update_race(&id, update).await {
-            Some(race) => {
+        match self.storage.update_race_with_previous(&id, update).await {
+            Some((previous_state, race)) => {
                 // Persist if this is now completed
-                if matches!(
-                    race.state,
-                    crate::models::RaceState::Passed
-                        | crate::models::RaceState::Failed
-                        | crate::models::RaceState::Canceled
-                ) {
+                if self.persists_completed(&race) {
                     use crate::persistence::RaceStore;
                     let _ = self.persistence.store_race(&race).await;
                 }
+                // Only the transition into a terminal state is announced, not repeats
+                if race.state.is_terminal() && !previous_state.is_terminal() {
+                    if let Some(webhooks) = &self.race_webhooks {
+                        webhooks.notify_completed(&race);
+                    }
+                }
                 
                 Ok(Response::new(race_to_proto(&race)))
             }
@@ -354,8 +453,8 @@ impl RaceService for RaceServiceImpl {
         &self,
         request: Request<AddEventRequest>,
     ) -> Result<Response<ProtoRace>, Status> {
-        if self.read_only {
-            return Err(Status::permission_denied("Server is in read-only mode"));
+        if !self.is_writable(&request) {
+            return Err(read_only_status());
         }
         let req = request.into_inner();
         let race_id = req.race_id;
@@ -363,6 +462,7 @@ impl RaceService for RaceServiceImpl {
         
         let internal_event = crate::models::Event {
             event_type: event.r#type,
+            kind: crate::models::EventKind::default(),
             data: event.data.map(|s| serde_json::Value::String(s)),
             timestamp: event
                 .timestamp
@@ -397,8 +497,8 @@ impl RaceService for RaceServiceImpl {
         &self,
         request: Request<DeleteRaceRequest>,
     ) -> Result<Response<()>, Status> {
-        if self.read_only {
-            return Err(Status::permission_denied("Server is in read-only mode"));
+        if !self.is_writable(&request) {
+            return Err(read_only_status());
         }
         let id = request.into_inner().id;
 
@@ -521,4 +621,35 @@ impl RaceService for RaceServiceImpl {
         Ok(Response::new(system_status))
     }
     
-}
\ No newline at end of file
+}
+
+/// Set the status for both the whole server (`""`) and `raceboard.RaceService`
+pub async fn set_health_status(reporter: &mut HealthReporter, status: ServingStatus) {
+    use tonic::server::NamedService;
+    reporter.set_service_status("", status).await;
+    reporter
+        .set_service_status(
+            <raceboard::race_service_server::RaceServiceServer<RaceServiceImpl>>::NAME,
+            status,
+        )
+        .await;
+}
+
+/// Mirror `GET /health`: serving unless storage reports critical errors
+pub async fn report_health(reporter: &mut HealthReporter, monitoring: &MonitoringSystem) {
+    let status = if monitoring.get_health().await.critical_errors.is_empty() {
+        ServingStatus::Serving
+    } else {
+        ServingStatus::NotServing
+    };
+    set_health_status(reporter, status).await;
+}
+
+/// Keep the gRPC health service in step with storage health until aborted at shutdown
+pub async fn run_health_reporter(mut reporter: HealthReporter, monitoring: Arc<MonitoringSystem>) {
+    let mut interval = tokio::time::interval(HEALTH_REPORT_INTERVAL);
+    loop {
+        interval.tick().await;
+        report_health(&mut reporter, &monitoring).await;
+    }
+}
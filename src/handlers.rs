@@ -1,37 +1,179 @@
-use crate::phased_rollout::RolloutMode;
+use crate::phased_rollout::{ManualChange, PhaseTransition, RolloutMode};
 use crate::{
-    app_state::AppState,
-    models::{Event, Race, RaceState, RaceUpdate},
+    app_state::{AppState, ADMIN_TOKEN_HEADER, WRITE_TOKEN_HEADER},
+    config::SourceEtaPolicy,
+    error::ApiError,
+    models::{EtaSource, Event, EventKind, Race, RaceState, RaceUpdate},
     processing::RaceProcessingRequest,
+    stats::PredictionMethod,
+    storage::CreateOutcome,
     adapter_status::{AdapterRegistration, AdapterType, AdapterMetrics},
 };
 use actix_web::http::StatusCode;
-use actix_web::{web, HttpResponse, Result};
+use actix_web::{web, HttpRequest, HttpResponse, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 
-pub async fn get_races(data: web::Data<AppState>) -> Result<HttpResponse> {
-    let races = data.storage.get_all_races().await;
+/// 503 for writes while read-only, unless the request carries the write token
+fn read_only_rejection(data: &AppState, req: &HttpRequest) -> Option<HttpResponse> {
+    let token = req
+        .headers()
+        .get(WRITE_TOKEN_HEADER)
+        .and_then(|v| v.to_str().ok());
+    if data.allows_write(token) {
+        return None;
+    }
+    let mut response = ApiError::read_only().response();
+    response.headers_mut().insert(
+        actix_web::http::header::HeaderName::from_static("x-raceboard-read-only"),
+        actix_web::http::header::HeaderValue::from_static("1"),
+    );
+    Some(response)
+}
+
+/// How race durations are rendered; seconds are always included
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DurationFormat {
+    #[default]
+    Seconds,
+    /// Also render `eta_sec`/`duration_sec` as ISO-8601 (`eta_iso8601`, `duration_iso8601`)
+    Iso8601,
+}
+
+#[derive(Deserialize)]
+pub struct DurationFormatQuery {
+    #[serde(default)]
+    pub duration_format: DurationFormat,
+}
+
+/// ISO-8601 duration for whole seconds, e.g. 200 -> `PT3M20S`
+pub fn iso8601_duration(seconds: i64) -> String {
+    let sign = if seconds < 0 { "-" } else { "" };
+    let total = seconds.unsigned_abs();
+    let (hours, minutes, secs) = (total / 3600, total / 60 % 60, total % 60);
+    let mut out = format!("{}PT", sign);
+    if hours > 0 {
+        out.push_str(&format!("{}H", hours));
+    }
+    if minutes > 0 {
+        out.push_str(&format!("{}M", minutes));
+    }
+    if secs > 0 || total == 0 {
+        out.push_str(&format!("{}S", secs));
+    }
+    out
+}
+
+/// Race JSON with the ISO-8601 duration fields added when requested
+fn race_json(race: &Race, format: DurationFormat) -> serde_json::Value {
+    let mut value = json!(race);
+    if format == DurationFormat::Iso8601 {
+        if let Some(eta) = race.eta_sec {
+            value["eta_iso8601"] = json!(iso8601_duration(eta));
+        }
+        if let Some(duration) = race.duration_sec {
+            value["duration_iso8601"] = json!(iso8601_duration(duration));
+        }
+    }
+    value
+}
+
+/// 403 while no admin token is configured, 401 unless the request carries it
+fn admin_rejection(data: &AppState, req: &HttpRequest) -> Option<HttpResponse> {
+    if data.admin_token.as_deref().is_none_or(str::is_empty) {
+        return Some(
+            ApiError::new(
+                StatusCode::FORBIDDEN,
+                "admin_disabled",
+                "Set server.admin_token to enable admin endpoints",
+            )
+            .response(),
+        );
+    }
+    let token = req
+        .headers()
+        .get(ADMIN_TOKEN_HEADER)
+        .and_then(|v| v.to_str().ok());
+    if data.is_admin(token) {
+        return None;
+    }
+    Some(
+        ApiError::new(
+            StatusCode::UNAUTHORIZED,
+            "unauthorized",
+            format!("Missing or invalid {} header", ADMIN_TOKEN_HEADER),
+        )
+        .response(),
+    )
+}
+
+fn race_not_found(id: &str) -> HttpResponse {
+    ApiError::not_found(format!("Race {} not found", id))
+        .with_details(json!({ "id": id }))
+        .response()
+}
+
+fn cluster_not_found(cluster_id: &str) -> HttpResponse {
+    ApiError::not_found(format!("Cluster {} not found", cluster_id))
+        .with_details(json!({ "cluster_id": cluster_id }))
+        .response()
+}
+
+#[derive(Deserialize)]
+pub struct RacesQuery {
+    /// Defaults to `server.races_include_completed`
+    pub include_completed: Option<bool>,
+    #[serde(default)]
+    pub duration_format: DurationFormat,
+}
+
+pub async fn get_races(
+    query: web::Query<RacesQuery>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let include_completed = query
+        .include_completed
+        .unwrap_or(data.races_include_completed);
+    let mut races = data.storage.get_all_races().await;
+    if !include_completed {
+        races.retain(|race| !race.state.is_terminal());
+    }
+    if query.duration_format == DurationFormat::Seconds {
+        return Ok(HttpResponse::Ok().json(races));
+    }
+    let races: Vec<_> = races
+        .iter()
+        .map(|race| race_json(race, query.duration_format))
+        .collect();
     Ok(HttpResponse::Ok().json(races))
 }
 
-pub async fn create_race(race: web::Json<Race>, data: web::Data<AppState>) -> Result<HttpResponse> {
-    if data.read_only {
-        return Ok(HttpResponse::ServiceUnavailable()
-            .insert_header(("X-Raceboard-Read-Only", "1"))
-            .json(json!({"error":"read_only","message":"Server is in read-only mode"})));
+pub async fn create_race(
+    req: HttpRequest,
+    race: web::Json<Race>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    if let Some(response) = read_only_rejection(&data, &req) {
+        return Ok(response);
     }
     let mut race = race.into_inner();
 
     // Reject adapter registrations via race endpoint — use dedicated adapter endpoints
     if crate::models::is_adapter_id(&race.id) {
-        return Ok(HttpResponse::BadRequest().json(json!({
-            "error": "invalid_operation",
-            "message": "Adapter registration must use REST /adapter/register and /adapter/health endpoints, not /race"
-        })));
+        return Ok(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "invalid_operation",
+            "Adapter registration must use REST /adapter/register and /adapter/health endpoints, not /race",
+        )
+        .response());
     }
 
+    race.source = crate::models::normalize_source(&race.source, &data.source_aliases);
+    let origin = format!("new race from {}", race.source);
+    crate::models::truncate_title(&mut race.title, data.max_title_len, &origin);
+
     // Centralized inference for ETA fields
     race.infer_eta_source();
 
@@ -44,52 +186,135 @@ pub async fn create_race(race: web::Json<Race>, data: web::Data<AppState>) -> Re
             .await;
 
         race.eta_sec = Some(eta_prediction.expected_seconds);
-        race.eta_source = Some(3); // EtaSource::Cluster
-        race.eta_confidence = Some(0.7); // Cluster predictions have 70% confidence
+        if matches!(
+            eta_prediction.method,
+            PredictionMethod::Bootstrap | PredictionMethod::GlobalDefault
+        ) {
+            // No usable cluster/source history: this is the cold-start default
+            race.eta_source = Some(EtaSource::Bootstrap);
+            race.eta_confidence = Some(eta_prediction.confidence);
+        } else {
+            race.eta_source = Some(EtaSource::Cluster);
+            race.eta_confidence = Some(0.7); // Cluster predictions have 70% confidence
+        }
+    } else if let Some(policy) = data
+        .source_eta_policies
+        .get(&race.source)
+        .filter(|p| p.prefer_cluster_eta)
+    {
+        override_adapter_eta(&mut race, policy, &data).await;
     }
 
     race.infer_eta_confidence();
     race.infer_update_interval_hint();
 
-    // Store the race in memory (UI/gRPC hot path only; no persistence at creation)
-    let race = data.storage.create_or_update_race(race).await;
+    // Store the race in memory (UI/gRPC hot path only; no persistence at creation).
+    // Replayed creates for completed races must not clobber their final stats.
+    match data.storage.create_race_idempotent(race).await {
+        CreateOutcome::Stored(race) => Ok(HttpResponse::Ok().json(race)),
+        CreateOutcome::AlreadyCompleted(existing) => Ok(HttpResponse::Conflict()
+            .insert_header(("X-Raceboard-Existing", "1"))
+            .json(existing)),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct PredictRequest {
+    pub source: String,
+    pub title: String,
+    #[serde(default)]
+    pub metadata: std::collections::HashMap<String, String>,
+}
+
+/// ETA the server would assign a race with this source/title/metadata, without
+/// creating it; lets adapters seed their own estimates from cluster history
+pub async fn predict(
+    body: web::Json<PredictRequest>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let mut body = body.into_inner();
+    let source = crate::models::normalize_source(&body.source, &data.source_aliases);
+    crate::models::truncate_title(&mut body.title, data.max_title_len, "prediction request");
+    let prediction = data
+        .prediction_engine
+        .predict_eta("", &body.title, &source, &body.metadata)
+        .await;
+    Ok(HttpResponse::Ok().json(prediction))
+}
+
+/// Swap an adapter ETA for the cluster prediction when the source is known to
+/// report bad estimates; the adapter value is kept in `adapter_eta_sec`. Only
+/// a cluster match qualifies, not a source average or a default.
+async fn override_adapter_eta(race: &mut Race, policy: &SourceEtaPolicy, data: &AppState) {
+    if race.eta_source != Some(EtaSource::Adapter) {
+        return;
+    }
+    let adapter_confidence = race
+        .eta_confidence
+        .unwrap_or_else(|| EtaSource::Adapter.default_confidence());
+    let metadata = race.metadata.clone().unwrap_or_default();
+    let prediction = data
+        .prediction_engine
+        .predict_eta(&race.id, &race.title, &race.source, &metadata)
+        .await;
+    if prediction.method != PredictionMethod::Cluster
+        || prediction.confidence < policy.min_cluster_confidence
+        || prediction.confidence <= adapter_confidence
+    {
+        return;
+    }
 
-    Ok(HttpResponse::Ok().json(race))
+    if let Some(adapter_eta) = race.eta_sec {
+        race.metadata
+            .get_or_insert_with(Default::default)
+            .insert("adapter_eta_sec".to_string(), adapter_eta.to_string());
+    }
+    race.eta_sec = Some(prediction.expected_seconds);
+    race.eta_source = Some(EtaSource::Cluster);
+    race.eta_confidence = Some(prediction.confidence);
 }
 
-pub async fn get_race(path: web::Path<String>, data: web::Data<AppState>) -> Result<HttpResponse> {
+pub async fn get_race(
+    path: web::Path<String>,
+    query: web::Query<DurationFormatQuery>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
     let id = path.into_inner();
 
     match data.storage.get_race(&id).await {
-        Some(race) => Ok(HttpResponse::Ok().json(race)),
-        None => Ok(HttpResponse::NotFound().json(json!({
-            "error": "Race not found",
-            "id": id
-        }))),
+        Some(race) => Ok(HttpResponse::Ok().json(race_json(&race, query.duration_format))),
+        None => Ok(race_not_found(&id)),
     }
 }
 
 pub async fn update_race(
+    req: HttpRequest,
     path: web::Path<String>,
     race_update: web::Json<RaceUpdate>,
     data: web::Data<AppState>,
 ) -> Result<HttpResponse> {
-    if data.read_only {
-        return Ok(HttpResponse::ServiceUnavailable()
-            .insert_header(("X-Raceboard-Read-Only", "1"))
-            .json(json!({"error":"read_only","message":"Server is in read-only mode"})));
+    if let Some(response) = read_only_rejection(&data, &req) {
+        return Ok(response);
     }
     let id = path.into_inner();
     
     // Reject adapter race updates — use dedicated REST adapter endpoints instead
     if crate::models::is_adapter_id(&id) {
-        return Ok(HttpResponse::BadRequest().json(json!({
-            "error": "invalid_operation",
-            "message": "Adapter registration/health must use REST /adapter/register and /adapter/health endpoints, not /race/{id}"
-        })));
+        return Ok(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "invalid_operation",
+            "Adapter registration/health must use REST /adapter/register and /adapter/health endpoints, not /race/{id}",
+        )
+        .response());
     }
     
-    let update = race_update.into_inner();
+    let mut update = race_update.into_inner();
+    if let Some(ref source) = update.source {
+        update.source = Some(crate::models::normalize_source(source, &data.source_aliases));
+    }
+    if let Some(ref mut title) = update.title {
+        crate::models::truncate_title(title, data.max_title_len, &format!("race {}", id));
+    }
 
     // If race is completing, calculate duration and update stats
     if let Some(ref state) = update.state {
@@ -119,10 +344,21 @@ pub async fn update_race(
     }
 
 
-    match data.storage.update_race(&id, update).await {
-        Some(race) => {
-            // Persist completed races only (historical store)
-            if matches!(
+    match data.storage.update_race_with_previous(&id, update).await {
+        Some((previous_state, race)) => {
+            // Persist completed races only (historical store), unless the source opted out
+            if race.state.is_terminal()
+                && !crate::config::persists_completed(
+                    &data.source_persistence.read().unwrap(),
+                    &race.source,
+                )
+            {
+                log::debug!(
+                    "Not persisting completed race {}: history disabled for source {}",
+                    race.id,
+                    race.source
+                );
+            } else if matches!(
                 race.state,
                 RaceState::Passed | RaceState::Failed | RaceState::Canceled
             ) {
@@ -138,72 +374,32 @@ pub async fn update_race(
                 } else {
                     log::warn!("HANDLER: Successfully persisted race {} to sled", race.id);
                 }
-                // Transitional: legacy JSON fallback (gated by config)
-                if data.legacy_json_fallback_enabled {
-                    log::warn!("Legacy JSON fallback enabled: writing ~/.raceboard/races.json (see server.legacy_json_fallback_enabled)");
-                    let mut path = dirs::home_dir().unwrap_or_else(|| std::path::PathBuf::from("."));
-                    path.push(".raceboard");
-                    let _ = std::fs::create_dir_all(&path);
-                    path.push("races.json");
-                    let mut races: Vec<Race> = if path.exists() {
-                        std::fs::read_to_string(&path)
-                            .ok()
-                            .and_then(|s| serde_json::from_str(&s).ok())
-                            .unwrap_or_default()
-                    } else {
-                        Vec::new()
-                    };
-                    if let Some(existing) = races.iter_mut().find(|r| r.id == race.id) {
-                        *existing = race.clone();
-                    } else {
-                        races.push(race.clone());
-                    }
-                    if let Ok(json) = serde_json::to_string_pretty(&races) {
-                        let _ = std::fs::write(&path, json);
-                    }
-                } else {
-                    // When disabled, write a backup file for operators (best-effort)
-                    let mut path = dirs::home_dir().unwrap_or_else(|| std::path::PathBuf::from("."));
-                    path.push(".raceboard");
-                    let _ = std::fs::create_dir_all(&path);
-                    path.push("races.json.bak");
-                    let mut races: Vec<Race> = if path.exists() {
-                        std::fs::read_to_string(&path)
-                            .ok()
-                            .and_then(|s| serde_json::from_str(&s).ok())
-                            .unwrap_or_default()
-                    } else {
-                        Vec::new()
-                    };
-                    if let Some(existing) = races.iter_mut().find(|r| r.id == race.id) {
-                        *existing = race.clone();
-                    } else {
-                        races.push(race.clone());
-                    }
-                    if let Ok(json) = serde_json::to_string_pretty(&races) {
-                        let _ = std::fs::write(&path, json);
-                        log::info!("Wrote backup to ~/.raceboard/races.json.bak (legacy JSON fallback disabled)");
-                    }
+                // Transitional: legacy JSON fallback (gated by config). The writer
+                // coalesces completions and rewrites races.json in the background.
+                if let Some(writer) = &data.legacy_json_writer {
+                    writer.enqueue(race.clone());
+                }
+            }
+            // Only the transition into a terminal state is announced, not repeats
+            if race.state.is_terminal() && !previous_state.is_terminal() {
+                if let Some(webhooks) = &data.race_webhooks {
+                    webhooks.notify_completed(&race);
                 }
             }
             Ok(HttpResponse::Ok().json(race))
         }
-        None => Ok(HttpResponse::NotFound().json(json!({
-            "error": "Race not found",
-            "id": id
-        }))),
+        None => Ok(race_not_found(&id)),
     }
 }
 
 pub async fn add_event(
+    req: HttpRequest,
     path: web::Path<String>,
     event: web::Json<Event>,
     data: web::Data<AppState>,
 ) -> Result<HttpResponse> {
-    if data.read_only {
-        return Ok(HttpResponse::ServiceUnavailable()
-            .insert_header(("X-Raceboard-Read-Only", "1"))
-            .json(json!({"error":"read_only","message":"Server is in read-only mode"})));
+    if let Some(response) = read_only_rejection(&data, &req) {
+        return Ok(response);
     }
     let id = path.into_inner();
 
@@ -216,21 +412,118 @@ pub async fn add_event(
             // Events are part of hot path; do not persist here
             Ok(HttpResponse::Ok().json(race))
         }
-        None => Ok(HttpResponse::NotFound().json(json!({
-            "error": "Race not found",
-            "id": id
+        None => Ok(race_not_found(&id)),
+    }
+}
+
+/// Active races live in memory; completed ones may only remain in persistence
+async fn find_race(data: &AppState, id: &str) -> Option<Race> {
+    match data.storage.get_race(id).await {
+        Some(race) => Some(race),
+        None => data.persistence.get_race(id).unwrap_or_else(|e| {
+            log::error!("Failed to load race {} from persistence: {}", id, e);
+            None
+        }),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RaceEventsQuery {
+    pub limit: Option<usize>,
+    pub cursor: Option<String>,
+    pub kind: Option<EventKind>,
+}
+
+pub async fn get_race_events(
+    path: web::Path<String>,
+    query: web::Query<RaceEventsQuery>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let id = path.into_inner();
+    let limit = query.limit.unwrap_or(100).min(1000);
+
+    let Some(race) = find_race(&data, &id).await else {
+        return Ok(race_not_found(&id));
+    };
+
+    // Newest first; events with equal timestamps keep reverse insertion order
+    let mut events = race.events.unwrap_or_default();
+    if let Some(kind) = query.kind {
+        events.retain(|e| e.kind == kind);
+    }
+    events.reverse();
+    events.sort_by_key(|e| std::cmp::Reverse(e.timestamp));
+
+    match paginate(&events, limit, query.cursor.as_deref()) {
+        Ok((items, next_cursor)) => Ok(HttpResponse::Ok().json(json!({
+            "race_id": id,
+            "items": items,
+            "next_cursor": next_cursor,
+            "total": events.len(),
+        }))),
+        Err(message) => Ok(ApiError::invalid_cursor(message).response()),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RaceHistoryQuery {
+    pub limit: Option<usize>,
+    pub cursor: Option<String>,
+}
+
+/// Events and ETA revisions merged into one oldest-first timeline. Entries carry
+/// a `type` of `event` or `eta_revision` next to the record itself.
+pub async fn get_race_history(
+    path: web::Path<String>,
+    query: web::Query<RaceHistoryQuery>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let id = path.into_inner();
+    let limit = query.limit.unwrap_or(100).min(1000);
+
+    let Some(race) = find_race(&data, &id).await else {
+        return Ok(race_not_found(&id));
+    };
+
+    let mut timeline: Vec<(DateTime<Utc>, serde_json::Value)> = race
+        .events
+        .unwrap_or_default()
+        .into_iter()
+        .map(|event| {
+            (
+                event.timestamp,
+                json!({"type": "event", "timestamp": event.timestamp, "event": event}),
+            )
+        })
+        .chain(race.eta_history.unwrap_or_default().into_iter().map(|revision| {
+            (
+                revision.timestamp,
+                json!({"type": "eta_revision", "timestamp": revision.timestamp, "eta_revision": revision}),
+            )
+        }))
+        .collect();
+    // Stable: on equal timestamps events stay ahead of the revision they caused
+    timeline.sort_by_key(|(timestamp, _)| *timestamp);
+    let entries: Vec<serde_json::Value> = timeline.into_iter().map(|(_, entry)| entry).collect();
+
+    match paginate(&entries, limit, query.cursor.as_deref()) {
+        Ok((items, next_cursor)) => Ok(HttpResponse::Ok().json(json!({
+            "race_id": id,
+            "items": items,
+            "next_cursor": next_cursor,
+            "total": entries.len(),
         }))),
+        Err(message) => Ok(ApiError::invalid_cursor(message).response()),
     }
 }
 
 pub async fn delete_race(
+    req: HttpRequest,
     path: web::Path<String>,
     data: web::Data<AppState>,
 ) -> Result<HttpResponse> {
-    if data.read_only {
-        return Ok(HttpResponse::ServiceUnavailable()
-            .insert_header(("X-Raceboard-Read-Only", "1"))
-            .json(json!({"error":"read_only","message":"Server is in read-only mode"})));
+    if let Some(response) = read_only_rejection(&data, &req) {
+        return Ok(response);
     }
     let id = path.into_inner();
 
@@ -241,20 +534,20 @@ pub async fn delete_race(
             "id": id
         })))
     } else {
-        Ok(HttpResponse::NotFound().json(json!({
-            "error": "Race not found",
-            "id": id
-        })))
+        Ok(race_not_found(&id))
     }
 }
 
 pub async fn health_check(data: web::Data<AppState>) -> Result<HttpResponse> {
     let storage_health = data.monitoring.get_health().await;
+    let processing_issues = data.processing_engine.degradation_reasons(Utc::now());
 
-    let status = if storage_health.critical_errors.is_empty() {
-        "healthy"
-    } else {
+    let status = if !storage_health.critical_errors.is_empty() {
         "critical"
+    } else if !processing_issues.is_empty() {
+        "degraded"
+    } else {
+        "healthy"
     };
 
     Ok(HttpResponse::Ok().json(json!({
@@ -264,34 +557,52 @@ pub async fn health_check(data: web::Data<AppState>) -> Result<HttpResponse> {
         "read_only_mode_active": data.read_only,
         "storage": {
             "total_races": storage_health.total_races,
+            "in_memory_races": storage_health.in_memory_races,
             "max_races": storage_health.max_races,
             "usage_percent": storage_health.usage_percent,
             "eviction_count": storage_health.eviction_count,
+            "evictions_by_reason": data.storage.eviction_counts(),
             "cluster_data_sufficient": storage_health.cluster_data_sufficient,
             "warnings": storage_health.warnings,
             "critical_errors": storage_health.critical_errors,
+        },
+        "processing": {
+            "queue": data.processing_engine.queue_stats(),
+            "degraded_reasons": processing_issues,
         }
     })))
 }
 
-pub async fn get_clusters(data: web::Data<AppState>) -> Result<HttpResponse> {
-    // Get all clusters from the clustering engine
-    let clusters = data
-        .prediction_engine
-        .clustering_engine
-        .clusters
-        .read()
-        .await;
+#[derive(Deserialize)]
+pub struct ClustersQuery {
+    /// Only clusters whose newest member started at or after this time
+    pub active_since: Option<DateTime<Utc>>,
+}
+
+pub async fn get_clusters(
+    query: web::Query<ClustersQuery>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    // Snapshot first so building the JSON doesn't hold off rebuild writers
+    let mut clusters = data.prediction_engine.clustering_engine.summaries().await;
+    if let Some(since) = query.active_since {
+        clusters.retain(|cluster| cluster.active_at() >= since);
+    }
+    let trend = &data.prediction_engine.clustering_engine.trend_adjustment;
+    let percentiles = &data.prediction_engine.clustering_engine.percentiles;
 
     // Convert to a serializable format
     let cluster_list: Vec<serde_json::Value> = clusters
-        .values()
+        .iter()
         .map(|cluster| {
             json!({
                 "cluster_id": cluster.cluster_id,
                 "source": cluster.source,
                 "representative_title": cluster.representative_title,
-                "member_count": cluster.member_race_ids.len(),
+                "member_count": cluster.member_count,
+                "quality_score": cluster.quality_score,
+                "prediction_accuracy": cluster.prediction_accuracy,
+                "latest_member_at": cluster.latest_member_at,
                 "last_updated": cluster.last_updated,
                 "last_accessed": cluster.last_accessed,
                 "stats": {
@@ -300,24 +611,22 @@ pub async fn get_clusters(data: web::Data<AppState>) -> Result<HttpResponse> {
                     "std_dev": cluster.stats.std_dev,
                     "mad": cluster.stats.mad,
                     "sample_size": cluster.stats.recent_times.len(),
-                    "percentiles": {
-                        "p10": cluster.stats.percentiles.p10,
-                        "p25": cluster.stats.percentiles.p25,
-                        "p50": cluster.stats.percentiles.p50,
-                        "p75": cluster.stats.percentiles.p75,
-                        "p90": cluster.stats.percentiles.p90,
-                        "p95": cluster.stats.percentiles.p95,
-                    },
+                    "percentiles": cluster
+                        .stats
+                        .percentile_report(percentiles)
+                        .into_iter()
+                        .map(|(label, value)| (label, json!(value)))
+                        .collect::<serde_json::Map<_, _>>(),
                     "trend": {
                         "direction": format!("{:?}", cluster.stats.trend.direction),
                         "rate": cluster.stats.trend.rate,
                         "confidence": cluster.stats.trend.confidence,
                     },
                     "eta_prediction": {
-                        "expected_seconds": cluster.stats.calculate_eta().expected_seconds,
-                        "confidence": cluster.stats.calculate_eta().confidence,
-                        "lower_bound": cluster.stats.calculate_eta().lower_bound,
-                        "upper_bound": cluster.stats.calculate_eta().upper_bound,
+                        "expected_seconds": cluster.stats.calculate_eta_with(trend, percentiles).expected_seconds,
+                        "confidence": cluster.stats.calculate_eta_with(trend, percentiles).confidence,
+                        "lower_bound": cluster.stats.calculate_eta_with(trend, percentiles).lower_bound,
+                        "upper_bound": cluster.stats.calculate_eta_with(trend, percentiles).upper_bound,
                     }
                 }
             })
@@ -327,8 +636,14 @@ pub async fn get_clusters(data: web::Data<AppState>) -> Result<HttpResponse> {
     Ok(HttpResponse::Ok().json(cluster_list))
 }
 
+#[derive(Deserialize)]
+pub struct ClusterDetailQuery {
+    pub include_members: Option<bool>,
+}
+
 pub async fn get_cluster(
     path: web::Path<String>,
+    query: web::Query<ClusterDetailQuery>,
     data: web::Data<AppState>,
 ) -> Result<HttpResponse> {
     let cluster_id = path.into_inner();
@@ -339,18 +654,28 @@ pub async fn get_cluster(
         .clusters
         .read()
         .await;
+    let trend = &data.prediction_engine.clustering_engine.trend_adjustment;
+    let percentiles = &data.prediction_engine.clustering_engine.percentiles;
 
     match clusters.get(&cluster_id) {
         Some(cluster) => {
-            let detail = json!({
+            let min_samples = data
+                .prediction_engine
+                .min_samples_for_prediction(&cluster.source);
+            let mut detail = json!({
                 "cluster_id": cluster.cluster_id,
                 "source": cluster.source,
                 "representative_title": cluster.representative_title,
                 "representative_metadata": cluster.representative_metadata,
-                "member_race_ids": cluster.member_race_ids,
                 "member_count": cluster.member_race_ids.len(),
+                "quality_score": cluster.quality_score,
+                "prediction_accuracy": cluster.prediction_accuracy,
+                "latest_member_at": cluster.latest_member_at,
                 "last_updated": cluster.last_updated,
                 "last_accessed": cluster.last_accessed,
+                // Too few completed runs: predictions skip this cluster
+                "insufficient_data": cluster.stats.recent_times.len() < min_samples,
+                "min_samples_for_prediction": min_samples,
                 "stats": {
                     "recent_times": cluster.stats.recent_times,
                     "mean": cluster.stats.mean,
@@ -358,36 +683,116 @@ pub async fn get_cluster(
                     "std_dev": cluster.stats.std_dev,
                     "mad": cluster.stats.mad,
                     "sample_size": cluster.stats.recent_times.len(),
-                    "percentiles": {
-                        "p10": cluster.stats.percentiles.p10,
-                        "p25": cluster.stats.percentiles.p25,
-                        "p50": cluster.stats.percentiles.p50,
-                        "p75": cluster.stats.percentiles.p75,
-                        "p90": cluster.stats.percentiles.p90,
-                        "p95": cluster.stats.percentiles.p95,
-                    },
+                    "percentiles": cluster
+                        .stats
+                        .percentile_report(percentiles)
+                        .into_iter()
+                        .map(|(label, value)| (label, json!(value)))
+                        .collect::<serde_json::Map<_, _>>(),
                     "trend": {
                         "direction": format!("{:?}", cluster.stats.trend.direction),
                         "rate": cluster.stats.trend.rate,
                         "confidence": cluster.stats.trend.confidence,
                     },
                     "eta_prediction": {
-                        "expected_seconds": cluster.stats.calculate_eta().expected_seconds,
-                        "confidence": cluster.stats.calculate_eta().confidence,
-                        "lower_bound": cluster.stats.calculate_eta().lower_bound,
-                        "upper_bound": cluster.stats.calculate_eta().upper_bound,
+                        "expected_seconds": cluster.stats.calculate_eta_with(trend, percentiles).expected_seconds,
+                        "confidence": cluster.stats.calculate_eta_with(trend, percentiles).confidence,
+                        "lower_bound": cluster.stats.calculate_eta_with(trend, percentiles).lower_bound,
+                        "upper_bound": cluster.stats.calculate_eta_with(trend, percentiles).upper_bound,
                     }
                 }
             });
+            // Full id list only on request; use /cluster/{id}/members to page through it
+            if query.include_members.unwrap_or(false) {
+                detail["member_race_ids"] = json!(cluster.member_race_ids);
+            }
             Ok(HttpResponse::Ok().json(detail))
         }
-        None => Ok(HttpResponse::NotFound().json(json!({
-            "error": "Cluster not found",
-            "cluster_id": cluster_id
-        }))),
+        None => Ok(cluster_not_found(&cluster_id)),
     }
 }
 
+#[derive(Deserialize)]
+pub struct ClusterMembersQuery {
+    pub limit: Option<usize>,
+    pub cursor: Option<String>,
+    pub include_details: Option<bool>,
+}
+
+/// Slice `items` starting at the offset encoded in `cursor`; returns the page and the next cursor
+fn paginate<T: Clone>(
+    items: &[T],
+    limit: usize,
+    cursor: Option<&str>,
+) -> std::result::Result<(Vec<T>, Option<String>), String> {
+    let offset = match cursor {
+        Some(c) => c
+            .parse::<usize>()
+            .map_err(|_| format!("invalid cursor: {}", c))?,
+        None => 0,
+    };
+    let start = offset.min(items.len());
+    let end = start.saturating_add(limit.max(1)).min(items.len());
+    let next_cursor = if end < items.len() {
+        Some(end.to_string())
+    } else {
+        None
+    };
+    Ok((items[start..end].to_vec(), next_cursor))
+}
+
+pub async fn get_cluster_members(
+    path: web::Path<String>,
+    query: web::Query<ClusterMembersQuery>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let cluster_id = path.into_inner();
+    let limit = query.limit.unwrap_or(100).min(1000);
+
+    let page = {
+        let clusters = data
+            .prediction_engine
+            .clustering_engine
+            .clusters
+            .read()
+            .await;
+        match clusters.get(&cluster_id) {
+            Some(cluster) => {
+                let total = cluster.member_race_ids.len();
+                paginate(&cluster.member_race_ids, limit, query.cursor.as_deref())
+                    .map(|(ids, next)| (ids, next, total))
+            }
+            None => return Ok(cluster_not_found(&cluster_id)),
+        }
+    };
+
+    let (member_ids, next_cursor, total) = match page {
+        Ok(page) => page,
+        Err(message) => return Ok(ApiError::invalid_cursor(message).response()),
+    };
+
+    let items: Vec<serde_json::Value> = if query.include_details.unwrap_or(false) {
+        let mut items = Vec::with_capacity(member_ids.len());
+        for id in &member_ids {
+            let race = data.storage.get_race(id).await;
+            items.push(json!({
+                "id": id,
+                "race": race,
+            }));
+        }
+        items
+    } else {
+        member_ids.iter().map(|id| json!({ "id": id })).collect()
+    };
+
+    Ok(HttpResponse::Ok().json(json!({
+        "cluster_id": cluster_id,
+        "items": items,
+        "next_cursor": next_cursor,
+        "total": total,
+    })))
+}
+
 // ============ Historic Data Management ============
 
 #[derive(Deserialize)]
@@ -398,6 +803,8 @@ pub struct HistoricRaceQuery {
     pub limit: Option<usize>,
     pub include_events: Option<bool>,
     pub cursor: Option<String>,
+    #[serde(default)]
+    pub duration_format: DurationFormat,
 }
 
 pub async fn get_historic_races(
@@ -405,9 +812,17 @@ pub async fn get_historic_races(
     data: web::Data<AppState>,
 ) -> Result<HttpResponse> {
     // Preferred path: scan from persistence using time index
-    let limit = query.limit.unwrap_or(100).min(10000); // Allow up to 10k records
+    if let Some(cursor) = query.cursor.as_deref() {
+        if !crate::persistence::PersistenceLayer::is_valid_cursor(cursor) {
+            return Ok(ApiError::invalid_cursor(format!("invalid cursor: {}", cursor)).response());
+        }
+    }
+
+    // scan_races clamps to storage.max_scan_batch
+    let limit = query.limit.unwrap_or(100);
     let filter = crate::persistence::RaceScanFilter {
         source: query.source.clone(),
+        sources: None,
         from: query.from,
         to: query.to,
         include_events: query.include_events.unwrap_or(false),
@@ -423,9 +838,7 @@ pub async fn get_historic_races(
             // Transitional fallback: legacy JSON (gated)
             if data.legacy_json_fallback_enabled {
                 log::warn!("Historic scan failed; using legacy JSON fallback (enable/disable via server.legacy_json_fallback_enabled)");
-                let mut path = dirs::home_dir().unwrap_or_else(|| std::path::PathBuf::from("."));
-                path.push(".raceboard");
-                path.push("races.json");
+                let path = data.persistence.data_dir().join("races.json");
                 if path.exists() {
                     if let Ok(contents) = std::fs::read_to_string(&path) {
                         if let Ok(mut races) = serde_json::from_str::<Vec<Race>>(&contents) {
@@ -449,7 +862,7 @@ pub async fn get_historic_races(
                                 true
                             });
                             races.sort_by(|a, b| a.started_at.cmp(&b.started_at));
-                            races.truncate(limit);
+                            races.truncate(limit.min(data.persistence.max_scan_batch()));
                             return Ok(HttpResponse::Ok().json(json!({
                                 "items": races,
                                 "next_cursor": null,
@@ -458,18 +871,13 @@ pub async fn get_historic_races(
                     }
                 }
             }
-            return Ok(HttpResponse::InternalServerError().json(json!({
-                "error": "internal",
-                "message": "Failed to read historic races",
-            })));
+            return Ok(ApiError::internal("Failed to read historic races").response());
         }
     };
 
     // If empty and first page, try legacy JSON fallback once (gated)
     if batch.items.is_empty() && query.cursor.is_none() && data.legacy_json_fallback_enabled {
-        let mut path = dirs::home_dir().unwrap_or_else(|| std::path::PathBuf::from("."));
-        path.push(".raceboard");
-        path.push("races.json");
+        let path = data.persistence.data_dir().join("races.json");
         if path.exists() {
             if let Ok(contents) = std::fs::read_to_string(&path) {
                 if let Ok(json_races) = serde_json::from_str::<Vec<Race>>(&contents) {
@@ -490,11 +898,16 @@ pub async fn get_historic_races(
 
     // Backward-compatible shape: include legacy keys
     let total = batch.items.len();
-    let items = batch.items;
+    let items: Vec<_> = batch
+        .items
+        .iter()
+        .map(|race| race_json(race, query.duration_format))
+        .collect();
     let items_clone = items.clone();
     Ok(HttpResponse::Ok().json(json!({
         "items": items,
         "next_cursor": batch.next_cursor,
+        "limit": batch.batch_size,
         "total": total,
         "races": items_clone,
     })))
@@ -505,27 +918,29 @@ pub async fn delete_historic_races(
     data: web::Data<AppState>,
 ) -> Result<HttpResponse> {
     // Historical deletion is not allowed via this endpoint; use admin purge.
-    Ok(HttpResponse::MethodNotAllowed().json(json!({
-        "error": "method_not_allowed",
-        "message": "Historic deletion is disabled. Use admin purge API.",
+    Ok(ApiError::new(
+        StatusCode::METHOD_NOT_ALLOWED,
+        "method_not_allowed",
+        "Historic deletion is disabled. Use admin purge API.",
+    )
+    .with_details(json!({
         "query": {
             "source": query.source,
             "from": query.from,
             "to": query.to,
         }
-    })))
+    }))
+    .response())
 }
 
 // ============ Rebuild Metrics & Debug Info ============
 
 pub async fn get_rebuild_metrics(data: web::Data<AppState>) -> Result<HttpResponse> {
-    // Get current rebuild metrics
-    let rollout = data.rebuild_trigger.rollout_controller.read().await;
-    let active_clusters = data.rebuild_clusters.active.read().await;
-    let inactive_clusters = data.rebuild_clusters.inactive.read().await;
-
-    // Calculate cluster statistics
-    let cluster_stats = {
+    // Each lock is held only while its numbers are read, never across the
+    // other awaits or the response serialization
+    let (active_count, inactive_count, cluster_stats) = {
+        let active_clusters = data.rebuild_clusters.active.read().await;
+        let inactive_clusters = data.rebuild_clusters.inactive.read().await;
         let total_clusters = active_clusters.len();
         let sources: std::collections::HashSet<_> =
             active_clusters.values().map(|c| c.source.clone()).collect();
@@ -543,16 +958,18 @@ pub async fn get_rebuild_metrics(data: web::Data<AppState>) -> Result<HttpRespon
             0.0
         };
 
-        json!({
+        let cluster_stats = json!({
             "total_clusters": total_clusters,
             "sources": sources,
             "singleton_clusters": singleton_clusters,
             "average_cluster_size": avg_cluster_size,
             "inactive_clusters": inactive_clusters.len(),
-        })
+        });
+        (total_clusters, inactive_clusters.len(), cluster_stats)
     };
 
     // Get rollout metrics
+    let rollout = data.rebuild_trigger.rollout_controller.read().await;
     let rollout_metrics = json!({
         "current_phase": format!("{:?}", rollout.current_phase),
         "total_rebuilds": rollout.global_metrics.total_rebuilds,
@@ -569,13 +986,28 @@ pub async fn get_rebuild_metrics(data: web::Data<AppState>) -> Result<HttpRespon
         "average_ari": rollout.global_metrics.average_ari,
         "rollback_count": rollout.global_metrics.rollback_count,
     });
+    drop(rollout);
+
+    let eps_by_source = data.rebuild_clusters.detected_eps.read().await.clone();
+    // Includes `ari_score` against the clusters the last rebuild replaced
+    let last_rebuild = data.rebuild_trigger.last_metrics().await;
+
+    let engine = &data.prediction_engine.clustering_engine;
+    let clustering_engine = json!({
+        "clusters": engine.clusters.read().await.len(),
+        "capacity": engine.max_clusters,
+        "evictions": engine.evictions(),
+    });
 
     Ok(HttpResponse::Ok().json(json!({
         "cluster_stats": cluster_stats,
+        "clustering_engine": clustering_engine,
         "rollout_metrics": rollout_metrics,
+        "last_rebuild": last_rebuild,
+        "eps_by_source": eps_by_source,
         "buffer_status": {
-            "active_clusters": active_clusters.len(),
-            "inactive_clusters": inactive_clusters.len(),
+            "active_clusters": active_count,
+            "inactive_clusters": inactive_count,
         }
     })))
 }
@@ -595,12 +1027,7 @@ pub async fn get_rollout_status(data: web::Data<AppState>) -> Result<HttpRespons
                 "last_rebuild": status.last_rebuild,
                 "success_count": status.success_count,
                 "failure_count": status.failure_count,
-                "success_rate": if status.success_count + status.failure_count > 0 {
-                    status.success_count as f64 /
-                    (status.success_count + status.failure_count) as f64
-                } else {
-                    0.0
-                },
+                "success_rate": status.success_rate(),
                 "parameters": {
                     "eps_range": status.current_parameters.eps_range,
                     "min_samples": status.current_parameters.min_samples,
@@ -627,23 +1054,7 @@ pub async fn get_rollout_status(data: web::Data<AppState>) -> Result<HttpRespons
         .iter()
         .rev()
         .take(10)
-        .map(|transition| {
-            json!({
-                "from": format!("{:?}", transition.from_phase),
-                "to": format!("{:?}", transition.to_phase),
-                "timestamp": transition.timestamp,
-                "reason": transition.reason,
-                "metrics_at_transition": {
-                    "total_rebuilds": transition.metrics_snapshot.total_rebuilds,
-                    "success_rate": if transition.metrics_snapshot.total_rebuilds > 0 {
-                        transition.metrics_snapshot.successful_rebuilds as f64 /
-                        transition.metrics_snapshot.total_rebuilds as f64
-                    } else {
-                        0.0
-                    },
-                }
-            })
-        })
+        .map(phase_transition_json)
         .collect();
 
     Ok(HttpResponse::Ok().json(json!({
@@ -656,24 +1067,159 @@ pub async fn get_rollout_status(data: web::Data<AppState>) -> Result<HttpRespons
             "canary_duration_hours": rollout.config.canary_duration.num_hours(),
             "canary_percentage": rollout.config.canary_percentage,
             "success_threshold": rollout.config.success_threshold,
-            "min_rebuilds_for_promotion": rollout.config.min_rebuilds_for_promotion,
+            "shadow_promotion_successes": rollout.config.shadow_promotion_successes,
+            "canary_promotion_successes": rollout.config.canary_promotion_successes,
             "auto_rollback": rollout.config.auto_rollback,
         }
     })))
 }
 
+/// Every source the server knows of (persisted races, tuned configs, rollout
+/// registrations, live clusters) with its effective rebuild config and state
+pub async fn get_sources(data: web::Data<AppState>) -> Result<HttpResponse> {
+    let rebuild_config = data.rebuild_trigger.config();
+    let race_counts = data.rebuild_trigger.race_counts_by_source().await;
+    let rollout = data.rebuild_trigger.rollout_controller.read().await;
+    let detected_eps = data.rebuild_clusters.detected_eps.read().await;
+    let paused = data.persistence.paused_sources();
+
+    let mut cluster_counts: std::collections::BTreeMap<String, usize> = Default::default();
+    for cluster in data
+        .prediction_engine
+        .clustering_engine
+        .clusters
+        .read()
+        .await
+        .values()
+    {
+        *cluster_counts.entry(cluster.source.clone()).or_insert(0) += 1;
+    }
+
+    let mut sources: std::collections::BTreeSet<&String> = race_counts.keys().collect();
+    sources.extend(rebuild_config.source_configs.keys());
+    sources.extend(rollout.source_status.keys());
+    sources.extend(cluster_counts.keys());
+    sources.extend(paused.iter());
+
+    let sources: Vec<_> = sources
+        .into_iter()
+        .map(|source| {
+            let source_config = rebuild_config.config_for_source(source);
+            json!({
+                "source": source,
+                "configured": rebuild_config.source_configs.contains_key(source),
+                "paused": paused.contains(source.as_str()),
+                "config": {
+                    "eps_range": source_config.eps_range,
+                    "min_samples": source_config.min_samples,
+                    "min_cluster_size": source_config.min_cluster_size,
+                    "noise_policy": source_config.noise_policy,
+                },
+                "rollout": rollout.source_status.get(source).map(|status| json!({
+                    "enabled": status.enabled,
+                    "mode": format!("{:?}", status.mode),
+                    "last_rebuild": status.last_rebuild,
+                })),
+                "cluster_count": cluster_counts.get(source).copied().unwrap_or(0),
+                "persisted_races": race_counts.get(source).copied().unwrap_or(0),
+                "last_eps": detected_eps.get(source).copied().or(source_config.last_eps),
+            })
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(json!({
+        "current_phase": format!("{:?}", rollout.current_phase),
+        "sources": sources,
+    })))
+}
+
+pub async fn pause_source(
+    req: HttpRequest,
+    path: web::Path<String>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    set_source_paused(req, path, data, true).await
+}
+
+pub async fn resume_source(
+    req: HttpRequest,
+    path: web::Path<String>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    set_source_paused(req, path, data, false).await
+}
+
+/// Paused sources are skipped by rebuilds, not clustered on completion and
+/// predicted only from bootstrap/global defaults; their rollout state is untouched
+async fn set_source_paused(
+    req: HttpRequest,
+    path: web::Path<String>,
+    data: web::Data<AppState>,
+    paused: bool,
+) -> Result<HttpResponse> {
+    if let Some(response) = read_only_rejection(&data, &req) {
+        return Ok(response);
+    }
+    let source = crate::models::normalize_source(&path.into_inner(), &data.source_aliases);
+    match data.persistence.set_source_paused(&source, paused) {
+        Ok(changed) => {
+            if changed {
+                log::info!(
+                    "Source {} {}",
+                    source,
+                    if paused { "paused" } else { "resumed" }
+                );
+            }
+            Ok(HttpResponse::Ok().json(json!({
+                "source": source,
+                "paused": paused,
+                "changed": changed,
+            })))
+        }
+        Err(e) => Ok(ApiError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "persistence_failed",
+            e.to_string(),
+        )
+        .response()),
+    }
+}
+
 #[derive(Deserialize)]
 pub struct EnableAllSourcesPayload {
     /// One of: "shadow", "production", "canary"
     pub mode: Option<String>,
     /// Canary percentage when mode == canary
     pub percentage: Option<u8>,
+    #[serde(flatten)]
+    pub change: ManualChange,
+}
+
+/// Audit a manual rollout change alongside its phase history entry
+fn audit_rollout_change(
+    data: &AppState,
+    action: &str,
+    change: &ManualChange,
+    details: serde_json::Value,
+) {
+    let audit = json!({
+        "action": action,
+        "requested_by": change.requested_by,
+        "reason": change.reason,
+        "timestamp": chrono::Utc::now(),
+        "details": details,
+    });
+    let _ = data.persistence.write_audit_record("rollout", &audit);
 }
 
 pub async fn enable_all_sources(
+    req: HttpRequest,
     payload: Option<web::Json<EnableAllSourcesPayload>>,
     data: web::Data<AppState>,
 ) -> Result<HttpResponse> {
+    if let Some(response) = read_only_rejection(&data, &req) {
+        return Ok(response);
+    }
     // Default to Shadow if not specified
     let mode = match payload.as_ref().and_then(|p| p.mode.as_ref()) {
         Some(m) if m.eq_ignore_ascii_case("production") => RolloutMode::Production,
@@ -684,19 +1230,153 @@ pub async fn enable_all_sources(
         _ => RolloutMode::Shadow,
     };
 
-    data.rebuild_trigger.enable_all_sources(mode).await;
+    let change = payload.map(|p| p.into_inner().change).unwrap_or_default();
+    data.rebuild_trigger.enable_all_sources(mode, &change).await;
+    audit_rollout_change(
+        &data,
+        "enable_all_sources",
+        &change,
+        json!({ "mode": rollout_mode_name(mode) }),
+    );
 
     Ok(HttpResponse::Ok().json(serde_json::json!({
         "status": "ok",
-        "mode": match mode {
-            RolloutMode::Shadow => "shadow",
-            RolloutMode::Production => "production",
-            RolloutMode::Canary { .. } => "canary",
-            RolloutMode::Disabled => "disabled",
-        },
+        "mode": rollout_mode_name(mode),
     })))
 }
 
+fn phase_transition_json(transition: &PhaseTransition) -> serde_json::Value {
+    json!({
+        "from": format!("{:?}", transition.from_phase),
+        "to": format!("{:?}", transition.to_phase),
+        "timestamp": transition.timestamp,
+        "reason": transition.reason,
+        "requested_by": transition.requested_by,
+        "metrics_at_transition": {
+            "total_rebuilds": transition.metrics_snapshot.total_rebuilds,
+            "success_rate": if transition.metrics_snapshot.total_rebuilds > 0 {
+                transition.metrics_snapshot.successful_rebuilds as f64 /
+                transition.metrics_snapshot.total_rebuilds as f64
+            } else {
+                0.0
+            },
+        }
+    })
+}
+
+#[derive(Deserialize)]
+pub struct RolloutHistoryQuery {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub limit: Option<usize>,
+    pub cursor: Option<String>,
+}
+
+/// Full rollout phase transition history, oldest first, within `[from, to]`
+pub async fn get_rollout_history(
+    query: web::Query<RolloutHistoryQuery>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let limit = query.limit.unwrap_or(100).min(1000);
+    let transitions: Vec<PhaseTransition> =
+        match data.persistence.phase_transitions(query.from, query.to) {
+            Ok(transitions) => transitions,
+            Err(e) => return Ok(ApiError::internal(e.to_string()).response()),
+        };
+
+    match paginate(&transitions, limit, query.cursor.as_deref()) {
+        Ok((items, next_cursor)) => Ok(HttpResponse::Ok().json(json!({
+            "items": items.iter().map(phase_transition_json).collect::<Vec<_>>(),
+            "next_cursor": next_cursor,
+            "total": transitions.len(),
+        }))),
+        Err(message) => Ok(ApiError::invalid_cursor(message).response()),
+    }
+}
+
+fn rollout_mode_name(mode: RolloutMode) -> &'static str {
+    match mode {
+        RolloutMode::Shadow => "shadow",
+        RolloutMode::Production => "production",
+        RolloutMode::Canary { .. } => "canary",
+        RolloutMode::Disabled => "disabled",
+    }
+}
+
+pub async fn promote_rollout_source(
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: Option<web::Json<ManualChange>>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    step_rollout_source(req, path, body, data, true).await
+}
+
+pub async fn demote_rollout_source(
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: Option<web::Json<ManualChange>>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    step_rollout_source(req, path, body, data, false).await
+}
+
+/// Manually move a source one rollout stage, outside the automatic promotion loop
+async fn step_rollout_source(
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: Option<web::Json<ManualChange>>,
+    data: web::Data<AppState>,
+    promote: bool,
+) -> Result<HttpResponse> {
+    if let Some(response) = read_only_rejection(&data, &req) {
+        return Ok(response);
+    }
+    let source = crate::models::normalize_source(&path.into_inner(), &data.source_aliases);
+    let previous = {
+        let rollout = data.rebuild_trigger.rollout_controller.read().await;
+        match rollout.source_status.get(&source) {
+            Some(status) => status.mode,
+            None => {
+                return Ok(ApiError::new(
+                    StatusCode::NOT_FOUND,
+                    "source_not_found",
+                    format!("Source {} is not registered in the rollout", source),
+                )
+                .response())
+            }
+        }
+    };
+
+    let change = body.map(web::Json::into_inner).unwrap_or_default();
+    let result = if promote {
+        data.rebuild_trigger.promote_source(&source, &change).await
+    } else {
+        data.rebuild_trigger.demote_source(&source, &change).await
+    };
+    match result {
+        Ok(mode) => {
+            let response = json!({
+                "source": source,
+                "previous_mode": rollout_mode_name(previous),
+                "mode": rollout_mode_name(mode),
+            });
+            let action = if promote {
+                "promote_source"
+            } else {
+                "demote_source"
+            };
+            audit_rollout_change(&data, action, &change, response.clone());
+            Ok(HttpResponse::Ok().json(response))
+        }
+        Err(e) => Ok(
+            ApiError::new(StatusCode::CONFLICT, "invalid_transition", e.to_string())
+                .with_details(json!({ "mode": rollout_mode_name(previous) }))
+                .response(),
+        ),
+    }
+}
+
 // ====== Admin: Purge and Compaction ======
 
 #[derive(Deserialize)]
@@ -707,21 +1387,17 @@ pub struct PurgeRequest {
 }
 
 pub async fn admin_purge(
+    req: HttpRequest,
     body: web::Json<PurgeRequest>,
     data: web::Data<AppState>,
 ) -> Result<HttpResponse> {
-    if data.read_only {
-        return Ok(HttpResponse::ServiceUnavailable()
-            .insert_header(("X-Raceboard-Read-Only", "1"))
-            .json(json!({"error":"read_only","message":"Server is in read-only mode"})));
+    if let Some(response) = read_only_rejection(&data, &req) {
+        return Ok(response);
     }
     // Basic synchronous implementation: delete from persistence only; in-memory is for active races
     let req = body.into_inner();
     if req.race_ids.is_empty() {
-        return Ok(HttpResponse::BadRequest().json(json!({
-            "error": "invalid_request",
-            "message": "race_ids must be a non-empty array",
-        })));
+        return Ok(ApiError::invalid_request("race_ids must be a non-empty array").response());
     }
     use crate::persistence::RaceStore;
     let mut purged = Vec::new();
@@ -754,11 +1430,9 @@ struct AcceptedJob {
     job_id: String,
 }
 
-pub async fn admin_compact(data: web::Data<AppState>) -> Result<HttpResponse> {
-    if data.read_only {
-        return Ok(HttpResponse::ServiceUnavailable()
-            .insert_header(("X-Raceboard-Read-Only", "1"))
-            .json(json!({"error":"read_only","message":"Server is in read-only mode"})));
+pub async fn admin_compact(req: HttpRequest, data: web::Data<AppState>) -> Result<HttpResponse> {
+    if let Some(response) = read_only_rejection(&data, &req) {
+        return Ok(response);
     }
     // sled compaction is automatic; expose a no-op Accepted response for UX
     let job = AcceptedJob {
@@ -781,6 +1455,75 @@ struct StorageReport {
     index_entries: usize,
 }
 
+#[derive(Deserialize)]
+pub struct AuditQuery {
+    pub kind: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub limit: Option<usize>,
+}
+
+pub async fn admin_audit(
+    req: HttpRequest,
+    query: web::Query<AuditQuery>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    if let Some(response) = admin_rejection(&data, &req) {
+        return Ok(response);
+    }
+    let limit = query.limit.unwrap_or(100).min(1000);
+    match data
+        .persistence
+        .read_audit_records(query.kind.as_deref(), query.from, query.to, limit)
+    {
+        Ok(records) => Ok(HttpResponse::Ok().json(json!({
+            "count": records.len(),
+            "records": records,
+        }))),
+        Err(e) => Ok(ApiError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "audit_read_failed",
+            e.to_string(),
+        )
+        .response()),
+    }
+}
+
+/// Re-read the config file and apply the hot-reloadable settings; reports
+/// which changed settings took effect and which need a restart
+pub async fn admin_reload_config(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    if let Some(response) = admin_rejection(&data, &req) {
+        return Ok(response);
+    }
+    let settings = match crate::config::Settings::load(data.settings.file()) {
+        Ok(settings) => settings,
+        Err(e) => {
+            return Ok(
+                ApiError::new(StatusCode::BAD_REQUEST, "invalid_config", e.to_string()).response(),
+            )
+        }
+    };
+    let reload = data.reload_settings(settings);
+    log::info!(
+        "Reloaded config from '{}': applied {:?}, requires restart {:?}",
+        data.settings.file(),
+        reload.applied,
+        reload.requires_restart
+    );
+
+    let audit = json!({
+        "action": "reload_config",
+        "timestamp": chrono::Utc::now(),
+        "applied": reload.applied,
+        "requires_restart": reload.requires_restart,
+    });
+    let _ = data.persistence.write_audit_record("config_reload", &audit);
+    Ok(HttpResponse::Ok().json(reload))
+}
+
 pub async fn admin_storage_report(data: web::Data<AppState>) -> Result<HttpResponse> {
     // Count races by reading persistence layer
     let version = data.persistence.get_schema_version();
@@ -793,6 +1536,73 @@ pub async fn admin_storage_report(data: web::Data<AppState>) -> Result<HttpRespo
     }))
 }
 
+pub async fn admin_reindex(data: web::Data<AppState>) -> Result<HttpResponse> {
+    // Rewriting the index while races are being stored would race with store_race
+    if !data.read_only {
+        return Ok(ApiError::new(
+            StatusCode::CONFLICT,
+            "not_read_only",
+            "Reindex requires the server to run in read-only mode",
+        )
+        .response());
+    }
+    let persistence = data.persistence.clone();
+    let report = match web::block(move || persistence.reindex_races()).await? {
+        Ok(report) => report,
+        Err(e) => {
+            return Ok(ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "reindex_failed",
+                e.to_string(),
+            )
+            .response())
+        }
+    };
+    let audit = json!({
+        "action": "reindex",
+        "timestamp": chrono::Utc::now(),
+        "rebuilt": report.rebuilt,
+        "orphans_dropped": report.orphans_dropped,
+        "undecodable": report.undecodable,
+    });
+    let _ = data.persistence.write_audit_record("reindex", &audit);
+    Ok(HttpResponse::Ok().json(report))
+}
+
+/// Persist in-flight races to the `active_snapshot` tree ahead of a planned restart
+pub async fn admin_checkpoint(data: web::Data<AppState>) -> Result<HttpResponse> {
+    let races: Vec<Race> = data
+        .storage
+        .get_all_races()
+        .await
+        .into_iter()
+        .filter(|r| !r.state.is_terminal())
+        .collect();
+    let persistence = data.persistence.clone();
+    let checkpointed = match web::block(move || persistence.checkpoint_active_races(&races)).await?
+    {
+        Ok(count) => count,
+        Err(e) => {
+            return Ok(ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "checkpoint_failed",
+                e.to_string(),
+            )
+            .response())
+        }
+    };
+    let audit = json!({
+        "action": "checkpoint",
+        "timestamp": chrono::Utc::now(),
+        "checkpointed": checkpointed,
+    });
+    let _ = data.persistence.write_audit_record("checkpoint", &audit);
+    Ok(HttpResponse::Ok().json(json!({
+        "checkpointed": checkpointed,
+        "message": "Restart with --restore-active-races to reload them"
+    })))
+}
+
 pub async fn admin_metrics(data: web::Data<AppState>) -> Result<HttpResponse> {
     // Get comprehensive metrics from the data layer
     if let Some(ref metrics) = data.data_layer_metrics {
@@ -802,68 +1612,130 @@ pub async fn admin_metrics(data: web::Data<AppState>) -> Result<HttpResponse> {
         Ok(HttpResponse::Ok().json(json!({
             "metrics": summary,
             "slo_violations": slo_violations,
+            "processing": data.processing_engine.queue_stats(),
+            "storage_evictions": data.storage.eviction_counts(),
             "timestamp": chrono::Utc::now(),
         })))
     } else {
-        Ok(HttpResponse::ServiceUnavailable().json(json!({
-            "error": "metrics_unavailable",
-            "message": "Data layer metrics not initialized"
-        })))
+        Ok(ApiError::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "metrics_unavailable",
+            "Data layer metrics not initialized",
+        )
+        .response())
     }
 }
 
+/// Start a rebuild in the background; poll `/rebuild/status?job=` for the outcome
 pub async fn trigger_rebuild(data: web::Data<AppState>) -> Result<HttpResponse> {
-    // Manually trigger a rebuild
-    match data.rebuild_trigger.trigger_rebuild().await {
-        Ok(_) => Ok(HttpResponse::Ok().json(json!({
-            "status": "success",
-            "message": "Rebuild triggered successfully"
-        }))),
-        Err(e) => Ok(HttpResponse::InternalServerError().json(json!({
-            "status": "error",
-            "message": format!("Failed to trigger rebuild: {}", e)
-        }))),
+    let job = data.rebuild_trigger.start_rebuild().await;
+    let message = if job.started {
+        "Rebuild started"
+    } else {
+        "Rebuild already running"
+    };
+    Ok(HttpResponse::Accepted().json(json!({
+        "status": "accepted",
+        "job_id": job.job_id,
+        "started": job.started,
+        "message": message
+    })))
+}
+
+#[derive(Deserialize)]
+pub struct RebuildStatusQuery {
+    pub job: Option<String>,
+}
+
+/// Whether a rebuild is running, on which source, and the last one's outcome.
+/// With `?job=` the id must be the running job or the last finished one.
+pub async fn get_rebuild_status(
+    query: web::Query<RebuildStatusQuery>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let status = data.rebuild_trigger.status().await;
+    if let Some(job) = &query.job {
+        let known = status.job_id.as_ref() == Some(job)
+            || status
+                .last_result
+                .as_ref()
+                .is_some_and(|r| &r.job_id == job);
+        if !known {
+            return Ok(ApiError::new(
+                StatusCode::NOT_FOUND,
+                "job_not_found",
+                format!("No running or last finished rebuild with job id {}", job),
+            )
+            .response());
+        }
     }
+    Ok(HttpResponse::Ok().json(status))
 }
 
-pub async fn reset_rollout_phase(data: web::Data<AppState>) -> Result<HttpResponse> {
+pub async fn reset_rollout_phase(
+    req: HttpRequest,
+    body: Option<web::Json<ManualChange>>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    if let Some(response) = read_only_rejection(&data, &req) {
+        return Ok(response);
+    }
     // Reset rollout to Phase 1 (single source)
-    data.rebuild_trigger.reset_to_phase_1().await;
+    let change = body.map(web::Json::into_inner).unwrap_or_default();
+    data.rebuild_trigger.reset_to_phase_1(&change).await;
+    audit_rollout_change(
+        &data,
+        "reset_to_phase_1",
+        &change,
+        json!({ "pilot_source": data.rebuild_trigger.config().pilot_source }),
+    );
 
     Ok(HttpResponse::Ok().json(json!({
         "status": "success",
         "message": "Rollout reset to Phase 1 (single source)",
         "phase": "SingleSource",
-        "pilot_source": "cargo"
+        "pilot_source": data.rebuild_trigger.config().pilot_source,
     })))
 }
 
+#[derive(Deserialize)]
+pub struct ClusterDebugQuery {
+    pub limit: Option<usize>,
+    pub cursor: Option<String>,
+}
+
 pub async fn get_cluster_debug(
     path: web::Path<String>,
+    query: web::Query<ClusterDebugQuery>,
     data: web::Data<AppState>,
 ) -> Result<HttpResponse> {
     let cluster_id = path.into_inner();
+    let limit = query.limit.unwrap_or(100).min(1000);
 
     let clusters = data.rebuild_clusters.active.read().await;
 
     match clusters.get(&cluster_id) {
         Some(cluster) => {
-            // Get member races for detailed debug info
-            let all_races = data.storage.get_all_races().await;
-            let member_races: Vec<_> = all_races
-                .iter()
-                .filter(|r| cluster.member_race_ids.contains(&r.id))
-                .map(|r| {
-                    json!({
+            let (member_ids, next_cursor) =
+                match paginate(&cluster.member_race_ids, limit, query.cursor.as_deref()) {
+                    Ok(page) => page,
+                    Err(message) => return Ok(ApiError::invalid_cursor(message).response()),
+                };
+            // Completed members have usually left the in-memory store, so fall
+            // back to persistence for each one on this page
+            let mut member_races = Vec::new();
+            for id in &member_ids {
+                if let Some(r) = find_race(&data, id).await {
+                    member_races.push(json!({
                         "id": r.id,
                         "title": r.title,
                         "state": r.state,
                         "started_at": r.started_at,
                         "eta_sec": r.eta_sec,
                         "metadata": r.metadata,
-                    })
-                })
-                .collect();
+                    }));
+                }
+            }
 
             let debug_info = json!({
                 "cluster_id": cluster.cluster_id,
@@ -872,6 +1744,7 @@ pub async fn get_cluster_debug(
                 "representative_title": cluster.representative_title,
                 "member_titles": cluster.member_titles,
                 "member_races": member_races,
+                "next_cursor": next_cursor,
                 "stats": {
                     "recent_times": cluster.stats.recent_times,
                     "mean": cluster.stats.mean,
@@ -891,13 +1764,234 @@ pub async fn get_cluster_debug(
 
             Ok(HttpResponse::Ok().json(debug_info))
         }
-        None => Ok(HttpResponse::NotFound().json(json!({
-            "error": "Cluster not found",
-            "cluster_id": cluster_id
-        }))),
+        None => Ok(cluster_not_found(&cluster_id)),
     }
 }
 
+#[derive(Deserialize)]
+pub struct DistanceQuery {
+    pub a: String,
+    pub b: String,
+}
+
+/// How far apart two races are under their source's current config
+pub async fn debug_distance(
+    query: web::Query<DistanceQuery>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let DistanceQuery { a, b } = query.into_inner();
+    let (race_a, race_b) = tokio::join!(find_race(&data, &a), find_race(&data, &b));
+    let (race_a, race_b) = match (race_a, race_b) {
+        (Some(race_a), Some(race_b)) => (race_a, race_b),
+        (race_a, _) => return Ok(race_not_found(if race_a.is_none() { &a } else { &b })),
+    };
+
+    if race_a.source != race_b.source {
+        return Ok(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "different_sources",
+            format!(
+                "Races from different sources ({} vs {}) are always at distance 1.0",
+                race_a.source, race_b.source
+            ),
+        )
+        .response());
+    }
+
+    let source_config = data.rebuild_trigger.config().config_for_source(&race_a.source);
+    let breakdown = crate::rebuild::distance_breakdown(&race_a, &race_b, &source_config);
+
+    Ok(HttpResponse::Ok().json(json!({
+        "a": race_a.id,
+        "b": race_b.id,
+        "source": race_a.source,
+        "distance": breakdown.distance,
+        "title_distance": breakdown.title_distance,
+        "metadata_jaccard": breakdown.metadata_jaccard,
+        "metadata_distance": breakdown.metadata_distance,
+        "weights": {
+            "w_title": breakdown.w_title,
+            "w_meta": breakdown.w_meta,
+        },
+    })))
+}
+
+#[derive(Deserialize)]
+pub struct NormalizeRequest {
+    pub text: String,
+}
+
+/// How a title is canonicalized before clustering, and how many hashed
+/// 3-gram buckets it lights up (read-only)
+pub async fn debug_normalize(body: web::Json<NormalizeRequest>) -> Result<HttpResponse> {
+    let text = body.into_inner().text;
+    let normalized = crate::rebuild::normalize_text(&text);
+    let vector = crate::rebuild::title_vector(&text);
+    let nonzero_buckets = vector.iter().filter(|v| **v != 0.0).count();
+
+    Ok(HttpResponse::Ok().json(json!({
+        "input": text,
+        "normalized": normalized,
+        "nonzero_buckets": nonzero_buckets,
+        "dimensions": vector.len(),
+    })))
+}
+
+#[derive(Deserialize)]
+pub struct EpsCurveQuery {
+    pub source: String,
+}
+
+/// k-distance curve and detected knee for a source, for eps tuning (read-only)
+pub async fn debug_eps_curve(
+    query: web::Query<EpsCurveQuery>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let source = query.into_inner().source;
+    let rebuild_config = data.rebuild_trigger.config().clone();
+    let source_config = rebuild_config.config_for_source(&source);
+
+    let mut races = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let filter = crate::persistence::RaceScanFilter {
+            source: Some(source.clone()),
+            sources: None,
+            from: None,
+            to: None,
+            include_events: false,
+        };
+        match data
+            .persistence
+            .scan_races(filter, rebuild_config.batch_size.max(1000), cursor.clone())
+            .await
+        {
+            Ok(batch) => {
+                races.extend(batch.items);
+                match batch.next_cursor {
+                    Some(next) => cursor = Some(next),
+                    None => break,
+                }
+            }
+            Err(e) => {
+                return Ok(ApiError::internal(format!("Failed to scan races: {}", e)).response())
+            }
+        }
+    }
+
+    if races.is_empty() {
+        return Ok(
+            ApiError::not_found(format!("No races found for source {}", source))
+                .with_details(json!({ "source": source }))
+                .response(),
+        );
+    }
+
+    let race_count = races.len();
+    let eps_range = source_config.eps_range;
+    let curve = web::block(move || {
+        crate::rebuild::compute_k_distance_curve(
+            &races,
+            source_config.min_samples,
+            &source_config,
+            &rebuild_config,
+        )
+    })
+    .await?;
+    let eps = curve
+        .knee
+        .map(|k| k.eps)
+        .unwrap_or((eps_range.0 + eps_range.1) / 2.0)
+        .clamp(eps_range.0, eps_range.1);
+
+    Ok(HttpResponse::Ok().json(json!({
+        "source": source,
+        "race_count": race_count,
+        "k": curve.k,
+        "sample_size": curve.sample_size,
+        "eps_range": eps_range,
+        "knee": curve.knee.map(|k| k.eps),
+        "knee_confidence": curve.knee.map(|k| k.confidence),
+        "eps": eps,
+        "distances": curve.distances,
+    })))
+}
+
+// ============ Dashboard Stats ============
+
+/// Consolidated overview for dashboards (races, clusters, rebuild, adapters, persistence)
+pub async fn get_stats(data: web::Data<AppState>) -> Result<HttpResponse> {
+    let (races, cluster_stats, rebuild_stats, adapter_summary, persistence_stats) = tokio::join!(
+        async {
+            let races = data.storage.get_all_races().await;
+            let mut by_state = std::collections::BTreeMap::from([
+                ("queued", 0usize),
+                ("running", 0),
+                ("passed", 0),
+                ("failed", 0),
+                ("canceled", 0),
+            ]);
+            for race in &races {
+                let key = match race.state {
+                    RaceState::Queued => "queued",
+                    RaceState::Running => "running",
+                    RaceState::Passed => "passed",
+                    RaceState::Failed => "failed",
+                    RaceState::Canceled => "canceled",
+                };
+                *by_state.entry(key).or_insert(0) += 1;
+            }
+            json!({
+                "total": races.len(),
+                "by_state": by_state,
+            })
+        },
+        async {
+            let active = data.rebuild_clusters.active.read().await;
+            json!({
+                "total": active.len(),
+                "noise_ratio": crate::rebuild::calculate_noise_ratio(&active),
+            })
+        },
+        async {
+            let phase = format!(
+                "{:?}",
+                data.rebuild_trigger.rollout_controller.read().await.current_phase
+            );
+            let last_rebuild = data.rebuild_trigger.last_rebuild_at().await;
+            json!({
+                "phase": phase,
+                "last_rebuild": last_rebuild,
+                "last_rebuild_age_seconds": Utc::now().signed_duration_since(last_rebuild).num_seconds(),
+            })
+        },
+        async {
+            let summary = data.adapter_registry.get_summary().await;
+            json!({
+                "total": summary.total_adapters,
+                "healthy": summary.healthy_count(),
+                "unhealthy": summary.unhealthy_count(),
+                "operational": summary.all_operational(),
+            })
+        },
+        async {
+            json!({
+                "db_size_bytes": data.persistence.get_db_size().ok(),
+                "races_count": data.persistence.races_count(),
+            })
+        },
+    );
+
+    Ok(HttpResponse::Ok().json(json!({
+        "races": races,
+        "clusters": cluster_stats,
+        "rebuild": rebuild_stats,
+        "adapters": adapter_summary,
+        "persistence": persistence_stats,
+        "timestamp": Utc::now(),
+    })))
+}
+
 // ============================================================================
 // Adapter Status Handlers
 // ============================================================================
@@ -911,7 +2005,7 @@ pub async fn adapter_register(
     
     // Register the adapter
     data.adapter_registry.register(registration.clone()).await
-        .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+        .map_err(|e| ApiError::internal(e.to_string()))?;
     
     log::info!(
         "Adapter registered: {} (instance: {})",
@@ -934,7 +2028,7 @@ pub async fn adapter_health(
 ) -> Result<HttpResponse> {
     let adapter_id = req.get("adapter_id")
         .and_then(|v| v.as_str())
-        .ok_or_else(|| actix_web::error::ErrorBadRequest("Missing adapter_id"))?;
+        .ok_or_else(|| ApiError::invalid_request("Missing adapter_id"))?;
     
     let metrics = req.get("metrics")
         .and_then(|v| serde_json::from_value::<AdapterMetrics>(v.clone()).ok())
@@ -946,7 +2040,7 @@ pub async fn adapter_health(
     
     // Update health
     data.adapter_registry.report_health(adapter_id, metrics, error).await
-        .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+        .map_err(|e| ApiError::internal(e.to_string()))?;
     
     Ok(HttpResponse::Ok().json(json!({
         "status": "ok",
@@ -962,11 +2056,11 @@ pub async fn adapter_deregister(
 ) -> Result<HttpResponse> {
     let adapter_id = req.get("adapter_id")
         .and_then(|v| v.as_str())
-        .ok_or_else(|| actix_web::error::ErrorBadRequest("Missing adapter_id"))?;
+        .ok_or_else(|| ApiError::invalid_request("Missing adapter_id"))?;
     
     // Deregister
     data.adapter_registry.deregister(adapter_id).await
-        .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+        .map_err(|e| ApiError::internal(e.to_string()))?;
     
     log::info!("Adapter deregistered: {}", adapter_id);
     
@@ -1032,3 +2126,1520 @@ pub async fn get_adapter_metrics(
         .content_type("text/plain; version=0.0.4")
         .body(metrics))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapter_status::AdapterRegistry;
+    use crate::cluster::ClusteringEngine;
+    use crate::config::{LiveSettings, Settings, DEFAULT_CONFIG_FILE};
+    use crate::monitoring::{AlertSystem, MonitoringSystem};
+    use crate::persistence::PersistenceLayer;
+    use crate::prediction::PredictionEngine;
+    use crate::processing::{ProcessingEngine, ProcessingOptions};
+    use crate::rate_limit::{LimitKey, RateLimitOptions, RateLimiter};
+    use crate::rebuild::{DoubleBufferClusters, RebuildConfig};
+    use crate::rebuild_trigger::RebuildTrigger;
+    use crate::storage::Storage;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    /// In-memory AppState; must be called inside a tokio runtime
+    fn test_state() -> web::Data<AppState> {
+        let storage = Arc::new(Storage::new());
+        let persistence = Arc::new(PersistenceLayer::new_in_memory().unwrap());
+        let clustering_engine = Arc::new(ClusteringEngine::new(100));
+        let prediction_engine = Arc::new(PredictionEngine::new(
+            clustering_engine.clone(),
+            persistence.clone(),
+        ));
+        let rebuild_clusters = Arc::new(DoubleBufferClusters::new(0));
+        web::Data::new(AppState {
+            storage,
+            prediction_engine: prediction_engine.clone(),
+            processing_engine: Arc::new(ProcessingEngine::new(
+                prediction_engine,
+                ProcessingOptions::default(),
+            )),
+            rebuild_clusters: rebuild_clusters.clone(),
+            rebuild_trigger: Arc::new(RebuildTrigger::new(
+                RebuildConfig::default(),
+                rebuild_clusters,
+                persistence.clone(),
+                clustering_engine,
+            )),
+            persistence,
+            monitoring: Arc::new(MonitoringSystem::new(100)),
+            alert_system: Arc::new(AlertSystem::new(None)),
+            data_layer_metrics: None,
+            adapter_registry: Arc::new(AdapterRegistry::new()),
+            rate_limiter: Arc::new(RateLimiter::new(RateLimitOptions::default())),
+            read_only: false,
+            write_token: None,
+            admin_token: None,
+            races_include_completed: false,
+            max_title_len: 1024,
+            settings: Arc::new(LiveSettings::new(DEFAULT_CONFIG_FILE, Settings::default())),
+            legacy_json_fallback_enabled: false,
+            legacy_json_writer: None,
+            race_webhooks: None,
+            source_aliases: HashMap::new(),
+            source_eta_policies: HashMap::new(),
+            source_persistence: Default::default(),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_sources_lists_unconfigured_source_with_defaults() {
+        use crate::persistence::RaceStore;
+        use crate::rebuild::SourceConfig;
+
+        let data = test_state();
+        for i in 0..3 {
+            let mut race = Race::new("buildkite".to_string(), format!("Pipeline {}", i));
+            race.id = format!("buildkite-{}", i);
+            data.persistence.store_race(&race).await.unwrap();
+        }
+
+        let body = body_json(get_sources(data).await.unwrap()).await;
+        let sources = body["sources"].as_array().unwrap();
+        let buildkite = sources
+            .iter()
+            .find(|s| s["source"] == "buildkite")
+            .expect("source with races should be listed");
+        assert_eq!(buildkite["configured"], json!(false));
+        assert_eq!(buildkite["persisted_races"], json!(3));
+        assert_eq!(buildkite["cluster_count"], json!(0));
+        assert_eq!(
+            buildkite["config"]["eps_range"],
+            json!(SourceConfig::default_for_unknown().eps_range)
+        );
+
+        // Tuned sources appear even before any of their races are stored
+        let cargo = sources.iter().find(|s| s["source"] == "cargo").unwrap();
+        assert_eq!(cargo["configured"], json!(true));
+        assert_eq!(cargo["persisted_races"], json!(0));
+    }
+
+    fn test_request() -> HttpRequest {
+        actix_web::test::TestRequest::default().to_http_request()
+    }
+
+    /// State with admin endpoints enabled; pair with [`admin_request`]
+    fn admin_state() -> web::Data<AppState> {
+        let mut state = (*test_state().into_inner()).clone();
+        state.admin_token = Some("adm1n".to_string());
+        web::Data::new(state)
+    }
+
+    fn admin_request() -> HttpRequest {
+        actix_web::test::TestRequest::default()
+            .insert_header((ADMIN_TOKEN_HEADER, "adm1n"))
+            .to_http_request()
+    }
+
+    async fn body_json(response: HttpResponse) -> serde_json::Value {
+        let bytes = actix_web::body::to_bytes(response.into_body()).await.unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    fn ids(n: usize) -> Vec<String> {
+        (0..n).map(|i| format!("race-{}", i)).collect()
+    }
+
+    #[test]
+    fn test_paginate_members_boundaries() {
+        let members = ids(5);
+
+        let (page, next) = paginate(&members, 2, None).unwrap();
+        assert_eq!(page, vec!["race-0", "race-1"]);
+        assert_eq!(next.as_deref(), Some("2"));
+
+        let (page, next) = paginate(&members, 2, Some("4")).unwrap();
+        assert_eq!(page, vec!["race-4"]);
+        assert!(next.is_none());
+
+        // Exact fit ends pagination without an empty trailing page
+        let (page, next) = paginate(&members, 5, None).unwrap();
+        assert_eq!(page.len(), 5);
+        assert!(next.is_none());
+
+        // Cursor past the end yields an empty page
+        let (page, next) = paginate(&members, 2, Some("10")).unwrap();
+        assert!(page.is_empty());
+        assert!(next.is_none());
+
+        assert!(paginate(&members, 2, Some("abc")).is_err());
+        assert!(paginate::<String>(&[], 10, None).unwrap().0.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_cluster_debug_pages_member_races() {
+        let data = test_state();
+        let members = ids(5);
+        for id in &members {
+            let mut race = Race::new("cargo".to_string(), "cargo build".to_string());
+            race.id = id.clone();
+            data.storage.create_or_update_race(race).await;
+        }
+        let member_refs: Vec<&str> = members.iter().map(String::as_str).collect();
+        let cluster = crate::cluster::test_utils::test_cluster("cargo:build", &member_refs);
+        data.rebuild_clusters
+            .active
+            .write()
+            .await
+            .insert(cluster.cluster_id.clone(), cluster);
+
+        let debug = |limit: usize, cursor: Option<&str>| {
+            get_cluster_debug(
+                web::Path::from("cargo:build".to_string()),
+                web::Query(ClusterDebugQuery {
+                    limit: Some(limit),
+                    cursor: cursor.map(str::to_string),
+                }),
+                data.clone(),
+            )
+        };
+        let body = body_json(debug(2, None).await.unwrap()).await;
+        assert_eq!(body["member_count"], 5);
+        let page: Vec<&str> = body["member_races"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["id"].as_str().unwrap())
+            .collect();
+        assert_eq!(page, vec!["race-0", "race-1"]);
+        assert_eq!(body["next_cursor"], "2");
+
+        let body = body_json(debug(2, Some("4")).await.unwrap()).await;
+        assert_eq!(body["member_races"].as_array().unwrap().len(), 1);
+        assert!(body["next_cursor"].is_null());
+
+        let response = debug(2, Some("abc")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    fn events_query(limit: usize, cursor: Option<&str>) -> web::Query<RaceEventsQuery> {
+        web::Query(RaceEventsQuery {
+            limit: Some(limit),
+            cursor: cursor.map(str::to_string),
+            kind: None,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_race_events_paginate_newest_first() {
+        let data = test_state();
+        let mut race = Race::new("codex".to_string(), "Session".to_string());
+        race.id = "codex-1".to_string();
+        data.storage.create_or_update_race(race).await;
+        let start = Utc::now();
+        for i in 0..5 {
+            let mut event = Event::new(format!("step_{}", i), None);
+            event.timestamp = start + chrono::Duration::seconds(i);
+            data.storage.add_event_to_race("codex-1", event).await;
+        }
+
+        let response = get_race_events(
+            web::Path::from("codex-1".to_string()),
+            events_query(2, None),
+            data.clone(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = body_json(response).await;
+        let types: Vec<&str> = body["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["type"].as_str().unwrap())
+            .collect();
+        assert_eq!(types, vec!["step_4", "step_3"]);
+        assert_eq!(body["total"], 5);
+        assert_eq!(body["next_cursor"], "2");
+
+        let response = get_race_events(
+            web::Path::from("codex-1".to_string()),
+            events_query(10, Some("4")),
+            data.clone(),
+        )
+        .await
+        .unwrap();
+        let body = body_json(response).await;
+        assert_eq!(body["items"].as_array().unwrap().len(), 1);
+        assert_eq!(body["items"][0]["type"], "step_0");
+        assert!(body["next_cursor"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_race_events_falls_back_to_persistence_and_404s() {
+        let data = test_state();
+        let mut race = Race::new("ci".to_string(), "Build".to_string());
+        race.id = "ci-done".to_string();
+        race.events = Some(vec![Event::new("finished".to_string(), None)]);
+        crate::persistence::RaceStore::store_race(data.persistence.as_ref(), &race)
+            .await
+            .unwrap();
+
+        let response = get_race_events(
+            web::Path::from("ci-done".to_string()),
+            events_query(10, None),
+            data.clone(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_json(response).await["items"][0]["type"], "finished");
+
+        let response = get_race_events(
+            web::Path::from("missing".to_string()),
+            events_query(10, None),
+            data.clone(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_race_events_filter_by_kind() {
+        let data = test_state();
+        let mut race = Race::new("codex".to_string(), "Session".to_string());
+        race.id = "codex-2".to_string();
+        data.storage.create_or_update_race(race).await;
+
+        // Untyped payloads from older adapters default to `log`
+        let legacy: Event =
+            serde_json::from_value(json!({"type": "stdout", "data": "compiling"})).unwrap();
+        assert_eq!(legacy.kind, EventKind::Log);
+        data.storage.add_event_to_race("codex-2", legacy).await;
+        let call = Event::new("shell".to_string(), None).with_kind(EventKind::FunctionCall);
+        data.storage.add_event_to_race("codex-2", call).await;
+
+        let query = web::Query(RaceEventsQuery {
+            limit: None,
+            cursor: None,
+            kind: Some(EventKind::FunctionCall),
+        });
+        let response = get_race_events(web::Path::from("codex-2".to_string()), query, data)
+            .await
+            .unwrap();
+        let body = body_json(response).await;
+        assert_eq!(body["total"], 1);
+        assert_eq!(body["items"][0]["type"], "shell");
+        assert_eq!(body["items"][0]["kind"], "function_call");
+
+        assert!(serde_json::from_value::<Event>(json!({"type": "x", "kind": "bogus"})).is_err());
+        let parsed: web::Query<RaceEventsQuery> =
+            web::Query::from_query("kind=state_change").unwrap();
+        assert_eq!(parsed.kind, Some(EventKind::StateChange));
+    }
+
+    #[actix_web::test]
+    async fn test_write_burst_past_bucket_is_rate_limited() {
+        use actix_web::{middleware::from_fn, test, App};
+
+        let mut state = (*test_state().into_inner()).clone();
+        state.rate_limiter = Arc::new(RateLimiter::new(RateLimitOptions {
+            enabled: true,
+            key_by: LimitKey::Source,
+            burst: 3,
+            per_second: 0.01,
+        }));
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(state))
+                .service(
+                    web::resource("/race")
+                        .wrap(from_fn(crate::rate_limit::limit_writes))
+                        .route(web::post().to(create_race)),
+                )
+                .service(
+                    web::resource("/race/{id}")
+                        .wrap(from_fn(crate::rate_limit::limit_writes))
+                        .route(web::get().to(get_race)),
+                ),
+        )
+        .await;
+
+        let post = |source: &str, id: &str| {
+            let mut race = Race::new(source.to_string(), "Build".to_string());
+            race.id = id.to_string();
+            test::TestRequest::post().uri("/race").set_json(race).to_request()
+        };
+
+        for i in 0..3 {
+            let response = test::call_service(&app, post("ci", &format!("ci-{}", i))).await;
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        let response = test::call_service(&app, post("ci", "ci-3")).await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let retry_after: u64 = response
+            .headers()
+            .get("Retry-After")
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(retry_after >= 1);
+
+        // Buckets are per source, and reads are never throttled
+        let response = test::call_service(&app, post("gitlab", "gl-1")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let request = test::TestRequest::get().uri("/race/ci-0").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[actix_web::test]
+    async fn test_rate_limit_keys_large_bodies_and_ignores_source_header() {
+        use actix_web::{middleware::from_fn, test, App};
+
+        let mut state = (*test_state().into_inner()).clone();
+        state.rate_limiter = Arc::new(RateLimiter::new(RateLimitOptions {
+            enabled: true,
+            key_by: LimitKey::Source,
+            burst: 1,
+            per_second: 0.01,
+        }));
+        let app = test::init_service(
+            App::new().app_data(web::Data::new(state)).service(
+                web::resource("/race")
+                    .wrap(from_fn(crate::rate_limit::limit_writes))
+                    .route(web::post().to(create_race)),
+            ),
+        )
+        .await;
+
+        // Past the default 256KB `Bytes` limit, but the handler still gets the body
+        let mut race = Race::new("ci".to_string(), "Build".to_string());
+        race.id = "ci-large".to_string();
+        race.metadata = Some(HashMap::from([("log".to_string(), "x".repeat(300 * 1024))]));
+        let request = test::TestRequest::post()
+            .uri("/race")
+            .set_json(&race)
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // A spoofed header doesn't move the write into another source's bucket
+        let mut race = Race::new("ci".to_string(), "Build".to_string());
+        race.id = "ci-spoofed".to_string();
+        let request = test::TestRequest::post()
+            .uri("/race")
+            .insert_header(("X-Raceboard-Source", "other"))
+            .set_json(race)
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    async fn seed_confident_cluster(data: &web::Data<AppState>, source: &str, title: &str) {
+        let engine = &data.prediction_engine.clustering_engine;
+        let cluster_id = engine
+            .assign_race_to_cluster("seed", title, source, &HashMap::new())
+            .await;
+        for _ in 0..20 {
+            engine.update_cluster_stats(&cluster_id, 300).await;
+        }
+    }
+
+    fn adapter_race(source: &str, id: &str, eta_sec: i64) -> web::Json<Race> {
+        let mut race = Race::new(source.to_string(), "Deploy pipeline".to_string());
+        race.id = id.to_string();
+        race.eta_sec = Some(eta_sec);
+        web::Json(race)
+    }
+
+    #[tokio::test]
+    async fn test_prefer_cluster_eta_overrides_adapter() {
+        let mut state = (*test_state().into_inner()).clone();
+        state.source_eta_policies.insert(
+            "gitlab".to_string(),
+            SourceEtaPolicy {
+                prefer_cluster_eta: true,
+                min_cluster_confidence: 0.6,
+            },
+        );
+        let data = web::Data::new(state);
+        seed_confident_cluster(&data, "gitlab", "Deploy pipeline").await;
+
+        let response = create_race(test_request(), adapter_race("gitlab", "gl-1", 5), data)
+            .await
+            .unwrap();
+        let body = body_json(response).await;
+        assert_eq!(body["eta_sec"], 300);
+        assert_eq!(body["eta_source"], json!(i32::from(EtaSource::Cluster)));
+        assert!(body["eta_confidence"].as_f64().unwrap() > 0.6);
+        assert_eq!(body["metadata"]["adapter_eta_sec"], "5");
+    }
+
+    #[tokio::test]
+    async fn test_adapter_eta_passes_through_without_confident_override() {
+        let mut state = (*test_state().into_inner()).clone();
+        state.source_eta_policies.insert(
+            "gitlab".to_string(),
+            SourceEtaPolicy {
+                prefer_cluster_eta: true,
+                min_cluster_confidence: 0.99,
+            },
+        );
+        let data = web::Data::new(state);
+        seed_confident_cluster(&data, "gitlab", "Deploy pipeline").await;
+        seed_confident_cluster(&data, "github", "Deploy pipeline").await;
+
+        // Cluster is confident but below this source's bar
+        let response = create_race(test_request(), adapter_race("gitlab", "gl-2", 5), data.clone())
+            .await
+            .unwrap();
+        let body = body_json(response).await;
+        assert_eq!(body["eta_sec"], 5);
+        assert_eq!(body["eta_source"], json!(i32::from(EtaSource::Adapter)));
+        assert!(body["metadata"].get("adapter_eta_sec").is_none());
+
+        // Sources without a policy keep trusting the adapter
+        let response = create_race(test_request(), adapter_race("github", "gh-1", 7), data)
+            .await
+            .unwrap();
+        let body = body_json(response).await;
+        assert_eq!(body["eta_sec"], 7);
+        assert_eq!(body["eta_source"], json!(i32::from(EtaSource::Adapter)));
+    }
+
+    #[tokio::test]
+    async fn test_source_average_does_not_override_adapter_eta() {
+        let mut state = (*test_state().into_inner()).clone();
+        state.source_eta_policies.insert(
+            "gitlab".to_string(),
+            SourceEtaPolicy {
+                prefer_cluster_eta: true,
+                min_cluster_confidence: 0.5,
+            },
+        );
+        let data = web::Data::new(state);
+        // Plenty of source history, but no cluster to match
+        for _ in 0..20 {
+            data.prediction_engine
+                .update_source_stats("gitlab", 300)
+                .await;
+        }
+
+        let response = create_race(test_request(), adapter_race("gitlab", "gl-3", 5), data)
+            .await
+            .unwrap();
+        let body = body_json(response).await;
+        assert_eq!(body["eta_sec"], 5);
+        assert_eq!(body["eta_source"], json!(i32::from(EtaSource::Adapter)));
+        assert!(body["metadata"].get("adapter_eta_sec").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_predict_uses_cluster_history_without_creating_race() {
+        let data = test_state();
+        seed_confident_cluster(&data, "codex-session", "Codex: refactor the parser").await;
+
+        let request = |title: &str| {
+            web::Json(PredictRequest {
+                source: "Codex-Session".to_string(),
+                title: title.to_string(),
+                metadata: HashMap::new(),
+            })
+        };
+        let body = body_json(
+            predict(request("Codex: refactor the parser"), data.clone())
+                .await
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(body["expected_seconds"], 300);
+        assert_eq!(body["method"], "cluster");
+        assert!(body["confidence"].as_f64().unwrap() > 0.5);
+
+        let body = body_json(predict(request("something unrelated"), data.clone()).await.unwrap())
+            .await;
+        assert_ne!(body["method"], "cluster");
+        assert!(data.storage.get_all_races().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_debug_distance_breakdown() {
+        let data = test_state();
+        let race = |id: &str, source: &str, title: &str, language: &str| {
+            let mut race = Race::new(source.to_string(), title.to_string());
+            race.id = id.to_string();
+            race.metadata = Some(HashMap::from([(
+                "language".to_string(),
+                language.to_string(),
+            )]));
+            race
+        };
+        data.storage
+            .create_or_update_race(race("a", "cargo", "cargo build", "rust"))
+            .await;
+        data.storage
+            .create_or_update_race(race("b", "cargo", "cargo test", "go"))
+            .await;
+        data.storage
+            .create_or_update_race(race("c", "npm", "npm test", "js"))
+            .await;
+
+        let query = |a: &str, b: &str| {
+            web::Query(DistanceQuery {
+                a: a.to_string(),
+                b: b.to_string(),
+            })
+        };
+
+        let response = debug_distance(query("a", "b"), data.clone()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = body_json(response).await;
+        let f = |key: &str| body[key].as_f64().unwrap();
+        let w_title = body["weights"]["w_title"].as_f64().unwrap();
+        let w_meta = body["weights"]["w_meta"].as_f64().unwrap();
+        assert_eq!(f("metadata_jaccard"), 0.0);
+        assert_eq!(f("metadata_distance"), 1.0);
+        assert!(f("title_distance") > 0.0 && f("title_distance") < 1.0);
+        let recombined = w_title * f("title_distance") + w_meta * f("metadata_distance");
+        assert!((recombined - f("distance")).abs() < 1e-9);
+
+        let response = debug_distance(query("a", "missing"), data.clone()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(body_json(response).await["details"]["id"], "missing");
+
+        let response = debug_distance(query("a", "c"), data).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_debug_normalize_applies_nfkc() {
+        let normalize = |text: &str| {
+            debug_normalize(web::Json(NormalizeRequest {
+                text: text.to_string(),
+            }))
+        };
+
+        // Fullwidth letters, an ideographic space, the "fi" ligature and the
+        // numero sign all fold to ASCII; the dash and punctuation are dropped
+        let response = normalize("ＣＡＲＧＯ\u{3000}Ｂｕｉｌｄ  \u{fb01}le – №1!")
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = body_json(response).await;
+        assert_eq!(body["normalized"], "cargo build file no1");
+        assert_eq!(body["dimensions"], crate::rebuild::EMBEDDING_DIM);
+        let buckets = body["nonzero_buckets"].as_u64().unwrap();
+        assert!(buckets > 0);
+
+        // The canonical form hashes to exactly the same buckets
+        let plain = body_json(normalize("cargo build file no1").await.unwrap()).await;
+        assert_eq!(plain["nonzero_buckets"].as_u64().unwrap(), buckets);
+
+        let empty = body_json(normalize("  –!  ").await.unwrap()).await;
+        assert_eq!(empty["normalized"], "");
+        assert_eq!(empty["nonzero_buckets"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_write_token_bypasses_read_only() {
+        let mut state = (*test_state().into_inner()).clone();
+        state.read_only = true;
+        state.write_token = Some("s3cret".to_string());
+        let data = web::Data::new(state);
+
+        let response = create_race(test_request(), adapter_race("ci", "ci-1", 30), data.clone())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body_json(response).await["code"], "read_only");
+
+        let wrong = actix_web::test::TestRequest::default()
+            .insert_header((WRITE_TOKEN_HEADER, "guess"))
+            .to_http_request();
+        let response = create_race(wrong, adapter_race("ci", "ci-1", 30), data.clone())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let tokened = actix_web::test::TestRequest::default()
+            .insert_header((WRITE_TOKEN_HEADER, "s3cret"))
+            .to_http_request();
+        let response = create_race(tokened.clone(), adapter_race("ci", "ci-1", 30), data.clone())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = delete_race(tokened, web::Path::from("ci-1".to_string()), data.clone())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // No configured token means nothing bypasses read-only
+        let mut state = (*data.into_inner()).clone();
+        state.write_token = None;
+        let empty = actix_web::test::TestRequest::default()
+            .insert_header((WRITE_TOKEN_HEADER, ""))
+            .to_http_request();
+        let response = create_race(empty, adapter_race("ci", "ci-2", 30), web::Data::new(state))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn test_purge_is_listed_in_audit_log() {
+        let data = admin_state();
+        let body = web::Json(PurgeRequest {
+            race_ids: vec!["gone-1".to_string()],
+            reason: Some("gdpr".to_string()),
+            requested_by: Some("ops".to_string()),
+        });
+        let response = admin_purge(test_request(), body, data.clone())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let query = |kind: &str| {
+            web::Query(AuditQuery {
+                kind: Some(kind.to_string()),
+                from: None,
+                to: None,
+                limit: None,
+            })
+        };
+        let response = admin_audit(test_request(), query("purge"), data.clone())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = admin_audit(test_request(), query("purge"), test_state())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = admin_audit(admin_request(), query("purge"), data.clone())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = body_json(response).await;
+        assert_eq!(body["count"], 1);
+        let record = &body["records"][0];
+        assert_eq!(record["kind"], "purge");
+        assert_eq!(record["payload"]["requested_by"], "ops");
+        assert_eq!(record["payload"]["reason"], "gdpr");
+
+        let response = admin_audit(admin_request(), query("restore"), data)
+            .await
+            .unwrap();
+        let body = body_json(response).await;
+        assert_eq!(body["count"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_duration_format_iso8601_renders_alongside_seconds() {
+        assert_eq!(iso8601_duration(200), "PT3M20S");
+        assert_eq!(iso8601_duration(0), "PT0S");
+        assert_eq!(iso8601_duration(3600), "PT1H");
+        assert_eq!(iso8601_duration(-5), "-PT5S");
+
+        let data = test_state();
+        let mut race = Race::new("cargo".to_string(), "cargo build".to_string());
+        race.id = "iso".to_string();
+        race.eta_sec = Some(200);
+        data.storage.create_or_update_race(race).await;
+
+        let get = |query: &str| {
+            get_race(
+                web::Path::from("iso".to_string()),
+                web::Query::from_query(query).unwrap(),
+                data.clone(),
+            )
+        };
+        let body = body_json(get("duration_format=iso8601").await.unwrap()).await;
+        assert_eq!(body["eta_sec"], 200);
+        assert_eq!(body["eta_iso8601"], "PT3M20S");
+        assert!(body.get("duration_iso8601").is_none());
+
+        let body = body_json(get("").await.unwrap()).await;
+        assert_eq!(body["eta_sec"], 200);
+        assert!(body.get("eta_iso8601").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_oversized_title_is_truncated_on_ingress() {
+        let mut state = (*test_state().into_inner()).clone();
+        state.max_title_len = 16;
+        let data = web::Data::new(state);
+
+        let mut race = Race::new("cargo".to_string(), "ü".repeat(100_000));
+        race.id = "long".to_string();
+        let response = create_race(test_request(), web::Json(race), data.clone())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let stored = data.storage.get_race("long").await.unwrap();
+        assert_eq!(stored.title, "ü".repeat(16));
+
+        let update: RaceUpdate =
+            serde_json::from_value(json!({ "title": format!("cargo test {}", "x".repeat(100)) }))
+                .unwrap();
+        update_race(
+            test_request(),
+            web::Path::from("long".to_string()),
+            web::Json(update),
+            data.clone(),
+        )
+        .await
+        .unwrap();
+        let stored = data.storage.get_race("long").await.unwrap();
+        assert_eq!(stored.title, "cargo test xxxxx");
+    }
+
+    #[tokio::test]
+    async fn test_races_exclude_completed_unless_requested() {
+        let data = test_state();
+        for (id, state) in [("live", RaceState::Running), ("done", RaceState::Passed)] {
+            let mut race = Race::new("cargo".to_string(), id.to_string());
+            race.id = id.to_string();
+            race.state = state;
+            data.storage.create_or_update_race(race).await;
+        }
+        let ids = |data: web::Data<AppState>, query: &'static str| async move {
+            let response = get_races(web::Query::from_query(query).unwrap(), data)
+                .await
+                .unwrap();
+            let mut ids: Vec<String> = body_json(response)
+                .await
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|race| race["id"].as_str().unwrap().to_string())
+                .collect();
+            ids.sort();
+            ids
+        };
+
+        assert_eq!(ids(data.clone(), "").await, ["live"]);
+        assert_eq!(
+            ids(data.clone(), "include_completed=true").await,
+            ["done", "live"]
+        );
+
+        // The compatibility flag restores the old default; the parameter still wins
+        let mut state = (*data.into_inner()).clone();
+        state.races_include_completed = true;
+        let data = web::Data::new(state);
+        assert_eq!(ids(data.clone(), "").await, ["done", "live"]);
+        assert_eq!(ids(data, "include_completed=false").await, ["live"]);
+    }
+
+    #[tokio::test]
+    async fn test_completed_race_posts_signed_webhook() {
+        use crate::webhook::{self, WebhookDispatcher};
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let config = crate::config::WebhooksConfig {
+            url: Some(format!("{}/webhook/completed", mockito::server_url())),
+            secret: Some("hook-secret".to_string()),
+            ..Default::default()
+        };
+        let webhooks = Arc::new(WebhookDispatcher::from_config(&config, temp_dir.path()).unwrap());
+        let mut state = (*test_state().into_inner()).clone();
+        state.race_webhooks = Some(webhooks.clone());
+        let data = web::Data::new(state);
+
+        let mut race = Race::new("cargo".to_string(), "cargo build".to_string());
+        race.id = "hooked".to_string();
+        data.storage.create_or_update_race(race).await;
+        // Completing twice announces the transition once
+        for _ in 0..2 {
+            let update: RaceUpdate = serde_json::from_value(json!({"state": "passed"})).unwrap();
+            update_race(
+                test_request(),
+                web::Path::from("hooked".to_string()),
+                web::Json(update),
+                data.clone(),
+            )
+            .await
+            .unwrap();
+        }
+
+        let body = WebhookDispatcher::payload(&data.storage.get_race("hooked").await.unwrap());
+        let receiver = mockito::mock("POST", "/webhook/completed")
+            .match_header("x-raceboard-event", webhook::RACE_COMPLETED_EVENT)
+            .match_header(
+                "x-raceboard-signature",
+                webhook::sign("hook-secret", &body).as_str(),
+            )
+            .match_body(String::from_utf8(body).unwrap().as_str())
+            .with_status(204)
+            .expect(1)
+            .create();
+        let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel();
+        let task = tokio::spawn(webhooks.clone().run(shutdown_rx));
+        for _ in 0..100 {
+            if webhooks.delivered_count() > 0 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        shutdown_tx.send(()).unwrap();
+        task.await.unwrap();
+
+        receiver.assert();
+        assert_eq!(webhooks.delivered_count(), 1);
+        assert_eq!(webhooks.dead_letter_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_error_responses_share_code_message_shape() {
+        let data = test_state();
+        let mut race = Race::new("cargo".to_string(), "cargo build".to_string());
+        race.id = "shape".to_string();
+        data.storage.create_or_update_race(race).await;
+
+        let missing = get_race(
+            web::Path::from("nope".to_string()),
+            web::Query::from_query("").unwrap(),
+            data.clone(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+        let body = body_json(missing).await;
+        assert_eq!(body["code"], "not_found");
+        assert!(body["message"].as_str().unwrap().contains("nope"));
+        assert_eq!(body["details"]["id"], "nope");
+
+        let cursor = get_race_events(
+            web::Path::from("shape".to_string()),
+            web::Query::from_query("cursor=bogus").unwrap(),
+            data.clone(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(cursor.status(), StatusCode::BAD_REQUEST);
+        let body = body_json(cursor).await;
+        assert_eq!(body["code"], "invalid_cursor");
+        assert!(body["message"].is_string());
+        assert!(body.get("details").is_none());
+        assert!(body.get("error").is_none());
+
+        let mut state = (*data.into_inner()).clone();
+        state.read_only = true;
+        let response = delete_race(
+            test_request(),
+            web::Path::from("shape".to_string()),
+            web::Data::new(state),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers().get("X-Raceboard-Read-Only").unwrap(), "1");
+        let body = body_json(response).await;
+        assert_eq!(body["code"], "read_only");
+        assert_eq!(body["message"], "Server is in read-only mode");
+
+        let data = test_state();
+        let errors = [
+            adapter_health(data.clone(), web::Json(json!({})))
+                .await
+                .unwrap_err(),
+            adapter_deregister(data, web::Json(json!({"adapter_id": 7})))
+                .await
+                .unwrap_err(),
+        ];
+        for err in errors {
+            let response = err.error_response();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+            let body = body_json(response).await;
+            assert_eq!(body["code"], "invalid_request");
+            assert_eq!(body["message"], "Missing adapter_id");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reload_config_applies_hot_settings() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let file = temp_dir.path().join("raceboard");
+        let file = file.to_str().unwrap();
+        let data = test_state();
+        let response = admin_reload_config(test_request(), data.clone())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let mut state = (*data.into_inner()).clone();
+        state.admin_token = Some("adm1n".to_string());
+        state.settings = Arc::new(LiveSettings::new(file, Settings::load(file).unwrap()));
+        let data = web::Data::new(state);
+        assert!(!data.rate_limiter.enabled());
+
+        std::fs::write(
+            temp_dir.path().join("raceboard.toml"),
+            "[limits]\nenabled = true\nburst = 5\n\n\
+             [alerts]\nwebhook_url = \"http://alerts.example/hook\"\n\n\
+             [server]\nhttp_port = 9999\n",
+        )
+        .unwrap();
+
+        let reload = |token: Option<&str>| {
+            let mut req = actix_web::test::TestRequest::post();
+            if let Some(token) = token {
+                req = req.insert_header((ADMIN_TOKEN_HEADER, token));
+            }
+            admin_reload_config(req.to_http_request(), data.clone())
+        };
+        let response = reload(None).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = reload(Some("guess")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert!(!data.rate_limiter.enabled());
+
+        let response = reload(Some("adm1n")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = body_json(response).await;
+        assert_eq!(
+            body["applied"],
+            json!(["alerts.webhook_url", "limits.burst", "limits.enabled"])
+        );
+        assert_eq!(body["requires_restart"], json!(["server.http_port"]));
+        assert!(data.rate_limiter.enabled());
+        assert_eq!(data.rate_limiter.options().burst, 5);
+        assert_eq!(
+            data.alert_system.webhook_url().as_deref(),
+            Some("http://alerts.example/hook")
+        );
+        assert_eq!(data.settings.current().server.http_port, 9999);
+
+        // Nothing changed since the last reload
+        let body = body_json(reload(Some("adm1n")).await.unwrap()).await;
+        assert_eq!(body["applied"], json!([]));
+        assert_eq!(body["requires_restart"], json!([]));
+    }
+
+    #[tokio::test]
+    async fn test_manual_enable_records_reason_in_history_and_audit() {
+        let data = admin_state();
+        let payload = web::Json(EnableAllSourcesPayload {
+            mode: Some("canary".to_string()),
+            percentage: Some(25),
+            change: ManualChange {
+                reason: Some("pilot looks healthy".to_string()),
+                requested_by: Some("ops".to_string()),
+            },
+        });
+        let response = enable_all_sources(test_request(), Some(payload), data.clone())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let query = web::Query(RolloutHistoryQuery {
+            from: None,
+            to: None,
+            limit: None,
+            cursor: None,
+        });
+        let body = body_json(get_rollout_history(query, data.clone()).await.unwrap()).await;
+        let last = body["items"].as_array().unwrap().last().unwrap().clone();
+        assert_eq!(
+            last["reason"],
+            "Manually enabled all sources in Canary { percentage: 25 } mode: pilot looks healthy"
+        );
+        assert_eq!(last["requested_by"], "ops");
+        let saved = data.persistence.load_rollout_config().unwrap().unwrap();
+        assert_eq!(
+            saved.phase_history.last().unwrap().requested_by.as_deref(),
+            Some("ops")
+        );
+
+        let query = web::Query(AuditQuery {
+            kind: Some("rollout".to_string()),
+            from: None,
+            to: None,
+            limit: None,
+        });
+        let body = body_json(admin_audit(admin_request(), query, data).await.unwrap()).await;
+        assert_eq!(body["count"], 1);
+        let payload = &body["records"][0]["payload"];
+        assert_eq!(payload["action"], "enable_all_sources");
+        assert_eq!(payload["reason"], "pilot looks healthy");
+        assert_eq!(payload["requested_by"], "ops");
+        assert_eq!(payload["details"]["mode"], "canary");
+    }
+
+    #[tokio::test]
+    async fn test_manual_rollout_changes_respect_read_only() {
+        let mut state = (*test_state().into_inner()).clone();
+        state.read_only = true;
+        state.write_token = Some("s3cret".to_string());
+        let data = web::Data::new(state);
+        let change = || ManualChange {
+            reason: Some("unverified".to_string()),
+            requested_by: Some("someone".to_string()),
+        };
+
+        let payload = web::Json(EnableAllSourcesPayload {
+            mode: Some("production".to_string()),
+            percentage: None,
+            change: change(),
+        });
+        let response = enable_all_sources(test_request(), Some(payload), data.clone())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let response = reset_rollout_phase(test_request(), Some(web::Json(change())), data.clone())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let records = data
+            .persistence
+            .read_audit_records(Some("rollout"), None, None, 10)
+            .unwrap();
+        assert!(records.is_empty());
+
+        let tokened = actix_web::test::TestRequest::default()
+            .insert_header((WRITE_TOKEN_HEADER, "s3cret"))
+            .to_http_request();
+        let response = reset_rollout_phase(tokened, Some(web::Json(change())), data.clone())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_rollout_history_accumulates_and_filters_by_time() {
+        use crate::phased_rollout::RolloutPhase;
+
+        let data = test_state();
+        let trigger = &data.rebuild_trigger;
+        trigger
+            .rollout_controller
+            .write()
+            .await
+            .trigger_rollback("High failure rate detected");
+        trigger.reset_to_phase_1(&ManualChange::default()).await;
+        trigger
+            .rollout_controller
+            .write()
+            .await
+            .trigger_rollback("High failure rate detected");
+        trigger.reset_to_phase_1(&ManualChange::default()).await;
+
+        // The resets appended every transition to the history store
+        let stored = data.persistence.phase_transitions(None, None).unwrap();
+        assert_eq!(stored.len(), 4);
+        assert_eq!(stored[1].to_phase, RolloutPhase::SingleSource);
+
+        // Transitions one day apart, oldest first, in a fresh store
+        let data = test_state();
+        let start = Utc::now() - chrono::Duration::days(10);
+        let mut rollout = data.rebuild_trigger.rollout_controller.write().await;
+        for _ in 0..2 {
+            rollout.trigger_rollback("High failure rate detected");
+            rollout.add_transition(
+                RolloutPhase::Rollback,
+                RolloutPhase::SingleSource,
+                "Manual reset to Phase 1".to_string(),
+            );
+        }
+        for (i, transition) in rollout.phase_history.iter_mut().enumerate() {
+            transition.timestamp = start + chrono::Duration::days(i as i64);
+        }
+        data.persistence.persist_rollout_config(&rollout).unwrap();
+        drop(rollout);
+
+        let history = |from: Option<i64>, to: Option<i64>, limit: Option<usize>| {
+            get_rollout_history(
+                web::Query(RolloutHistoryQuery {
+                    from: from.map(|d| start + chrono::Duration::days(d)),
+                    to: to.map(|d| start + chrono::Duration::days(d)),
+                    limit,
+                    cursor: None,
+                }),
+                data.clone(),
+            )
+        };
+
+        let body = body_json(history(None, None, None).await.unwrap()).await;
+        assert_eq!(body["total"], 4);
+        assert_eq!(body["items"][0]["to"], "Rollback");
+        assert_eq!(body["items"][3]["reason"], "Manual reset to Phase 1");
+
+        let body = body_json(history(Some(1), Some(2), None).await.unwrap()).await;
+        assert_eq!(body["total"], 2);
+        assert_eq!(body["items"][0]["from"], "Rollback");
+        assert_eq!(body["items"][1]["to"], "Rollback");
+
+        let body = body_json(history(Some(1), None, Some(2)).await.unwrap()).await;
+        assert_eq!(body["total"], 3);
+        assert_eq!(body["items"].as_array().unwrap().len(), 2);
+        assert_eq!(body["next_cursor"], "2");
+    }
+
+    #[tokio::test]
+    async fn test_manual_rollout_source_transitions() {
+        let data = test_state();
+        {
+            let mut rollout = data.rebuild_trigger.rollout_controller.write().await;
+            for (source, mode, successes, failures) in [
+                ("ci", RolloutMode::Shadow, 1, 0),
+                ("flaky", RolloutMode::Shadow, 1, 9),
+                ("fresh", RolloutMode::Disabled, 0, 0),
+            ] {
+                rollout.seed_source(source, mode, successes, failures);
+            }
+        }
+        let promote = |source: &str| {
+            let path = web::Path::from(source.to_string());
+            promote_rollout_source(test_request(), path, None, data.clone())
+        };
+        let demote = |source: &str| {
+            let path = web::Path::from(source.to_string());
+            demote_rollout_source(test_request(), path, None, data.clone())
+        };
+        let expect = |response: HttpResponse, status: StatusCode, mode: &'static str| async move {
+            assert_eq!(response.status(), status);
+            let body = body_json(response).await;
+            // Rejections carry the unchanged mode in the error details
+            let current = if status.is_success() {
+                &body["mode"]
+            } else {
+                &body["details"]["mode"]
+            };
+            assert_eq!(current, mode);
+        };
+
+        // Legal: one stage at a time, well before the automatic count thresholds
+        expect(promote("ci").await.unwrap(), StatusCode::OK, "canary").await;
+        expect(promote("ci").await.unwrap(), StatusCode::OK, "production").await;
+        expect(demote("ci").await.unwrap(), StatusCode::OK, "canary").await;
+        let saved = data.persistence.load_rollout_config().unwrap().unwrap();
+        assert!(matches!(saved.source_status["ci"].mode, RolloutMode::Canary { .. }));
+
+        // Illegal: past production, from disabled, below the success rate
+        expect(promote("ci").await.unwrap(), StatusCode::OK, "production").await;
+        let response = promote("ci").await.unwrap();
+        assert_eq!(body_json(response).await["code"], "invalid_transition");
+        expect(
+            promote("fresh").await.unwrap(),
+            StatusCode::CONFLICT,
+            "disabled",
+        )
+        .await;
+        expect(
+            demote("fresh").await.unwrap(),
+            StatusCode::CONFLICT,
+            "disabled",
+        )
+        .await;
+        expect(
+            promote("flaky").await.unwrap(),
+            StatusCode::CONFLICT,
+            "shadow",
+        )
+        .await;
+
+        // Demoting a shadow source disables it
+        expect(demote("flaky").await.unwrap(), StatusCode::OK, "disabled").await;
+        let rollout = data.rebuild_trigger.rollout_controller.read().await;
+        assert!(!rollout.source_status["flaky"].enabled);
+        drop(rollout);
+
+        let response = promote("missing").await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_paused_source_skipped_in_rebuild_and_prediction() {
+        use crate::persistence::RaceStore;
+        use crate::phased_rollout::RolloutMode;
+        use crate::stats::PredictionMethod;
+
+        let data = test_state();
+        let metadata = HashMap::new();
+        for source in ["ci", "buildkite"] {
+            for i in 0..10 {
+                let mut race = Race::new(source.to_string(), "deploy web".to_string());
+                race.id = format!("{}-{}", source, i);
+                race.state = RaceState::Passed;
+                race.duration_sec = Some(120);
+                data.persistence.store_race(&race).await.unwrap();
+                data.prediction_engine
+                    .on_race_completed(&race.id, &race.title, source, &metadata, 120)
+                    .await;
+            }
+        }
+        let predict = || {
+            data.prediction_engine
+                .predict_eta("new", "deploy web", "buildkite", &metadata)
+        };
+        assert_eq!(predict().await.method, PredictionMethod::Cluster);
+
+        let response = pause_source(
+            test_request(),
+            web::Path::from("buildkite".to_string()),
+            data.clone(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(body_json(response).await["changed"], json!(true));
+
+        let prediction = predict().await;
+        assert!(!matches!(
+            prediction.method,
+            PredictionMethod::Cluster | PredictionMethod::SourceAverage
+        ));
+
+        data.rebuild_trigger
+            .enable_all_sources(RolloutMode::Production, &ManualChange::default())
+            .await;
+        data.rebuild_trigger.trigger_rebuild().await.unwrap();
+        let active = data.rebuild_clusters.active.read().await;
+        assert!(active.values().any(|c| c.source == "ci"));
+        assert!(active.values().all(|c| c.source != "buildkite"));
+        drop(active);
+
+        let body = body_json(get_sources(data.clone()).await.unwrap()).await;
+        let buildkite = body["sources"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|s| s["source"] == "buildkite")
+            .unwrap()
+            .clone();
+        assert_eq!(buildkite["paused"], json!(true));
+
+        resume_source(
+            test_request(),
+            web::Path::from("buildkite".to_string()),
+            data.clone(),
+        )
+        .await
+        .unwrap();
+        // The rebuild replaced its clusters, but its own history is used again
+        assert_eq!(predict().await.method, PredictionMethod::SourceAverage);
+    }
+
+    #[tokio::test]
+    async fn test_historic_races_rejects_malformed_cursor() {
+        let data = test_state();
+        let query = |cursor: &str| {
+            web::Query::<HistoricRaceQuery>::from_query(&format!("cursor={}", cursor)).unwrap()
+        };
+
+        let response = get_historic_races(query("not-a-cursor"), data.clone())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(body_json(response).await["code"], json!("invalid_cursor"));
+
+        // Valid base64 that doesn't decode to a cursor object is rejected too
+        let response = get_historic_races(query("e30%3D"), data.clone())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = get_historic_races(web::Query::from_query("limit=10").unwrap(), data)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_source_without_history_completes_without_persisting() {
+        use crate::config::SourcePersistencePolicy;
+        use crate::persistence::RaceStore;
+
+        let mut state = (*test_state().into_inner()).clone();
+        state.source_persistence.write().unwrap().insert(
+            "cmd".to_string(),
+            SourcePersistencePolicy {
+                persist_completed: false,
+            },
+        );
+        let data = web::Data::new(state);
+
+        for (id, source) in [("cmd-1", "cmd"), ("cargo-1", "cargo")] {
+            let mut race = Race::new(source.to_string(), "run".to_string());
+            race.id = id.to_string();
+            data.storage.create_or_update_race(race).await;
+            let update: RaceUpdate = serde_json::from_value(json!({"state": "passed"})).unwrap();
+            let response = update_race(
+                test_request(),
+                web::Path::from(id.to_string()),
+                web::Json(update),
+                data.clone(),
+            )
+            .await
+            .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        let stored: Vec<String> = data
+            .persistence
+            .get_all_races()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|r| r.id)
+            .collect();
+        assert_eq!(stored, vec!["cargo-1"]);
+
+        // Stats processing still runs for the opted-out source
+        for _ in 0..100 {
+            if data.processing_engine.queue_stats().processed >= 2 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(data.processing_engine.queue_stats().processed, 2);
+    }
+
+    #[tokio::test]
+    async fn test_completion_skips_legacy_json_when_fallback_disabled() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut state = (*test_state().into_inner()).clone();
+        state.persistence = Arc::new(PersistenceLayer::open_in(temp_dir.path()).unwrap());
+        state.legacy_json_fallback_enabled = false;
+        let data = web::Data::new(state);
+
+        let mut race = Race::new("cargo".to_string(), "cargo build".to_string());
+        race.id = "json-1".to_string();
+        data.storage.create_or_update_race(race).await;
+        let update: RaceUpdate = serde_json::from_value(json!({"state": "passed"})).unwrap();
+        let response = update_race(
+            test_request(),
+            web::Path::from("json-1".to_string()),
+            web::Json(update),
+            data.clone(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // Stored in sled only; no legacy file or backup is read or rewritten
+        use crate::persistence::RaceStore;
+        assert_eq!(data.persistence.get_all_races().await.unwrap().len(), 1);
+        assert!(!temp_dir.path().join("races.json").exists());
+        assert!(!temp_dir.path().join("races.json.bak").exists());
+    }
+
+    #[tokio::test]
+    async fn test_race_history_interleaves_events_and_eta_revisions() {
+        use crate::models::{EtaRevision, EtaSource};
+
+        let data = test_state();
+        let start = Utc::now() - chrono::Duration::minutes(10);
+        let at = |minutes: i64| start + chrono::Duration::minutes(minutes);
+        let event = |name: &str, minutes: i64| Event {
+            timestamp: at(minutes),
+            ..Event::new(name.to_string(), None)
+        };
+        let revision = |eta_sec: i64, minutes: i64| EtaRevision {
+            eta_sec,
+            timestamp: at(minutes),
+            source: EtaSource::Cluster,
+            confidence: None,
+        };
+
+        let mut race = Race::new("ci".to_string(), "Build".to_string());
+        race.id = "ci-history".to_string();
+        race.events = Some(vec![
+            event("started", 0),
+            event("tests", 3),
+            event("done", 5),
+        ]);
+        race.eta_history = Some(vec![revision(300, 1), revision(240, 3), revision(120, 4)]);
+        crate::persistence::RaceStore::store_race(data.persistence.as_ref(), &race)
+            .await
+            .unwrap();
+
+        let history = |cursor: Option<&str>| {
+            get_race_history(
+                web::Path::from("ci-history".to_string()),
+                web::Query(RaceHistoryQuery {
+                    limit: Some(4),
+                    cursor: cursor.map(str::to_string),
+                }),
+                data.clone(),
+            )
+        };
+        let label = |entry: &serde_json::Value| match entry["type"].as_str().unwrap() {
+            "event" => entry["event"]["type"].as_str().unwrap().to_string(),
+            _ => entry["eta_revision"]["eta_sec"].to_string(),
+        };
+
+        let first = body_json(history(None).await.unwrap()).await;
+        assert_eq!(first["total"], json!(6));
+        let next = first["next_cursor"].as_str().unwrap().to_string();
+        let second = body_json(history(Some(&next)).await.unwrap()).await;
+        assert!(second["next_cursor"].is_null());
+
+        let labels: Vec<String> = first["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .chain(second["items"].as_array().unwrap())
+            .map(label)
+            .collect();
+        assert_eq!(
+            labels,
+            vec!["started", "300", "tests", "240", "120", "done"]
+        );
+
+        let response = get_race_history(
+            web::Path::from("missing".to_string()),
+            web::Query(RaceHistoryQuery {
+                limit: None,
+                cursor: None,
+            }),
+            data,
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_clusters_active_since_hides_stale_clusters() {
+        let data = test_state();
+        seed_confident_cluster(&data, "ci", "nightly build").await;
+        seed_confident_cluster(&data, "cargo", "cargo build").await;
+        {
+            let mut clusters = data.prediction_engine.clustering_engine.clusters.write().await;
+            let stale = clusters.values_mut().find(|c| c.source == "ci").unwrap();
+            stale.latest_member_at = Some(Utc::now() - chrono::Duration::days(200));
+            stale.last_updated = Utc::now() - chrono::Duration::days(200);
+        }
+        let sources = |body: serde_json::Value| -> Vec<String> {
+            let mut sources: Vec<String> = body
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|c| c["source"].as_str().unwrap().to_string())
+                .collect();
+            sources.sort();
+            sources
+        };
+
+        let all = get_clusters(
+            web::Query(ClustersQuery { active_since: None }),
+            data.clone(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(sources(body_json(all).await), vec!["cargo", "ci"]);
+
+        let recent = get_clusters(
+            web::Query(ClustersQuery {
+                active_since: Some(Utc::now() - chrono::Duration::days(30)),
+            }),
+            data,
+        )
+        .await
+        .unwrap();
+        let body = body_json(recent).await;
+        assert!(body[0]["latest_member_at"].is_string());
+        assert_eq!(sources(body), vec!["cargo"]);
+    }
+}
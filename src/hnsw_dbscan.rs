@@ -7,7 +7,8 @@ use std::collections::{HashMap, HashSet, VecDeque};
 
 use crate::models::Race;
 use crate::rebuild::{
-    custom_distance, race_to_vector, ClusterId, CorePointIndex, DBSCANResult, RaceId, SourceConfig,
+    custom_distance_with, race_to_vector_with, recency_weight, ClusterId, CorePointIndex,
+    DBSCANResult, NormalizedTitles, RaceId, SourceConfig,
 };
 
 /// Custom distance metric for HNSW
@@ -51,6 +52,8 @@ pub struct HnswDBSCAN {
     hnsw: Option<Hnsw<RaceDistanceMetric, Vec<f32>, StdRng, 16, 32>>,
     distance_cache: LruCache<(usize, usize), f64>,
     races: Vec<Race>,
+    /// Normalized once in `build_index` for vectors and exact distances
+    titles: NormalizedTitles,
     race_vectors: Vec<Vec<f32>>,
     config: SourceConfig,
 }
@@ -61,19 +64,27 @@ impl HnswDBSCAN {
             hnsw: None,
             distance_cache: LruCache::new(cache_size.try_into().unwrap()),
             races: Vec::new(),
+            titles: NormalizedTitles::default(),
             race_vectors: Vec::new(),
             config,
         }
     }
 
+    #[cfg(test)]
+    pub fn normalized_titles(&self) -> &NormalizedTitles {
+        &self.titles
+    }
+
     /// Build HNSW index for the races
     pub fn build_index(&mut self, races: Vec<Race>) -> Result<(), String> {
         self.races = races;
+        self.titles = NormalizedTitles::from_races(&self.races);
         self.race_vectors.clear();
 
         // Create vectors for all races
         for race in &self.races {
-            self.race_vectors.push(race_to_vector(race));
+            self.race_vectors
+                .push(race_to_vector_with(race, &self.titles));
         }
 
         if self.race_vectors.is_empty() {
@@ -106,7 +117,13 @@ impl HnswDBSCAN {
     }
 
     /// Find neighbors using HNSW with exact distance verification
+    ///
+    /// Stops at `k` neighbors, which only suffices when each counts 1.0; with
+    /// recency weighting the whole eps-neighborhood is needed to sum density.
     fn find_neighbors_ann(&mut self, race_idx: usize, eps: f64, k: usize) -> Vec<usize> {
+        if self.config.recency_half_life_days.is_some() {
+            return self.find_neighbors_brute(race_idx, eps);
+        }
         if let Some(hnsw) = &self.hnsw {
             let query_vector = &self.race_vectors[race_idx];
             let mut searcher = Searcher::default();
@@ -172,7 +189,12 @@ impl HnswDBSCAN {
             return dist;
         }
 
-        let distance = custom_distance(&self.races[idx1], &self.races[idx2], &self.config);
+        let distance = custom_distance_with(
+            &self.races[idx1],
+            &self.races[idx2],
+            &self.config,
+            &self.titles,
+        );
         self.distance_cache.put(key, distance);
         distance
     }
@@ -182,6 +204,9 @@ impl HnswDBSCAN {
         let n = self.races.len();
         let mut labels = vec![-1i32; n]; // -1 = unclassified, -2 = noise, >= 0 = cluster id
         let mut cluster_id = 0;
+        let weights = self.recency_weights();
+        let density = |neighbors: &[usize]| -> f64 { neighbors.iter().map(|&i| weights[i]).sum() };
+        let min_density = min_samples as f64;
 
         for idx in 0..n {
             if labels[idx] != -1 {
@@ -191,7 +216,7 @@ impl HnswDBSCAN {
             // Find neighbors using HNSW
             let neighbors = self.find_neighbors_ann(idx, eps, min_samples * 2);
 
-            if neighbors.len() < min_samples {
+            if density(&neighbors) < min_density {
                 labels[idx] = -2; // Mark as noise
                 continue;
             }
@@ -221,7 +246,7 @@ impl HnswDBSCAN {
                     let neighbor_neighbors =
                         self.find_neighbors_ann(neighbor_idx, eps, min_samples);
 
-                    if neighbor_neighbors.len() >= min_samples {
+                    if density(&neighbor_neighbors) >= min_density {
                         // This is a core point, add its neighbors to seeds
                         for nn_idx in neighbor_neighbors {
                             if !processed.contains(&nn_idx) {
@@ -236,11 +261,26 @@ impl HnswDBSCAN {
         }
 
         // Convert labels to result format
-        self.labels_to_result(labels, eps, min_samples)
+        self.labels_to_result(labels, eps, &weights, min_density)
+    }
+
+    /// Per-index weights for neighbor counts (see [`recency_weight`])
+    fn recency_weights(&self) -> Vec<f64> {
+        let now = chrono::Utc::now();
+        self.races
+            .iter()
+            .map(|race| recency_weight(race, &self.config, now))
+            .collect()
     }
 
     /// Convert label array to DBSCANResult
-    fn labels_to_result(&mut self, labels: Vec<i32>, eps: f64, min_samples: usize) -> DBSCANResult {
+    fn labels_to_result(
+        &mut self,
+        labels: Vec<i32>,
+        eps: f64,
+        weights: &[f64],
+        min_density: f64,
+    ) -> DBSCANResult {
         let mut clusters: HashMap<ClusterId, Vec<RaceId>> = HashMap::new();
         let mut noise = Vec::new();
         let mut border_points = HashMap::new();
@@ -268,7 +308,7 @@ impl HnswDBSCAN {
         // Second pass: check for border points
         for (idx, _, race_id, cluster_id) in cluster_members {
             let neighbors = self.find_neighbors_brute(idx, eps);
-            if neighbors.len() < min_samples {
+            if neighbors.iter().map(|&i| weights[i]).sum::<f64>() < min_density {
                 border_points.insert(race_id, cluster_id);
             }
         }
@@ -367,12 +407,14 @@ impl Default for ValidationMetrics {
 pub async fn validate_clusters_comprehensive(
     new_clusters: &HashMap<ClusterId, crate::cluster::RaceCluster>,
     old_clusters: &HashMap<ClusterId, crate::cluster::RaceCluster>,
+    sample_races: &[Race],
     holdout_set: &[Race],
     criteria: &ValidationCriteria,
     config: &SourceConfig,
 ) -> ValidationResult {
     use crate::rebuild::{
-        adjusted_rand_index, calculate_average_cohesion, calculate_noise_ratio, silhouette_sampled,
+        adjusted_rand_index, calculate_cohesion, calculate_noise_ratio, calculate_separation,
+        silhouette_sampled,
     };
 
     let mut failures = Vec::new();
@@ -387,7 +429,7 @@ pub async fn validate_clusters_comprehensive(
         ));
     }
 
-    let cohesion = calculate_average_cohesion(new_clusters);
+    let cohesion = calculate_cohesion(new_clusters, |_| config.clone());
     if cohesion < criteria.min_cohesion {
         failures.push(format!(
             "Cohesion {:.3} below minimum {:.3}",
@@ -395,8 +437,18 @@ pub async fn validate_clusters_comprehensive(
         ));
     }
 
-    let silhouette = if holdout_set.len() >= 10 {
-        silhouette_sampled(new_clusters, holdout_set, config, 100)
+    let separation = calculate_separation(new_clusters, |_| config.clone());
+    if separation < criteria.min_separation {
+        failures.push(format!(
+            "Separation {:.3} below minimum {:.3}",
+            separation, criteria.min_separation
+        ));
+    }
+
+    // Silhouette needs cluster members; prediction error is measured on races the
+    // new clusters were not built from when there are any
+    let silhouette = if sample_races.len() >= 10 {
+        silhouette_sampled(new_clusters, sample_races, config, 100)
     } else {
         0.0
     };
@@ -416,10 +468,17 @@ pub async fn validate_clusters_comprehensive(
         ));
     }
 
+    let eval_set = if holdout_set.is_empty() {
+        sample_races
+    } else {
+        holdout_set
+    };
+
     // Calculate MAE on holdout set
-    let (mae, p90_error, success_rate) = calculate_prediction_metrics(new_clusters, holdout_set);
+    let (mae, p90_error, success_rate) =
+        calculate_prediction_metrics(new_clusters, eval_set, config);
 
-    let old_mae = calculate_mae(old_clusters, holdout_set);
+    let old_mae = calculate_mae(old_clusters, eval_set, config);
     let mae_increase = if old_mae > 0.0 {
         (mae - old_mae) / old_mae
     } else {
@@ -448,7 +507,7 @@ pub async fn validate_clusters_comprehensive(
         success_rate,
         noise_ratio,
         cohesion,
-        separation: 1.0 / new_clusters.len().max(1) as f64,
+        separation,
         silhouette,
         ari,
     };
@@ -464,16 +523,16 @@ pub async fn validate_clusters_comprehensive(
 fn calculate_prediction_metrics(
     clusters: &HashMap<ClusterId, crate::cluster::RaceCluster>,
     holdout: &[Race],
+    config: &SourceConfig,
 ) -> (f64, f64, f64) {
     let mut errors = Vec::new();
     let mut successful = 0;
 
     for race in holdout {
-        // Find matching cluster
-        let predicted_eta = clusters
-            .values()
-            .find(|c| c.member_race_ids.contains(&race.id))
-            .map(|c| c.stats.median as i64);
+        // Predict from the closest centroid so old and new clusters are compared
+        // the same way, whether or not they contain the race
+        let predicted_eta = crate::rebuild::nearest_cluster(clusters, race, config)
+            .map(|(_, c)| c.stats.median as i64);
 
         if let Some(predicted) = predicted_eta {
             // Use the race's duration_sec field directly
@@ -505,8 +564,9 @@ fn calculate_prediction_metrics(
 fn calculate_mae(
     clusters: &HashMap<ClusterId, crate::cluster::RaceCluster>,
     holdout: &[Race],
+    config: &SourceConfig,
 ) -> f64 {
-    let (mae, _, _) = calculate_prediction_metrics(clusters, holdout);
+    let (mae, _, _) = calculate_prediction_metrics(clusters, holdout, config);
     mae
 }
 
@@ -0,0 +1,181 @@
+use crate::models::Race;
+use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Debounced writer for the legacy `races.json` fallback. Completions are
+/// coalesced in memory and the file is rewritten at most once per interval,
+/// instead of a full read-modify-write on every completed race.
+pub struct LegacyJsonWriter {
+    path: PathBuf,
+    interval: Duration,
+    /// Completed races not yet merged into the file, keyed by race id
+    pending: Mutex<HashMap<String, Race>>,
+    /// Number of file rewrites, for diagnostics
+    writes: AtomicU64,
+}
+
+impl LegacyJsonWriter {
+    pub fn new(data_dir: &Path, interval: Duration) -> Self {
+        Self {
+            path: data_dir.join("races.json"),
+            interval: interval.max(Duration::from_millis(1)),
+            pending: Mutex::new(HashMap::new()),
+            writes: AtomicU64::new(0),
+        }
+    }
+
+    /// Queue a completed race; a later update to the same race replaces it
+    pub fn enqueue(&self, race: Race) {
+        self.pending.lock().unwrap().insert(race.id.clone(), race);
+    }
+
+    pub fn write_count(&self) -> u64 {
+        self.writes.load(Ordering::Relaxed)
+    }
+
+    /// Merge pending races into the file in one rewrite; returns how many were written.
+    /// On error the races stay pending for the next flush.
+    pub fn flush(&self) -> Result<usize> {
+        let pending = std::mem::take(&mut *self.pending.lock().unwrap());
+        if pending.is_empty() {
+            return Ok(0);
+        }
+
+        if let Err(e) = self.merge_into_file(&pending) {
+            // A completion queued since the take is newer and wins
+            let mut queued = self.pending.lock().unwrap();
+            for (id, race) in pending {
+                queued.entry(id).or_insert(race);
+            }
+            return Err(e);
+        }
+        self.writes.fetch_add(1, Ordering::Relaxed);
+        log::debug!("Wrote {} completed races to {:?}", pending.len(), self.path);
+        Ok(pending.len())
+    }
+
+    fn merge_into_file(&self, pending: &HashMap<String, Race>) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut races: Vec<Race> = if self.path.exists() {
+            std::fs::read_to_string(&self.path)
+                .ok()
+                .and_then(|s| serde_json::from_str(&s).ok())
+                .unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        let mut merged = HashSet::new();
+        for existing in races.iter_mut() {
+            if let Some(race) = pending.get(&existing.id) {
+                *existing = race.clone();
+                merged.insert(existing.id.clone());
+            }
+        }
+        races.extend(
+            pending
+                .values()
+                .filter(|race| !merged.contains(&race.id))
+                .cloned(),
+        );
+
+        std::fs::write(&self.path, serde_json::to_string_pretty(&races)?)?;
+        Ok(())
+    }
+
+    /// `flush` on the blocking pool; the rewrite is O(file size) of sync I/O
+    async fn flush_blocking(self: &Arc<Self>) -> Result<usize> {
+        let this = self.clone();
+        tokio::task::spawn_blocking(move || this.flush()).await?
+    }
+
+    /// Rewrite the file every interval while races are pending until `shutdown`
+    /// fires, then flush once more so no completion is dropped.
+    pub async fn run(self: Arc<Self>, mut shutdown: tokio::sync::oneshot::Receiver<()>) {
+        let mut interval = tokio::time::interval(self.interval);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    if let Err(e) = self.flush_blocking().await {
+                        log::error!("Legacy JSON write failed: {}", e);
+                    }
+                }
+                _ = &mut shutdown => break,
+            }
+        }
+        if let Err(e) = self.flush_blocking().await {
+            log::error!("Final legacy JSON write on shutdown failed: {}", e);
+        }
+        log::info!(
+            "Legacy JSON writer stopped after {} rewrites of {:?}",
+            self.write_count(),
+            self.path
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_rapid_completions_coalesce_into_one_write() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let writer = Arc::new(LegacyJsonWriter::new(
+            temp_dir.path(),
+            Duration::from_secs(3600),
+        ));
+        let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel();
+        let task = {
+            let writer = writer.clone();
+            tokio::spawn(async move { writer.run(shutdown_rx).await })
+        };
+
+        for i in 0..5 {
+            let mut race = Race::new("cargo".to_string(), format!("build {}", i));
+            race.id = format!("race-{}", i);
+            writer.enqueue(race);
+        }
+        // A repeated completion replaces the queued copy rather than adding a row
+        let mut race = Race::new("cargo".to_string(), "build 0 (retried)".to_string());
+        race.id = "race-0".to_string();
+        writer.enqueue(race);
+
+        shutdown_tx.send(()).unwrap();
+        task.await.unwrap();
+
+        assert_eq!(writer.write_count(), 1);
+        assert_eq!(writer.flush().unwrap(), 0);
+        let contents = std::fs::read_to_string(temp_dir.path().join("races.json")).unwrap();
+        let races: Vec<Race> = serde_json::from_str(&contents).unwrap();
+        assert_eq!(races.len(), 5);
+        assert!(races.iter().any(|r| r.title == "build 0 (retried)"));
+    }
+
+    #[test]
+    fn test_failed_write_keeps_races_pending() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        // A file where the data directory should be makes the write fail
+        let data_dir = temp_dir.path().join("data");
+        std::fs::write(&data_dir, "").unwrap();
+        let writer = LegacyJsonWriter::new(&data_dir, Duration::from_secs(3600));
+        for i in 0..3 {
+            let mut race = Race::new("cargo".to_string(), format!("build {}", i));
+            race.id = format!("race-{}", i);
+            writer.enqueue(race);
+        }
+        assert!(writer.flush().is_err());
+        assert_eq!(writer.write_count(), 0);
+
+        std::fs::remove_file(&data_dir).unwrap();
+        assert_eq!(writer.flush().unwrap(), 3);
+        let contents = std::fs::read_to_string(data_dir.join("races.json")).unwrap();
+        let races: Vec<Race> = serde_json::from_str(&contents).unwrap();
+        assert_eq!(races.len(), 3);
+    }
+}
@@ -3,19 +3,24 @@ pub mod adapter_status;
 pub mod app_state;
 pub mod cluster;
 pub mod config;
+pub mod error;
 pub mod grpc_service;
 pub mod handlers;
 pub mod hnsw_dbscan;
+pub mod legacy_json;
 pub mod models;
 pub mod monitoring;
 pub mod persistence;
 pub mod phased_rollout;
 pub mod prediction;
 pub mod processing;
+pub mod rate_limit;
 pub mod rebuild;
 pub mod rebuild_trigger;
+pub mod shutdown;
 pub mod stats;
 pub mod storage;
+pub mod webhook;
 
 #[cfg(test)]
 mod tests;
@@ -2,21 +2,26 @@ mod adapter_status;
 mod app_state;
 mod cluster;
 mod config;
+mod error;
 mod grpc_service;
 mod handlers;
 mod hnsw_dbscan;
+mod legacy_json;
 mod models;
 mod monitoring;
 mod persistence;
 mod phased_rollout;
 mod prediction;
 mod processing;
+mod rate_limit;
 mod rebuild;
 mod rebuild_trigger;
+mod shutdown;
 mod stats;
 mod storage;
 #[cfg(test)]
 mod tests;
+mod webhook;
 
 use actix_web::dev::ServerHandle;
 use actix_web::{middleware, web, App, HttpServer};
@@ -25,7 +30,7 @@ use cluster::ClusteringEngine;
 use config::Settings;
 use grpc_service::raceboard::race_service_server::RaceServiceServer;
 use grpc_service::RaceServiceImpl;
-use persistence::{PersistenceLayer, RaceStore};
+use persistence::{PersistenceError, PersistenceLayer, RaceStore};
 use prediction::PredictionEngine;
 use processing::ProcessingEngine;
 use rebuild::{DoubleBufferClusters, RebuildConfig};
@@ -40,6 +45,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Parse command-line arguments
     let args: Vec<String> = std::env::args().collect();
     let clear_clusters = args.contains(&"--clear-clusters".to_string());
+    let restore_active_races = args.contains(&"--restore-active-races".to_string());
     
     if clear_clusters {
         log::info!("Starting with --clear-clusters flag: will clear all clusters on startup");
@@ -48,16 +54,25 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Load configuration
     let settings = Settings::new()?;
 
-    // Initialize logging
-    env_logger::init_from_env(env_logger::Env::new().default_filter_or(&settings.logging.level));
+    // Initialize logging. A plain level is enforced through log's max level so
+    // /admin/reload-config can change it; RUST_LOG or a directive list is
+    // handed to env_logger as-is
+    let runtime_level = settings.logging.runtime_level();
+    let default_filter = match runtime_level {
+        Some(_) => "trace",
+        None => settings.logging.level.as_str(),
+    };
+    env_logger::init_from_env(env_logger::Env::new().default_filter_or(default_filter));
+    if let Some(level) = runtime_level {
+        log::set_max_level(level);
+    }
 
     log::info!("Starting Raceboard Server");
     log::info!("HTTP: http://{}", settings.http_addr());
     log::info!("gRPC: grpc://{}", settings.grpc_addr());
 
-    // Initialize storage with config
-    // CRITICAL: Use much larger limits to support cluster rebuilding
-    let max_races = settings.storage.max_races.max(100_000); // Minimum 100k for ML
+    // In-memory storage only holds live races; rebuilds scan persistence for history
+    let max_races = settings.storage.active_race_capacity();
     let max_events = settings.storage.max_events_per_race.max(1000);
 
     log::info!(
@@ -65,40 +80,67 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         max_races,
         max_events
     );
-    let storage = Arc::new(Storage::with_config(max_races, max_events));
+    let storage = Arc::new(
+        Storage::with_config(max_races, max_events)
+            .with_eviction_events(settings.storage.eviction_events),
+    );
 
     // Initialize monitoring system
     let monitoring = Arc::new(monitoring::MonitoringSystem::new(max_races));
-    let alert_system = Arc::new(monitoring::AlertSystem::new(None)); // Configure webhook URL if needed
+    let alert_system = Arc::new(monitoring::AlertSystem::new(
+        settings.alerts.webhook_url.clone(),
+    ));
 
     // Start monitoring - but we need persistence first, so we'll start it later
     log::info!("Monitoring system initialized");
 
     // Initialize ETA prediction system
     log::info!("Initializing ETA prediction system...");
-    let clustering_engine = Arc::new(ClusteringEngine::new(1000));
+    let mut clustering_engine = ClusteringEngine::new(settings.prediction.max_clusters);
+    clustering_engine.trend_adjustment = settings.prediction.trend_adjustment();
+    clustering_engine.percentiles = settings.prediction.percentile_set();
+    clustering_engine.outlier_filter = settings.prediction.outlier_filter();
+    let clustering_engine = Arc::new(clustering_engine);
 
     // Try to initialize persistence, but continue if it fails
-    let persistence = match PersistenceLayer::new(None) {
+    let data_dir = settings.storage.data_dir();
+    log::info!("Data directory: {:?}", data_dir);
+    let persistence = match PersistenceLayer::open_in(&data_dir) {
         Ok(p) => {
             log::info!("Persistence layer initialized");
-            Arc::new(p)
+            p
         }
         Err(e) => {
+            exit_if_locked(&e);
             log::warn!(
                 "Failed to initialize persistence layer: {}. Running without persistence.",
                 e
             );
             // Create a dummy persistence layer that uses an in-memory database
             match PersistenceLayer::new(Some(std::path::PathBuf::from("/tmp/raceboard_temp.db"))) {
-                Ok(p) => Arc::new(p),
-                Err(_) => {
+                Ok(p) => p,
+                Err(e) => {
+                    exit_if_locked(&e);
                     // Last resort: use in-memory sled
-                    Arc::new(PersistenceLayer::new_in_memory()?)
+                    PersistenceLayer::new_in_memory()?
                 }
             }
         }
     };
+    let persistence = Arc::new(
+        persistence
+            .with_trace(settings.storage.persistence_trace)
+            .with_quarantine(settings.storage.quarantine_corrupt)
+            .with_write_batching(settings.storage.write_batching())
+            .with_max_scan_batch(settings.storage.max_scan_batch),
+    );
+
+    // Flush batched race writes on a timer; the final flush runs on shutdown
+    let (flush_shutdown_tx, flush_shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+    let flush_task = {
+        let persistence = persistence.clone();
+        tokio::spawn(async move { persistence.run_flush_loop(flush_shutdown_rx).await })
+    };
 
     // Do initial data check for accurate stats
     monitoring.check_initial_data(&storage, &persistence).await;
@@ -131,6 +173,20 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
+    // Reload races checkpointed via POST /admin/checkpoint (opt-in only)
+    if restore_active_races {
+        match persistence.take_active_snapshot() {
+            Ok(races) => {
+                let count = races.len();
+                for race in races {
+                    storage.create_or_update_race(race).await;
+                }
+                log::info!("Restored {} checkpointed active races", count);
+            }
+            Err(e) => log::error!("Failed to restore checkpointed races: {}", e),
+        }
+    }
+
     // Bootstrap: import existing JSON history into sled on first run only
     {
         // Check if we already have data in sled
@@ -145,9 +201,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             );
         } else {
             // Perform one-time migration
-            let mut path = dirs::home_dir().unwrap_or_else(|| std::path::PathBuf::from("."));
-            path.push(".raceboard");
-            path.push("races.json");
+            let path = persistence.data_dir().join("races.json");
             if path.exists() {
                 match std::fs::read_to_string(&path) {
                     Ok(contents) => {
@@ -201,15 +255,46 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Do not migrate active in-memory races; persistence is for historical (completed) data only.
 
-    let prediction_engine = Arc::new(PredictionEngine::new(
-        clustering_engine.clone(),
-        persistence.clone(),
-    ));
+    let rebuild_config = RebuildConfig::default()
+        .with_default_source_config(settings.sources.default_source_config.clone())
+        .with_outlier_filter(settings.prediction.outlier_filter())
+        .with_batch_size(settings.prediction.rebuild_scan_batch_size)
+        .with_centroid_title_mode(settings.prediction.centroid_title_mode)
+        .with_parallel_distances(settings.prediction.rebuild_parallel)
+        .with_pilot_source(&settings.prediction.rollout_pilot_source)
+        .with_promotion_thresholds(
+            settings.prediction.rollout_shadow_promotion_successes,
+            settings.prediction.rollout_canary_promotion_successes,
+            settings.prediction.rollout_promotion_success_rate,
+        )
+        .with_rollback_recovery_after(std::time::Duration::from_secs(
+            settings.prediction.rollout_rollback_recovery_minutes * 60,
+        ));
+    rebuild_config.validate()?;
+    if !rebuild_config
+        .source_configs
+        .contains_key(&rebuild_config.pilot_source)
+    {
+        log::warn!(
+            "Rollout pilot source '{}' has no tuned clustering config (known: {:?}); it uses the default source config",
+            rebuild_config.pilot_source,
+            rebuild_config.source_configs.keys().collect::<Vec<_>>()
+        );
+    }
 
-    let processing_engine = Arc::new(ProcessingEngine::new(prediction_engine.clone()));
+    let prediction_engine = Arc::new(
+        PredictionEngine::new(clustering_engine.clone(), persistence.clone())
+            .with_cold_start_defaults(&rebuild_config)
+            .with_fallback_chains(settings.prediction.fallback_chains.clone())
+            .with_default_eta(settings.prediction.default_eta_sec),
+    );
+
+    let processing_engine = Arc::new(ProcessingEngine::new(
+        prediction_engine.clone(),
+        settings.processing.options(),
+    ));
 
     // Initialize rebuild system
-    let rebuild_config = RebuildConfig::default();
     let rebuild_clusters = Arc::new(DoubleBufferClusters::new(100_000_000)); // 100MB baseline
 
     // Initialize active buffer with current clusters (unless --clear-clusters is set)
@@ -222,12 +307,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     log::info!("Creating RebuildTrigger...");
-    let rebuild_trigger = Arc::new(RebuildTrigger::new(
-        rebuild_config,
-        rebuild_clusters.clone(),
-        persistence.clone(),
-        clustering_engine.clone(),
-    ));
+    let rebuild_trigger = Arc::new(
+        RebuildTrigger::new(
+            rebuild_config,
+            rebuild_clusters.clone(),
+            persistence.clone(),
+            clustering_engine.clone(),
+        )
+        .with_alert_system(alert_system.clone()),
+    );
 
     // Start rebuild monitoring
     log::info!("Starting rebuild monitoring...");
@@ -248,6 +336,73 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         monitor.run().await;
     });
 
+    let rate_limiter = Arc::new(rate_limit::RateLimiter::new(settings.limits.options()));
+    if rate_limiter.enabled() {
+        log::info!(
+            "Rate limiting race writes by {:?}: burst {}, {}/s",
+            settings.limits.key_by,
+            settings.limits.burst,
+            settings.limits.per_second
+        );
+    }
+    // Always running: limits can be switched on by a config reload
+    tokio::spawn(rate_limit::run_prune_loop(rate_limiter.clone()));
+    let source_persistence: config::SharedPersistencePolicies =
+        Arc::new(std::sync::RwLock::new(settings.sources.persistence.clone()));
+
+    let read_only = settings.server.read_only
+        || std::env::var("RACEBOARD_READ_ONLY")
+            .ok()
+            .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+            .unwrap_or(false);
+
+    // Mid-run ETA recalculation writes to races, so it never runs read-only
+    if processing_engine.eta_refresh_options().enabled && !read_only {
+        log::info!(
+            "Refreshing stale server ETAs every {:?}",
+            processing_engine.eta_refresh_options().interval
+        );
+        tokio::spawn(
+            processing_engine
+                .clone()
+                .run_eta_refresh_loop(storage.clone()),
+        );
+    }
+
+    let legacy_json_fallback_enabled = settings.server.legacy_json_fallback_enabled
+        || std::env::var("RACEBOARD_SERVER__LEGACY_JSON_FALLBACK_ENABLED")
+            .ok()
+            .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+            .unwrap_or(false);
+
+    // races.json is rewritten in the background at most once per interval
+    let (legacy_json_shutdown_tx, legacy_json_shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+    let legacy_json_writer = legacy_json_fallback_enabled.then(|| {
+        Arc::new(legacy_json::LegacyJsonWriter::new(
+            persistence.data_dir(),
+            settings.server.legacy_json_flush_interval(),
+        ))
+    });
+    let legacy_json_task = legacy_json_writer
+        .clone()
+        .map(|writer| tokio::spawn(async move { writer.run(legacy_json_shutdown_rx).await }));
+
+    // Completed races are announced to the outbound webhook from a background queue
+    let (webhook_shutdown_tx, webhook_shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+    let race_webhooks =
+        webhook::WebhookDispatcher::from_config(&settings.webhooks, persistence.data_dir())
+            .map(Arc::new);
+    if let Some(webhooks) = &race_webhooks {
+        log::info!(
+            "Delivering race completion webhooks to {} (dead letters: {:?})",
+            webhooks.url(),
+            webhooks.dead_letter_path()
+        );
+    }
+    let webhook_task = race_webhooks
+        .clone()
+        .map(|webhooks| tokio::spawn(webhooks.run(webhook_shutdown_rx)));
+
     let app_state = AppState {
         storage: storage.clone(),
         prediction_engine: prediction_engine.clone(),
@@ -259,58 +414,89 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         alert_system: alert_system.clone(),
         data_layer_metrics: Some(data_layer_metrics.clone()),
         adapter_registry: adapter_registry.clone(),
-        read_only: settings.server.read_only
-            || std::env::var("RACEBOARD_READ_ONLY")
-                .ok()
-                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
-                .unwrap_or(false),
-        legacy_json_fallback_enabled: settings.server.legacy_json_fallback_enabled
-            || std::env::var("RACEBOARD_SERVER__LEGACY_JSON_FALLBACK_ENABLED")
-                .ok()
-                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
-                .unwrap_or(false),
+        rate_limiter,
+        read_only,
+        write_token: settings.server.write_token.clone(),
+        admin_token: settings.server.admin_token.clone(),
+        races_include_completed: settings.server.races_include_completed,
+        max_title_len: settings.server.max_title_len,
+        settings: Arc::new(config::LiveSettings::new(
+            config::DEFAULT_CONFIG_FILE,
+            settings.clone(),
+        )),
+        legacy_json_fallback_enabled,
+        legacy_json_writer,
+        race_webhooks: race_webhooks.clone(),
+        source_aliases: settings.sources.aliases.clone(),
+        source_eta_policies: settings.sources.eta.clone(),
+        source_persistence: source_persistence.clone(),
     };
     log::info!("Storage: in-memory with ETA prediction and cluster rebuilding");
 
     // Start HTTP server within current Tokio runtime
     let http_settings = settings.clone();
     let http_state = app_state.clone();
+    let cors_config = settings.cors.clone();
     let http_server = HttpServer::new(move || {
         App::new()
             .app_data(web::Data::new(http_state.clone()))
+            .app_data(web::JsonConfig::default().error_handler(error::json_payload_error))
+            .app_data(web::QueryConfig::default().error_handler(error::query_payload_error))
             .wrap(middleware::Logger::default())
-            .wrap(
-                middleware::DefaultHeaders::new()
-                    .add(("Access-Control-Allow-Origin", "*"))
-                    .add((
-                        "Access-Control-Allow-Methods",
-                        "GET, POST, PATCH, DELETE, OPTIONS",
-                    ))
-                    .add(("Access-Control-Allow-Headers", "Content-Type")),
-            )
+            .wrap(cors_config.middleware())
             .service(web::resource("/health").route(web::get().to(handlers::health_check)))
             .service(web::resource("/races").route(web::get().to(handlers::get_races)))
-            .service(web::resource("/race").route(web::post().to(handlers::create_race)))
+            .service(
+                web::resource("/race")
+                    .wrap(middleware::from_fn(rate_limit::limit_writes))
+                    .route(web::post().to(handlers::create_race)),
+            )
             .service(
                 web::resource("/race/{id}")
+                    .wrap(middleware::from_fn(rate_limit::limit_writes))
                     .route(web::get().to(handlers::get_race))
                     .route(web::patch().to(handlers::update_race))
                     .route(web::delete().to(handlers::delete_race)),
             )
-            .service(web::resource("/race/{id}/event").route(web::post().to(handlers::add_event)))
+            .service(
+                web::resource("/race/{id}/event")
+                    .wrap(middleware::from_fn(rate_limit::limit_writes))
+                    .route(web::post().to(handlers::add_event)),
+            )
+            .service(
+                web::resource("/race/{id}/events").route(web::get().to(handlers::get_race_events)),
+            )
+            .service(
+                web::resource("/race/{id}/history")
+                    .route(web::get().to(handlers::get_race_history)),
+            )
+            .service(web::resource("/predict").route(web::post().to(handlers::predict)))
             .service(web::resource("/clusters").route(web::get().to(handlers::get_clusters)))
             .service(web::resource("/cluster/{id}").route(web::get().to(handlers::get_cluster)))
+            .service(
+                web::resource("/cluster/{id}/members")
+                    .route(web::get().to(handlers::get_cluster_members)),
+            )
             // Historic data management
             .service(
                 web::resource("/historic/races").route(web::get().to(handlers::get_historic_races)),
             )
             .service(web::resource("/admin/purge").route(web::post().to(handlers::admin_purge)))
             .service(web::resource("/admin/compact").route(web::post().to(handlers::admin_compact)))
+            .service(web::resource("/admin/reindex").route(web::post().to(handlers::admin_reindex)))
+            .service(
+                web::resource("/admin/checkpoint").route(web::post().to(handlers::admin_checkpoint)),
+            )
             .service(
                 web::resource("/admin/storage-report")
                     .route(web::get().to(handlers::admin_storage_report)),
             )
             .service(web::resource("/admin/metrics").route(web::get().to(handlers::admin_metrics)))
+            .service(web::resource("/admin/audit").route(web::get().to(handlers::admin_audit)))
+            .service(
+                web::resource("/admin/reload-config")
+                    .route(web::post().to(handlers::admin_reload_config)),
+            )
             // Rebuild metrics and debug endpoints
             .service(
                 web::resource("/metrics/rebuild")
@@ -320,6 +506,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 web::resource("/metrics/rollout")
                     .route(web::get().to(handlers::get_rollout_status)),
             )
+            .service(web::resource("/sources").route(web::get().to(handlers::get_sources)))
+            .service(
+                web::resource("/sources/{source}/pause")
+                    .route(web::post().to(handlers::pause_source)),
+            )
+            .service(
+                web::resource("/sources/{source}/resume")
+                    .route(web::post().to(handlers::resume_source)),
+            )
             .service(
                 web::resource("/rollout/enable_all")
                     .route(web::post().to(handlers::enable_all_sources)),
@@ -327,14 +522,39 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             .service(
                 web::resource("/rebuild/trigger").route(web::post().to(handlers::trigger_rebuild)),
             )
+            .service(
+                web::resource("/rebuild/status").route(web::get().to(handlers::get_rebuild_status)),
+            )
             .service(
                 web::resource("/rollout/reset")
                     .route(web::post().to(handlers::reset_rollout_phase)),
             )
+            .service(
+                web::resource("/rollout/history")
+                    .route(web::get().to(handlers::get_rollout_history)),
+            )
+            .service(
+                web::resource("/rollout/source/{source}/promote")
+                    .route(web::post().to(handlers::promote_rollout_source)),
+            )
+            .service(
+                web::resource("/rollout/source/{source}/demote")
+                    .route(web::post().to(handlers::demote_rollout_source)),
+            )
             .service(
                 web::resource("/debug/cluster/{id}")
                     .route(web::get().to(handlers::get_cluster_debug)),
             )
+            .service(
+                web::resource("/debug/distance").route(web::get().to(handlers::debug_distance)),
+            )
+            .service(
+                web::resource("/debug/normalize").route(web::post().to(handlers::debug_normalize)),
+            )
+            .service(
+                web::resource("/debug/eps-curve").route(web::post().to(handlers::debug_eps_curve)),
+            )
+            .service(web::resource("/stats").route(web::get().to(handlers::get_stats)))
             // Adapter status endpoints
             .service(
                 web::resource("/adapter/register")
@@ -359,7 +579,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     })
     .bind(http_settings.http_addr())
     .unwrap()
-    .shutdown_timeout(5)
+    .shutdown_timeout(settings.server.shutdown_timeout().as_secs())
     .run();
 
     let http_handle: ServerHandle = http_server.handle();
@@ -367,12 +587,42 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Start gRPC server with graceful shutdown
     let grpc_settings = settings.clone();
-    let grpc_service = RaceServiceImpl::new(storage.clone(), persistence.clone(), adapter_registry.clone(), settings.server.read_only);
+    let grpc_service = RaceServiceImpl::new(
+        storage.clone(),
+        persistence.clone(),
+        adapter_registry.clone(),
+        read_only,
+        settings.sources.aliases.clone(),
+    )
+    .with_write_token(settings.server.write_token.clone())
+    .with_max_title_len(settings.server.max_title_len)
+    .with_source_persistence(source_persistence)
+    .with_webhooks(race_webhooks);
+    // grpc.health.v1 follows the same storage health as GET /health
+    let (mut health_reporter, health_service) = tonic_health::server::health_reporter();
+    grpc_service::report_health(&mut health_reporter, &monitoring).await;
+    let health_task = tokio::spawn(grpc_service::run_health_reporter(
+        health_reporter.clone(),
+        monitoring.clone(),
+    ));
+    let reflection_service = if settings.server.grpc_reflection {
+        Some(
+            tonic_reflection::server::Builder::configure()
+                .register_encoded_file_descriptor_set(grpc_service::FILE_DESCRIPTOR_SET)
+                .register_encoded_file_descriptor_set(tonic_health::pb::FILE_DESCRIPTOR_SET)
+                .build()
+                .expect("Failed to build gRPC reflection service"),
+        )
+    } else {
+        None
+    };
     let (grpc_shutdown_tx, grpc_shutdown_rx) = tokio::sync::oneshot::channel::<()>();
     let grpc_task = tokio::spawn(async move {
         let addr = grpc_settings.grpc_addr().parse().unwrap();
         log::info!("gRPC server listening on {}", addr);
         GrpcServer::builder()
+            .add_service(health_service)
+            .add_optional_service(reflection_service)
             .add_service(RaceServiceServer::new(grpc_service))
             .serve_with_shutdown(addr, async move {
                 let _ = grpc_shutdown_rx.await;
@@ -429,23 +679,66 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
-    // Trigger shutdown
+    // Trigger shutdown; report NOT_SERVING first so health-checking clients drain
+    health_task.abort();
+    grpc_service::set_health_status(
+        &mut health_reporter,
+        tonic_health::ServingStatus::NotServing,
+    )
+    .await;
     let _ = grpc_shutdown_tx.send(());
     let _ = snapshot_shutdown_tx.send(());
-    let stop_fut = http_handle.stop(true);
-    // Best-effort graceful stop within a timeout
-    let _ = tokio::time::timeout(std::time::Duration::from_secs(5), stop_fut).await;
+    // One deadline shared by every subsystem, so the total wait is bounded
+    let shutdown_timeout = settings.server.shutdown_timeout();
+    let deadline = tokio::time::Instant::now() + shutdown_timeout;
+    log::info!(
+        "Waiting up to {}s for servers and tasks to stop",
+        shutdown_timeout.as_secs()
+    );
+    shutdown::join_by_deadline("HTTP server stop", http_handle.stop(true), deadline).await;
 
     // Await tasks
-    if let Err(e) = grpc_task.await {
-        log::error!("gRPC server error: {:?}", e);
+    match shutdown::join_by_deadline("gRPC server", grpc_task, deadline).await {
+        Some(Ok(Err(e))) => log::error!("gRPC server error: {:?}", e),
+        Some(Err(e)) => log::error!("gRPC server task error: {:?}", e),
+        _ => {}
     }
-    if let Err(e) = http_task.await {
+    if let Some(Err(e)) = shutdown::join_by_deadline("HTTP server", http_task, deadline).await {
         log::error!("HTTP server task error: {:?}", e);
     }
-    if let Err(e) = snapshot_task.await {
+    if let Some(Err(e)) = shutdown::join_by_deadline("Snapshot task", snapshot_task, deadline).await
+    {
         log::error!("Snapshot task error: {:?}", e);
     }
+    // The final flush is not bounded: buffered race writes must reach disk
+    // HTTP has stopped, so no further completions can be queued
+    let _ = legacy_json_shutdown_tx.send(());
+    if let Some(task) = legacy_json_task {
+        if let Err(e) = task.await {
+            log::error!("Legacy JSON writer task error: {:?}", e);
+        }
+    }
+    let _ = webhook_shutdown_tx.send(());
+    if let Some(task) = webhook_task {
+        if let Err(e) = task.await {
+            log::error!("Webhook dispatcher task error: {:?}", e);
+        }
+    }
+    let _ = flush_shutdown_tx.send(());
+    if let Err(e) = flush_task.await {
+        log::error!("Persistence flush task error: {:?}", e);
+    }
 
     Ok(())
 }
+
+/// Another server instance holds the DB lock: explain and exit instead of
+/// silently falling back to a throwaway database.
+fn exit_if_locked(err: &anyhow::Error) {
+    if let Some(PersistenceError::Locked { path }) = err.downcast_ref::<PersistenceError>() {
+        eprintln!("ERROR: Database is locked at {:?}", path);
+        eprintln!("Another instance of the server is likely running.");
+        eprintln!("Please stop it first with: pkill -f raceboard-server");
+        std::process::exit(1);
+    }
+}
@@ -1,14 +1,86 @@
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::HashMap;
 use uuid::Uuid;
 
+// How an ETA was determined. Serialized as the proto EtaSource integer so the
+// JSON and gRPC wire formats are unchanged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EtaSource {
+    Unspecified = 0,
+    Exact = 1,     // Known end time (e.g., calendar events)
+    Adapter = 2,   // Provided by adapter (estimated)
+    Cluster = 3,   // Server prediction from clusters
+    Bootstrap = 4, // Server bootstrap default
+}
+
+impl EtaSource {
+    // Default ETA source for an adapter-supplied ETA from the given race source
+    pub fn default_for_source(source: &str) -> Self {
+        match source {
+            "google-calendar" => EtaSource::Exact,
+            "gitlab" | "github" | "jenkins" => EtaSource::Adapter,
+            _ => EtaSource::Adapter,
+        }
+    }
+
+    pub fn default_confidence(self) -> f64 {
+        match self {
+            EtaSource::Exact => 1.0,
+            EtaSource::Cluster => 0.7,
+            EtaSource::Adapter => 0.5,
+            EtaSource::Bootstrap => 0.2,
+            EtaSource::Unspecified => 0.3,
+        }
+    }
+
+    pub fn default_update_interval(self) -> i32 {
+        match self {
+            EtaSource::Exact => 60,
+            EtaSource::Adapter => 10,
+            EtaSource::Cluster => 15,
+            EtaSource::Bootstrap => 10,
+            EtaSource::Unspecified => 10,
+        }
+    }
+}
+
+impl From<i32> for EtaSource {
+    fn from(value: i32) -> Self {
+        match value {
+            1 => EtaSource::Exact,
+            2 => EtaSource::Adapter,
+            3 => EtaSource::Cluster,
+            4 => EtaSource::Bootstrap,
+            _ => EtaSource::Unspecified,
+        }
+    }
+}
+
+impl From<EtaSource> for i32 {
+    fn from(value: EtaSource) -> Self {
+        value as i32
+    }
+}
+
+impl Serialize for EtaSource {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i32(*self as i32)
+    }
+}
+
+impl<'de> Deserialize<'de> for EtaSource {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        i32::deserialize(deserializer).map(EtaSource::from)
+    }
+}
+
 // For ETA history tracking
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EtaRevision {
     pub eta_sec: i64,
     pub timestamp: DateTime<Utc>,
-    pub source: i32, // Maps to proto EtaSource
+    pub source: EtaSource,
     pub confidence: Option<f64>,
 }
 
@@ -22,6 +94,13 @@ pub enum RaceState {
     Canceled,
 }
 
+impl RaceState {
+    /// Passed, failed and canceled races are final and carry their stats
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, RaceState::Passed | RaceState::Failed | RaceState::Canceled)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Race {
     pub id: String,
@@ -46,7 +125,7 @@ pub struct Race {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub last_eta_update: Option<DateTime<Utc>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub eta_source: Option<i32>, // Maps to proto EtaSource enum
+    pub eta_source: Option<EtaSource>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub eta_confidence: Option<f64>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -115,7 +194,7 @@ impl Race {
                 let revision = EtaRevision {
                     eta_sec,
                     timestamp: Utc::now(),
-                    source: self.eta_source.unwrap_or(0), // Will be inferred later
+                    source: self.eta_source.unwrap_or(EtaSource::Unspecified), // Will be inferred later
                     confidence: self.eta_confidence,
                 };
                 
@@ -159,37 +238,25 @@ impl Race {
     // Infer ETA source based on source name if not set
     pub fn infer_eta_source(&mut self) {
         if self.eta_source.is_none() && self.eta_sec.is_some() {
-            self.eta_source = Some(match self.source.as_str() {
-                "google-calendar" => 1, // EtaSource::Exact
-                "gitlab" | "github" | "jenkins" => 2, // EtaSource::Adapter
-                _ => 2, // Default to Adapter
-            });
+            self.eta_source = Some(EtaSource::default_for_source(&self.source));
         }
     }
     
     // Infer confidence based on source
     pub fn infer_eta_confidence(&mut self) {
-        if self.eta_confidence.is_none() && self.eta_source.is_some() {
-            self.eta_confidence = Some(match self.eta_source.unwrap() {
-                1 => 1.0,   // Exact
-                3 => 0.7,   // Cluster
-                2 => 0.5,   // Adapter
-                4 => 0.2,   // Bootstrap
-                _ => 0.3,
-            });
+        if self.eta_confidence.is_none() {
+            if let Some(eta_source) = self.eta_source {
+                self.eta_confidence = Some(eta_source.default_confidence());
+            }
         }
     }
 
     // Infer update interval hint based on source
     pub fn infer_update_interval_hint(&mut self) {
-        if self.update_interval_hint.is_none() && self.eta_source.is_some() {
-            self.update_interval_hint = Some(match self.eta_source.unwrap() {
-                1 => 60,    // Exact: 60s
-                2 => 10,    // Adapter: 10s
-                3 => 15,    // Cluster: 15s
-                4 => 10,    // Bootstrap: 10s
-                _ => 10,
-            });
+        if self.update_interval_hint.is_none() {
+            if let Some(eta_source) = self.eta_source {
+                self.update_interval_hint = Some(eta_source.default_update_interval());
+            }
         }
     }
 
@@ -213,15 +280,29 @@ pub struct RaceUpdate {
     pub deeplink: Option<String>,
     pub metadata: Option<HashMap<String, String>>,
     // New optional fields from adapters
-    pub eta_source: Option<i32>,
+    pub eta_source: Option<EtaSource>,
     pub eta_confidence: Option<f64>,
     pub update_interval_hint: Option<i32>,
 }
 
+/// Coarse event category for filtering timelines; unknown kinds are rejected on ingress
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    StateChange,
+    ProgressUpdate,
+    #[default]
+    Log,
+    FunctionCall,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Event {
     #[serde(rename = "type")]
     pub event_type: String,
+    /// Defaults to `log` for events from clients that predate typed kinds
+    #[serde(default)]
+    pub kind: EventKind,
     pub data: Option<serde_json::Value>,
     #[serde(default = "Utc::now")]
     pub timestamp: DateTime<Utc>,
@@ -231,10 +312,45 @@ impl Event {
     pub fn new(event_type: String, data: Option<serde_json::Value>) -> Self {
         Event {
             event_type,
+            kind: EventKind::default(),
             data,
             timestamp: Utc::now(),
         }
     }
+
+    pub fn with_kind(mut self, kind: EventKind) -> Self {
+        self.kind = kind;
+        self
+    }
+}
+
+// Canonical source name: trimmed and lowercased, then resolved through the
+// configured alias map (e.g. "gh" -> "github") so casing variants share clusters.
+pub fn normalize_source(source: &str, aliases: &HashMap<String, String>) -> String {
+    let normalized = source.trim().to_lowercase();
+    aliases
+        .iter()
+        .find(|(alias, _)| alias.trim().to_lowercase() == normalized)
+        .map(|(_, canonical)| canonical.trim().to_lowercase())
+        .unwrap_or(normalized)
+}
+
+/// Cut a title longer than `max_chars` characters down to that length, logging
+/// a warning that names `origin`; returns whether it was cut. Titles feed
+/// normalization, edit distance and vector hashing, so one oversized title is
+/// costly everywhere.
+pub fn truncate_title(title: &mut String, max_chars: usize, origin: &str) -> bool {
+    let Some((cut, _)) = title.char_indices().nth(max_chars) else {
+        return false;
+    };
+    log::warn!(
+        "Truncated {}-byte title ({}) to {} characters",
+        title.len(),
+        origin,
+        max_chars
+    );
+    title.truncate(cut);
+    true
 }
 
 // Centralized adapter ID check: "adapter:{type}:{instance}"
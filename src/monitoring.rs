@@ -7,7 +7,11 @@ use tokio::sync::RwLock;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StorageHealth {
+    /// In-memory plus persisted races
     pub total_races: usize,
+    /// Races currently held in memory; `usage_percent` is measured against `max_races`
+    #[serde(default)]
+    pub in_memory_races: usize,
     pub max_races: usize,
     pub usage_percent: f64,
     pub persistence_healthy: bool,
@@ -31,6 +35,7 @@ impl MonitoringSystem {
     pub fn new(max_races: usize) -> Self {
         let initial_health = StorageHealth {
             total_races: 0,
+            in_memory_races: 0,
             max_races,
             usage_percent: 0.0,
             persistence_healthy: true,
@@ -76,14 +81,16 @@ impl MonitoringSystem {
 
     pub async fn update_storage_stats(
         &self,
+        in_memory_races: usize,
         total_races: usize,
         races_by_source: std::collections::HashMap<String, usize>,
     ) {
         let mut health = self.storage_health.write().await;
 
-        // Use in-memory counts for active races; historical counts are tracked separately via analytics
+        // Capacity only bounds the in-memory store; persisted history is unbounded
         health.total_races = total_races;
-        health.usage_percent = (health.total_races as f64 / health.max_races as f64) * 100.0;
+        health.in_memory_races = in_memory_races;
+        health.usage_percent = (in_memory_races as f64 / health.max_races as f64) * 100.0;
         health.races_by_source = races_by_source;
 
         // Clear previous warnings and errors
@@ -161,6 +168,7 @@ impl MonitoringSystem {
         // Count persisted races
         let filter = crate::persistence::RaceScanFilter {
             source: None,
+            sources: None,
             from: None,
             to: None,
             include_events: false,
@@ -192,7 +200,7 @@ impl MonitoringSystem {
         }
 
         let total_races = races_by_source.values().sum();
-        self.update_storage_stats(total_races, races_by_source)
+        self.update_storage_stats(in_memory_races.len(), total_races, races_by_source)
             .await;
     }
 
@@ -254,6 +262,7 @@ impl MonitoringSystem {
                 // Get persisted race counts by source
                 let filter = crate::persistence::RaceScanFilter {
                     source: None,
+                    sources: None,
                     from: None,
                     to: None,
                     include_events: false,
@@ -288,7 +297,7 @@ impl MonitoringSystem {
                 // Use total count from both sources
                 let total_races = races_by_source.values().sum();
                 monitoring
-                    .update_storage_stats(total_races, races_by_source)
+                    .update_storage_stats(in_memory_races.len(), total_races, races_by_source)
                     .await;
 
                 // Log health summary
@@ -306,10 +315,11 @@ impl MonitoringSystem {
                 }
 
                 log::info!(
-                    "Storage health: {}/{} races ({:.1}%), {} evictions, clustering data: {}",
-                    health.total_races,
+                    "Storage health: {}/{} races in memory ({:.1}%), {} total, {} evictions, clustering data: {}",
+                    health.in_memory_races,
                     health.max_races,
                     health.usage_percent,
+                    health.total_races,
                     health.eviction_count,
                     if health.cluster_data_sufficient {
                         "sufficient"
@@ -518,19 +528,30 @@ impl DataLayerMetrics {
 
 // Alert system for critical events
 pub struct AlertSystem {
-    webhook_url: Option<String>,
+    webhook_url: std::sync::RwLock<Option<String>>,
 }
 
 impl AlertSystem {
     pub fn new(webhook_url: Option<String>) -> Self {
-        Self { webhook_url }
+        Self {
+            webhook_url: std::sync::RwLock::new(webhook_url),
+        }
+    }
+
+    /// Point later alerts at a new webhook (or none), e.g. after a config reload
+    pub fn set_webhook_url(&self, webhook_url: Option<String>) {
+        *self.webhook_url.write().unwrap() = webhook_url;
+    }
+
+    pub fn webhook_url(&self) -> Option<String> {
+        self.webhook_url.read().unwrap().clone()
     }
 
     pub async fn send_critical_alert(&self, message: &str) {
         log::error!("CRITICAL ALERT: {}", message);
 
         // Send to webhook if configured
-        if let Some(url) = &self.webhook_url {
+        if let Some(url) = self.webhook_url() {
             let client = reqwest::Client::new();
             let payload = serde_json::json!({
                 "text": format!("🚨 RACEBOARD CRITICAL: {}", message),
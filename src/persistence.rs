@@ -5,8 +5,9 @@ use anyhow::Result;
 use async_trait::async_trait;
 use base64::{engine::general_purpose, Engine as _};
 use chrono::{DateTime, Utc};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 
 #[async_trait]
 pub trait RaceStore: Send + Sync {
@@ -23,6 +24,118 @@ pub struct PersistenceLayer {
     clusters_tree: sled::Tree,
     source_stats_tree: sled::Tree,
     meta_tree: sled::Tree,
+    /// Records that failed checksum verification, keyed by "<tree>/<key>"
+    corrupt_tree: sled::Tree,
+    /// In-flight races written by an explicit checkpoint; never part of history
+    active_snapshot_tree: sled::Tree,
+    /// Every rollout phase transition, append-only, keyed by big-endian `seq`
+    phase_history_tree: sled::Tree,
+    /// Emit per-operation diagnostic chatter at trace level
+    trace: bool,
+    /// Move records failing checksum verification into `corrupt_tree`
+    quarantine_corrupt: bool,
+    /// Directory holding the DB, snapshots and legacy JSON files
+    data_dir: PathBuf,
+    write_batching: WriteBatching,
+    /// Race writes not yet flushed to disk (batching mode only)
+    pending_writes: AtomicUsize,
+    /// Number of race flush points, for throughput diagnostics
+    flush_count: AtomicU64,
+    /// Upper bound on `scan_races` batch sizes, whatever the caller asks for
+    max_scan_batch: usize,
+    /// In-memory copy of the paused set, read on every prediction; writers
+    /// hold its lock across the sled update so no change is lost
+    paused_sources: std::sync::RwLock<HashSet<String>>,
+}
+
+/// Default cap on a single `scan_races` batch
+pub const DEFAULT_MAX_SCAN_BATCH: usize = 10_000;
+
+/// Defer fsyncs of stored races: flush every `max_pending` writes or on the
+/// `interval_ms` timer, trading a short durability window for throughput.
+#[derive(Debug, Clone)]
+pub struct WriteBatching {
+    pub enabled: bool,
+    pub interval_ms: u64,
+    pub max_pending: usize,
+}
+
+impl Default for WriteBatching {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_ms: 500,
+            max_pending: 100,
+        }
+    }
+}
+
+/// Outcome of `persist_all_clusters`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ClusterSyncSummary {
+    pub written: usize,
+    pub deleted: usize,
+    pub unchanged: usize,
+}
+
+const PAUSED_SOURCES_KEY: &[u8] = b"paused_sources";
+
+/// Record header: magic, format version, CRC32 (big-endian) of the payload.
+/// Headerless records predate format version 3 and are still readable.
+const RECORD_MAGIC: [u8; 2] = [0xFE, b'R'];
+const RECORD_FORMAT_VERSION: u8 = 3;
+const RECORD_HEADER_LEN: usize = 7;
+
+/// A stored record whose checksum does not match its payload (disk corruption,
+/// as opposed to a schema mismatch between versions)
+#[derive(Debug)]
+pub struct CorruptRecord {
+    pub reason: String,
+}
+
+impl std::fmt::Display for CorruptRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "corrupt record: {}", self.reason)
+    }
+}
+
+impl std::error::Error for CorruptRecord {}
+
+/// Errors opening the persistence layer that callers may want to handle
+#[derive(Debug)]
+pub enum PersistenceError {
+    /// The DB is held by another process (usually another server instance)
+    Locked { path: PathBuf },
+}
+
+impl std::fmt::Display for PersistenceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PersistenceError::Locked { path } => {
+                write!(f, "database is locked at {:?}", path)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PersistenceError {}
+
+/// Diagnostic logging routed through the layer's trace flag
+macro_rules! ptrace {
+    ($layer:expr, $($arg:tt)+) => {
+        if $layer.trace {
+            log::trace!($($arg)+);
+        }
+    };
+}
+
+fn load_paused_sources(meta_tree: &sled::Tree) -> HashSet<String> {
+    meta_tree
+        .get(PAUSED_SOURCES_KEY)
+        .ok()
+        .flatten()
+        .and_then(|v| serde_json::from_slice(&v).ok())
+        .unwrap_or_default()
 }
 
 impl PersistenceLayer {
@@ -34,6 +147,10 @@ impl PersistenceLayer {
         let clusters_tree = db.open_tree("clusters")?;
         let source_stats_tree = db.open_tree("source_stats")?;
         let meta_tree = db.open_tree("meta")?;
+        let corrupt_tree = db.open_tree("corrupt")?;
+        let active_snapshot_tree = db.open_tree("active_snapshot")?;
+        let phase_history_tree = db.open_tree("phase_history")?;
+        let paused_sources = std::sync::RwLock::new(load_paused_sources(&meta_tree));
         Ok(Self {
             db,
             races_tree,
@@ -41,16 +158,32 @@ impl PersistenceLayer {
             clusters_tree,
             source_stats_tree,
             meta_tree,
+            corrupt_tree,
+            active_snapshot_tree,
+            phase_history_tree,
+            trace: false,
+            quarantine_corrupt: true,
+            data_dir: crate::config::default_data_dir(),
+            write_batching: WriteBatching::default(),
+            pending_writes: AtomicUsize::new(0),
+            flush_count: AtomicU64::new(0),
+            max_scan_batch: DEFAULT_MAX_SCAN_BATCH,
+            paused_sources,
         })
     }
 
+    /// Open `eta_history.db` inside `data_dir`
+    pub fn open_in(data_dir: &Path) -> Result<Self> {
+        Self::new(Some(data_dir.join("eta_history.db")))
+    }
+
     pub fn new(db_path: Option<PathBuf>) -> Result<Self> {
-        let path = db_path.unwrap_or_else(|| {
-            let mut path = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
-            path.push(".raceboard");
-            path.push("eta_history.db");
-            path
-        });
+        let path =
+            db_path.unwrap_or_else(|| crate::config::default_data_dir().join("eta_history.db"));
+        let data_dir = path
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_else(crate::config::default_data_dir);
 
         // Create directory if it doesn't exist
         if let Some(parent) = path.parent() {
@@ -65,6 +198,10 @@ impl PersistenceLayer {
                 let clusters_tree = db.open_tree("clusters")?;
                 let source_stats_tree = db.open_tree("source_stats")?;
                 let meta_tree = db.open_tree("meta")?;
+                let corrupt_tree = db.open_tree("corrupt")?;
+                let active_snapshot_tree = db.open_tree("active_snapshot")?;
+                let phase_history_tree = db.open_tree("phase_history")?;
+                let paused_sources = std::sync::RwLock::new(load_paused_sources(&meta_tree));
                 log::info!("Successfully opened sled database at {:?}", path);
                 let layer = Self {
                     db,
@@ -73,8 +210,19 @@ impl PersistenceLayer {
                     clusters_tree,
                     source_stats_tree,
                     meta_tree,
+                    corrupt_tree,
+                    active_snapshot_tree,
+                    phase_history_tree,
+                    trace: false,
+                    quarantine_corrupt: true,
+                    data_dir,
+                    write_batching: WriteBatching::default(),
+                    pending_writes: AtomicUsize::new(0),
+                    flush_count: AtomicU64::new(0),
+                    max_scan_batch: DEFAULT_MAX_SCAN_BATCH,
+                    paused_sources,
                 };
-                layer.ensure_schema_version(2)?;
+                layer.ensure_schema_version(4)?;
                 Ok(layer)
             }
             Err(e) => {
@@ -82,10 +230,7 @@ impl PersistenceLayer {
                 if e.to_string().contains("could not acquire lock")
                     || e.to_string().contains("Resource temporarily unavailable")
                 {
-                    eprintln!("ERROR: Database is locked at {:?}", path);
-                    eprintln!("Another instance of the server is likely running.");
-                    eprintln!("Please stop it first with: pkill -f raceboard-server");
-                    std::process::exit(1);
+                    Err(PersistenceError::Locked { path }.into())
                 } else {
                     Err(e.into())
                 }
@@ -93,9 +238,122 @@ impl PersistenceLayer {
         }
     }
 
+    /// Enable verbose per-operation diagnostics (logged at trace level)
+    pub fn with_trace(mut self, trace: bool) -> Self {
+        self.trace = trace;
+        self
+    }
+
+    pub fn with_write_batching(mut self, write_batching: WriteBatching) -> Self {
+        self.write_batching = write_batching;
+        self
+    }
+
+    pub fn with_max_scan_batch(mut self, max_scan_batch: usize) -> Self {
+        self.max_scan_batch = max_scan_batch.max(1);
+        self
+    }
+
+    pub fn max_scan_batch(&self) -> usize {
+        self.max_scan_batch
+    }
+
+    /// Flush pending race writes on the batching timer until `shutdown` fires,
+    /// then flush once more so nothing buffered is lost.
+    pub async fn run_flush_loop(&self, mut shutdown: tokio::sync::oneshot::Receiver<()>) {
+        if !self.write_batching.enabled {
+            return;
+        }
+        let period = std::time::Duration::from_millis(self.write_batching.interval_ms.max(1));
+        let mut interval = tokio::time::interval(period);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    if self.pending_writes() > 0 {
+                        if let Err(e) = self.flush_races() {
+                            log::error!("PERSISTENCE: Batched flush failed: {}", e);
+                        }
+                    }
+                }
+                _ = &mut shutdown => break,
+            }
+        }
+        if let Err(e) = self.flush() {
+            log::error!("PERSISTENCE: Final flush on shutdown failed: {}", e);
+        }
+    }
+
+    pub fn pending_writes(&self) -> usize {
+        self.pending_writes.load(Ordering::Relaxed)
+    }
+
+    pub fn flush_count(&self) -> u64 {
+        self.flush_count.load(Ordering::Relaxed)
+    }
+
+    fn flush_races(&self) -> Result<()> {
+        self.pending_writes.store(0, Ordering::Relaxed);
+        self.races_tree.flush()?;
+        self.races_by_time.flush()?;
+        self.flush_count.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Directory holding the DB, snapshots and legacy JSON files
+    pub fn data_dir(&self) -> &Path {
+        &self.data_dir
+    }
+
+    /// Move records that fail checksum verification into the `corrupt` tree
+    pub fn with_quarantine(mut self, quarantine_corrupt: bool) -> Self {
+        self.quarantine_corrupt = quarantine_corrupt;
+        self
+    }
+
+    /// Number of quarantined records awaiting inspection
+    pub fn corrupt_count(&self) -> usize {
+        self.corrupt_tree.len()
+    }
+
+    /// Returns true if `err` is a checksum failure; the record is quarantined when enabled
+    fn handle_corruption(
+        &self,
+        err: &anyhow::Error,
+        tree: &sled::Tree,
+        tree_name: &str,
+        key: &[u8],
+        value: &[u8],
+    ) -> bool {
+        if err.downcast_ref::<CorruptRecord>().is_none() {
+            return false;
+        }
+        let key_str = String::from_utf8_lossy(key);
+        if !self.quarantine_corrupt {
+            return true;
+        }
+        let quarantine_key = format!("{}/{}", tree_name, key_str);
+        let moved = self
+            .corrupt_tree
+            .insert(quarantine_key.as_bytes(), value)
+            .and_then(|_| tree.remove(key))
+            .and_then(|_| self.corrupt_tree.flush().map(|_| ()));
+        match moved {
+            Ok(()) => log::error!(
+                "PERSISTENCE: Quarantined corrupt record {} into corrupt tree",
+                quarantine_key
+            ),
+            Err(e) => log::error!(
+                "PERSISTENCE: Failed to quarantine corrupt record {}: {}",
+                quarantine_key,
+                e
+            ),
+        }
+        true
+    }
+
     pub fn persist_cluster(&self, cluster: &RaceCluster) -> Result<()> {
         let key = cluster.cluster_id.as_bytes();
-        let value = self.serialize_enveloped(cluster, "RaceCluster@2")?;
+        let value = self.serialize_enveloped(cluster, "RaceCluster@3")?;
         // Write to dedicated clusters tree
         self.clusters_tree.insert(key, value)?;
         self.clusters_tree.flush()?;
@@ -110,7 +368,8 @@ impl PersistenceLayer {
             let cluster_id = String::from_utf8_lossy(&key).to_string();
 
             match self.deserialize_enveloped::<RaceCluster>(&value) {
-                Ok(cluster) => {
+                Ok(mut cluster) => {
+                    cluster.stats.ensure_trend();
                     if validate_cluster_data(&cluster).is_ok() {
                         clusters.insert(cluster_id, cluster);
                     } else {
@@ -118,7 +377,9 @@ impl PersistenceLayer {
                     }
                 }
                 Err(e) => {
-                    eprintln!("Failed to deserialize cluster {}: {}", cluster_id, e);
+                    if !self.handle_corruption(&e, &self.clusters_tree, "clusters", &key, &value) {
+                        eprintln!("Failed to deserialize cluster {}: {}", cluster_id, e);
+                    }
                 }
             }
         }
@@ -135,7 +396,8 @@ impl PersistenceLayer {
             if clusters.contains_key(&cluster_id) {
                 continue;
             }
-            if let Ok(cluster) = bincode::deserialize::<RaceCluster>(&value) {
+            if let Ok(mut cluster) = bincode::deserialize::<RaceCluster>(&value) {
+                cluster.stats.ensure_trend();
                 if validate_cluster_data(&cluster).is_ok() {
                     clusters.insert(cluster_id, cluster);
                 }
@@ -158,32 +420,79 @@ impl PersistenceLayer {
         Ok(())
     }
 
-    pub fn persist_all_clusters(&self, clusters: &HashMap<String, RaceCluster>) -> Result<()> {
-        for cluster in clusters.values() {
-            self.persist_cluster(cluster)?;
+    /// Make `clusters_tree` match `clusters`: write only clusters whose content
+    /// hash differs from the stored copy and delete ones no longer present.
+    pub fn persist_all_clusters(
+        &self,
+        clusters: &HashMap<String, RaceCluster>,
+    ) -> Result<ClusterSyncSummary> {
+        let mut stored_hashes = HashMap::new();
+        let mut summary = ClusterSyncSummary::default();
+
+        for item in self.clusters_tree.iter() {
+            let (key, value) = item?;
+            let cluster_id = String::from_utf8_lossy(&key).to_string();
+            if !clusters.contains_key(&cluster_id) {
+                self.clusters_tree.remove(&key)?;
+                summary.deleted += 1;
+                continue;
+            }
+            // Undecodable records just count as changed and get rewritten
+            if let Ok(cluster) = self.deserialize_enveloped::<RaceCluster>(&value) {
+                stored_hashes.insert(cluster_id, cluster.content_hash());
+            }
         }
-        Ok(())
+
+        for (cluster_id, cluster) in clusters {
+            if stored_hashes.get(cluster_id) == Some(&cluster.content_hash()) {
+                summary.unchanged += 1;
+                continue;
+            }
+            let value = self.serialize_enveloped(cluster, "RaceCluster@3")?;
+            self.clusters_tree.insert(cluster_id.as_bytes(), value)?;
+            summary.written += 1;
+        }
+
+        if summary.written + summary.deleted > 0 {
+            self.clusters_tree.flush()?;
+        }
+        ptrace!(
+            self,
+            "PERSISTENCE: Cluster sync wrote {}, deleted {}, kept {}",
+            summary.written,
+            summary.deleted,
+            summary.unchanged
+        );
+        Ok(summary)
     }
 
-    pub fn cleanup_old_data(&self, ttl_days: u32) -> Result<usize> {
+    /// Remove persisted clusters not accessed within `ttl_days`. Bootstrap
+    /// clusters and clusters carrying a bootstrap alias are always kept.
+    /// Returns the removed cluster ids for auditing.
+    pub fn cleanup_old_data(&self, ttl_days: u32) -> Result<Vec<String>> {
         use chrono::{Duration, Utc};
 
         let cutoff = Utc::now() - Duration::days(ttl_days as i64);
-        let mut deleted_count = 0;
+        let mut removed = Vec::new();
 
-        for item in self.db.iter() {
+        for item in self.clusters_tree.iter() {
             let (key, value) = item?;
-
-            if let Ok(cluster) = bincode::deserialize::<RaceCluster>(&value) {
-                if cluster.last_accessed < cutoff {
-                    self.db.remove(key)?;
-                    deleted_count += 1;
-                }
+            let cluster = match self.deserialize_enveloped::<RaceCluster>(&value) {
+                Ok(cluster) => cluster,
+                Err(_) => continue,
+            };
+            let is_protected = cluster.cluster_id.starts_with("bootstrap:")
+                || cluster
+                    .representative_metadata
+                    .contains_key("bootstrap_alias");
+            if !is_protected && cluster.last_accessed < cutoff {
+                self.clusters_tree.remove(&key)?;
+                removed.push(String::from_utf8_lossy(&key).to_string());
             }
         }
 
-        self.db.flush()?;
-        Ok(deleted_count)
+        self.clusters_tree.flush()?;
+        Ok(removed)
     }
 
     pub fn get_db_size(&self) -> Result<u64> {
@@ -193,7 +502,7 @@ impl PersistenceLayer {
     // Source stats persistence methods
     pub fn persist_source_stats(&self, source: &str, stats: &SourceStats) -> Result<()> {
         let key = source.as_bytes();
-        let value = self.serialize_enveloped(stats, "SourceStats@2")?;
+        let value = self.serialize_enveloped(stats, "SourceStats@3")?;
         self.source_stats_tree.insert(key, value)?;
         self.source_stats_tree.flush()?;
         Ok(())
@@ -206,11 +515,20 @@ impl PersistenceLayer {
             let (key, value) = item?;
             let source = String::from_utf8_lossy(&key).to_string();
             match self.deserialize_enveloped::<SourceStats>(&value) {
-                Ok(source_stats) => {
+                Ok(mut source_stats) => {
+                    source_stats.stats.ensure_trend();
                     stats.insert(source, source_stats);
                 }
                 Err(e) => {
-                    eprintln!("Failed to deserialize source stats for {}: {}", source, e);
+                    if !self.handle_corruption(
+                        &e,
+                        &self.source_stats_tree,
+                        "source_stats",
+                        &key,
+                        &value,
+                    ) {
+                        eprintln!("Failed to deserialize source stats for {}: {}", source, e);
+                    }
                 }
             }
         }
@@ -225,7 +543,8 @@ impl PersistenceLayer {
             if stats.contains_key(&source) {
                 continue;
             }
-            if let Ok(source_stats) = bincode::deserialize::<SourceStats>(&value) {
+            if let Ok(mut source_stats) = bincode::deserialize::<SourceStats>(&value) {
+                source_stats.stats.ensure_trend();
                 stats.insert(source, source_stats);
             }
         }
@@ -244,7 +563,7 @@ impl PersistenceLayer {
 #[async_trait]
 impl RaceStore for PersistenceLayer {
     async fn get_all_races(&self) -> Result<Vec<Race>> {
-        log::warn!(
+        log::debug!(
             "PERSISTENCE: get_all_races called, tree has {} items",
             self.races_tree.len()
         );
@@ -253,9 +572,10 @@ impl RaceStore for PersistenceLayer {
         for item in self.races_tree.iter() {
             let (key, value) = item?;
             let id = String::from_utf8_lossy(&key);
-            log::warn!("PERSISTENCE: Found race key: {}", id);
+            ptrace!(self, "PERSISTENCE: Found race key: {}", id);
             match self.deserialize_enveloped::<Race>(&value) {
                 Ok(race) => races.push(race),
+                Err(e) if self.handle_corruption(&e, &self.races_tree, "races", &key, &value) => {}
                 Err(e) => {
                     // Try legacy decode
                     match bincode::deserialize::<Race>(&value) {
@@ -274,14 +594,14 @@ impl RaceStore for PersistenceLayer {
             }
         }
 
-        log::warn!("PERSISTENCE: Returning {} races", races.len());
+        log::debug!("PERSISTENCE: Returning {} races", races.len());
         Ok(races)
     }
 
     async fn store_race(&self, race: &Race) -> Result<()> {
-        log::warn!("PERSISTENCE: Storing race {}", race.id);
+        log::debug!("PERSISTENCE: Storing race {}", race.id);
         let key = race.id.as_bytes();
-        let value = self.serialize_enveloped(race, "Race@2")?;
+        let value = self.serialize_enveloped(race, "Race@3")?;
         // Maintain time index (remove old if started_at changed)
         if let Ok(Some(old)) = self.races_tree.get(key) {
             // Try envelope first, then legacy
@@ -293,17 +613,19 @@ impl RaceStore for PersistenceLayer {
                 let _ = self.races_by_time.remove(old_idx);
             }
         }
-        log::warn!(
+        ptrace!(
+            self,
             "PERSISTENCE: Serialized race {} to {} bytes",
             race.id,
             value.len()
         );
         self.races_tree.insert(key, value.clone())?;
-        log::warn!("PERSISTENCE: Inserted race {} into tree", race.id);
+        ptrace!(self, "PERSISTENCE: Inserted race {} into tree", race.id);
 
         // Debug: immediately try to read it back
         if let Ok(Some(stored)) = self.races_tree.get(key) {
-            log::warn!(
+            ptrace!(
+                self,
                 "PERSISTENCE: Read back {} bytes for race {}",
                 stored.len(),
                 race.id
@@ -317,7 +639,8 @@ impl RaceStore for PersistenceLayer {
             }
             // Try to deserialize immediately
             match self.deserialize_enveloped::<Race>(&stored) {
-                Ok(_) => log::warn!(
+                Ok(_) => ptrace!(
+                    self,
                     "PERSISTENCE: Successfully deserialized race {} immediately after store",
                     race.id
                 ),
@@ -331,13 +654,20 @@ impl RaceStore for PersistenceLayer {
         // Insert new index key
         let idx_key = Self::encode_time_index(&race.started_at, &race.id);
         self.races_by_time.insert(idx_key, &[])?;
-        self.races_tree.flush()?;
-        self.races_by_time.flush()?;
-        log::warn!("PERSISTENCE: Flushed race {} to disk", race.id);
+        if !self.write_batching.enabled {
+            self.flush_races()?;
+            ptrace!(self, "PERSISTENCE: Flushed race {} to disk", race.id);
+        } else {
+            let pending = self.pending_writes.fetch_add(1, Ordering::Relaxed) + 1;
+            if pending >= self.write_batching.max_pending.max(1) {
+                self.flush_races()?;
+                ptrace!(self, "PERSISTENCE: Flushed batch of {} races to disk", pending);
+            }
+        }
 
         // Verify it was stored
         if let Ok(Some(_)) = self.races_tree.get(key) {
-            log::warn!("PERSISTENCE: Verified race {} exists in tree", race.id);
+            ptrace!(self, "PERSISTENCE: Verified race {} exists in tree", race.id);
         } else {
             log::error!("PERSISTENCE: Race {} NOT FOUND after store!", race.id);
         }
@@ -388,15 +718,44 @@ fn validate_cluster_data(cluster: &RaceCluster) -> Result<()> {
 #[derive(Debug, Clone)]
 pub struct RaceScanFilter {
     pub source: Option<String>,
+    /// Only races from one of these sources, e.g. a per-source rebuild
+    pub sources: Option<HashSet<String>>,
     pub from: Option<DateTime<Utc>>,
     pub to: Option<DateTime<Utc>>,
     pub include_events: bool,
 }
 
+impl RaceScanFilter {
+    fn admits_source(&self, source: &str) -> bool {
+        self.source.as_deref().is_none_or(|s| s == source)
+            && self.sources.as_ref().is_none_or(|set| set.contains(source))
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ReindexReport {
+    /// Index entries written from `races_tree`
+    pub rebuilt: usize,
+    /// Index entries that pointed at missing or moved races
+    pub orphans_dropped: usize,
+    /// Stored races that could not be decoded and were left unindexed
+    pub undecodable: usize,
+}
+
+/// Entry written by `write_audit_record`
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct AuditRecord {
+    pub kind: String,
+    pub recorded_at: DateTime<Utc>,
+    pub payload: serde_json::Value,
+}
+
 #[derive(Debug, Clone)]
 pub struct RaceBatch {
     pub items: Vec<Race>,
     pub next_cursor: Option<String>,
+    /// Batch size actually applied after clamping to `[1, max_scan_batch]`
+    pub batch_size: usize,
 }
 
 impl PersistenceLayer {
@@ -423,6 +782,11 @@ impl PersistenceLayer {
         Some((dt, id))
     }
 
+    /// Whether `cursor` is one `scan_races` handed out (as opposed to garbage)
+    pub fn is_valid_cursor(cursor: &str) -> bool {
+        Self::decode_cursor(cursor).is_some()
+    }
+
     fn encode_cursor(ts: &DateTime<Utc>, id: &str) -> String {
         let obj = serde_json::json!({
             "sec": ts.timestamp(),
@@ -439,15 +803,33 @@ impl PersistenceLayer {
         batch_size: usize,
         cursor: Option<String>,
     ) -> Result<RaceBatch> {
-        log::warn!(
-            "SCAN: Starting scan with filter: source={:?}, from={:?}, to={:?}, batch_size={}",
+        let requested = batch_size;
+        // A zero batch would never advance the cursor
+        let batch_size = requested.clamp(1, self.max_scan_batch);
+        if batch_size != requested {
+            log::debug!(
+                "SCAN: Clamped batch_size {} to {} (max_scan_batch={})",
+                requested,
+                batch_size,
+                self.max_scan_batch
+            );
+        }
+        log::debug!(
+            "SCAN: Starting scan with filter: source={:?}, sources={:?}, from={:?}, to={:?}, batch_size={}",
             filter.source,
+            filter.sources,
             filter.from,
             filter.to,
             batch_size
         );
-        let start_key = if let Some(c) = cursor.as_ref().and_then(|c| Self::decode_cursor(c)) {
-            let (ts, id) = c;
+        let decoded = match cursor.as_deref() {
+            // Restarting from page 1 on a bad cursor would loop paging clients forever
+            Some(c) => Some(
+                Self::decode_cursor(c).ok_or_else(|| anyhow::anyhow!("invalid cursor: {}", c))?,
+            ),
+            None => None,
+        };
+        let start_key = if let Some((ts, id)) = decoded {
             // Start strictly after the cursor key
             let mut k = Self::encode_time_index(&ts, &id);
             k.push(0x00);
@@ -467,12 +849,12 @@ impl PersistenceLayer {
             None
         };
 
-        log::warn!("SCAN: Index has {} entries", self.races_by_time.len());
+        ptrace!(self, "SCAN: Index has {} entries", self.races_by_time.len());
         let range = match end_bound {
             Some(end) => self.races_by_time.range(start_key..=end),
             None => self.races_by_time.range(start_key..),
         };
-        log::warn!("SCAN: Created range iterator");
+        ptrace!(self, "SCAN: Created range iterator");
 
         let mut items = Vec::with_capacity(batch_size);
         let mut last_ts: Option<DateTime<Utc>> = None;
@@ -497,15 +879,16 @@ impl PersistenceLayer {
 
             // Fetch race record
             if let Some(val) = self.races_tree.get(id.as_bytes())? {
-                log::debug!("SCAN: Found race {} in tree, attempting deserialize", id);
+                ptrace!(self, "SCAN: Found race {} in tree, attempting deserialize", id);
                 // Envelope or legacy
                 if let Ok(mut race) = self.deserialize_enveloped::<Race>(&val) {
-                    if let Some(ref src) = filter.source {
-                        if &race.source != src {
-                            log::debug!("SCAN: Filtering out race {} with source '{}' (looking for '{}')", 
-                                id, race.source, src);
-                            continue;
-                        }
+                    if !filter.admits_source(&race.source) {
+                        log::debug!(
+                            "SCAN: Filtering out race {} with source '{}'",
+                            id,
+                            race.source
+                        );
+                        continue;
                     }
                     if !filter.include_events {
                         race.events = None;
@@ -517,13 +900,14 @@ impl PersistenceLayer {
                         break;
                     }
                 } else if let Ok(mut race) = bincode::deserialize::<Race>(&val) {
-                    log::warn!("SCAN: Deserialized race {} via legacy format", id);
-                    if let Some(ref src) = filter.source {
-                        if &race.source != src {
-                            log::debug!("SCAN: Filtering out legacy race {} with source '{}' (looking for '{}')", 
-                                id, race.source, src);
-                            continue;
-                        }
+                    log::debug!("SCAN: Deserialized race {} via legacy format", id);
+                    if !filter.admits_source(&race.source) {
+                        log::debug!(
+                            "SCAN: Filtering out legacy race {} with source '{}'",
+                            id,
+                            race.source
+                        );
+                        continue;
                     }
                     if !filter.include_events {
                         race.events = None;
@@ -537,6 +921,14 @@ impl PersistenceLayer {
                 } else {
                     // Log detailed error
                     match self.deserialize_enveloped::<Race>(&val) {
+                        Err(e1)
+                            if self.handle_corruption(
+                                &e1,
+                                &self.races_tree,
+                                "races",
+                                id.as_bytes(),
+                                &val,
+                            ) => {}
                         Err(e1) => match bincode::deserialize::<Race>(&val) {
                             Err(e2) => {
                                 log::error!("SCAN: Failed to deserialize race {} - envelope: {}, legacy: {}", id, e1, e2);
@@ -551,7 +943,7 @@ impl PersistenceLayer {
             }
         }
 
-        log::warn!(
+        log::debug!(
             "SCAN: Iterated {} index entries, collected {} items",
             count,
             items.len()
@@ -567,10 +959,16 @@ impl PersistenceLayer {
             None
         };
 
-        Ok(RaceBatch { items, next_cursor })
+        Ok(RaceBatch {
+            items,
+            next_cursor,
+            batch_size,
+        })
     }
 
+    /// Synchronous durability point for all trees, including batched race writes
     pub fn flush(&self) -> Result<()> {
+        self.pending_writes.store(0, Ordering::Relaxed);
         self.db.flush()?;
         Ok(())
     }
@@ -583,6 +981,84 @@ impl PersistenceLayer {
         Ok(())
     }
 
+    /// Audit records (optionally of one `kind`) recorded within `[from, to]`,
+    /// newest first. Keys end in the write time in nanoseconds.
+    pub fn read_audit_records(
+        &self,
+        kind: Option<&str>,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+        limit: usize,
+    ) -> Result<Vec<AuditRecord>> {
+        let prefix = match kind {
+            Some(kind) => format!("audit:{}:", kind),
+            None => "audit:".to_string(),
+        };
+
+        let mut records = Vec::new();
+        for entry in self.meta_tree.scan_prefix(prefix.as_bytes()) {
+            let (key, value) = entry?;
+            let key = String::from_utf8_lossy(&key);
+            let Some((kind, nanos)) = key
+                .strip_prefix("audit:")
+                .and_then(|rest| rest.rsplit_once(':'))
+            else {
+                continue;
+            };
+            let Ok(nanos) = nanos.parse::<i64>() else {
+                continue;
+            };
+            let recorded_at = DateTime::<Utc>::from_timestamp_nanos(nanos);
+            if from.is_some_and(|from| recorded_at < from) || to.is_some_and(|to| recorded_at > to)
+            {
+                continue;
+            }
+            let payload = match serde_json::from_slice(&value) {
+                Ok(payload) => payload,
+                Err(e) => {
+                    log::warn!("Skipping undecodable audit record {}: {}", key, e);
+                    continue;
+                }
+            };
+            records.push(AuditRecord {
+                kind: kind.to_string(),
+                recorded_at,
+                payload,
+            });
+        }
+
+        records.sort_by_key(|r| std::cmp::Reverse(r.recorded_at));
+        records.truncate(limit);
+        Ok(records)
+    }
+
+    /// Sources excluded from clustering and cluster/source-average predictions
+    pub fn paused_sources(&self) -> HashSet<String> {
+        self.paused_sources.read().unwrap().clone()
+    }
+
+    pub fn is_source_paused(&self, source: &str) -> bool {
+        self.paused_sources.read().unwrap().contains(source)
+    }
+
+    /// Pause or resume `source`; returns whether its state changed
+    pub fn set_source_paused(&self, source: &str, paused: bool) -> Result<bool> {
+        let mut cached = self.paused_sources.write().unwrap();
+        let mut sources = cached.clone();
+        let changed = if paused {
+            sources.insert(source.to_string())
+        } else {
+            sources.remove(source)
+        };
+        if changed {
+            self.meta_tree
+                .insert(PAUSED_SOURCES_KEY, serde_json::to_vec(&sources)?)?;
+            self.meta_tree.flush()?;
+            *cached = sources;
+        }
+        Ok(changed)
+    }
+
     pub fn get_schema_version(&self) -> Option<String> {
         self.meta_tree
             .get(b"schema_version")
@@ -591,6 +1067,66 @@ impl PersistenceLayer {
             .map(|v| String::from_utf8_lossy(&v).to_string())
     }
 
+    /// Replace the active-race snapshot with `races`; returns how many were written
+    pub fn checkpoint_active_races(&self, races: &[Race]) -> Result<usize> {
+        let mut batch = sled::Batch::default();
+        for key in self.active_snapshot_tree.iter().keys() {
+            batch.remove(key?);
+        }
+        for race in races {
+            let value = self.serialize_enveloped(race, "Race@3")?;
+            batch.insert(race.id.as_bytes(), value);
+        }
+        self.active_snapshot_tree.apply_batch(batch)?;
+        self.active_snapshot_tree.flush()?;
+        log::info!("Checkpointed {} active races", races.len());
+        Ok(races.len())
+    }
+
+    /// Races from the last checkpoint, consuming the snapshot so a later restart
+    /// cannot resurrect them
+    pub fn take_active_snapshot(&self) -> Result<Vec<Race>> {
+        let mut races = Vec::new();
+        for item in self.active_snapshot_tree.iter() {
+            let (key, value) = item?;
+            match self.deserialize_enveloped::<Race>(&value) {
+                Ok(race) => races.push(race),
+                Err(e) => {
+                    if !self.handle_corruption(
+                        &e,
+                        &self.active_snapshot_tree,
+                        "active_snapshot",
+                        &key,
+                        &value,
+                    ) {
+                        log::error!(
+                            "Failed to decode checkpointed race {}: {}",
+                            String::from_utf8_lossy(&key),
+                            e
+                        );
+                    }
+                }
+            }
+        }
+        self.active_snapshot_tree.clear()?;
+        self.active_snapshot_tree.flush()?;
+        Ok(races)
+    }
+
+    /// Load a single persisted race (envelope or legacy encoding)
+    pub fn get_race(&self, race_id: &str) -> Result<Option<Race>> {
+        let Some(value) = self.races_tree.get(race_id.as_bytes())? else {
+            return Ok(None);
+        };
+        match self.deserialize_enveloped::<Race>(&value) {
+            Ok(race) => Ok(Some(race)),
+            Err(e) if e.downcast_ref::<CorruptRecord>().is_some() => Err(e),
+            Err(e) => bincode::deserialize::<Race>(&value)
+                .map(Some)
+                .map_err(|e2| anyhow::anyhow!("{} / legacy: {}", e, e2)),
+        }
+    }
+
     pub fn races_count(&self) -> usize {
         self.races_tree.len()
     }
@@ -599,6 +1135,59 @@ impl PersistenceLayer {
         self.races_by_time.len()
     }
 
+    /// Rebuild `races_by_time` from `races_tree`, dropping index entries that
+    /// no longer point at a stored race.
+    pub fn reindex_races(&self) -> Result<ReindexReport> {
+        let mut expected = std::collections::BTreeSet::new();
+        let mut undecodable = 0;
+        for item in self.races_tree.iter() {
+            let (key, value) = item?;
+            let race = self
+                .deserialize_enveloped::<Race>(&value)
+                .or_else(|_| bincode::deserialize::<Race>(&value).map_err(anyhow::Error::from));
+            match race {
+                Ok(race) => {
+                    expected.insert(Self::encode_time_index(&race.started_at, &race.id));
+                }
+                Err(e) => {
+                    undecodable += 1;
+                    log::error!(
+                        "REINDEX: Skipping race {}: {}",
+                        String::from_utf8_lossy(&key),
+                        e
+                    );
+                }
+            }
+        }
+
+        let mut orphans_dropped = 0;
+        for item in self.races_by_time.iter() {
+            let (key, _) = item?;
+            if !expected.contains(key.as_ref()) {
+                orphans_dropped += 1;
+            }
+        }
+
+        self.races_by_time.clear()?;
+        for key in expected.iter() {
+            self.races_by_time.insert(key.as_slice(), &[])?;
+        }
+        self.races_by_time.flush()?;
+
+        let report = ReindexReport {
+            rebuilt: expected.len(),
+            orphans_dropped,
+            undecodable,
+        };
+        log::info!(
+            "REINDEX: rebuilt={} orphans_dropped={} undecodable={}",
+            report.rebuilt,
+            report.orphans_dropped,
+            report.undecodable
+        );
+        Ok(report)
+    }
+
     pub fn is_migration_complete(&self) -> bool {
         self.meta_tree
             .get(b"__migration_complete__")
@@ -624,12 +1213,49 @@ impl PersistenceLayer {
         value: &T,
         _schema_tag: &str,
     ) -> Result<Vec<u8>> {
-        // Use JSON serialization for all data
-        serde_json::to_vec(value).map_err(|e| anyhow::anyhow!("serialize failed: {}", e))
+        // JSON payload behind a checksummed header
+        let payload =
+            serde_json::to_vec(value).map_err(|e| anyhow::anyhow!("serialize failed: {}", e))?;
+        let mut record = Vec::with_capacity(RECORD_HEADER_LEN + payload.len());
+        record.extend_from_slice(&RECORD_MAGIC);
+        record.push(RECORD_FORMAT_VERSION);
+        record.extend_from_slice(&crc32fast::hash(&payload).to_be_bytes());
+        record.extend_from_slice(&payload);
+        Ok(record)
     }
 
     fn deserialize_enveloped<T: serde::de::DeserializeOwned>(&self, data: &[u8]) -> Result<T> {
-        // Try JSON first (current format)
+        if data.starts_with(&RECORD_MAGIC) {
+            if data.len() < RECORD_HEADER_LEN {
+                log::error!("PERSISTENCE: Corruption detected: truncated record header");
+                return Err(CorruptRecord {
+                    reason: format!("truncated header ({} bytes)", data.len()),
+                }
+                .into());
+            }
+            let version = data[2];
+            let stored = u32::from_be_bytes([data[3], data[4], data[5], data[6]]);
+            let payload = &data[RECORD_HEADER_LEN..];
+            let computed = crc32fast::hash(payload);
+            if stored != computed {
+                log::error!(
+                    "PERSISTENCE: Corruption detected: checksum {:08x} does not match payload {:08x}",
+                    stored,
+                    computed
+                );
+                return Err(CorruptRecord {
+                    reason: format!("checksum mismatch ({:08x} != {:08x})", stored, computed),
+                }
+                .into());
+            }
+            if version != RECORD_FORMAT_VERSION {
+                return Err(anyhow::anyhow!("unsupported record format version {}", version));
+            }
+            return serde_json::from_slice(payload)
+                .map_err(|e| anyhow::anyhow!("schema mismatch: {}", e));
+        }
+
+        // Headerless records: try JSON first (format version 2)
         match serde_json::from_slice(data) {
             Ok(v) => Ok(v),
             Err(_) => {
@@ -639,12 +1265,22 @@ impl PersistenceLayer {
         }
     }
 
+    /// Record `version` on a fresh database and raise an older one. Older
+    /// records keep loading (version 4 stores cluster `member_titles` as
+    /// title counts; the earlier title lists are read and tallied).
     fn ensure_schema_version(&self, version: u32) -> Result<()> {
         let key = b"schema_version";
         if let Some(val) = self.meta_tree.get(key)? {
             let existing = String::from_utf8_lossy(&val);
-            log::info!("Schema version present: {}", existing);
-            return Ok(());
+            match existing.parse::<u32>() {
+                Ok(old) if old < version => {
+                    log::info!("Upgrading schema version {} -> {}", old, version);
+                }
+                _ => {
+                    log::info!("Schema version present: {}", existing);
+                    return Ok(());
+                }
+            }
         }
         self.meta_tree.insert(key, version.to_string().as_bytes())?;
         self.meta_tree.flush()?;
@@ -659,8 +1295,7 @@ impl PersistenceLayer {
         use std::io::Write;
 
         // Determine snapshot path
-        let mut path = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
-        path.push(".raceboard");
+        let mut path = self.data_dir.clone();
         std::fs::create_dir_all(&path)?;
 
         let timestamp = Utc::now().format("%Y%m%d_%H%M%S");
@@ -733,8 +1368,7 @@ impl PersistenceLayer {
         use chrono::Duration;
 
         let cutoff = Utc::now() - Duration::days(retention_days as i64);
-        let mut snapshots_dir = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
-        snapshots_dir.push(".raceboard");
+        let snapshots_dir = self.data_dir.clone();
 
         if let Ok(entries) = std::fs::read_dir(&snapshots_dir) {
             for entry in entries.flatten() {
@@ -770,10 +1404,23 @@ impl PersistenceLayer {
     }
 
     // Persist and load rollout configuration
+    /// Save the rollout state and append its transitions newer than the
+    /// history store's last one
     pub fn persist_rollout_config(
         &self,
         rollout: &crate::phased_rollout::PhasedRollout,
     ) -> Result<()> {
+        let stored_seq = self.last_phase_transition_seq()?;
+        let mut batch = sled::Batch::default();
+        for transition in rollout.phase_history.iter().filter(|t| t.seq > stored_seq) {
+            batch.insert(
+                &transition.seq.to_be_bytes(),
+                serde_json::to_vec(transition)?,
+            );
+        }
+        self.phase_history_tree.apply_batch(batch)?;
+        self.phase_history_tree.flush()?;
+
         let key = b"rollout_config";
         let value = serde_json::to_vec(rollout)?;
         self.meta_tree.insert(key, value)?;
@@ -782,6 +1429,35 @@ impl PersistenceLayer {
         Ok(())
     }
 
+    /// `seq` of the newest transition in the history store, 0 when empty
+    pub fn last_phase_transition_seq(&self) -> Result<u64> {
+        Ok(self
+            .phase_history_tree
+            .last()?
+            .and_then(|(key, _)| key.as_ref().try_into().ok())
+            .map(u64::from_be_bytes)
+            .unwrap_or(0))
+    }
+
+    /// Stored phase transitions within `[from, to]`, oldest first
+    pub fn phase_transitions(
+        &self,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> Result<Vec<crate::phased_rollout::PhaseTransition>> {
+        let mut transitions = Vec::new();
+        for value in self.phase_history_tree.iter().values() {
+            let transition: crate::phased_rollout::PhaseTransition =
+                serde_json::from_slice(&value?)?;
+            if from.is_none_or(|from| transition.timestamp >= from)
+                && to.is_none_or(|to| transition.timestamp <= to)
+            {
+                transitions.push(transition);
+            }
+        }
+        Ok(transitions)
+    }
+
     pub fn load_rollout_config(&self) -> Result<Option<crate::phased_rollout::PhasedRollout>> {
         if let Some(value) = self.meta_tree.get(b"rollout_config")? {
             match serde_json::from_slice(&value) {
@@ -859,7 +1535,7 @@ impl PersistenceLayer {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::stats::ExecutionStats;
+    use crate::cluster::test_utils::test_cluster;
     use chrono::Utc;
     use tempfile::TempDir;
 
@@ -870,20 +1546,7 @@ mod tests {
 
         let persistence = PersistenceLayer::new(Some(db_path))?;
 
-        let cluster = RaceCluster {
-            cluster_id: "test:cluster".to_string(),
-            source: "test".to_string(),
-            representative_title: "Test Title".to_string(),
-            representative_metadata: HashMap::new(),
-            stats: ExecutionStats::new_with_default(30),
-            member_race_ids: vec!["race1".to_string()],
-            member_titles: vec!["Test Title".to_string()],
-            member_metadata_history: vec![],
-            last_updated: Utc::now(),
-            last_accessed: Utc::now(),
-        };
-
-        persistence.persist_cluster(&cluster)?;
+        persistence.persist_cluster(&test_cluster("test:cluster", &["race1"]))?;
 
         let loaded_clusters = persistence.load_clusters()?;
         assert_eq!(loaded_clusters.len(), 1);
@@ -891,4 +1554,440 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_persist_all_clusters_writes_only_changes() -> Result<()> {
+        let persistence = PersistenceLayer::new_in_memory()?;
+        let mut clusters: HashMap<String, RaceCluster> = (0..3)
+            .map(|i| {
+                let id = format!("test:cluster{}", i);
+                let cluster = test_cluster(&id, &[&format!("race{}", i)]);
+                (id, cluster)
+            })
+            .collect();
+
+        let first = persistence.persist_all_clusters(&clusters)?;
+        assert_eq!(first.written, 3);
+
+        // A rebuild reproduces every cluster with fresh timestamps, changing one
+        for cluster in clusters.values_mut() {
+            cluster.last_updated = Utc::now() + chrono::Duration::seconds(5);
+            cluster.last_accessed = cluster.last_updated;
+        }
+        let changed = clusters.get_mut("test:cluster1").unwrap();
+        changed.member_race_ids.push("race9".to_string());
+        let second = persistence.persist_all_clusters(&clusters)?;
+        assert_eq!(
+            second,
+            ClusterSyncSummary {
+                written: 1,
+                deleted: 0,
+                unchanged: 2
+            }
+        );
+
+        clusters.remove("test:cluster2");
+        let third = persistence.persist_all_clusters(&clusters)?;
+        assert_eq!((third.written, third.deleted), (0, 1));
+        let loaded = persistence.load_clusters()?;
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded["test:cluster1"].member_race_ids.len(), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_active_checkpoint_roundtrip() -> Result<()> {
+        use crate::models::RaceState;
+        use crate::storage::Storage;
+
+        let temp_dir = TempDir::new()?;
+        let storage = Storage::new();
+        for (id, progress) in [("build-1", 10), ("build-2", 60)] {
+            let mut race = Race::new("cargo".to_string(), format!("cargo build {}", id));
+            race.id = id.to_string();
+            race.state = RaceState::Running;
+            race.progress = Some(progress);
+            storage.create_or_update_race(race).await;
+        }
+
+        {
+            let persistence = PersistenceLayer::open_in(temp_dir.path())?;
+            let written = persistence.checkpoint_active_races(&storage.get_all_races().await)?;
+            assert_eq!(written, 2);
+            // Checkpoints never leak into the historical store
+            assert_eq!(persistence.races_count(), 0);
+        }
+
+        // Simulate a restart. sled's background threadpool can hold the file
+        // lock briefly after drop, so block on the lock before reopening.
+        let db_file = std::fs::File::open(temp_dir.path().join("eta_history.db").join("db"))?;
+        db_file.lock()?;
+        drop(db_file);
+
+        let persistence = PersistenceLayer::open_in(temp_dir.path())?;
+        let restored_storage = Storage::new();
+        for race in persistence.take_active_snapshot()? {
+            restored_storage.create_or_update_race(race).await;
+        }
+        let restored = restored_storage.get_race("build-2").await.unwrap();
+        assert!(matches!(restored.state, RaceState::Running));
+        assert_eq!(restored.progress, Some(60));
+        assert_eq!(restored_storage.get_all_races().await.len(), 2);
+
+        // The snapshot is consumed by the restore
+        assert!(persistence.take_active_snapshot()?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_concurrent_source_pauses_are_all_kept() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let persistence = std::sync::Arc::new(PersistenceLayer::open_in(temp_dir.path())?);
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let persistence = persistence.clone();
+                std::thread::spawn(move || {
+                    persistence.set_source_paused(&format!("src{}", i), true)
+                })
+            })
+            .collect();
+        for handle in handles {
+            assert!(handle.join().unwrap()?);
+        }
+
+        let paused = persistence.paused_sources();
+        assert_eq!(paused.len(), 8);
+        assert!((0..8).all(|i| persistence.is_source_paused(&format!("src{}", i))));
+        assert!(persistence.set_source_paused("src3", false)?);
+        assert!(!persistence.is_source_paused("src3"));
+        assert_eq!(load_paused_sources(&persistence.meta_tree).len(), 7);
+        Ok(())
+    }
+
+    struct CaptureLogger;
+
+    static CAPTURED: std::sync::Mutex<Vec<(log::Level, String)>> =
+        std::sync::Mutex::new(Vec::new());
+
+    impl log::Log for CaptureLogger {
+        fn enabled(&self, _: &log::Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record) {
+            CAPTURED
+                .lock()
+                .unwrap()
+                .push((record.level(), record.args().to_string()));
+        }
+
+        fn flush(&self) {}
+    }
+
+    fn captured_for(race_id: &str) -> Vec<(log::Level, String)> {
+        CAPTURED
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, msg)| msg.contains(race_id))
+            .cloned()
+            .collect()
+    }
+
+    fn install_capture_logger() {
+        static INIT: std::sync::Once = std::sync::Once::new();
+        INIT.call_once(|| {
+            let _ = log::set_logger(&CaptureLogger);
+            log::set_max_level(log::LevelFilter::Trace);
+        });
+    }
+
+    #[tokio::test]
+    async fn test_store_race_is_quiet_without_trace_flag() -> Result<()> {
+        install_capture_logger();
+        let persistence = PersistenceLayer::new_in_memory()?;
+        let mut race = Race::new("test".to_string(), "Quiet".to_string());
+        race.id = "quiet-race".to_string();
+
+        persistence.store_race(&race).await?;
+        persistence.get_all_races().await?;
+
+        let logs = captured_for("quiet-race");
+        assert!(!logs.is_empty(), "expected debug output for the store");
+        assert!(logs.iter().all(|(level, _)| *level > log::Level::Warn));
+        assert!(logs.iter().all(|(level, _)| *level != log::Level::Trace));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_store_race_traces_with_flag() -> Result<()> {
+        install_capture_logger();
+        let persistence = PersistenceLayer::new_in_memory()?.with_trace(true);
+        let mut race = Race::new("test".to_string(), "Traced".to_string());
+        race.id = "traced-race".to_string();
+
+        persistence.store_race(&race).await?;
+
+        let logs = captured_for("traced-race");
+        assert!(logs.iter().any(|(level, _)| *level == log::Level::Trace));
+        assert!(logs.iter().all(|(level, _)| *level > log::Level::Warn));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_flipped_byte_is_detected_and_quarantined() -> Result<()> {
+        let persistence = PersistenceLayer::new_in_memory()?;
+        let mut race = Race::new("test".to_string(), "Checksummed".to_string());
+        race.id = "crc-race".to_string();
+        persistence.store_race(&race).await?;
+
+        let mut stored = persistence.races_tree.get(b"crc-race")?.unwrap().to_vec();
+        let last = stored.len() - 2;
+        stored[last] ^= 0x01;
+        persistence.races_tree.insert(b"crc-race", stored)?;
+
+        let err = persistence
+            .deserialize_enveloped::<Race>(&persistence.races_tree.get(b"crc-race")?.unwrap())
+            .unwrap_err();
+        assert!(err.downcast_ref::<CorruptRecord>().is_some());
+
+        let races = persistence.get_all_races().await?;
+        assert!(races.iter().all(|r| r.id != "crc-race"));
+        assert!(persistence.races_tree.get(b"crc-race")?.is_none());
+        assert_eq!(persistence.corrupt_count(), 1);
+        assert!(persistence.corrupt_tree.get(b"races/crc-race")?.is_some());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_headerless_records_still_readable() -> Result<()> {
+        let persistence = PersistenceLayer::new_in_memory()?;
+        let mut race = Race::new("test".to_string(), "Legacy".to_string());
+        race.id = "legacy-race".to_string();
+
+        // Format version 2 stored plain JSON without a header
+        persistence
+            .races_tree
+            .insert(b"legacy-race", serde_json::to_vec(&race)?)?;
+
+        let races = persistence.get_all_races().await?;
+        assert_eq!(races.len(), 1);
+        assert_eq!(races[0].title, "Legacy");
+
+        // Schema mismatches are not treated as corruption
+        let err = persistence.deserialize_enveloped::<Race>(b"{\"id\": 1}").unwrap_err();
+        assert!(err.downcast_ref::<CorruptRecord>().is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reindex_recovers_drifted_index() -> Result<()> {
+        let persistence = PersistenceLayer::new_in_memory()?;
+        let mut races = Vec::new();
+        for i in 0..3 {
+            let mut race = Race::new("test".to_string(), format!("Race {}", i));
+            race.id = format!("reindex-{}", i);
+            persistence.store_race(&race).await?;
+            races.push(race);
+        }
+
+        // Simulate a crash between the two flushes plus a stale entry
+        persistence
+            .races_by_time
+            .remove(PersistenceLayer::encode_time_index(&races[1].started_at, &races[1].id))?;
+        persistence.races_by_time.insert(
+            PersistenceLayer::encode_time_index(&Utc::now(), "deleted-race"),
+            &[],
+        )?;
+
+        let filter = || RaceScanFilter {
+            source: None,
+            sources: None,
+            from: None,
+            to: None,
+            include_events: false,
+        };
+        let before = persistence.scan_races(filter(), 100, None).await?;
+        assert_eq!(before.items.len(), 2);
+
+        let report = persistence.reindex_races()?;
+        assert_eq!(report.rebuilt, 3);
+        assert_eq!(report.orphans_dropped, 1);
+        assert_eq!(report.undecodable, 0);
+
+        let after = persistence.scan_races(filter(), 100, None).await?;
+        assert_eq!(after.items.len(), 3);
+        assert_eq!(persistence.index_entries(), 3);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_scan_batch_size_is_clamped_and_cursor_covers_all() -> Result<()> {
+        let persistence = PersistenceLayer::new_in_memory()?.with_max_scan_batch(2);
+        for i in 0..5 {
+            let mut race = Race::new("test".to_string(), format!("Race {}", i));
+            race.id = format!("clamp-{}", i);
+            race.started_at = Utc::now() + chrono::Duration::seconds(i);
+            persistence.store_race(&race).await?;
+        }
+        let filter = || RaceScanFilter {
+            source: None,
+            sources: None,
+            from: None,
+            to: None,
+            include_events: false,
+        };
+
+        let mut seen = Vec::new();
+        let mut cursor = None;
+        loop {
+            let batch = persistence.scan_races(filter(), 1_000_000, cursor).await?;
+            assert_eq!(batch.batch_size, 2);
+            assert!(batch.items.len() <= 2);
+            seen.extend(batch.items.into_iter().map(|r| r.id));
+            cursor = batch.next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+        assert_eq!(seen.len(), 5);
+        seen.dedup();
+        assert_eq!(seen.len(), 5);
+
+        // Zero is raised to one rather than returning an endless empty cursor
+        let batch = persistence.scan_races(filter(), 0, None).await?;
+        assert_eq!(batch.batch_size, 1);
+        assert_eq!(batch.items.len(), 1);
+        assert!(batch.next_cursor.is_some());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_data_dir_holds_db_and_snapshots() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let storage = crate::config::StorageConfig {
+            max_races: 100,
+            max_events_per_race: 10,
+            persistence_trace: false,
+            quarantine_corrupt: true,
+            data_dir: Some(temp_dir.path().join("raceboard")),
+            write_batching_enabled: true,
+            flush_interval_ms: 500,
+            flush_max_pending: 100,
+            max_scan_batch: 10_000,
+            eviction_events: true,
+        };
+        let data_dir = storage.data_dir();
+
+        let persistence = PersistenceLayer::open_in(&data_dir)?;
+        assert_eq!(persistence.data_dir(), data_dir.as_path());
+        assert!(data_dir.join("eta_history.db").exists());
+
+        let race = Race::new("test".to_string(), "Snapshot".to_string());
+        persistence.store_race(&race).await?;
+        persistence.create_json_snapshot().await?;
+
+        let snapshots: Vec<_> = std::fs::read_dir(&data_dir)?
+            .flatten()
+            .filter(|e| e.file_name().to_string_lossy().starts_with("races.snapshot."))
+            .collect();
+        assert!(!snapshots.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_opening_locked_db_returns_locked_error() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let db_path = temp_dir.path().join("locked.db");
+        let _first = PersistenceLayer::new(Some(db_path.clone()))?;
+
+        let err = match PersistenceLayer::new(Some(db_path.clone())) {
+            Ok(_) => panic!("second open of the same DB should fail"),
+            Err(e) => e,
+        };
+        match err.downcast_ref::<PersistenceError>() {
+            Some(PersistenceError::Locked { path }) => assert_eq!(path, &db_path),
+            None => panic!("expected PersistenceError::Locked, got {}", err),
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_write_batching_reduces_flushes_under_burst() -> Result<()> {
+        async fn burst(persistence: &PersistenceLayer) -> Result<()> {
+            for i in 0..200 {
+                let mut race = Race::new("burst".to_string(), format!("Pipeline {}", i));
+                race.id = format!("burst-{}", i);
+                persistence.store_race(&race).await?;
+            }
+            Ok(())
+        }
+
+        let unbatched = PersistenceLayer::new_in_memory()?.with_write_batching(WriteBatching {
+            enabled: false,
+            ..WriteBatching::default()
+        });
+        burst(&unbatched).await?;
+        assert_eq!(unbatched.flush_count(), 200);
+
+        let batched = PersistenceLayer::new_in_memory()?.with_write_batching(WriteBatching {
+            enabled: true,
+            interval_ms: 60_000,
+            max_pending: 50,
+        });
+        burst(&batched).await?;
+        assert_eq!(batched.flush_count(), 4);
+        assert_eq!(batched.pending_writes(), 0);
+        assert_eq!(batched.races_count(), 200);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_flush_loop_flushes_pending_on_shutdown() -> Result<()> {
+        let persistence = PersistenceLayer::new_in_memory()?.with_write_batching(WriteBatching {
+            enabled: true,
+            interval_ms: 60_000,
+            max_pending: 100,
+        });
+        let persistence = std::sync::Arc::new(persistence);
+        let race = Race::new("test".to_string(), "Buffered".to_string());
+        persistence.store_race(&race).await?;
+        assert_eq!(persistence.pending_writes(), 1);
+
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        let task = {
+            let persistence = persistence.clone();
+            tokio::spawn(async move { persistence.run_flush_loop(rx).await })
+        };
+        let _ = tx.send(());
+        task.await?;
+        assert_eq!(persistence.pending_writes(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_cleanup_removes_only_stale_clusters() -> Result<()> {
+        let persistence = PersistenceLayer::new_in_memory()?;
+        let cluster = |id: &str, days_ago: i64| RaceCluster {
+            last_accessed: Utc::now() - chrono::Duration::days(days_ago),
+            ..test_cluster(id, &["race1"])
+        };
+
+        persistence.persist_cluster(&cluster("test:fresh", 1))?;
+        persistence.persist_cluster(&cluster("test:stale", 90))?;
+        let mut aliased = cluster("test:aliased", 90);
+        aliased
+            .representative_metadata
+            .insert("bootstrap_alias".to_string(), "bootstrap:ci".to_string());
+        persistence.persist_cluster(&aliased)?;
+
+        let removed = persistence.cleanup_old_data(30)?;
+        assert_eq!(removed, vec!["test:stale".to_string()]);
+
+        let remaining = persistence.load_clusters()?;
+        assert!(remaining.contains_key("test:fresh"));
+        assert!(remaining.contains_key("test:aliased"));
+        assert!(!remaining.contains_key("test:stale"));
+        Ok(())
+    }
 }
@@ -4,13 +4,21 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 use crate::hnsw_dbscan::{ValidationCriteria, ValidationResult};
-use crate::rebuild::SourceConfig;
+use crate::rebuild::{NoisePolicy, SourceConfig};
+
+/// Transitions kept on the rollout state, which is persisted with every
+/// change; the full history is appended to its own store tree
+pub const MAX_PHASE_HISTORY: usize = 100;
 
 /// Phased rollout controller for gradual cluster rebuilding deployment
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PhasedRollout {
     pub current_phase: RolloutPhase,
+    /// The last `MAX_PHASE_HISTORY` transitions
     pub phase_history: Vec<PhaseTransition>,
+    /// `seq` of the newest transition
+    #[serde(default)]
+    pub last_transition_seq: u64,
     pub source_status: HashMap<String, SourceRolloutStatus>,
     pub global_metrics: RolloutMetrics,
     pub config: RolloutConfig,
@@ -30,13 +38,27 @@ pub enum RolloutPhase {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PhaseTransition {
+    /// Position in the full history, from 1; 0 until `sequence_transitions`
+    /// numbers transitions saved before it was tracked
+    #[serde(default)]
+    pub seq: u64,
     pub from_phase: RolloutPhase,
     pub to_phase: RolloutPhase,
     pub timestamp: DateTime<Utc>,
     pub reason: String,
+    /// Who asked for a manual change; `None` for automatic transitions
+    #[serde(default)]
+    pub requested_by: Option<String>,
     pub metrics_snapshot: RolloutMetrics,
 }
 
+/// Operator-supplied context for a manual rollout change
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ManualChange {
+    pub reason: Option<String>,
+    pub requested_by: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SourceRolloutStatus {
     pub source: String,
@@ -49,6 +71,13 @@ pub struct SourceRolloutStatus {
     pub validation_results: Vec<ValidationResult>,
 }
 
+impl SourceRolloutStatus {
+    /// Share of recorded rebuilds that passed validation; 0 before any
+    pub fn success_rate(&self) -> f64 {
+        self.success_count as f64 / (self.success_count + self.failure_count).max(1) as f64
+    }
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
 pub enum RolloutMode {
     /// Not yet enabled for rebuilding
@@ -79,11 +108,31 @@ pub struct RolloutConfig {
     pub canary_duration: Duration,
     pub canary_percentage: u8,
     pub success_threshold: f64, // e.g., 0.95 for 95% success rate
-    pub min_rebuilds_for_promotion: u32,
+    /// Successful rebuilds a shadow source needs before promotion to canary
+    #[serde(default = "default_shadow_promotion_successes")]
+    pub shadow_promotion_successes: u32,
+    /// Successful rebuilds a canary source needs before promotion to production
+    #[serde(default = "default_canary_promotion_successes")]
+    pub canary_promotion_successes: u32,
     pub auto_rollback: bool,
+    /// With auto_rollback, a Rollback phase older than this is recovered automatically
+    #[serde(default = "default_rollback_recovery_after")]
+    pub rollback_recovery_after: Duration,
     pub validation_criteria: ValidationCriteria,
 }
 
+fn default_rollback_recovery_after() -> Duration {
+    Duration::hours(1)
+}
+
+fn default_shadow_promotion_successes() -> u32 {
+    5
+}
+
+fn default_canary_promotion_successes() -> u32 {
+    10
+}
+
 impl Default for RolloutConfig {
     fn default() -> Self {
         Self {
@@ -92,8 +141,10 @@ impl Default for RolloutConfig {
             canary_duration: Duration::days(2),
             canary_percentage: 10,
             success_threshold: 0.95,
-            min_rebuilds_for_promotion: 10,
+            shadow_promotion_successes: default_shadow_promotion_successes(),
+            canary_promotion_successes: default_canary_promotion_successes(),
             auto_rollback: true,
+            rollback_recovery_after: default_rollback_recovery_after(),
             validation_criteria: ValidationCriteria::default(),
         }
     }
@@ -105,6 +156,7 @@ impl PhasedRollout {
         Self {
             current_phase: RolloutPhase::SingleSource,
             phase_history: Vec::new(),
+            last_transition_seq: 0,
             source_status: HashMap::new(),
             global_metrics: RolloutMetrics {
                 total_rebuilds: 0,
@@ -144,6 +196,13 @@ impl PhasedRollout {
                         tau_merge_lo: 0.35,
                         tau_merge_hi: 0.6,
                         last_eps: None,
+                        stats_window: crate::stats::DEFAULT_WINDOW_SIZE,
+                        default_eta_sec: None,
+                        noise_policy: NoisePolicy::default(),
+                        recency_half_life_days: None,
+                        metadata_key_weights: HashMap::new(),
+                        min_samples_for_prediction: 1,
+                        rebuild_interval_hours: None,
                     },
                     validation_results: Vec::new(),
                 },
@@ -159,12 +218,18 @@ impl PhasedRollout {
     }
 
     /// Enable all known sources with a given rollout mode
-    pub fn enable_all_sources(&mut self, mode: RolloutMode) {
+    pub fn enable_all_sources(&mut self, mode: RolloutMode, change: &ManualChange) {
         for (_source, status) in self.source_status.iter_mut() {
             status.enabled = true;
             status.mode = mode;
             status.last_rebuild = status.last_rebuild.or(Some(Utc::now()));
         }
+        self.add_manual_transition(
+            self.current_phase,
+            self.current_phase,
+            &format!("Manually enabled all sources in {:?} mode", mode),
+            change,
+        );
     }
 
     /// Start Phase 1: Enable single pilot source
@@ -197,9 +262,7 @@ impl PhasedRollout {
                 ));
             }
 
-            let success_rate = status.success_count as f64
-                / (status.success_count + status.failure_count).max(1) as f64;
-
+            let success_rate = status.success_rate();
             if success_rate < self.config.success_threshold {
                 return Err(anyhow::anyhow!(
                     "Success rate {:.2}% below threshold {:.2}%",
@@ -227,6 +290,15 @@ impl PhasedRollout {
                 ));
             }
 
+            let success_rate = status.success_rate();
+            if success_rate < self.config.success_threshold {
+                return Err(anyhow::anyhow!(
+                    "Success rate {:.2}% below threshold {:.2}%",
+                    success_rate * 100.0,
+                    self.config.success_threshold * 100.0
+                ));
+            }
+
             status.mode = RolloutMode::Production;
             Ok(())
         } else {
@@ -234,6 +306,30 @@ impl PhasedRollout {
         }
     }
 
+    /// Move source one stage back: production → canary → shadow → disabled
+    pub fn demote(&mut self, source: &str) -> Result<()> {
+        let canary_percentage = self.config.canary_percentage;
+        let status = self
+            .source_status
+            .get_mut(source)
+            .ok_or_else(|| anyhow::anyhow!("Source not found: {}", source))?;
+
+        status.mode = match status.mode {
+            RolloutMode::Production => RolloutMode::Canary {
+                percentage: canary_percentage,
+            },
+            RolloutMode::Canary { .. } => RolloutMode::Shadow,
+            RolloutMode::Shadow => {
+                status.enabled = false;
+                RolloutMode::Disabled
+            }
+            RolloutMode::Disabled => {
+                return Err(anyhow::anyhow!("Source is already disabled"));
+            }
+        };
+        Ok(())
+    }
+
     /// Advance to next phase if criteria met
     pub fn try_advance_phase(&mut self) -> Result<bool> {
         match self.current_phase {
@@ -241,7 +337,7 @@ impl PhasedRollout {
                 // Check if pilot source is ready for production
                 if let Some(status) = self.source_status.get(&self.config.pilot_source) {
                     if status.mode == RolloutMode::Production
-                        && status.success_count >= self.config.min_rebuilds_for_promotion
+                        && status.success_count >= self.config.canary_promotion_successes
                     {
                         // Move to Phase 2: Enable all sources conservatively
                         self.current_phase = RolloutPhase::AllSourcesConservative;
@@ -277,7 +373,7 @@ impl PhasedRollout {
                     .map(|s| s.success_count)
                     .sum::<u32>();
 
-                if all_production && total_success >= self.config.min_rebuilds_for_promotion * 5 {
+                if all_production && total_success >= self.config.canary_promotion_successes * 5 {
                     // Move to Phase 3: Enable automatic tuning
                     self.current_phase = RolloutPhase::AutomaticTuning;
 
@@ -377,15 +473,104 @@ impl PhasedRollout {
         self.add_transition(prev_phase, RolloutPhase::Rollback, reason.to_string());
     }
 
+    /// Leave a Rollback that has lasted `rollback_recovery_after` (only with
+    /// auto_rollback): resume the phase it interrupted, with its sources back in
+    /// shadow mode and their recent validation results cleared so old failures
+    /// don't immediately roll back again. Returns the re-enabled sources, or
+    /// None when there is nothing to recover yet.
+    pub fn recover_stale_rollback(&mut self, now: DateTime<Utc>) -> Option<Vec<String>> {
+        if !self.config.auto_rollback || self.current_phase != RolloutPhase::Rollback {
+            return None;
+        }
+        // Manual changes while rolled back are logged as Rollback → Rollback;
+        // the rollback itself is the last entry into it from another phase
+        let rollback = self
+            .phase_history
+            .iter()
+            .rev()
+            .find(|t| {
+                t.to_phase == RolloutPhase::Rollback && t.from_phase != RolloutPhase::Rollback
+            })
+            .map(|t| (t.timestamp, t.from_phase));
+        // Without a recorded transition the rollback's age is unknown; don't stay wedged
+        if let Some((since, _)) = rollback {
+            if now - since < self.config.rollback_recovery_after {
+                return None;
+            }
+        }
+        let resume_phase = rollback.map_or(RolloutPhase::SingleSource, |(_, phase)| phase);
+
+        let mut recovered = Vec::new();
+        for (source, status) in self.source_status.iter_mut() {
+            if resume_phase == RolloutPhase::SingleSource && *source != self.config.pilot_source {
+                continue;
+            }
+            status.enabled = true;
+            status.mode = RolloutMode::Shadow;
+            status.validation_results.clear();
+            recovered.push(source.clone());
+        }
+        recovered.sort();
+
+        self.current_phase = resume_phase;
+        self.add_transition(
+            RolloutPhase::Rollback,
+            resume_phase,
+            format!(
+                "Auto-recovered after {} minutes in rollback",
+                self.config.rollback_recovery_after.num_minutes()
+            ),
+        );
+        Some(recovered)
+    }
+
     /// Add phase transition to history
-    fn add_transition(&mut self, from: RolloutPhase, to: RolloutPhase, reason: String) {
+    pub fn add_transition(&mut self, from: RolloutPhase, to: RolloutPhase, reason: String) {
+        self.last_transition_seq += 1;
         self.phase_history.push(PhaseTransition {
+            seq: self.last_transition_seq,
             from_phase: from,
             to_phase: to,
             timestamp: Utc::now(),
             reason,
+            requested_by: None,
             metrics_snapshot: self.global_metrics.clone(),
         });
+        if self.phase_history.len() > MAX_PHASE_HISTORY {
+            let excess = self.phase_history.len() - MAX_PHASE_HISTORY;
+            self.phase_history.drain(..excess);
+        }
+    }
+
+    /// Number unsequenced transitions from a rollout saved before `seq`
+    /// existed, continuing after `stored_seq`, the newest one already in the
+    /// history store
+    pub fn sequence_transitions(&mut self, stored_seq: u64) {
+        let mut seq = self.last_transition_seq.max(stored_seq);
+        for transition in self.phase_history.iter_mut().filter(|t| t.seq == 0) {
+            seq += 1;
+            transition.seq = seq;
+        }
+        self.last_transition_seq = seq;
+    }
+
+    /// Add a transition for an operator action, appending the supplied reason
+    /// to `action` and recording who requested it
+    pub fn add_manual_transition(
+        &mut self,
+        from: RolloutPhase,
+        to: RolloutPhase,
+        action: &str,
+        change: &ManualChange,
+    ) {
+        let reason = match change.reason.as_deref().map(str::trim) {
+            Some(reason) if !reason.is_empty() => format!("{}: {}", action, reason),
+            _ => action.to_string(),
+        };
+        self.add_transition(from, to, reason);
+        if let Some(transition) = self.phase_history.last_mut() {
+            transition.requested_by = change.requested_by.clone();
+        }
     }
 
     /// Update global metrics with new result
@@ -424,3 +609,24 @@ impl PhasedRollout {
         }
     }
 }
+
+#[cfg(test)]
+impl PhasedRollout {
+    /// Register `source` in `mode` (enabled unless `Disabled`) with the given
+    /// rebuild record
+    pub fn seed_source(
+        &mut self,
+        source: &str,
+        mode: RolloutMode,
+        successes: u32,
+        failures: u32,
+    ) -> &mut SourceRolloutStatus {
+        self.register_source(source);
+        let status = self.source_status.get_mut(source).unwrap();
+        status.enabled = mode != RolloutMode::Disabled;
+        status.mode = mode;
+        status.success_count = successes;
+        status.failure_count = failures;
+        status
+    }
+}
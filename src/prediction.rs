@@ -1,6 +1,7 @@
 use crate::cluster::{extract_operation_type, ClusteringEngine};
 use crate::persistence::PersistenceLayer;
-use crate::stats::{EtaPrediction, ExecutionStats};
+use crate::rebuild::{BootstrapPattern, RebuildConfig, SourceConfig};
+use crate::stats::{EtaPrediction, ExecutionStats, PredictionMethod, DEFAULT_FALLBACK_CHAIN};
 use chrono::{DateTime, Utc};
 use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
@@ -10,8 +11,18 @@ pub struct PredictionEngine {
     pub clustering_engine: Arc<ClusteringEngine>,
     persistence: Arc<PersistenceLayer>,
     source_stats: Arc<RwLock<HashMap<String, SourceStats>>>,
+    source_configs: HashMap<String, SourceConfig>,
+    bootstrap_patterns: Vec<BootstrapPattern>,
+    /// `min_samples_for_prediction` of sources without a tuned config
+    default_min_samples_for_prediction: usize,
+    fallback_chains: HashMap<String, Vec<PredictionMethod>>,
+    /// ETA of the `GlobalDefault` stage
+    default_eta_sec: i64,
 }
 
+/// ETA when nothing more specific is known
+const GLOBAL_DEFAULT_ETA_SEC: i64 = 30;
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct SourceStats {
     pub source: String,
@@ -37,9 +48,46 @@ impl PredictionEngine {
             clustering_engine,
             persistence,
             source_stats: Arc::new(RwLock::new(initial_stats)),
+            source_configs: HashMap::new(),
+            bootstrap_patterns: Vec::new(),
+            default_min_samples_for_prediction: 1,
+            fallback_chains: HashMap::new(),
+            default_eta_sec: GLOBAL_DEFAULT_ETA_SEC,
         }
     }
 
+    /// ETA reported when no stage knows anything about a race (at least 1s)
+    pub fn with_default_eta(mut self, default_eta_sec: i64) -> Self {
+        self.default_eta_sec = default_eta_sec.max(1);
+        self
+    }
+
+    /// Use per-source `default_eta_sec` and bootstrap patterns for cold-start
+    /// predictions, and per-source `min_samples_for_prediction` for clusters
+    pub fn with_cold_start_defaults(mut self, config: &RebuildConfig) -> Self {
+        self.source_configs = config.source_configs.clone();
+        self.bootstrap_patterns = config.bootstrap_patterns.clone();
+        self.default_min_samples_for_prediction =
+            config.default_source_config.min_samples_for_prediction;
+        self
+    }
+
+    /// Completed runs a cluster of `source` needs before it produces an ETA
+    pub fn min_samples_for_prediction(&self, source: &str) -> usize {
+        self.source_configs
+            .get(source)
+            .map_or(self.default_min_samples_for_prediction, |c| {
+                c.min_samples_for_prediction
+            })
+            .max(1)
+    }
+
+    /// Per-source prediction stage order; other sources use `DEFAULT_FALLBACK_CHAIN`
+    pub fn with_fallback_chains(mut self, chains: HashMap<String, Vec<PredictionMethod>>) -> Self {
+        self.fallback_chains = chains;
+        self
+    }
+
     pub async fn predict_eta(
         &self,
         race_id: &str,
@@ -47,58 +95,123 @@ impl PredictionEngine {
         race_source: &str,
         race_metadata: &HashMap<String, String>,
     ) -> EtaPrediction {
-        // Level 1: Try to find best matching cluster
-        if let Some(cluster_id) = self
-            .clustering_engine
-            .find_best_cluster(race_id, race_title, race_source, race_metadata)
-            .await
-        {
-            if let Some(prediction) = self.clustering_engine.get_cluster_eta(&cluster_id).await {
-                if prediction.confidence > 0.3 {
-                    return prediction;
+        let chain = self
+            .fallback_chains
+            .get(race_source)
+            .map(Vec::as_slice)
+            .unwrap_or(&DEFAULT_FALLBACK_CHAIN);
+
+        // A paused source keeps only the defaults until it is resumed
+        let paused = self.persistence.is_source_paused(race_source);
+
+        for method in chain {
+            let prediction = match method {
+                PredictionMethod::Cluster | PredictionMethod::SourceAverage if paused => None,
+                PredictionMethod::Cluster => {
+                    self.cluster_prediction(race_id, race_title, race_source, race_metadata)
+                        .await
+                }
+                PredictionMethod::SourceAverage => {
+                    self.source_average_prediction(race_source).await
                 }
+                PredictionMethod::Bootstrap => self
+                    .get_bootstrap_default(race_source, race_title, race_metadata)
+                    .map(|eta| Self::fixed_prediction(eta, 0.2, PredictionMethod::Bootstrap)),
+                PredictionMethod::GlobalDefault => Some(self.global_default()),
+            };
+            // A zero or negative ETA means the stage had nothing real to go on
+            if let Some(prediction) = prediction.filter(|p| p.expected_seconds > 0) {
+                return prediction;
             }
         }
 
-        // Level 2: Source-level statistics
-        let source_stats = self.source_stats.read().await;
-        if let Some(source_stat) = source_stats.get(race_source) {
-            if source_stat.execution_history.len() >= 5 {
-                let prediction = source_stat.stats.calculate_eta();
-                // Boost confidence slightly for source-level stats
-                return EtaPrediction {
-                    expected_seconds: prediction.expected_seconds,
-                    confidence: (prediction.confidence * 0.7).min(0.6),
-                    lower_bound: prediction.lower_bound,
-                    upper_bound: prediction.upper_bound,
-                };
-            }
+        // A chain without GlobalDefault still has to produce something
+        self.global_default()
+    }
+
+    async fn cluster_prediction(
+        &self,
+        race_id: &str,
+        race_title: &str,
+        race_source: &str,
+        race_metadata: &HashMap<String, String>,
+    ) -> Option<EtaPrediction> {
+        // Cold start: nothing has been clustered yet
+        if self.clustering_engine.clusters.read().await.is_empty() {
+            log::debug!(
+                "No clusters yet; skipping cluster prediction for {}",
+                race_id
+            );
+            return None;
         }
-        drop(source_stats);
+        let cluster_id = self
+            .clustering_engine
+            .find_best_cluster(race_id, race_title, race_source, race_metadata)
+            .await?;
+        self.clustering_engine
+            .predict_from_cluster(&cluster_id, self.min_samples_for_prediction(race_source))
+            .await
+            .filter(|prediction| prediction.confidence > 0.3)
+    }
 
-        // Level 3: Bootstrap defaults
-        let default_eta = self
-            .get_bootstrap_default(race_source, race_title, race_metadata)
-            .await;
+    async fn source_average_prediction(&self, race_source: &str) -> Option<EtaPrediction> {
+        let source_stats = self.source_stats.read().await;
+        let source_stat = source_stats.get(race_source)?;
+        if source_stat.execution_history.len() < 5 {
+            return None;
+        }
+        let prediction = source_stat.stats.calculate_eta_with(
+            &self.clustering_engine.trend_adjustment,
+            &self.clustering_engine.percentiles,
+        );
+        // Source-level stats are less specific than a cluster match
+        Some(EtaPrediction {
+            confidence: (prediction.confidence * 0.7).min(0.6),
+            method: PredictionMethod::SourceAverage,
+            ..prediction
+        })
+    }
 
+    fn fixed_prediction(eta: i64, confidence: f64, method: PredictionMethod) -> EtaPrediction {
         EtaPrediction {
-            expected_seconds: default_eta,
-            confidence: 0.2,
-            lower_bound: (default_eta as f64 * 0.5) as i64,
-            upper_bound: (default_eta as f64 * 2.0) as i64,
+            expected_seconds: eta,
+            confidence,
+            lower_bound: (eta as f64 * 0.5) as i64,
+            upper_bound: (eta as f64 * 2.0) as i64,
+            method,
         }
     }
 
-    async fn get_bootstrap_default(
+    fn global_default(&self) -> EtaPrediction {
+        Self::fixed_prediction(self.default_eta_sec, 0.1, PredictionMethod::GlobalDefault)
+    }
+
+    fn get_bootstrap_default(
         &self,
         source: &str,
         title: &str,
         metadata: &HashMap<String, String>,
-    ) -> i64 {
+    ) -> Option<i64> {
+        // Configured cold-start defaults take precedence over the built-in table
+        if let Some(pattern) = self
+            .bootstrap_patterns
+            .iter()
+            .find(|p| p.matches_race(source, title))
+        {
+            return Some(pattern.default_eta);
+        }
+        if let Some(default_eta) = self
+            .source_configs
+            .get(source)
+            .and_then(|c| c.default_eta_sec)
+        {
+            return Some(default_eta);
+        }
+
         let operation = extract_operation_type(source, title, metadata);
 
         // Match against bootstrap patterns
-        match (source, operation.as_str()) {
+        let eta = match (source, operation.as_str()) {
             ("claude-code", "simple_prompt") => 15,
             ("claude-code", "code_generation") => 30,
             ("claude-code", "complex_analysis") => 45,
@@ -129,8 +242,9 @@ impl PredictionEngine {
             ("jenkins", "deploy_production") => 600,
             ("jenkins", _) => 300,
 
-            _ => 30, // Ultimate fallback
-        }
+            _ => return None, // Unknown source: GlobalDefault's job
+        };
+        Some(eta)
     }
 
     pub async fn update_source_stats(&self, source: &str, duration: i64) {
@@ -153,7 +267,9 @@ impl PredictionEngine {
         }
 
         // Update statistics with full history
-        entry.stats.update_with_duration(duration);
+        entry
+            .stats
+            .update_with_duration_filtered(duration, &self.clustering_engine.outlier_filter);
         entry.last_updated = Utc::now();
     }
 
@@ -165,6 +281,15 @@ impl PredictionEngine {
         race_metadata: &HashMap<String, String>,
         duration: i64,
     ) {
+        if self.persistence.is_source_paused(race_source) {
+            log::debug!(
+                "Not clustering race {} of paused source {}",
+                race_id,
+                race_source
+            );
+            return;
+        }
+
         // Assign to cluster if not already assigned
         let cluster_id = self
             .clustering_engine
@@ -220,20 +345,260 @@ mod tests {
 
         let metadata = HashMap::new();
 
-        let eta = predictor
-            .get_bootstrap_default("cargo", "cargo build", &metadata)
+        let eta = predictor.get_bootstrap_default("cargo", "cargo build", &metadata);
+        assert_eq!(eta, Some(5)); // incremental_build
+
+        let eta = predictor.get_bootstrap_default("cargo", "cargo test", &metadata);
+        assert_eq!(eta, Some(30)); // test_suite
+
+        // Unknown sources are left to the global default
+        let eta = predictor.get_bootstrap_default("unknown", "something", &metadata);
+        assert_eq!(eta, None);
+        let prediction = predictor
+            .predict_eta("r1", "something", "unknown", &metadata)
             .await;
-        assert_eq!(eta, 5); // incremental_build
+        assert_eq!(prediction.expected_seconds, 30);
+        assert_eq!(prediction.method, PredictionMethod::GlobalDefault);
+    }
+
+    #[tokio::test]
+    async fn test_prediction_bumps_cluster_last_accessed() {
+        let clustering_engine = Arc::new(ClusteringEngine::new(100));
+        let persistence = Arc::new(PersistenceLayer::new_in_memory().unwrap());
+        let metadata = HashMap::new();
+        let cluster_id = clustering_engine
+            .assign_race_to_cluster("r1", "cargo build", "cargo", &metadata)
+            .await;
+        let stale = Utc::now() - chrono::Duration::hours(1);
+        clustering_engine
+            .clusters
+            .write()
+            .await
+            .get_mut(&cluster_id)
+            .unwrap()
+            .last_accessed = stale;
 
-        let eta = predictor
-            .get_bootstrap_default("cargo", "cargo test", &metadata)
+        let predictor = PredictionEngine::new(clustering_engine.clone(), persistence);
+        predictor
+            .predict_eta("r2", "cargo build", "cargo", &metadata)
             .await;
-        assert_eq!(eta, 30); // test_suite
 
-        let eta = predictor
-            .get_bootstrap_default("unknown", "something", &metadata)
+        let clusters = clustering_engine.clusters.read().await;
+        assert!(clusters[&cluster_id].last_accessed > stale);
+    }
+
+    #[tokio::test]
+    async fn test_cold_start_uses_configured_defaults() {
+        let clustering_engine = Arc::new(ClusteringEngine::new(100));
+        let persistence = Arc::new(PersistenceLayer::new_in_memory().unwrap());
+
+        let mut config = RebuildConfig::default();
+        config.source_configs.get_mut("cargo").unwrap().default_eta_sec = Some(42);
+        config.bootstrap_patterns.push(BootstrapPattern {
+            id: "cargo-bench".to_string(),
+            source: "cargo".to_string(),
+            title: "cargo bench".to_string(),
+            metadata: HashMap::new(),
+            default_eta: 99,
+            is_critical: false,
+        });
+        let predictor =
+            PredictionEngine::new(clustering_engine, persistence).with_cold_start_defaults(&config);
+
+        let metadata = HashMap::new();
+
+        // No clusters or source history: configured source default wins over the table
+        let prediction = predictor
+            .predict_eta("r1", "cargo build", "cargo", &metadata)
+            .await;
+        assert_eq!(prediction.expected_seconds, 42);
+
+        // Bootstrap pattern outranks the source default
+        let prediction = predictor
+            .predict_eta("r2", "cargo bench --all", "cargo", &metadata)
+            .await;
+        assert_eq!(prediction.expected_seconds, 99);
+
+        // Sources without configuration keep the built-in defaults
+        let prediction = predictor
+            .predict_eta("r3", "npm install", "npm", &metadata)
+            .await;
+        assert_eq!(prediction.expected_seconds, 30);
+    }
+
+    #[tokio::test]
+    async fn test_fallback_chain_reports_stage() {
+        let clustering_engine = Arc::new(ClusteringEngine::new(100));
+        let persistence = Arc::new(PersistenceLayer::new_in_memory().unwrap());
+        let chains = HashMap::from([
+            ("npm".to_string(), vec![PredictionMethod::Bootstrap]),
+            ("make".to_string(), vec![PredictionMethod::SourceAverage]),
+        ]);
+        let predictor = PredictionEngine::new(clustering_engine.clone(), persistence)
+            .with_fallback_chains(chains);
+        let metadata = HashMap::new();
+
+        // Cluster: a well-sampled matching cluster exists
+        let cluster_id = clustering_engine
+            .assign_race_to_cluster("seed", "deploy staging", "jenkins", &metadata)
+            .await;
+        for _ in 0..20 {
+            clustering_engine.update_cluster_stats(&cluster_id, 240).await;
+        }
+        let prediction = predictor
+            .predict_eta("r1", "deploy staging", "jenkins", &metadata)
+            .await;
+        assert_eq!(prediction.method, PredictionMethod::Cluster);
+        assert_eq!(prediction.expected_seconds, 240);
+
+        // SourceAverage: history for the source but no cluster
+        for _ in 0..5 {
+            predictor.update_source_stats("cargo", 70).await;
+            predictor.update_source_stats("npm", 70).await;
+        }
+        let prediction = predictor
+            .predict_eta("r2", "cargo build", "cargo", &metadata)
+            .await;
+        assert_eq!(prediction.method, PredictionMethod::SourceAverage);
+        assert_eq!(prediction.expected_seconds, 70);
+
+        // Bootstrap: no history, known source
+        let prediction = predictor
+            .predict_eta("r3", "go vet", "codex", &metadata)
+            .await;
+        assert_eq!(prediction.method, PredictionMethod::Bootstrap);
+
+        // GlobalDefault: nothing known about the source
+        let prediction = predictor
+            .predict_eta("r4", "something", "unknown", &metadata)
+            .await;
+        assert_eq!(prediction.method, PredictionMethod::GlobalDefault);
+
+        // Configured chains skip stages even when their data exists
+        let prediction = predictor
+            .predict_eta("r5", "npm install", "npm", &metadata)
+            .await;
+        assert_eq!(prediction.method, PredictionMethod::Bootstrap);
+        assert_eq!(prediction.expected_seconds, 30);
+
+        // An exhausted chain still ends at the global default
+        let prediction = predictor.predict_eta("r6", "make all", "make", &metadata).await;
+        assert_eq!(prediction.method, PredictionMethod::GlobalDefault);
+    }
+
+    #[tokio::test]
+    async fn test_cold_start_without_clusters_returns_labeled_default() {
+        let clustering_engine = Arc::new(ClusteringEngine::new(100));
+        let persistence = Arc::new(PersistenceLayer::new_in_memory().unwrap());
+        let predictor =
+            PredictionEngine::new(clustering_engine.clone(), persistence).with_default_eta(45);
+        let metadata = HashMap::new();
+        assert!(clustering_engine.clusters.read().await.is_empty());
+
+        let prediction = predictor
+            .predict_eta("r1", "mystery job", "unknown", &metadata)
+            .await;
+        assert_eq!(prediction.method, PredictionMethod::GlobalDefault);
+        assert_eq!(prediction.expected_seconds, 45);
+        assert!(prediction.confidence <= 0.1);
+        assert!(0 < prediction.lower_bound && prediction.lower_bound <= 45);
+        assert!(prediction.upper_bound >= 45);
+
+        // Known sources still get their bootstrap default
+        let prediction = predictor
+            .predict_eta("r2", "cargo test", "cargo", &metadata)
+            .await;
+        assert_eq!(prediction.method, PredictionMethod::Bootstrap);
+        assert_eq!(prediction.expected_seconds, 30);
+    }
+
+    #[tokio::test]
+    async fn test_cluster_below_min_samples_falls_back_to_source_average() {
+        let clustering_engine = Arc::new(ClusteringEngine::new(100));
+        let metadata = HashMap::new();
+        let cluster_id = clustering_engine
+            .assign_race_to_cluster("r1", "cargo build --release", "cargo", &metadata)
+            .await;
+        for _ in 0..3 {
+            clustering_engine
+                .update_cluster_stats(&cluster_id, 600)
+                .await;
+        }
+
+        let predictor_with = |min_samples: usize| {
+            let mut config = RebuildConfig::default();
+            config
+                .source_configs
+                .get_mut("cargo")
+                .unwrap()
+                .min_samples_for_prediction = min_samples;
+            PredictionEngine::new(
+                clustering_engine.clone(),
+                Arc::new(PersistenceLayer::new_in_memory().unwrap()),
+            )
+            .with_cold_start_defaults(&config)
+        };
+
+        // At the threshold the cluster's own median is used
+        let predictor = predictor_with(3);
+        for _ in 0..5 {
+            predictor.update_source_stats("cargo", 70).await;
+        }
+        let prediction = predictor
+            .predict_eta("r2", "cargo build --release", "cargo", &metadata)
+            .await;
+        assert_eq!(prediction.method, PredictionMethod::Cluster);
+        assert_eq!(prediction.expected_seconds, 600);
+
+        // Below it, prediction falls through to the source average
+        let predictor = predictor_with(5);
+        for _ in 0..5 {
+            predictor.update_source_stats("cargo", 70).await;
+        }
+        let prediction = predictor
+            .predict_eta("r3", "cargo build --release", "cargo", &metadata)
+            .await;
+        assert_eq!(prediction.method, PredictionMethod::SourceAverage);
+        assert_eq!(prediction.expected_seconds, 70);
+        assert_eq!(predictor.min_samples_for_prediction("cargo"), 5);
+        assert_eq!(predictor.min_samples_for_prediction("unlisted"), 1);
+    }
+
+    #[tokio::test]
+    async fn test_cluster_with_empty_stats_falls_through() {
+        let clustering_engine = Arc::new(ClusteringEngine::new(100));
+        let persistence = Arc::new(PersistenceLayer::new_in_memory().unwrap());
+        let metadata = HashMap::new();
+        // Assigned but never completed: the cluster has no recent_times
+        let cluster_id = clustering_engine
+            .assign_race_to_cluster("r1", "nightly sync", "unknown", &metadata)
+            .await;
+        assert!(clustering_engine.clusters.read().await[&cluster_id]
+            .stats
+            .recent_times
+            .is_empty());
+        assert!(clustering_engine
+            .predict_from_cluster(&cluster_id, 1)
+            .await
+            .is_none());
+
+        let predictor = PredictionEngine::new(clustering_engine, persistence);
+        let prediction = predictor
+            .predict_eta("r2", "nightly sync", "unknown", &metadata)
+            .await;
+        assert_eq!(prediction.method, PredictionMethod::GlobalDefault);
+        assert_eq!(prediction.expected_seconds, GLOBAL_DEFAULT_ETA_SEC);
+
+        // Source stats with no samples are skipped the same way
+        let chain = HashMap::from([(
+            "unknown".to_string(),
+            vec![PredictionMethod::Cluster, PredictionMethod::SourceAverage],
+        )]);
+        let predictor = predictor.with_fallback_chains(chain);
+        let prediction = predictor
+            .predict_eta("r3", "nightly sync", "unknown", &metadata)
             .await;
-        assert_eq!(eta, 30); // fallback
+        assert_eq!(prediction.method, PredictionMethod::GlobalDefault);
     }
 }
 
@@ -1,8 +1,15 @@
+use crate::models::{EtaSource, RaceUpdate};
 use crate::prediction::PredictionEngine;
+use crate::stats::PredictionMethod;
+use crate::storage::Storage;
+use chrono::{DateTime, TimeZone, Utc};
+use serde::Serialize;
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::{mpsc, Mutex};
 use tokio::time::timeout;
 
 #[derive(Debug, Clone)]
@@ -14,34 +21,302 @@ pub struct RaceProcessingRequest {
     pub duration: Option<i64>, // Only set when race completes
 }
 
+/// Queue and worker pool sizing for completed-race processing
+#[derive(Debug, Clone)]
+pub struct ProcessingOptions {
+    pub queue_capacity: usize,
+    pub workers: usize,
+    /// Drop submissions when the queue is full instead of waiting for space
+    pub drop_when_full: bool,
+    /// Queue depth above which health reports the processor as degraded
+    pub degraded_queue_depth: usize,
+    /// Health reports a stall when requests are queued but none has finished
+    /// for this long
+    pub stall_after: Duration,
+    pub eta_refresh: EtaRefreshOptions,
+}
+
+impl Default for ProcessingOptions {
+    fn default() -> Self {
+        Self {
+            queue_capacity: 100,
+            workers: 4,
+            drop_when_full: false,
+            degraded_queue_depth: 80,
+            stall_after: Duration::from_secs(60),
+            eta_refresh: EtaRefreshOptions::default(),
+        }
+    }
+}
+
+/// Periodic re-prediction of server-assigned ETAs for races still in flight
+#[derive(Debug, Clone)]
+pub struct EtaRefreshOptions {
+    pub enabled: bool,
+    /// How often active races are scanned; each race is only re-predicted once
+    /// its ETA is older than its `update_interval_hint`
+    pub interval: Duration,
+    /// Relative change below which a new prediction is not applied
+    pub min_change_ratio: f64,
+}
+
+impl Default for EtaRefreshOptions {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval: Duration::from_secs(30),
+            min_change_ratio: 0.1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessingQueueStats {
+    pub depth: usize,
+    pub capacity: usize,
+    pub workers: usize,
+    pub drop_when_full: bool,
+    pub processed: u64,
+    pub dropped: u64,
+    pub last_processed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Default)]
+struct ProcessingCounters {
+    processed: AtomicU64,
+    dropped: AtomicU64,
+    /// Unix milliseconds of the last finished request; 0 before the first
+    last_processed_ms: AtomicI64,
+    /// Unix milliseconds when the queue last went from empty to non-empty
+    busy_since_ms: AtomicI64,
+}
+
 pub struct ProcessingEngine {
+    prediction_engine: Arc<PredictionEngine>,
     sender: mpsc::Sender<RaceProcessingRequest>,
+    options: ProcessingOptions,
+    counters: Arc<ProcessingCounters>,
+    started_at: DateTime<Utc>,
 }
 
 impl ProcessingEngine {
-    pub fn new(prediction_engine: Arc<PredictionEngine>) -> Self {
-        let (sender, receiver) = mpsc::channel::<RaceProcessingRequest>(100);
+    pub fn new(
+        prediction_engine: Arc<PredictionEngine>,
+        options: ProcessingOptions,
+    ) -> Self {
+        let options = ProcessingOptions {
+            queue_capacity: options.queue_capacity.max(1),
+            workers: options.workers.max(1),
+            ..options
+        };
+        let (sender, receiver) = mpsc::channel::<RaceProcessingRequest>(options.queue_capacity);
+        let receiver = Arc::new(Mutex::new(receiver));
+        let counters = Arc::new(ProcessingCounters::default());
+
+        // Spawn a fixed pool of workers sharing the bounded queue
+        for _ in 0..options.workers {
+            let receiver = receiver.clone();
+            let engine = prediction_engine.clone();
+            let counters = counters.clone();
+            tokio::spawn(async move {
+                Self::process_queue(receiver, engine, counters).await;
+            });
+        }
+
+        Self {
+            prediction_engine,
+            sender,
+            options,
+            counters,
+            started_at: Utc::now(),
+        }
+    }
+
+    pub fn eta_refresh_options(&self) -> &EtaRefreshOptions {
+        &self.options.eta_refresh
+    }
 
-        // Spawn background processing task
-        tokio::spawn(async move {
-            Self::process_queue(receiver, prediction_engine).await;
-        });
+    /// Re-predict the ETA of running/queued races whose ETA came from the server
+    /// (cluster or bootstrap) and is older than their update interval. Adapter
+    /// and exact ETAs are never touched. Returns the number of races updated.
+    pub async fn refresh_stale_etas(&self, storage: &Storage) -> usize {
+        let now = Utc::now();
+        let mut refreshed = 0;
 
-        Self { sender }
+        for race in storage.get_all_races().await {
+            let Some(eta_source @ (EtaSource::Cluster | EtaSource::Bootstrap)) = race.eta_source
+            else {
+                continue;
+            };
+            if race.state.is_terminal() {
+                continue;
+            }
+            let interval = race
+                .update_interval_hint
+                .unwrap_or_else(|| eta_source.default_update_interval());
+            let last_update = race.last_eta_update.unwrap_or(race.started_at);
+            if (now - last_update).num_seconds() < interval as i64 {
+                continue;
+            }
+
+            let metadata = race.metadata.clone().unwrap_or_default();
+            let prediction = self
+                .prediction_engine
+                .predict_eta(&race.id, &race.title, &race.source, &metadata)
+                .await;
+            let current = race.eta_sec.unwrap_or(0);
+            let change =
+                (prediction.expected_seconds - current).abs() as f64 / current.max(1) as f64;
+            if change < self.options.eta_refresh.min_change_ratio {
+                continue;
+            }
+
+            let new_source = match prediction.method {
+                PredictionMethod::Bootstrap | PredictionMethod::GlobalDefault => {
+                    EtaSource::Bootstrap
+                }
+                PredictionMethod::Cluster | PredictionMethod::SourceAverage => EtaSource::Cluster,
+            };
+            let update = RaceUpdate {
+                source: None,
+                title: None,
+                state: None,
+                started_at: None,
+                eta_sec: Some(prediction.expected_seconds),
+                progress: None,
+                deeplink: None,
+                metadata: None,
+                eta_source: Some(new_source),
+                eta_confidence: Some(prediction.confidence),
+                update_interval_hint: None,
+            };
+            if storage.update_race(&race.id, update).await.is_some() {
+                log::debug!(
+                    "Refreshed ETA for race {}: {}s -> {}s ({:?})",
+                    race.id,
+                    current,
+                    prediction.expected_seconds,
+                    prediction.method
+                );
+                refreshed += 1;
+            }
+        }
+        refreshed
+    }
+
+    /// Background task driving `refresh_stale_etas`; not started in read-only mode
+    pub async fn run_eta_refresh_loop(self: Arc<Self>, storage: Arc<Storage>) {
+        let mut interval = tokio::time::interval(self.options.eta_refresh.interval);
+        loop {
+            interval.tick().await;
+            let refreshed = self.refresh_stale_etas(&storage).await;
+            if refreshed > 0 {
+                log::info!("Refreshed ETA of {} active races", refreshed);
+            }
+        }
     }
 
     pub async fn submit_race(&self, request: RaceProcessingRequest) -> Result<(), String> {
-        self.sender
-            .send(request)
-            .await
-            .map_err(|e| format!("Failed to submit race for processing: {}", e))
+        let was_empty = self.sender.capacity() == self.sender.max_capacity();
+        if !self.options.drop_when_full {
+            // Backpressure: wait for a free slot
+            self.sender
+                .send(request)
+                .await
+                .map_err(|e| format!("Failed to submit race for processing: {}", e))?;
+            self.mark_busy(was_empty);
+            return Ok(());
+        }
+
+        match self.sender.try_send(request) {
+            Ok(()) => {
+                self.mark_busy(was_empty);
+                Ok(())
+            }
+            Err(TrySendError::Full(request)) => {
+                self.counters.dropped.fetch_add(1, Ordering::Relaxed);
+                log::warn!(
+                    "processing_queue_full capacity={} action=drop race_id={}",
+                    self.options.queue_capacity,
+                    request.race_id
+                );
+                Err(format!(
+                    "Processing queue full, dropped race {}",
+                    request.race_id
+                ))
+            }
+            Err(TrySendError::Closed(_)) => {
+                Err("Failed to submit race for processing: queue closed".to_string())
+            }
+        }
+    }
+
+    /// Start the stall clock when a request lands in an empty queue
+    fn mark_busy(&self, was_empty: bool) {
+        if was_empty {
+            self.counters
+                .busy_since_ms
+                .store(Utc::now().timestamp_millis(), Ordering::Relaxed);
+        }
+    }
+
+    pub fn queue_stats(&self) -> ProcessingQueueStats {
+        let last_processed_ms = self.counters.last_processed_ms.load(Ordering::Relaxed);
+        ProcessingQueueStats {
+            depth: self.sender.max_capacity() - self.sender.capacity(),
+            capacity: self.options.queue_capacity,
+            workers: self.options.workers,
+            drop_when_full: self.options.drop_when_full,
+            processed: self.counters.processed.load(Ordering::Relaxed),
+            dropped: self.counters.dropped.load(Ordering::Relaxed),
+            last_processed_at: (last_processed_ms > 0)
+                .then(|| Utc.timestamp_millis_opt(last_processed_ms).single())
+                .flatten(),
+        }
+    }
+
+    /// Why the processor counts as degraded at `now`: the backlog is above
+    /// `degraded_queue_depth`, or requests are queued but nothing has finished
+    /// within `stall_after` of the later of the last finish and the queue
+    /// becoming non-empty. Empty when healthy.
+    pub fn degradation_reasons(&self, now: DateTime<Utc>) -> Vec<String> {
+        let stats = self.queue_stats();
+        let mut reasons = Vec::new();
+        if stats.depth > self.options.degraded_queue_depth {
+            reasons.push(format!(
+                "Processing queue depth {} exceeds {}",
+                stats.depth, self.options.degraded_queue_depth
+            ));
+        }
+        // Time spent idle with an empty queue is not a stall
+        let busy_since_ms = self.counters.busy_since_ms.load(Ordering::Relaxed);
+        let busy_since = Utc.timestamp_millis_opt(busy_since_ms).single();
+        let last_progress = stats
+            .last_processed_at
+            .unwrap_or(self.started_at)
+            .max(busy_since.unwrap_or(self.started_at));
+        let idle = (now - last_progress).to_std().unwrap_or_default();
+        if stats.depth > 0 && idle > self.options.stall_after {
+            reasons.push(format!(
+                "Processing stalled: {} queued, nothing processed for {}s",
+                stats.depth,
+                idle.as_secs()
+            ));
+        }
+        reasons
     }
 
     async fn process_queue(
-        mut receiver: mpsc::Receiver<RaceProcessingRequest>,
+        receiver: Arc<Mutex<mpsc::Receiver<RaceProcessingRequest>>>,
         prediction_engine: Arc<PredictionEngine>,
+        counters: Arc<ProcessingCounters>,
     ) {
-        while let Some(request) = receiver.recv().await {
+        loop {
+            // Hold the lock only while waiting for the next request
+            let Some(request) = receiver.lock().await.recv().await else {
+                break;
+            };
+
             // Clone request data before moving into async block
             let race_id = request.race_id.clone();
             let race_title = request.race_title.clone();
@@ -72,6 +347,10 @@ impl ProcessingEngine {
                     eprintln!("Processing timeout for race {}: {}", race_id, race_title);
                 }
             }
+            counters.processed.fetch_add(1, Ordering::Relaxed);
+            counters
+                .last_processed_ms
+                .store(Utc::now().timestamp_millis(), Ordering::Relaxed);
         }
     }
 }
@@ -80,6 +359,7 @@ impl ProcessingEngine {
 mod tests {
     use super::*;
     use crate::cluster::ClusteringEngine;
+    use crate::models::{Race, RaceState};
     use crate::persistence::PersistenceLayer;
 
     #[tokio::test]
@@ -88,7 +368,8 @@ mod tests {
         let persistence = Arc::new(PersistenceLayer::new_in_memory().unwrap());
         let prediction_engine = Arc::new(PredictionEngine::new(clustering_engine, persistence));
 
-        let processing_engine = ProcessingEngine::new(prediction_engine);
+        let processing_engine =
+            ProcessingEngine::new(prediction_engine, ProcessingOptions::default());
 
         let request = RaceProcessingRequest {
             race_id: "test-race".to_string(),
@@ -104,4 +385,202 @@ mod tests {
         // Give it time to process
         tokio::time::sleep(Duration::from_millis(50)).await;
     }
+
+    fn test_request(id: usize) -> RaceProcessingRequest {
+        RaceProcessingRequest {
+            race_id: format!("race-{}", id),
+            race_title: "Test Race".to_string(),
+            race_source: "test".to_string(),
+            race_metadata: HashMap::new(),
+            duration: Some(10),
+        }
+    }
+
+    fn test_prediction_engine() -> Arc<PredictionEngine> {
+        let clustering_engine = Arc::new(ClusteringEngine::new(100));
+        let persistence = Arc::new(PersistenceLayer::new_in_memory().unwrap());
+        Arc::new(PredictionEngine::new(clustering_engine, persistence))
+    }
+
+    #[tokio::test]
+    async fn test_backpressure_past_capacity_does_not_deadlock() {
+        let engine = ProcessingEngine::new(
+            test_prediction_engine(),
+            ProcessingOptions {
+                queue_capacity: 2,
+                workers: 2,
+                drop_when_full: false,
+                ..ProcessingOptions::default()
+            },
+        );
+
+        let submit_all = async {
+            for i in 0..20 {
+                engine.submit_race(test_request(i)).await.unwrap();
+            }
+        };
+        timeout(Duration::from_secs(5), submit_all)
+            .await
+            .expect("submitting past capacity deadlocked");
+
+        timeout(Duration::from_secs(5), async {
+            while engine.queue_stats().processed < 20 {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("queue never drained");
+        assert_eq!(engine.queue_stats().depth, 0);
+    }
+
+    #[tokio::test]
+    async fn test_drop_when_full_reports_depth() {
+        let engine = ProcessingEngine::new(
+            test_prediction_engine(),
+            ProcessingOptions {
+                queue_capacity: 2,
+                workers: 1,
+                drop_when_full: true,
+                ..ProcessingOptions::default()
+            },
+        );
+
+        // Workers cannot run until this task yields, so the queue fills up
+        let mut results = Vec::new();
+        for i in 0..5 {
+            results.push(engine.submit_race(test_request(i)).await.is_ok());
+        }
+        assert_eq!(results, vec![true, true, false, false, false]);
+
+        let stats = engine.queue_stats();
+        assert_eq!(stats.depth, 2);
+        assert_eq!(stats.capacity, 2);
+        assert_eq!(stats.dropped, 3);
+    }
+
+    #[tokio::test]
+    async fn test_stalled_processor_reports_degraded() {
+        let engine = ProcessingEngine::new(
+            test_prediction_engine(),
+            ProcessingOptions {
+                queue_capacity: 10,
+                workers: 1,
+                drop_when_full: true,
+                degraded_queue_depth: 5,
+                stall_after: Duration::from_secs(60),
+                ..ProcessingOptions::default()
+            },
+        );
+        let now = Utc::now();
+        assert!(engine.degradation_reasons(now).is_empty());
+
+        // Workers cannot run until this task yields, so the requests stay queued
+        for i in 0..3 {
+            engine.submit_race(test_request(i)).await.unwrap();
+        }
+        assert!(engine.degradation_reasons(now).is_empty());
+        let stalled = engine.degradation_reasons(now + chrono::Duration::minutes(5));
+        assert_eq!(stalled.len(), 1);
+        assert!(stalled[0].contains("stalled"));
+
+        for i in 3..8 {
+            engine.submit_race(test_request(i)).await.unwrap();
+        }
+        let backlog = engine.degradation_reasons(now);
+        assert_eq!(backlog.len(), 1);
+        assert!(backlog[0].contains("depth 8 exceeds 5"));
+
+        // Once the worker drains the queue the signal clears
+        timeout(Duration::from_secs(5), async {
+            while engine.queue_stats().processed < 8 {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("queue never drained");
+        assert!(engine.queue_stats().last_processed_at.is_some());
+        assert!(engine
+            .degradation_reasons(Utc::now() + chrono::Duration::minutes(5))
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_first_request_after_idle_period_is_not_stalled() {
+        let engine = ProcessingEngine::new(
+            test_prediction_engine(),
+            ProcessingOptions {
+                queue_capacity: 10,
+                workers: 1,
+                stall_after: Duration::from_secs(60),
+                ..ProcessingOptions::default()
+            },
+        );
+        // The last request finished ten minutes ago and the queue sat empty since
+        let idle_since = Utc::now() - chrono::Duration::minutes(10);
+        engine
+            .counters
+            .last_processed_ms
+            .store(idle_since.timestamp_millis(), Ordering::Relaxed);
+
+        // Workers cannot run until this task yields, so the request stays queued
+        engine.submit_race(test_request(0)).await.unwrap();
+        let now = Utc::now();
+        assert!(engine.degradation_reasons(now).is_empty());
+        let stalled = engine.degradation_reasons(now + chrono::Duration::minutes(2));
+        assert_eq!(stalled.len(), 1);
+        assert!(stalled[0].contains("stalled"));
+    }
+
+    #[tokio::test]
+    async fn test_refresh_stale_etas_updates_only_stale_server_etas() {
+        let clustering_engine = Arc::new(ClusteringEngine::new(100));
+        let persistence = Arc::new(PersistenceLayer::new_in_memory().unwrap());
+        let prediction_engine = Arc::new(PredictionEngine::new(
+            clustering_engine.clone(),
+            persistence,
+        ));
+        let metadata = HashMap::new();
+        let cluster_id = clustering_engine
+            .assign_race_to_cluster("history", "cargo build --release", "ci", &metadata)
+            .await;
+        for _ in 0..20 {
+            clustering_engine
+                .update_cluster_stats(&cluster_id, 300)
+                .await;
+        }
+
+        let engine = ProcessingEngine::new(prediction_engine, ProcessingOptions::default());
+        let storage = Storage::new();
+        let running = |id: &str, eta_source: EtaSource, age_secs: i64| {
+            let mut race = Race::new("ci".to_string(), "cargo build --release".to_string());
+            race.id = id.to_string();
+            race.state = RaceState::Running;
+            race.started_at = Utc::now() - chrono::Duration::seconds(age_secs);
+            race.eta_sec = Some(30);
+            race.eta_source = Some(eta_source);
+            race.update_interval_hint = Some(15);
+            race
+        };
+        storage
+            .create_or_update_race(running("stale", EtaSource::Cluster, 3600))
+            .await;
+        storage
+            .create_or_update_race(running("adapter", EtaSource::Adapter, 3600))
+            .await;
+        storage
+            .create_or_update_race(running("fresh", EtaSource::Cluster, 1))
+            .await;
+
+        assert_eq!(engine.refresh_stale_etas(&storage).await, 1);
+
+        let stale = storage.get_race("stale").await.unwrap();
+        assert!(stale.eta_sec.unwrap() > 200);
+        assert_eq!(stale.eta_source, Some(EtaSource::Cluster));
+        assert!(stale.last_eta_update.is_some());
+        assert_eq!(storage.get_race("adapter").await.unwrap().eta_sec, Some(30));
+        assert_eq!(storage.get_race("fresh").await.unwrap().eta_sec, Some(30));
+
+        // Just refreshed, so nothing is stale on the next pass
+        assert_eq!(engine.refresh_stale_etas(&storage).await, 0);
+    }
 }
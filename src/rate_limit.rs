@@ -0,0 +1,264 @@
+use crate::app_state::AppState;
+use crate::error::ApiError;
+use actix_web::body::MessageBody;
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::error::PayloadError;
+use actix_web::http::{header, Method, StatusCode};
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpMessage};
+use futures::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+/// What a write request is charged against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitKey {
+    /// The race `source` (body or stored race), falling back to client IP
+    #[default]
+    Source,
+    Ip,
+}
+
+#[derive(Debug, Clone)]
+pub struct RateLimitOptions {
+    pub enabled: bool,
+    pub key_by: LimitKey,
+    /// Bucket size: writes a key may burst before being throttled
+    pub burst: u32,
+    /// Sustained writes per second once the burst is spent
+    pub per_second: f64,
+}
+
+impl Default for RateLimitOptions {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            key_by: LimitKey::Source,
+            burst: 60,
+            per_second: 10.0,
+        }
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+/// Token buckets for the race write endpoints, keyed by source or client IP
+pub struct RateLimiter {
+    options: RwLock<RateLimitOptions>,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(options: RateLimitOptions) -> Self {
+        Self {
+            options: RwLock::new(options),
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.options.read().unwrap().enabled
+    }
+
+    pub fn options(&self) -> RateLimitOptions {
+        self.options.read().unwrap().clone()
+    }
+
+    /// Apply new limits to the running server; existing buckets are clamped to
+    /// the new burst on their next write
+    pub fn set_options(&self, options: RateLimitOptions) {
+        *self.options.write().unwrap() = options;
+    }
+
+    /// Take one token for `key`, or return how long until one is available
+    pub fn check(&self, key: &str) -> Result<(), Duration> {
+        self.check_at(key, Instant::now())
+    }
+
+    fn check_at(&self, key: &str, now: Instant) -> Result<(), Duration> {
+        let options = self.options();
+        let capacity = options.burst.max(1) as f64;
+        let rate = options.per_second.max(f64::EPSILON);
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets.entry(key.to_string()).or_insert(Bucket {
+            tokens: capacity,
+            refilled_at: now,
+        });
+
+        let elapsed = now.saturating_duration_since(bucket.refilled_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(capacity);
+        bucket.refilled_at = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / rate))
+        }
+    }
+
+    /// Drop buckets that have refilled completely; they are equivalent to new ones
+    fn prune(&self, now: Instant) {
+        let options = self.options();
+        let capacity = options.burst.max(1) as f64;
+        let rate = options.per_second.max(f64::EPSILON);
+        let mut buckets = self.buckets.lock().unwrap();
+        buckets.retain(|_, b| {
+            b.tokens + now.saturating_duration_since(b.refilled_at).as_secs_f64() * rate < capacity
+        });
+    }
+}
+
+#[derive(Deserialize)]
+struct SourceOnly {
+    source: Option<String>,
+}
+
+/// Largest body buffered to find its source; matches `web::JsonConfig`'s default limit
+const MAX_KEYED_BODY: usize = 2 * 1024 * 1024;
+
+/// Buffer up to `MAX_KEYED_BODY` of the body, then hand the handler the
+/// buffered bytes followed by whatever was left unread (or the read error)
+async fn peek_body(req: &mut ServiceRequest) -> Option<web::Bytes> {
+    let mut payload = req.take_payload();
+    let mut buffered = web::BytesMut::new();
+    let mut error = None;
+    while buffered.len() <= MAX_KEYED_BODY {
+        match payload.next().await {
+            Some(Ok(chunk)) => buffered.extend_from_slice(&chunk),
+            Some(Err(e)) => {
+                error = Some(e);
+                break;
+            }
+            None => break,
+        }
+    }
+
+    let body = buffered.freeze();
+    let head = std::iter::once(Ok(body.clone())).chain(error.map(Err));
+    let stream: Pin<Box<dyn Stream<Item = Result<web::Bytes, PayloadError>>>> =
+        Box::pin(futures::stream::iter(head).chain(payload));
+    req.set_payload(Payload::from(stream));
+    (body.len() <= MAX_KEYED_BODY).then_some(body)
+}
+
+/// Resolve the bucket key, buffering the body when the source has to be read from it
+async fn limit_key(req: &mut ServiceRequest, key_by: LimitKey, state: &AppState) -> String {
+    let ip = req
+        .connection_info()
+        .realip_remote_addr()
+        .unwrap_or("unknown")
+        .to_string();
+    if key_by == LimitKey::Ip {
+        return format!("ip:{}", ip);
+    }
+
+    let source = match req.match_info().get("id").map(str::to_string) {
+        // Existing race: charge the source it was created with
+        Some(id) => state.storage.get_race(&id).await.map(|race| race.source),
+        None => peek_body(req).await.and_then(|body| {
+            serde_json::from_slice::<SourceOnly>(&body)
+                .ok()
+                .and_then(|b| b.source)
+        }),
+    };
+
+    match source {
+        Some(source) => format!(
+            "source:{}",
+            crate::models::normalize_source(&source, &state.source_aliases)
+        ),
+        None => format!("ip:{}", ip),
+    }
+}
+
+/// Middleware for the race write resources; safe methods pass through untouched
+pub async fn limit_writes(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let is_write = matches!(*req.method(), Method::POST | Method::PATCH | Method::DELETE);
+    let state = req.app_data::<web::Data<AppState>>().cloned();
+
+    if let Some(state) = state.filter(|s| is_write && s.rate_limiter.enabled()) {
+        let key = limit_key(&mut req, state.rate_limiter.options().key_by, &state).await;
+        if let Err(wait) = state.rate_limiter.check(&key) {
+            let retry_after = wait.as_secs_f64().ceil().max(1.0) as u64;
+            log::warn!("Rate limit exceeded for {} on {} {}", key, req.method(), req.path());
+            let mut response = ApiError::new(
+                StatusCode::TOO_MANY_REQUESTS,
+                "rate_limited",
+                format!("Too many writes for {}; retry in {}s", key, retry_after),
+            )
+            .with_details(json!({ "retry_after_secs": retry_after }))
+            .response();
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, header::HeaderValue::from(retry_after));
+            return Ok(req.into_response(response).map_into_right_body());
+        }
+    }
+
+    next.call(req)
+        .await
+        .map(ServiceResponse::map_into_left_body)
+}
+
+/// Periodically forget idle buckets so one-off sources/IPs don't accumulate
+pub async fn run_prune_loop(limiter: std::sync::Arc<RateLimiter>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(60));
+    loop {
+        interval.tick().await;
+        limiter.prune(Instant::now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(burst: u32, per_second: f64) -> RateLimiter {
+        RateLimiter::new(RateLimitOptions {
+            enabled: true,
+            key_by: LimitKey::Source,
+            burst,
+            per_second,
+        })
+    }
+
+    #[test]
+    fn test_bucket_refills_over_time() {
+        let limiter = limiter(2, 1.0);
+        let start = Instant::now();
+        assert!(limiter.check_at("source:ci", start).is_ok());
+        assert!(limiter.check_at("source:ci", start).is_ok());
+        let wait = limiter.check_at("source:ci", start).unwrap_err();
+        assert!(wait <= Duration::from_secs(1));
+
+        // Other keys have their own bucket
+        assert!(limiter.check_at("source:other", start).is_ok());
+
+        assert!(limiter
+            .check_at("source:ci", start + Duration::from_millis(1100))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_prune_drops_only_full_buckets() {
+        let limiter = limiter(2, 1.0);
+        let start = Instant::now();
+        limiter.check_at("source:ci", start).unwrap();
+        limiter.prune(start);
+        assert_eq!(limiter.buckets.lock().unwrap().len(), 1);
+        limiter.prune(start + Duration::from_secs(5));
+        assert!(limiter.buckets.lock().unwrap().is_empty());
+    }
+}
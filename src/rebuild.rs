@@ -1,21 +1,24 @@
 use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose, Engine as _};
 use chrono::{DateTime, Utc};
 use lru::LruCache;
 use rand::prelude::*;
 use rand::rngs::StdRng;
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet, VecDeque};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use tokio::sync::RwLock;
 use unicode_normalization::UnicodeNormalization;
 
-use crate::cluster::RaceCluster;
+use crate::cluster::{count_titles, PredictionAccuracy, RaceCluster};
 use crate::hnsw_dbscan::{validate_clusters_comprehensive, HnswDBSCAN, ValidationCriteria};
 use crate::models::Race;
 
 pub type RaceId = String;
 pub type ClusterId = String;
-use crate::stats::ExecutionStats;
+use crate::stats::{ExecutionStats, OutlierFilter, DEFAULT_WINDOW_SIZE};
 
 pub const METRIC_VERSION: &str = "v1.0.1";
 pub const TOKENIZER_VERSION: &str = "v1.0.1";
@@ -25,6 +28,8 @@ pub struct RebuildConfig {
     pub source_configs: HashMap<String, SourceConfig>,
     pub max_clusters: usize,
     pub max_memory_multiplier: f64,
+    /// Completed races kept out of a validated rebuild to score its predictions
+    /// out of sample, then added back to their nearest cluster; 0 disables
     pub validation_holdout_ratio: f64,
     pub max_mae_increase: f64,
     pub max_noise_ratio: f64,
@@ -34,19 +39,60 @@ pub struct RebuildConfig {
     pub min_silhouette: f64,
     pub use_ann_optimization: bool,
     pub distance_cache_size: usize,
+    /// Spread brute-force DBSCAN distance computations over the rayon pool
+    pub parallel_distances: bool,
+    /// Races fetched per persistence scan call when a rebuild loads history;
+    /// smaller batches lower peak memory at the cost of more round-trips
     pub batch_size: usize,
+    /// Only source the phased rollout enables in Phase 1
+    pub pilot_source: String,
+    /// Passing rebuilds a shadow source needs before it goes canary
+    pub shadow_promotion_successes: u32,
+    /// Passing rebuilds a canary source needs before it goes to production
+    pub canary_promotion_successes: u32,
+    /// Share of a source's rebuilds that must pass for either promotion
+    pub promotion_success_rate: f64,
+    /// How long a rollout Rollback lasts before it is recovered automatically
+    pub rollback_recovery_after: std::time::Duration,
     pub max_rebuild_duration: std::time::Duration,
     pub shadow_mode_duration: std::time::Duration,
     pub canary_duration: std::time::Duration,
     pub rebuild_interval: std::time::Duration,
     pub kneedle_sensitivity: f64,
     pub kneedle_smoothing: usize,
+    /// Knees below this confidence are treated as arbitrary and the previous eps is kept
+    pub min_knee_confidence: f64,
+    /// Fraction of a source's races sampled for eps detection
+    pub eps_subsample_fraction: f64,
+    /// Races sampled for eps detection when the fraction yields fewer (all, if the source is smaller)
+    pub eps_subsample_size: usize,
+    pub eps_subsample_seed: u64,
     pub metric_version: String,
     pub tokenizer_version: String,
     pub eps_ema_smoothing: f32,
+    pub bootstrap_patterns: Vec<BootstrapPattern>,
+    pub centroid_title_mode: CentroidTitleMode,
+    /// Titles considered for a cluster's representative title; larger clusters are subsampled
+    pub centroid_max_titles: usize,
+    /// Applied while replaying member durations into rebuilt cluster stats
+    pub outlier_filter: OutlierFilter,
+    /// Catch-all config for sources without an entry in `source_configs`
+    pub default_source_config: SourceConfig,
 }
 
+/// How a cluster's representative title is derived from its members
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CentroidTitleMode {
+    /// Member title with the smallest average Levenshtein distance to the others
+    Medoid,
+    /// Tokens shared by most members, in the order they first appear
+    Template,
+}
+
+/// Missing fields fall back to [`SourceConfig::default_for_unknown`]
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct SourceConfig {
     pub eps_range: (f64, f64),
     pub min_samples: usize,
@@ -59,6 +105,157 @@ pub struct SourceConfig {
     pub tau_merge_lo: f64,
     pub tau_merge_hi: f64,
     pub last_eps: Option<f64>,
+    /// Number of recent durations each cluster's ExecutionStats retains
+    #[serde(default = "default_stats_window")]
+    pub stats_window: usize,
+    /// Cold-start ETA used when no cluster or source average exists yet
+    #[serde(default)]
+    pub default_eta_sec: Option<i64>,
+    /// What the rebuild does with DBSCAN noise points
+    #[serde(default)]
+    pub noise_policy: NoisePolicy,
+    /// Discount each race's weight in DBSCAN neighbor counts by age, halving
+    /// every this many days; unset counts every race fully
+    #[serde(default)]
+    pub recency_half_life_days: Option<f64>,
+    /// Weight of each metadata key (e.g. `model`) in the weighted Jaccard;
+    /// keys not listed weigh 1.0, so an empty map is plain Jaccard
+    #[serde(default)]
+    pub metadata_key_weights: HashMap<String, f64>,
+    /// Completed runs a cluster needs before it predicts; smaller clusters
+    /// fall through to the source average or defaults
+    #[serde(default = "default_min_samples_for_prediction")]
+    pub min_samples_for_prediction: usize,
+    /// Hours between periodic rebuilds of this source; unset uses the global
+    /// `rebuild_interval`
+    #[serde(default)]
+    pub rebuild_interval_hours: Option<u64>,
+}
+
+/// Handling of races DBSCAN leaves unclustered.
+///
+/// `SourceAverage` keeps a catch-all prediction for unmatched races but lets
+/// unrelated outliers skew it. `Discard` keeps predictions clean, at the cost
+/// of unmatched races falling through to source stats or bootstrap defaults.
+/// `PerPointSingleton` predicts each outlier's recurrences from its own
+/// history; singletons are not counted as noise but do lower cohesion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum NoisePolicy {
+    /// Pool all noise into a `{source}:source_avg` cluster
+    #[default]
+    SourceAverage,
+    /// Drop noise points from the cluster set
+    Discard,
+    /// One `{source}:noise:{race_id}` cluster per noise point
+    PerPointSingleton,
+}
+
+fn default_stats_window() -> usize {
+    DEFAULT_WINDOW_SIZE
+}
+
+fn default_min_samples_for_prediction() -> usize {
+    1
+}
+
+/// Tolerance when checking that distance weights sum to 1.0
+const WEIGHT_SUM_EPSILON: f64 = 1e-6;
+
+impl Default for SourceConfig {
+    fn default() -> Self {
+        Self::default_for_unknown()
+    }
+}
+
+impl SourceConfig {
+    /// Built-in catch-all for sources with no tuned config
+    pub fn default_for_unknown() -> Self {
+        Self {
+            eps_range: (0.25, 0.45),
+            min_samples: 2,
+            min_cluster_size: 2,
+            preserve_bootstraps: false,
+            w_title: 0.6,
+            w_meta: 0.4,
+            tau_match: 0.5,
+            tau_split: 0.35,
+            tau_merge_lo: 0.35,
+            tau_merge_hi: 0.6,
+            last_eps: None,
+            stats_window: DEFAULT_WINDOW_SIZE,
+            default_eta_sec: None,
+            noise_policy: NoisePolicy::default(),
+            recency_half_life_days: None,
+            metadata_key_weights: HashMap::new(),
+            min_samples_for_prediction: 1,
+            rebuild_interval_hours: None,
+        }
+    }
+
+    /// Reject configs that would produce out-of-range distances or degenerate DBSCAN runs
+    pub fn validate(&self) -> Result<()> {
+        if self.w_title < 0.0 || self.w_meta < 0.0 {
+            return Err(anyhow!(
+                "weights must be non-negative (w_title={}, w_meta={})",
+                self.w_title,
+                self.w_meta
+            ));
+        }
+        let weight_sum = self.w_title + self.w_meta;
+        if (weight_sum - 1.0).abs() > WEIGHT_SUM_EPSILON {
+            return Err(anyhow!(
+                "w_title + w_meta must equal 1.0 (w_title={}, w_meta={}, sum={})",
+                self.w_title,
+                self.w_meta,
+                weight_sum
+            ));
+        }
+        if self.eps_range.0 >= self.eps_range.1 {
+            return Err(anyhow!(
+                "eps_range lower bound must be below upper bound (got {:?})",
+                self.eps_range
+            ));
+        }
+        if self.min_samples == 0 {
+            return Err(anyhow!("min_samples must be positive"));
+        }
+        if self.min_samples_for_prediction > self.stats_window {
+            return Err(anyhow!(
+                "min_samples_for_prediction ({}) must not exceed stats_window ({})",
+                self.min_samples_for_prediction,
+                self.stats_window
+            ));
+        }
+        if self.rebuild_interval_hours == Some(0) {
+            return Err(anyhow!("rebuild_interval_hours must be positive"));
+        }
+        if let Some(half_life) = self.recency_half_life_days {
+            if half_life.is_nan() || half_life <= 0.0 {
+                return Err(anyhow!(
+                    "recency_half_life_days must be positive (got {})",
+                    half_life
+                ));
+            }
+        }
+        for (key, weight) in &self.metadata_key_weights {
+            if !RELEVANT_KEYS.contains(&key.as_str()) {
+                return Err(anyhow!(
+                    "metadata_key_weights key '{}' is not one of {:?}",
+                    key,
+                    RELEVANT_KEYS
+                ));
+            }
+            if !weight.is_finite() || *weight < 0.0 {
+                return Err(anyhow!(
+                    "metadata_key_weights['{}'] must be non-negative (got {})",
+                    key,
+                    weight
+                ));
+            }
+        }
+        Ok(())
+    }
 }
 
 impl Default for RebuildConfig {
@@ -79,6 +276,13 @@ impl Default for RebuildConfig {
                 tau_merge_lo: 0.35,
                 tau_merge_hi: 0.6,
                 last_eps: None,
+                stats_window: DEFAULT_WINDOW_SIZE,
+                default_eta_sec: None,
+                noise_policy: NoisePolicy::default(),
+                recency_half_life_days: None,
+                metadata_key_weights: HashMap::new(),
+                min_samples_for_prediction: 1,
+                rebuild_interval_hours: None,
             },
         );
 
@@ -96,6 +300,13 @@ impl Default for RebuildConfig {
                 tau_merge_lo: 0.35,
                 tau_merge_hi: 0.6,
                 last_eps: None,
+                stats_window: DEFAULT_WINDOW_SIZE,
+                default_eta_sec: None,
+                noise_policy: NoisePolicy::default(),
+                recency_half_life_days: None,
+                metadata_key_weights: HashMap::new(),
+                min_samples_for_prediction: 1,
+                rebuild_interval_hours: None,
             },
         );
 
@@ -113,6 +324,13 @@ impl Default for RebuildConfig {
                 tau_merge_lo: 0.35,
                 tau_merge_hi: 0.6,
                 last_eps: None,
+                stats_window: DEFAULT_WINDOW_SIZE,
+                default_eta_sec: None,
+                noise_policy: NoisePolicy::default(),
+                recency_half_life_days: None,
+                metadata_key_weights: HashMap::new(),
+                min_samples_for_prediction: 1,
+                rebuild_interval_hours: None,
             },
         );
 
@@ -130,6 +348,13 @@ impl Default for RebuildConfig {
                 tau_merge_lo: 0.35,
                 tau_merge_hi: 0.6,
                 last_eps: None,
+                stats_window: DEFAULT_WINDOW_SIZE,
+                default_eta_sec: None,
+                noise_policy: NoisePolicy::default(),
+                recency_half_life_days: None,
+                metadata_key_weights: HashMap::new(),
+                min_samples_for_prediction: 1,
+                rebuild_interval_hours: None,
             },
         );
 
@@ -147,6 +372,13 @@ impl Default for RebuildConfig {
                 tau_merge_lo: 0.35,
                 tau_merge_hi: 0.6,
                 last_eps: None,
+                stats_window: DEFAULT_WINDOW_SIZE,
+                default_eta_sec: None,
+                noise_policy: NoisePolicy::default(),
+                recency_half_life_days: None,
+                metadata_key_weights: HashMap::new(),
+                min_samples_for_prediction: 1,
+                rebuild_interval_hours: None,
             },
         );
 
@@ -164,6 +396,13 @@ impl Default for RebuildConfig {
                 tau_merge_lo: 0.35,
                 tau_merge_hi: 0.6,
                 last_eps: None,
+                stats_window: DEFAULT_WINDOW_SIZE,
+                default_eta_sec: None,
+                noise_policy: NoisePolicy::default(),
+                recency_half_life_days: None,
+                metadata_key_weights: HashMap::new(),
+                min_samples_for_prediction: 1,
+                rebuild_interval_hours: None,
             },
         );
 
@@ -181,6 +420,13 @@ impl Default for RebuildConfig {
                 tau_merge_lo: 0.35,
                 tau_merge_hi: 0.6,
                 last_eps: None,
+                stats_window: DEFAULT_WINDOW_SIZE,
+                default_eta_sec: None,
+                noise_policy: NoisePolicy::default(),
+                recency_half_life_days: None,
+                metadata_key_weights: HashMap::new(),
+                min_samples_for_prediction: 1,
+                rebuild_interval_hours: None,
             },
         );
 
@@ -197,17 +443,124 @@ impl Default for RebuildConfig {
             min_silhouette: -0.1, // Silhouette can be negative
             use_ann_optimization: true,
             distance_cache_size: 10_000,
-            batch_size: 100,
+            parallel_distances: true,
+            batch_size: 10_000,
+            pilot_source: "cargo".to_string(),
+            shadow_promotion_successes: 5,
+            canary_promotion_successes: 10,
+            promotion_success_rate: 0.95,
+            rollback_recovery_after: std::time::Duration::from_secs(3600),
             max_rebuild_duration: std::time::Duration::from_secs(300),
             shadow_mode_duration: std::time::Duration::from_secs(86400),
             canary_duration: std::time::Duration::from_secs(172800),
             rebuild_interval: std::time::Duration::from_secs(604800), // 1 week
             kneedle_sensitivity: 1.0,
             kneedle_smoothing: 7,
+            min_knee_confidence: 0.1,
+            eps_subsample_fraction: 0.15,
+            eps_subsample_size: 50_000,
+            eps_subsample_seed: 42,
             metric_version: METRIC_VERSION.to_string(),
             tokenizer_version: TOKENIZER_VERSION.to_string(),
             eps_ema_smoothing: 0.2,
+            bootstrap_patterns: Vec::new(),
+            centroid_title_mode: CentroidTitleMode::Medoid,
+            centroid_max_titles: 200,
+            outlier_filter: OutlierFilter::default(),
+            default_source_config: SourceConfig::default_for_unknown(),
+        }
+    }
+}
+
+impl RebuildConfig {
+    /// Config for `source`, falling back to the catch-all used for unlisted sources
+    pub fn config_for_source(&self, source: &str) -> SourceConfig {
+        self.source_configs
+            .get(source)
+            .cloned()
+            .unwrap_or_else(|| self.default_source_config.clone())
+    }
+
+    /// How long `source` goes between periodic rebuilds
+    pub fn rebuild_interval_for(&self, source: &str) -> std::time::Duration {
+        self.source_configs
+            .get(source)
+            .unwrap_or(&self.default_source_config)
+            .rebuild_interval_hours
+            .map(|hours| std::time::Duration::from_secs(hours * 3600))
+            .unwrap_or(self.rebuild_interval)
+    }
+
+    /// Replace the catch-all source config, e.g. from `[sources.default_source_config]`
+    pub fn with_default_source_config(mut self, source_config: Option<SourceConfig>) -> Self {
+        if let Some(source_config) = source_config {
+            self.default_source_config = source_config;
+        }
+        self
+    }
+
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    pub fn with_centroid_title_mode(mut self, mode: CentroidTitleMode) -> Self {
+        self.centroid_title_mode = mode;
+        self
+    }
+
+    pub fn with_parallel_distances(mut self, parallel: bool) -> Self {
+        self.parallel_distances = parallel;
+        self
+    }
+
+    pub fn with_pilot_source(mut self, pilot_source: &str) -> Self {
+        self.pilot_source = pilot_source.trim().to_string();
+        self
+    }
+
+    pub fn with_promotion_thresholds(
+        mut self,
+        shadow: u32,
+        canary: u32,
+        success_rate: f64,
+    ) -> Self {
+        self.shadow_promotion_successes = shadow;
+        self.canary_promotion_successes = canary;
+        self.promotion_success_rate = success_rate;
+        self
+    }
+
+    pub fn with_rollback_recovery_after(mut self, after: std::time::Duration) -> Self {
+        self.rollback_recovery_after = after;
+        self
+    }
+
+    pub fn with_outlier_filter(mut self, outlier_filter: OutlierFilter) -> Self {
+        self.outlier_filter = outlier_filter;
+        self
+    }
+
+    /// Validate every per-source config, naming the offending source on failure
+    pub fn validate(&self) -> Result<()> {
+        if self.pilot_source.is_empty() {
+            return Err(anyhow!("rollout pilot source must not be empty"));
+        }
+        if !(0.0..=1.0).contains(&self.promotion_success_rate) {
+            return Err(anyhow!(
+                "promotion success rate must be within [0, 1] (got {})",
+                self.promotion_success_rate
+            ));
         }
+        self.default_source_config
+            .validate()
+            .map_err(|e| anyhow!("invalid default source config: {}", e))?;
+        for (source, source_config) in &self.source_configs {
+            source_config
+                .validate()
+                .map_err(|e| anyhow!("invalid config for source '{}': {}", source, e))?;
+        }
+        Ok(())
     }
 }
 
@@ -221,55 +574,184 @@ pub fn normalize_text(text: &str) -> String {
         .join(" ")
 }
 
+/// Titles are compared on at most this many characters: Levenshtein is
+/// O(n·m), so a pathological title would otherwise stall every pairwise distance
+pub const MAX_COMPARED_TITLE_CHARS: usize = 256;
+
+/// The prefix of `title` that edit-distance comparisons look at
+pub fn compared_title(title: &str) -> &str {
+    match title.char_indices().nth(MAX_COMPARED_TITLE_CHARS) {
+        Some((cut, _)) => &title[..cut],
+        None => title,
+    }
+}
+
+/// `normalize_text` of race titles keyed by race id, so a rebuild normalizes
+/// each title once instead of on every distance and vector computation. Ids
+/// are assumed to keep their title for the lifetime of the map.
+#[derive(Debug, Clone, Default)]
+pub struct NormalizedTitles {
+    titles: HashMap<RaceId, String>,
+    /// Titles this map has normalized, cached or not, for tests asserting reuse
+    #[cfg(test)]
+    normalized: Arc<std::sync::atomic::AtomicUsize>,
+}
+
+impl NormalizedTitles {
+    pub fn from_races<'a>(races: impl IntoIterator<Item = &'a Race>) -> Self {
+        let mut titles = Self::default();
+        for race in races {
+            titles.insert(race);
+        }
+        titles
+    }
+
+    /// Normalize the race's title unless its id is already known
+    pub fn insert(&mut self, race: &Race) {
+        if !self.titles.contains_key(&race.id) {
+            let title = self.normalize(&race.title);
+            self.titles.insert(race.id.clone(), title);
+        }
+    }
+
+    /// The cached form, or the title normalized on the spot for unknown races
+    pub fn get(&self, race: &Race) -> std::borrow::Cow<'_, str> {
+        match self.titles.get(&race.id) {
+            Some(title) => std::borrow::Cow::Borrowed(title),
+            None => std::borrow::Cow::Owned(self.normalize(&race.title)),
+        }
+    }
+
+    fn normalize(&self, title: &str) -> String {
+        #[cfg(test)]
+        self.normalized.fetch_add(1, Ordering::Relaxed);
+        normalize_text(title)
+    }
+
+    /// `normalize_text` calls made through this map (and its clones)
+    #[cfg(test)]
+    pub fn normalizations(&self) -> usize {
+        self.normalized.load(Ordering::Relaxed)
+    }
+}
+
 pub fn custom_distance(r1: &Race, r2: &Race, config: &SourceConfig) -> f64 {
+    custom_distance_with(r1, r2, config, &NormalizedTitles::default())
+}
+
+/// [`custom_distance`] reusing already normalized titles
+pub fn custom_distance_with(
+    r1: &Race,
+    r2: &Race,
+    config: &SourceConfig,
+    titles: &NormalizedTitles,
+) -> f64 {
     if r1.source != r2.source {
         return 1.0;
     }
+    distance_breakdown_with(r1, r2, config, titles).distance
+}
+
+/// Components of `custom_distance` for two races of the same source
+#[derive(Debug, Clone, Serialize)]
+pub struct DistanceBreakdown {
+    pub distance: f64,
+    /// Normalized Levenshtein distance between normalized titles
+    pub title_distance: f64,
+    /// Jaccard similarity of relevant metadata; None when either race lacks metadata
+    /// (when both lack it, the distance is title-only: w_title = 1, w_meta = 0)
+    pub metadata_jaccard: Option<f64>,
+    pub metadata_distance: f64,
+    pub w_title: f64,
+    pub w_meta: f64,
+}
 
-    let title1_norm = normalize_text(&r1.title);
-    let title2_norm = normalize_text(&r2.title);
+pub fn distance_breakdown(r1: &Race, r2: &Race, config: &SourceConfig) -> DistanceBreakdown {
+    distance_breakdown_with(r1, r2, config, &NormalizedTitles::default())
+}
+
+fn distance_breakdown_with(
+    r1: &Race,
+    r2: &Race,
+    config: &SourceConfig,
+    titles: &NormalizedTitles,
+) -> DistanceBreakdown {
+    let title1_norm = titles.get(r1);
+    let title2_norm = titles.get(r2);
+    let (title1_norm, title2_norm) = (compared_title(&title1_norm), compared_title(&title2_norm));
 
     let max_len = title1_norm.chars().count().max(title2_norm.chars().count()) as f64;
     let title_distance = if max_len > 0.0 {
-        levenshtein::levenshtein(&title1_norm, &title2_norm) as f64 / max_len
+        levenshtein::levenshtein(title1_norm, title2_norm) as f64 / max_len
     } else {
         0.0
     };
 
-    let metadata_distance = if let (Some(m1), Some(m2)) = (&r1.metadata, &r2.metadata) {
-        1.0 - jaccard_metadata_similarity(m1, m2)
-    } else {
-        0.5 // Default distance when metadata is missing
+    let set1 = relevant_metadata(r1.metadata.as_ref());
+    let set2 = relevant_metadata(r2.metadata.as_ref());
+
+    if set1.is_empty() && set2.is_empty() {
+        // Nothing to compare: a flat metadata term would only dilute the title signal
+        return DistanceBreakdown {
+            distance: title_distance.clamp(0.0, 1.0),
+            title_distance,
+            metadata_jaccard: None,
+            metadata_distance: 0.0,
+            w_title: 1.0,
+            w_meta: 0.0,
+        };
+    }
+
+    let metadata_jaccard = match (&r1.metadata, &r2.metadata) {
+        (Some(_), Some(_)) => Some(jaccard_similarity(
+            &set1,
+            &set2,
+            &config.metadata_key_weights,
+        )),
+        _ => None,
     };
+    // Default distance when only one race has metadata
+    let metadata_distance = metadata_jaccard.map_or(0.5, |j| 1.0 - j);
 
-    // Use weights from SourceConfig (they should sum to 1.0)
+    // Use weights from SourceConfig (SourceConfig::validate enforces they sum to 1.0)
     let w_title = config.w_title;
     let w_meta = config.w_meta;
 
-    (w_title * title_distance + w_meta * metadata_distance).clamp(0.0, 1.0)
+    DistanceBreakdown {
+        distance: (w_title * title_distance + w_meta * metadata_distance).clamp(0.0, 1.0),
+        title_distance,
+        metadata_jaccard,
+        metadata_distance,
+        w_title,
+        w_meta,
+    }
 }
 
-fn jaccard_metadata_similarity(m1: &HashMap<String, String>, m2: &HashMap<String, String>) -> f64 {
-    const RELEVANT_KEYS: &[&str] = &["model", "tool", "language", "file_extension"];
-
-    let set1: HashSet<String> = m1
-        .iter()
-        .filter(|(k, _)| RELEVANT_KEYS.contains(&k.as_str()))
-        .map(|(k, v)| format!("{}={}", normalize_text(k), normalize_text(v)))
-        .collect();
+/// Metadata keys that characterize a race for clustering and cluster labels
+const RELEVANT_KEYS: &[&str] = &["model", "tool", "language", "file_extension"];
 
-    let set2: HashSet<String> = m2
-        .iter()
+/// `key=value` pairs (value normalized) of the metadata keys that matter for clustering
+fn relevant_metadata(metadata: Option<&HashMap<String, String>>) -> HashSet<String> {
+    metadata
+        .into_iter()
+        .flatten()
         .filter(|(k, _)| RELEVANT_KEYS.contains(&k.as_str()))
-        .map(|(k, v)| format!("{}={}", normalize_text(k), normalize_text(v)))
-        .collect();
-
-    if set1.is_empty() && set2.is_empty() {
-        return 1.0;
-    }
+        .map(|(k, v)| format!("{}={}", k, normalize_text(v)))
+        .collect()
+}
 
-    let intersection = set1.intersection(&set2).count() as f64;
-    let union = set1.union(&set2).count() as f64;
+/// Weighted Jaccard: each `key=value` token counts with its key's weight
+fn jaccard_similarity(
+    set1: &HashSet<String>,
+    set2: &HashSet<String>,
+    key_weights: &HashMap<String, f64>,
+) -> f64 {
+    let weight = |token: &String| {
+        let key = token.split_once('=').map_or(token.as_str(), |(k, _)| k);
+        key_weights.get(key).copied().unwrap_or(1.0)
+    };
+    let intersection: f64 = set1.intersection(set2).map(weight).sum();
+    let union: f64 = set1.union(set2).map(weight).sum();
 
     if union > 0.0 {
         intersection / union
@@ -278,19 +760,105 @@ fn jaccard_metadata_similarity(m1: &HashMap<String, String>, m2: &HashMap<String
     }
 }
 
+/// Dimension of race vectors; precomputed binary embeddings must match it
+pub const EMBEDDING_DIM: usize = 4096;
+
+/// Longest `embedding` JSON string worth parsing: generous per-value budget
+/// for EMBEDDING_DIM floats, so oversized payloads are rejected before allocating
+const MAX_EMBEDDING_JSON_LEN: usize = EMBEDDING_DIM * 32;
+
+/// Base64 of packed little-endian f32s, rejected unless exactly EMBEDDING_DIM long
+fn decode_embedding_b64(encoded: &str) -> Option<Vec<f32>> {
+    // Base64 of EMBEDDING_DIM f32s; anything longer can't be valid
+    if encoded.len() > (EMBEDDING_DIM * 4).div_ceil(3) * 4 + 2 {
+        log::debug!("Ignoring oversized embedding_b64 ({} chars)", encoded.len());
+        return None;
+    }
+    let bytes = general_purpose::STANDARD.decode(encoded.trim()).ok()?;
+    if bytes.len() != EMBEDDING_DIM * 4 {
+        log::debug!(
+            "Ignoring embedding_b64 of {} bytes (expected {})",
+            bytes.len(),
+            EMBEDDING_DIM * 4
+        );
+        return None;
+    }
+    validate_embedding(
+        bytes
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect(),
+        "embedding_b64",
+    )
+}
+
+fn decode_embedding_json(embedding: &str) -> Option<Vec<f32>> {
+    if embedding.len() > MAX_EMBEDDING_JSON_LEN {
+        log::debug!("Ignoring oversized embedding ({} chars)", embedding.len());
+        return None;
+    }
+    match serde_json::from_str::<Vec<f32>>(embedding) {
+        Ok(vec) => validate_embedding(vec, "embedding"),
+        Err(e) => {
+            log::debug!("Ignoring malformed embedding: {}", e);
+            None
+        }
+    }
+}
+
+/// Non-finite values or a foreign dimension would poison every distance the
+/// vector takes part in, so such embeddings are dropped. Rejections log at
+/// debug: this runs for every race of every rebuild.
+fn validate_embedding(vec: Vec<f32>, key: &str) -> Option<Vec<f32>> {
+    if vec.len() != EMBEDDING_DIM {
+        log::debug!(
+            "Ignoring {} with {} dimensions (expected {})",
+            key,
+            vec.len(),
+            EMBEDDING_DIM
+        );
+        return None;
+    }
+    if let Some(i) = vec.iter().position(|x| !x.is_finite()) {
+        log::debug!("Ignoring {} with non-finite value at index {}", key, i);
+        return None;
+    }
+    Some(vec)
+}
+
 pub fn race_to_vector(race: &Race) -> Vec<f32> {
-    // Check if race has precomputed embedding
+    race_to_vector_with(race, &NormalizedTitles::default())
+}
+
+/// [`race_to_vector`] reusing an already normalized title
+pub fn race_to_vector_with(race: &Race, titles: &NormalizedTitles) -> Vec<f32> {
+    // Check if race has precomputed embedding; the packed form wins over JSON
     if let Some(metadata) = &race.metadata {
-        if let Some(embedding) = metadata.get("embedding") {
-            if let Ok(vec) = serde_json::from_str::<Vec<f32>>(embedding) {
-                return vec;
-            }
+        if let Some(vec) = metadata
+            .get("embedding_b64")
+            .and_then(|e| decode_embedding_b64(e))
+        {
+            return vec;
+        }
+        if let Some(vec) = metadata
+            .get("embedding")
+            .and_then(|e| decode_embedding_json(e))
+        {
+            return vec;
         }
     }
 
-    // Fallback: hashed character 3-gram TF-IDF, L2-normalized
-    let s = normalize_text(&race.title);
-    let mut feats = vec![0f32; 4096];
+    normalized_title_vector(&titles.get(race))
+}
+
+/// Hashed character 3-gram TF-IDF of the normalized title, L2-normalized
+pub fn title_vector(title: &str) -> Vec<f32> {
+    normalized_title_vector(&normalize_text(title))
+}
+
+/// [`title_vector`] of a title already passed through `normalize_text`
+fn normalized_title_vector(s: &str) -> Vec<f32> {
+    let mut feats = vec![0f32; EMBEDDING_DIM];
     let chars: Vec<char> = s.chars().collect();
 
     for w in chars.windows(3) {
@@ -382,42 +950,137 @@ pub fn assign_noise_as_border(
     out
 }
 
+/// Weight below which a race is too stale to feed rebuilt cluster stats
+const STALE_RECENCY_WEIGHT: f64 = 0.05;
+
+/// Weight of `race` in DBSCAN neighbor counts: 1.0 unless the source sets
+/// `recency_half_life_days`, then `0.5^(age / half_life)` by completion time
+pub fn recency_weight(race: &Race, config: &SourceConfig, now: DateTime<Utc>) -> f64 {
+    let Some(half_life) = config.recency_half_life_days else {
+        return 1.0;
+    };
+    let finished = race.completed_at.unwrap_or(race.started_at);
+    let age_days = (now - finished).num_seconds().max(0) as f64 / 86_400.0;
+    0.5_f64.powf(age_days / half_life)
+}
+
+/// Independently locked LRU shards of the distance cache, so parallel
+/// neighbor scans don't all contend on one lock
+struct ShardedDistanceCache {
+    shards: Vec<Mutex<LruCache<(RaceId, RaceId), f64>>>,
+}
+
+const DISTANCE_CACHE_SHARDS: usize = 16;
+
+impl ShardedDistanceCache {
+    /// `capacity` is split evenly across the shards
+    fn new(capacity: usize) -> Self {
+        let per_shard = capacity.div_ceil(DISTANCE_CACHE_SHARDS).max(1);
+        let shards = (0..DISTANCE_CACHE_SHARDS)
+            .map(|_| Mutex::new(LruCache::new(per_shard.try_into().unwrap())))
+            .collect();
+        Self { shards }
+    }
+
+    fn shard(&self, key: &(RaceId, RaceId)) -> &Mutex<LruCache<(RaceId, RaceId), f64>> {
+        let hash = seahash::hash(key.0.as_bytes()) ^ seahash::hash(key.1.as_bytes()).rotate_left(1);
+        &self.shards[hash as usize % self.shards.len()]
+    }
+
+    fn get(&self, key: &(RaceId, RaceId)) -> Option<f64> {
+        self.shard(key).lock().unwrap().get(key).copied()
+    }
+
+    fn put(&self, key: (RaceId, RaceId), distance: f64) {
+        self.shard(&key).lock().unwrap().put(key, distance);
+    }
+}
+
+/// Brute-force DBSCAN over pairwise `custom_distance`. The distance cache is
+/// keyed by race ids, so one instance can serve eps detection and the
+/// following `run_dbscan` without recomputing shared pairs. Each point's
+/// distances to the others are computed on the rayon pool unless parallelism
+/// is turned off; neighbor lists keep input order, so clusterings match the
+/// serial run exactly.
 pub struct OptimizedDBSCAN {
-    distance_cache: LruCache<(String, String), f64>,
+    distance_cache: ShardedDistanceCache,
     races_map: HashMap<RaceId, Race>,
+    titles: NormalizedTitles,
+    parallel: bool,
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
 }
 
 impl OptimizedDBSCAN {
     pub fn new(cache_size: usize) -> Self {
         Self {
-            distance_cache: LruCache::new(cache_size.try_into().unwrap()),
+            distance_cache: ShardedDistanceCache::new(cache_size),
             races_map: HashMap::new(),
+            titles: NormalizedTitles::default(),
+            parallel: true,
+            cache_hits: AtomicU64::new(0),
+            cache_misses: AtomicU64::new(0),
         }
     }
 
-    fn get_or_compute_distance(
-        &mut self,
-        id1: &RaceId,
-        id2: &RaceId,
-        config: &SourceConfig,
-    ) -> f64 {
+    /// Compute each point's distances serially on the calling thread instead
+    pub fn with_parallel(mut self, parallel: bool) -> Self {
+        self.parallel = parallel;
+        self
+    }
+
+    /// Distance lookups answered from the cache
+    pub fn cache_hits(&self) -> u64 {
+        self.cache_hits.load(Ordering::Relaxed)
+    }
+
+    /// Distance lookups that ran `custom_distance`
+    pub fn cache_misses(&self) -> u64 {
+        self.cache_misses.load(Ordering::Relaxed)
+    }
+
+    fn get_or_compute_distance(&self, id1: &RaceId, id2: &RaceId, config: &SourceConfig) -> f64 {
         let key = if id1 < id2 {
             (id1.clone(), id2.clone())
         } else {
             (id2.clone(), id1.clone())
         };
 
-        if let Some(&dist) = self.distance_cache.get(&key) {
+        if let Some(dist) = self.distance_cache.get(&key) {
+            self.cache_hits.fetch_add(1, Ordering::Relaxed);
             return dist;
         }
 
+        self.cache_misses.fetch_add(1, Ordering::Relaxed);
         let r1 = &self.races_map[id1];
         let r2 = &self.races_map[id2];
-        let distance = custom_distance(r1, r2, config);
+        let distance = custom_distance_with(r1, r2, config, &self.titles);
         self.distance_cache.put(key, distance);
         distance
     }
 
+    /// Distances from `race_id` to every other race, in `others` order. Each
+    /// pair is distinct, so parallel runs compute and cache exactly the same
+    /// pairs as serial ones.
+    fn distances_from(
+        &self,
+        race_id: &RaceId,
+        others: &[&Race],
+        config: &SourceConfig,
+    ) -> Vec<(RaceId, f64)> {
+        let distance = |other: &&Race| {
+            (other.id != *race_id).then(|| {
+                let distance = self.get_or_compute_distance(race_id, &other.id, config);
+                (other.id.clone(), distance)
+            })
+        };
+        if self.parallel {
+            others.par_iter().filter_map(distance).collect()
+        } else {
+            others.iter().filter_map(distance).collect()
+        }
+    }
+
     pub fn run_dbscan(
         &mut self,
         races: Vec<Race>,
@@ -428,8 +1091,20 @@ impl OptimizedDBSCAN {
         // Build races map
         self.races_map.clear();
         for race in races.iter() {
+            self.titles.insert(race);
             self.races_map.insert(race.id.clone(), race.clone());
         }
+        let candidates: Vec<&Race> = races.iter().collect();
+        // Core points need `min_samples` of neighbor weight; all 1.0 unless
+        // recency weighting is on
+        let now = Utc::now();
+        let weights: HashMap<&RaceId, f64> = races
+            .iter()
+            .map(|race| (&race.id, recency_weight(race, config, now)))
+            .collect();
+        let density =
+            |neighbors: &[RaceId]| -> f64 { neighbors.iter().map(|id| weights[id]).sum() };
+        let min_density = min_samples as f64;
 
         let mut labels: HashMap<RaceId, i32> = HashMap::new();
         let mut cluster_id = 0;
@@ -440,9 +1115,9 @@ impl OptimizedDBSCAN {
             }
 
             // Find neighbors within eps
-            let neighbors = self.find_neighbors(&race.id, &races, eps, config);
+            let neighbors = self.find_neighbors(&race.id, &candidates, eps, config);
 
-            if neighbors.len() < min_samples {
+            if density(&neighbors) < min_density {
                 labels.insert(race.id.clone(), -1); // Noise
                 continue;
             }
@@ -467,8 +1142,9 @@ impl OptimizedDBSCAN {
 
                 labels.insert(neighbor_id.clone(), cluster_id);
 
-                let neighbor_neighbors = self.find_neighbors(&neighbor_id, &races, eps, config);
-                if neighbor_neighbors.len() >= min_samples {
+                let neighbor_neighbors =
+                    self.find_neighbors(&neighbor_id, &candidates, eps, config);
+                if density(&neighbor_neighbors) >= min_density {
                     for nn in neighbor_neighbors {
                         if !labels.contains_key(&nn) {
                             seeds.push_back(nn);
@@ -494,8 +1170,8 @@ impl OptimizedDBSCAN {
                     .push(race_id.clone());
 
                 // Check if border point (has < min_samples neighbors)
-                let neighbors = self.find_neighbors(&race_id, &races, eps, config);
-                if neighbors.len() < min_samples {
+                let neighbors = self.find_neighbors(&race_id, &candidates, eps, config);
+                if density(&neighbors) < min_density {
                     border_points.insert(race_id, cluster_id);
                 }
             }
@@ -509,63 +1185,138 @@ impl OptimizedDBSCAN {
     }
 
     fn find_neighbors(
-        &mut self,
+        &self,
         race_id: &RaceId,
-        races: &[Race],
+        races: &[&Race],
         eps: f64,
         config: &SourceConfig,
     ) -> Vec<RaceId> {
-        let mut neighbors = Vec::new();
-
-        for other in races {
-            if &other.id == race_id {
-                continue;
-            }
-
-            let distance = self.get_or_compute_distance(race_id, &other.id, config);
-            if distance <= eps {
-                neighbors.push(other.id.clone());
-            }
-        }
-
-        neighbors
+        self.distances_from(race_id, races, config)
+            .into_iter()
+            .filter(|(_, distance)| *distance <= eps)
+            .map(|(id, _)| id)
+            .collect()
     }
 }
 
+/// Knee of the k-distance curve, clamped to `[eps_min, eps_max]`. Distances
+/// land in `dbscan`'s cache; pass the same instance to the `run_dbscan` that
+/// follows so the pairs aren't computed twice.
 pub fn detect_optimal_eps(
     races: &[Race],
     min_samples: usize,
     eps_min: f64,
     eps_max: f64,
     config: &SourceConfig,
+    rebuild_config: &RebuildConfig,
+    dbscan: &mut OptimizedDBSCAN,
+) -> KneeResult {
+    let curve = compute_k_distance_curve_with(races, min_samples, config, rebuild_config, dbscan);
+
+    // No curve at all: the midpoint is arbitrary, so report zero confidence
+    let knee = curve.knee.unwrap_or(KneeResult {
+        eps: (eps_min + eps_max) / 2.0,
+        confidence: 0.0,
+    });
+
+    KneeResult {
+        eps: knee.eps.clamp(eps_min, eps_max),
+        confidence: knee.confidence,
+    }
+}
+
+/// Pick the eps for a rebuild. Low-confidence knees defer to the previous eps when there is one,
+/// otherwise the detected value is blended with `last_eps` via EMA.
+pub fn choose_eps(
+    knee: &KneeResult,
+    last_eps: Option<f64>,
+    source_config: &SourceConfig,
+    rebuild_config: &RebuildConfig,
 ) -> f64 {
+    let (eps_min, eps_max) = source_config.eps_range;
+
+    match last_eps {
+        Some(last) if knee.confidence < rebuild_config.min_knee_confidence => {
+            log::warn!(
+                "Low-confidence eps detection (confidence={:.3}, eps={:.4}); keeping previous eps {:.4}",
+                knee.confidence,
+                knee.eps,
+                last
+            );
+            last.clamp(eps_min, eps_max)
+        }
+        Some(last) => {
+            let alpha = rebuild_config.eps_ema_smoothing as f64;
+            (alpha * knee.eps + (1.0 - alpha) * last).clamp(eps_min, eps_max)
+        }
+        None => {
+            if knee.confidence < rebuild_config.min_knee_confidence {
+                log::warn!(
+                    "Low-confidence eps detection (confidence={:.3}) and no previous eps; using {:.4}",
+                    knee.confidence,
+                    knee.eps
+                );
+            }
+            knee.eps
+        }
+    }
+}
+
+/// Smoothed k-distance curve (descending) and the knee detected on it
+#[derive(Debug, Clone, Serialize)]
+pub struct KDistanceCurve {
+    pub k: usize,
+    pub sample_size: usize,
+    pub distances: Vec<f64>,
+    pub knee: Option<KneeResult>,
+}
+
+pub fn compute_k_distance_curve(
+    races: &[Race],
+    min_samples: usize,
+    config: &SourceConfig,
+    rebuild_config: &RebuildConfig,
+) -> KDistanceCurve {
+    let mut dbscan = OptimizedDBSCAN::new(rebuild_config.distance_cache_size)
+        .with_parallel(rebuild_config.parallel_distances);
+    compute_k_distance_curve_with(races, min_samples, config, rebuild_config, &mut dbscan)
+}
+
+/// [`compute_k_distance_curve`] computing distances through a caller-owned cache
+pub fn compute_k_distance_curve_with(
+    races: &[Race],
+    min_samples: usize,
+    config: &SourceConfig,
+    rebuild_config: &RebuildConfig,
+    dbscan: &mut OptimizedDBSCAN,
+) -> KDistanceCurve {
     let k = min_samples;
 
     // Subsample for scalability
-    let mut rng = StdRng::seed_from_u64(42);
+    let mut rng = StdRng::seed_from_u64(rebuild_config.eps_subsample_seed);
     let n = races.len();
-    let sample_size = ((n * 15) / 100).max(n.min(50_000)).min(n);
+    let sample_size = eps_subsample_size(n, rebuild_config);
 
     let sample: Vec<&Race> = races.choose_multiple(&mut rng, sample_size).collect();
 
     // Calculate k-distances
-    let mut dbscan = OptimizedDBSCAN::new(10_000);
     for race in sample.iter() {
+        dbscan.titles.insert(race);
         dbscan.races_map.insert(race.id.clone(), (*race).clone());
     }
 
     let mut k_distances: Vec<f64> = Vec::new();
 
     for race in sample.iter() {
-        let mut distances: Vec<f64> = sample
-            .iter()
-            .filter(|r| r.id != race.id)
-            .map(|r| dbscan.get_or_compute_distance(&race.id, &r.id, config))
+        let mut distances: Vec<f64> = dbscan
+            .distances_from(&race.id, &sample, config)
+            .into_iter()
+            .map(|(_, distance)| distance)
             .collect();
 
         distances.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
 
-        if distances.len() >= k {
+        if k > 0 && distances.len() >= k {
             k_distances.push(distances[k - 1]);
         }
     }
@@ -574,18 +1325,30 @@ pub fn detect_optimal_eps(
     k_distances.sort_by(|a, b| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
 
     // Apply moving average
-    let smoothed = moving_average(&k_distances, 7);
+    let smoothed = moving_average(&k_distances, rebuild_config.kneedle_smoothing);
 
     // Use proper Kneedle algorithm for knee detection
-    let eps = detect_knee_kneedle(
+    let knee = detect_knee_kneedle(
         &smoothed,
         Curve::Concave,
         Direction::Decreasing,
-        1.0, // sensitivity
-    )
-    .unwrap_or((eps_min + eps_max) / 2.0);
+        rebuild_config.kneedle_sensitivity,
+    );
+
+    KDistanceCurve {
+        k,
+        sample_size,
+        distances: smoothed,
+        knee,
+    }
+}
 
-    eps.clamp(eps_min, eps_max)
+/// Number of races sampled for eps detection out of `n`
+fn eps_subsample_size(n: usize, rebuild_config: &RebuildConfig) -> usize {
+    let by_fraction = (n as f64 * rebuild_config.eps_subsample_fraction.clamp(0.0, 1.0)) as usize;
+    by_fraction
+        .max(n.min(rebuild_config.eps_subsample_size))
+        .min(n)
 }
 
 fn moving_average(data: &[f64], window_size: usize) -> Vec<f64> {
@@ -627,6 +1390,15 @@ fn detect_knee_simple(data: &[f64]) -> Option<f64> {
 }
 
 // Proper Kneedle algorithm implementation
+/// Detected knee and how pronounced it is: the knee's normalized distance from
+/// the straight line between the curve's endpoints, in `[0, 1]`. Flat or linear
+/// curves score ~0.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct KneeResult {
+    pub eps: f64,
+    pub confidence: f64,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum Curve {
     Concave,
@@ -644,7 +1416,7 @@ pub fn detect_knee_kneedle(
     curve: Curve,
     direction: Direction,
     sensitivity: f64,
-) -> Option<f64> {
+) -> Option<KneeResult> {
     if data.len() < 3 {
         return None;
     }
@@ -655,7 +1427,10 @@ pub fn detect_knee_kneedle(
     let range = max_val - min_val;
 
     if range == 0.0 {
-        return Some(data[0]);
+        return Some(KneeResult {
+            eps: data[0],
+            confidence: 0.0,
+        });
     }
 
     let normalized: Vec<f64> = data.iter().map(|&x| (x - min_val) / range).collect();
@@ -684,19 +1459,23 @@ pub fn detect_knee_kneedle(
     }
 
     // Find the knee as the point with maximum difference
-    let threshold = differences
-        .iter()
-        .cloned()
-        .fold(f64::NEG_INFINITY, f64::max)
+    let max_difference = differences.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let threshold = max_difference
         - sensitivity * differences.iter().map(|x| x.abs()).sum::<f64>() / differences.len() as f64;
 
     for (i, &diff) in differences.iter().enumerate() {
         if diff >= threshold {
-            return Some(data[i]);
+            return Some(KneeResult {
+                eps: data[i],
+                confidence: max_difference.clamp(0.0, 1.0),
+            });
         }
     }
 
-    Some(data[data.len() / 2])
+    Some(KneeResult {
+        eps: data[data.len() / 2],
+        confidence: 0.0,
+    })
 }
 
 #[derive(Debug, Clone)]
@@ -906,6 +1685,38 @@ pub fn map_stable_ids(
     result
 }
 
+/// Fraction of races present in both sets whose new cluster maps (via
+/// `map_stable_ids`) to the id of the cluster they were in before
+pub fn membership_stability(
+    prev: &HashMap<ClusterId, RaceCluster>,
+    next: &HashMap<ClusterId, RaceCluster>,
+    th: MappingThresholds,
+) -> f64 {
+    let stable_ids = map_stable_ids(prev, next, th);
+    let next_cluster_of: HashMap<&str, &ClusterId> = next
+        .iter()
+        .flat_map(|(id, c)| c.member_race_ids.iter().map(move |r| (r.as_str(), id)))
+        .collect();
+
+    let mut kept = 0usize;
+    let mut total = 0usize;
+    for (prev_id, cluster) in prev {
+        for race_id in &cluster.member_race_ids {
+            if let Some(next_id) = next_cluster_of.get(race_id.as_str()) {
+                total += 1;
+                if stable_ids.get(*next_id) == Some(prev_id) {
+                    kept += 1;
+                }
+            }
+        }
+    }
+
+    if total == 0 {
+        return 1.0;
+    }
+    kept as f64 / total as f64
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BootstrapPattern {
     pub id: String,
@@ -921,6 +1732,14 @@ impl BootstrapPattern {
         format!("bootstrap:{}", self.id)
     }
 
+    /// Whether a new race from `source` looks like an instance of this pattern
+    pub fn matches_race(&self, source: &str, title: &str) -> bool {
+        let pattern_title = normalize_text(&self.title);
+        self.source == source
+            && !pattern_title.is_empty()
+            && normalize_text(title).contains(&pattern_title)
+    }
+
     pub fn matches_race_id(&self, race_id: &str) -> bool {
         // Simplified: check if race was created from this bootstrap
         race_id.contains(&self.id)
@@ -978,6 +1797,8 @@ pub type ClusterSet = HashMap<ClusterId, RaceCluster>;
 pub struct DoubleBufferClusters {
     pub active: Arc<RwLock<ClusterSet>>,
     pub inactive: Arc<RwLock<ClusterSet>>,
+    /// Eps used for each source in the most recent rebuild
+    pub detected_eps: Arc<RwLock<HashMap<String, f64>>>,
     baseline_memory: usize,
 }
 
@@ -986,26 +1807,44 @@ impl DoubleBufferClusters {
         Self {
             active: Arc::new(RwLock::new(HashMap::new())),
             inactive: Arc::new(RwLock::new(HashMap::new())),
+            detected_eps: Arc::new(RwLock::new(HashMap::new())),
             baseline_memory,
         }
     }
 
+    /// Rebuild clusters for `sources` from `races` and swap them in.
+    ///
+    /// Every old cluster of a listed source is replaced, even when the rebuild
+    /// yields no clusters for it; clusters of other sources are kept as-is.
     pub async fn rebuild_with_zero_downtime(
         &self,
         races: Vec<Race>,
+        sources: &HashSet<String>,
         config: &RebuildConfig,
     ) -> Result<()> {
-        if !self.check_memory_budget() {
-            return Err(anyhow!("Insufficient memory for rebuild"));
-        }
-
         // Build new clusters (no locks held)
         let snapshot = {
             let active = self.active.read().await;
             active.clone()
         };
 
-        let new_clusters = self.run_dbscan_rebuild(races, &snapshot, config).await?;
+        // Hold out part of the races to check predictions out of sample; nothing
+        // is validated on the initial bootstrap, so everything trains then
+        let (races, holdout) = if snapshot.is_empty() {
+            (races, Vec::new())
+        } else {
+            split_holdout(
+                races,
+                config.validation_holdout_ratio,
+                config.eps_subsample_seed,
+            )
+        };
+
+        let mut new_clusters = self.run_dbscan_rebuild(&races, &snapshot, config).await?;
+
+        if !self.check_memory_budget(&new_clusters, config) {
+            return Err(anyhow!("Insufficient memory for rebuild"));
+        }
 
         // Skip validation if we have no existing clusters (initial bootstrap)
         // Use the snapshot (old clusters) not the current active buffer
@@ -1020,8 +1859,10 @@ impl DoubleBufferClusters {
             eprintln!("Validating new clusters against {} existing clusters", 
                      self.active.read().await.len());
             // Validate only if we have existing clusters to compare against
+            let races_by_id: HashMap<&str, &Race> =
+                races.iter().map(|r| (r.id.as_str(), r)).collect();
             if !self
-                .validate_new_clusters(&new_clusters, &snapshot, config)
+                .validate_new_clusters(&new_clusters, &snapshot, &races_by_id, &holdout, config)
                 .await
             {
                 return Err(anyhow!("Validation failed"));
@@ -1029,21 +1870,25 @@ impl DoubleBufferClusters {
         } else {
             eprintln!("Skipping validation - no existing clusters (initial bootstrap)");
         }
+        // Absorption measures every held-out race against every cluster of its source
+        let eps = self.detected_eps.read().await.clone();
+        let absorb_config = config.clone();
+        let new_clusters = tokio::task::spawn_blocking(move || {
+            absorb_holdout(&mut new_clusters, holdout, &absorb_config, &eps);
+            new_clusters
+        })
+        .await?;
 
         // Atomic swap with selective replacement
         let mut inactive = self.inactive.write().await;
         let mut active = self.active.write().await;
 
-        // Get the source(s) being rebuilt from new_clusters
-        let rebuilding_sources: std::collections::HashSet<String> = new_clusters
-            .values()
-            .map(|c| c.source.clone())
-            .collect();
-        
-        // Start with existing clusters, but remove ones from sources being rebuilt
+        // Start with existing clusters, but remove ones from sources being rebuilt.
+        // Use the requested sources rather than those of new_clusters so a source
+        // that rebuilt to nothing (e.g. all noise under Discard) is cleared too.
         *inactive = active
             .iter()
-            .filter(|(_, cluster)| !rebuilding_sources.contains(&cluster.source))
+            .filter(|(_, cluster)| !sources.contains(&cluster.source))
             .map(|(k, v)| (k.clone(), v.clone()))
             .collect();
         
@@ -1060,7 +1905,7 @@ impl DoubleBufferClusters {
 
     async fn run_dbscan_rebuild(
         &self,
-        races: Vec<Race>,
+        races: &[Race],
         old_clusters: &ClusterSet,
         config: &RebuildConfig,
     ) -> Result<ClusterSet> {
@@ -1072,52 +1917,40 @@ impl DoubleBufferClusters {
             races_by_source
                 .entry(race.source.clone())
                 .or_insert_with(Vec::new)
-                .push(race);
+                .push(race.clone());
         }
 
         // Process each source
         for (source, source_races) in races_by_source {
-            let source_config = config
-                .source_configs
-                .get(&source)
-                .cloned()
-                .unwrap_or_else(|| SourceConfig {
-                    eps_range: (0.25, 0.45),
-                    min_samples: 2,
-                    min_cluster_size: 2,
-                    preserve_bootstraps: false,
-                    w_title: 0.6,
-                    w_meta: 0.4,
-                    tau_match: 0.5,
-                    tau_split: 0.35,
-                    tau_merge_lo: 0.35,
-                    tau_merge_hi: 0.6,
-                    last_eps: None,
-                });
+            let source_config = config.config_for_source(&source);
+            // Shared by eps detection and brute-force DBSCAN
+            let mut dbscan = OptimizedDBSCAN::new(config.distance_cache_size)
+                .with_parallel(config.parallel_distances);
 
             // Detect optimal eps
-            let eps = if let Some(last_eps) = source_config.last_eps {
-                // Use EMA with last eps
-                let suggested_eps = detect_optimal_eps(
-                    &source_races,
-                    source_config.min_samples,
-                    source_config.eps_range.0,
-                    source_config.eps_range.1,
-                    &source_config,
-                );
-
-                let alpha = config.eps_ema_smoothing as f64;
-                (alpha * suggested_eps + (1.0 - alpha) * last_eps)
-                    .clamp(source_config.eps_range.0, source_config.eps_range.1)
+            let knee = detect_optimal_eps(
+                &source_races,
+                source_config.min_samples,
+                source_config.eps_range.0,
+                source_config.eps_range.1,
+                &source_config,
+                config,
+                &mut dbscan,
+            );
+            // Fall back to the eps from the previous rebuild when no persisted value exists
+            let previous_eps = match source_config.last_eps {
+                Some(last) => Some(last),
+                None => self.detected_eps.read().await.get(&source).copied(),
+            };
+            let eps = if source_config.last_eps.is_some()
+                || knee.confidence < config.min_knee_confidence
+            {
+                choose_eps(&knee, previous_eps, &source_config, config)
             } else {
-                detect_optimal_eps(
-                    &source_races,
-                    source_config.min_samples,
-                    source_config.eps_range.0,
-                    source_config.eps_range.1,
-                    &source_config,
-                )
+                knee.eps
             };
+            self.detected_eps.write().await.insert(source.clone(), eps);
+            log::info!("Rebuild eps for source '{}': {:.4}", source, eps);
 
             // Choose between HNSW and brute force based on data size
             let result = if config.use_ann_optimization && source_races.len() > 1000 {
@@ -1129,7 +1962,6 @@ impl DoubleBufferClusters {
                         "Failed to build HNSW index: {}, falling back to brute force",
                         e
                     );
-                    let mut dbscan = OptimizedDBSCAN::new(config.distance_cache_size);
                     dbscan.run_dbscan(
                         source_races.clone(),
                         eps,
@@ -1141,7 +1973,6 @@ impl DoubleBufferClusters {
                 }
             } else {
                 // Use brute force for small datasets
-                let mut dbscan = OptimizedDBSCAN::new(config.distance_cache_size);
                 dbscan.run_dbscan(
                     source_races.clone(),
                     eps,
@@ -1149,8 +1980,15 @@ impl DoubleBufferClusters {
                     &source_config,
                 )
             };
+            log::debug!(
+                "Distance cache for '{}': {} hits, {} misses",
+                source,
+                dbscan.cache_hits(),
+                dbscan.cache_misses()
+            );
 
             // Convert to RaceCluster format
+            let mut source_clusters = Vec::new();
             for (cluster_id, member_ids) in result.clusters {
                 if member_ids.len() >= source_config.min_cluster_size {
                     let cluster = self.create_race_cluster(
@@ -1158,22 +1996,27 @@ impl DoubleBufferClusters {
                         source.clone(),
                         member_ids,
                         &source_races,
+                        &source_config,
+                        config,
                     );
-                    new_clusters.insert(cluster_id, cluster);
+                    source_clusters.push((cluster_id, cluster));
                 }
             }
 
-            // Handle noise points as source average fallback
-            if !result.noise.is_empty() {
-                let noise_cluster_id = format!("{}:source_avg", source);
-                let cluster = self.create_race_cluster(
-                    noise_cluster_id.clone(),
-                    source.clone(),
-                    result.noise,
-                    &source_races,
-                );
-                new_clusters.insert(noise_cluster_id, cluster);
-            }
+            source_clusters.extend(self.noise_clusters(
+                &source,
+                result.noise,
+                &source_races,
+                &source_config,
+                config,
+            ));
+            new_clusters.extend(self.cap_source_clusters(
+                &source,
+                source_clusters,
+                &source_races,
+                &source_config,
+                config,
+            ));
         }
 
         // Apply stable IDs
@@ -1186,12 +2029,126 @@ impl DoubleBufferClusters {
         Ok(mapping.apply_stable_ids(new_clusters))
     }
 
+    /// Clusters built from a source's noise points according to its `noise_policy`
+    fn noise_clusters(
+        &self,
+        source: &str,
+        noise: Vec<RaceId>,
+        source_races: &[Race],
+        source_config: &SourceConfig,
+        config: &RebuildConfig,
+    ) -> Vec<(ClusterId, RaceCluster)> {
+        if noise.is_empty() {
+            return Vec::new();
+        }
+
+        match source_config.noise_policy {
+            NoisePolicy::SourceAverage => {
+                let noise_cluster_id = format!("{}:source_avg", source);
+                let cluster = self.create_race_cluster(
+                    noise_cluster_id.clone(),
+                    source.to_string(),
+                    noise,
+                    source_races,
+                    source_config,
+                    config,
+                );
+                vec![(noise_cluster_id, cluster)]
+            }
+            NoisePolicy::Discard => Vec::new(),
+            NoisePolicy::PerPointSingleton => noise
+                .into_iter()
+                .map(|race_id| {
+                    let cluster_id = format!("{}:noise:{}", source, race_id);
+                    let cluster = self.create_race_cluster(
+                        cluster_id.clone(),
+                        source.to_string(),
+                        vec![race_id],
+                        source_races,
+                        source_config,
+                        config,
+                    );
+                    (cluster_id, cluster)
+                })
+                .collect(),
+        }
+    }
+
+    /// Keep at most `config.max_clusters` clusters for one source. The best
+    /// clusters (by quality score, then member count) survive; members of the
+    /// rest are folded into the `{source}:source_avg` bucket, which counts
+    /// toward the cap, or dropped when the source's noise policy is `Discard`.
+    fn cap_source_clusters(
+        &self,
+        source: &str,
+        clusters: Vec<(ClusterId, RaceCluster)>,
+        source_races: &[Race],
+        source_config: &SourceConfig,
+        config: &RebuildConfig,
+    ) -> Vec<(ClusterId, RaceCluster)> {
+        let cap = config.max_clusters.max(1);
+        if clusters.len() <= cap {
+            return clusters;
+        }
+
+        let source_avg_id = format!("{}:source_avg", source);
+        let (mut folded, mut ranked): (Vec<RaceId>, Vec<(ClusterId, RaceCluster)>) =
+            (Vec::new(), Vec::with_capacity(clusters.len()));
+        for (cluster_id, cluster) in clusters {
+            if cluster_id == source_avg_id {
+                folded.extend(cluster.member_race_ids);
+            } else {
+                ranked.push((cluster_id, cluster));
+            }
+        }
+
+        ranked.sort_by(|(id_a, a), (id_b, b)| {
+            b.quality_score
+                .unwrap_or(0.0)
+                .total_cmp(&a.quality_score.unwrap_or(0.0))
+                .then_with(|| b.member_race_ids.len().cmp(&a.member_race_ids.len()))
+                .then_with(|| id_a.cmp(id_b))
+        });
+        if source_config.noise_policy == NoisePolicy::Discard {
+            let dropped = ranked.split_off(cap);
+            log::info!(
+                "Source '{}' exceeds max_clusters ({}); discarding {} clusters",
+                source,
+                cap,
+                dropped.len()
+            );
+            return ranked;
+        }
+        let dropped = ranked.split_off(cap - 1);
+        log::info!(
+            "Source '{}' exceeds max_clusters ({}); folding {} clusters into {}",
+            source,
+            cap,
+            dropped.len(),
+            source_avg_id
+        );
+        folded.extend(dropped.into_iter().flat_map(|(_, c)| c.member_race_ids));
+
+        let source_avg = self.create_race_cluster(
+            source_avg_id.clone(),
+            source.to_string(),
+            folded,
+            source_races,
+            source_config,
+            config,
+        );
+        ranked.push((source_avg_id, source_avg));
+        ranked
+    }
+
     fn create_race_cluster(
         &self,
         cluster_id: ClusterId,
         source: String,
         member_ids: Vec<RaceId>,
         all_races: &[Race],
+        source_config: &SourceConfig,
+        config: &RebuildConfig,
     ) -> RaceCluster {
         let members: Vec<&Race> = all_races
             .iter()
@@ -1200,17 +2157,43 @@ impl DoubleBufferClusters {
 
         let titles: Vec<String> = members.iter().map(|r| r.title.clone()).collect();
 
-        let representative_title = if !titles.is_empty() {
-            compute_centroid_title(&titles)
-        } else {
-            String::new()
-        };
+        let representative_title = representative_title(
+            &titles,
+            config.centroid_title_mode,
+            config.centroid_max_titles,
+        );
+        let representative_metadata = compute_representative_metadata(&members);
+        let quality_score = cluster_quality_score(&members, source_config);
+        let latest_member_at = members.iter().map(|r| r.started_at).max();
+        let history_start = members
+            .len()
+            .saturating_sub(crate::cluster::MAX_MEMBER_HISTORY);
+        let member_metadata_history = members[history_start..]
+            .iter()
+            .map(|r| r.metadata.clone().unwrap_or_default())
+            .collect();
 
-        let mut stats = ExecutionStats::new();
-        for race in members {
+        // With recency weighting, replay oldest first so the window keeps the
+        // newest runs, and leave fully decayed races out
+        let mut stats_members = members;
+        if source_config.recency_half_life_days.is_some() {
+            let now = Utc::now();
+            let fresh: Vec<&Race> = stats_members
+                .iter()
+                .copied()
+                .filter(|race| recency_weight(race, source_config, now) >= STALE_RECENCY_WEIGHT)
+                .collect();
+            if !fresh.is_empty() {
+                stats_members = fresh;
+            }
+            stats_members.sort_by_key(|race| race.completed_at.unwrap_or(race.started_at));
+        }
+
+        let mut stats = ExecutionStats::new_with_window(source_config.stats_window);
+        for race in stats_members {
             // Use the race's duration_sec field directly
             if let Some(duration) = race.duration_sec {
-                stats.update_with_duration(duration);
+                stats.update_with_duration_filtered(duration, &config.outlier_filter);
             }
         }
 
@@ -1218,55 +2201,30 @@ impl DoubleBufferClusters {
             cluster_id,
             source,
             representative_title,
-            representative_metadata: HashMap::new(),
+            representative_metadata,
             stats,
             member_race_ids: member_ids,
-            member_titles: titles,
-            member_metadata_history: vec![],
+            member_titles: count_titles(titles),
+            member_metadata_history,
+            quality_score: Some(quality_score),
+            prediction_accuracy: PredictionAccuracy::default(),
+            latest_member_at,
             last_updated: Utc::now(),
             last_accessed: Utc::now(),
         }
     }
 
+    /// `races_by_id` resolves cluster members to the races the rebuild was trained
+    /// on; prediction error is measured on `holdout` when it is non-empty
     async fn validate_new_clusters(
         &self,
         new_clusters: &ClusterSet,
         old_clusters: &ClusterSet,
+        races_by_id: &HashMap<&str, &Race>,
+        holdout: &[Race],
         config: &RebuildConfig,
     ) -> bool {
-        // Get sample races for validation
-        let sample_races: Vec<Race> = new_clusters
-            .values()
-            .flat_map(|c| {
-                c.member_race_ids
-                    .iter()
-                    .take(10) // Sample up to 10 races per cluster
-                    .filter_map(|id| {
-                        // Reconstruct race from cluster data (simplified)
-                        Some(Race {
-                            id: id.clone(),
-                            source: c.source.clone(),
-                            title: c.representative_title.clone(),
-                            state: crate::models::RaceState::Passed,
-                            started_at: c.last_updated,
-                            completed_at: None,
-                            duration_sec: Some(c.stats.median as i64),
-                            eta_sec: Some(c.stats.median as i64),
-                            progress: Some(100),
-                            metadata: Some(c.representative_metadata.clone()),
-                            deeplink: None,
-                            events: Some(Vec::new()),
-                            // New optimistic progress fields
-                            last_progress_update: None,
-                            last_eta_update: None,
-                            eta_source: Some(3), // CLUSTER source
-                            eta_confidence: Some(0.7),
-                            update_interval_hint: Some(15),
-                            eta_history: None,
-                        })
-                    })
-            })
-            .collect();
+        let sample_races = validation_sample(new_clusters, races_by_id);
 
         let criteria = ValidationCriteria {
             max_mae_increase: config.max_mae_increase,
@@ -1285,24 +2243,13 @@ impl DoubleBufferClusters {
             .values()
             .next()
             .cloned()
-            .unwrap_or_else(|| SourceConfig {
-                eps_range: (0.25, 0.45),
-                min_samples: 2,
-                min_cluster_size: 2,
-                preserve_bootstraps: false,
-                w_title: 0.6,
-                w_meta: 0.4,
-                tau_match: 0.5,
-                tau_split: 0.35,
-                tau_merge_lo: 0.35,
-                tau_merge_hi: 0.6,
-                last_eps: None,
-            });
+            .unwrap_or_else(|| config.default_source_config.clone());
 
         let result = validate_clusters_comprehensive(
             new_clusters,
             old_clusters,
             &sample_races,
+            holdout,
             &criteria,
             &source_config,
         )
@@ -1315,12 +2262,260 @@ impl DoubleBufferClusters {
         result.passed
     }
 
-    fn check_memory_budget(&self) -> bool {
-        // Simplified memory check
+    /// Whether `new_clusters` fits in `baseline_memory * max_memory_multiplier`.
+    /// A zero baseline means no budget is enforced.
+    fn check_memory_budget(&self, new_clusters: &ClusterSet, config: &RebuildConfig) -> bool {
+        if self.baseline_memory == 0 {
+            return true;
+        }
+
+        let budget = (self.baseline_memory as f64 * config.max_memory_multiplier) as usize;
+        let estimated = estimate_cluster_set_bytes(new_clusters);
+        if estimated > budget {
+            log::warn!(
+                "Rebuild exceeds memory budget: {} clusters need ~{} bytes, budget is {} bytes",
+                new_clusters.len(),
+                estimated,
+                budget
+            );
+            return false;
+        }
         true
     }
 }
 
+/// Add each held-out race to the nearest cluster of its source, so holding it
+/// out for validation does not drop it from the cluster stats. Races farther
+/// than the source's rebuild eps (`eps`, by source) join the source's noise
+/// bucket when it has one and otherwise stay out, as noise does.
+fn absorb_holdout(
+    clusters: &mut ClusterSet,
+    holdout: Vec<Race>,
+    config: &RebuildConfig,
+    eps: &HashMap<String, f64>,
+) {
+    for race in holdout {
+        let source_config = config.config_for_source(&race.source);
+        let source_eps = eps
+            .get(&race.source)
+            .copied()
+            .unwrap_or(source_config.eps_range.1);
+        let nearest = clusters
+            .iter()
+            .filter(|(id, c)| c.source == race.source && !id.ends_with(":source_avg"))
+            .map(|(id, c)| {
+                (
+                    custom_distance(&race, &centroid_race(id, c), &source_config),
+                    id,
+                )
+            })
+            .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+        let cluster_id = match nearest {
+            Some((distance, id)) if distance <= source_eps => id.clone(),
+            _ => format!("{}:source_avg", race.source),
+        };
+        if let Some(cluster) = clusters.get_mut(&cluster_id) {
+            cluster.latest_member_at = cluster.latest_member_at.max(Some(race.started_at));
+            cluster.member_race_ids.push(race.id);
+            cluster.add_member_title(&race.title);
+            cluster.add_member_metadata(race.metadata.unwrap_or_default());
+            if let Some(duration) = race.duration_sec {
+                cluster
+                    .stats
+                    .update_with_duration_filtered(duration, &config.outlier_filter);
+            }
+        }
+    }
+}
+
+/// Up to 10 completed member races per cluster, with their real titles, metadata
+/// and durations; members that can't be resolved are skipped
+fn validation_sample(clusters: &ClusterSet, races_by_id: &HashMap<&str, &Race>) -> Vec<Race> {
+    clusters
+        .values()
+        .flat_map(|c| {
+            c.member_race_ids
+                .iter()
+                .filter_map(|id| races_by_id.get(id.as_str()))
+                .filter(|race| race.duration_sec.is_some())
+                .take(10)
+                .map(|race| (*race).clone())
+        })
+        .collect()
+}
+
+/// Rough heap + inline footprint of a cluster set: ids, titles, metadata and
+/// the stats window. Ignores allocator and hash map overhead.
+fn estimate_cluster_set_bytes(clusters: &ClusterSet) -> usize {
+    let strings = |items: &[String]| -> usize {
+        items
+            .iter()
+            .map(|s| std::mem::size_of::<String>() + s.len())
+            .sum()
+    };
+    let metadata = |map: &HashMap<String, String>| -> usize {
+        map.iter()
+            .map(|(k, v)| 2 * std::mem::size_of::<String>() + k.len() + v.len())
+            .sum()
+    };
+
+    clusters
+        .iter()
+        .map(|(id, cluster)| {
+            std::mem::size_of::<String>()
+                + id.len()
+                + std::mem::size_of::<RaceCluster>()
+                + cluster.cluster_id.len()
+                + cluster.source.len()
+                + cluster.representative_title.len()
+                + metadata(&cluster.representative_metadata)
+                + strings(&cluster.member_race_ids)
+                + cluster
+                    .member_titles
+                    .keys()
+                    .map(|t| std::mem::size_of::<String>() + std::mem::size_of::<u32>() + t.len())
+                    .sum::<usize>()
+                + cluster
+                    .member_metadata_history
+                    .iter()
+                    .map(metadata)
+                    .sum::<usize>()
+                + cluster.stats.recent_times.len() * std::mem::size_of::<i64>()
+        })
+        .sum()
+}
+
+/// Most common value of each relevant metadata key across members; ties go
+/// to the lexicographically smallest value so labels are deterministic.
+fn compute_representative_metadata(members: &[&Race]) -> HashMap<String, String> {
+    let mut counts: HashMap<&str, HashMap<&str, usize>> = HashMap::new();
+    for metadata in members.iter().filter_map(|r| r.metadata.as_ref()) {
+        for (key, value) in metadata {
+            if RELEVANT_KEYS.contains(&key.as_str()) {
+                *counts
+                    .entry(key.as_str())
+                    .or_default()
+                    .entry(value.as_str())
+                    .or_insert(0) += 1;
+            }
+        }
+    }
+
+    counts
+        .into_iter()
+        .filter_map(|(key, values)| {
+            values
+                .into_iter()
+                .max_by(|(va, ca), (vb, cb)| ca.cmp(cb).then_with(|| vb.cmp(va)))
+                .map(|(value, _)| (key.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// Members sampled for pairwise cohesion; keeps scoring O(1) per cluster
+const QUALITY_COHESION_SAMPLE: usize = 20;
+/// Member count at which the sample-size term reaches ~63%
+const QUALITY_SIZE_SCALE: f64 = 10.0;
+
+/// Trustworthiness in [0,1]: weighted mix of sample size, title/metadata
+/// cohesion (1 - mean pairwise `custom_distance` over a member sample) and
+/// duration consistency (1 / (1 + coefficient of variation)).
+pub fn cluster_quality_score(members: &[&Race], config: &SourceConfig) -> f64 {
+    if members.is_empty() {
+        return 0.0;
+    }
+
+    let size = 1.0 - (-(members.len() as f64) / QUALITY_SIZE_SCALE).exp();
+
+    let step = (members.len() as f64 / QUALITY_COHESION_SAMPLE as f64).max(1.0);
+    let sample: Vec<&Race> = (0..members.len().min(QUALITY_COHESION_SAMPLE))
+        .map(|i| members[(i as f64 * step) as usize])
+        .collect();
+    let titles = NormalizedTitles::from_races(sample.iter().copied());
+    let mut distance_sum = 0.0;
+    let mut pairs = 0usize;
+    for (i, a) in sample.iter().enumerate() {
+        for b in &sample[i + 1..] {
+            distance_sum += custom_distance_with(a, b, config, &titles);
+            pairs += 1;
+        }
+    }
+    // A lone member says nothing about cohesion
+    let cohesion = if pairs > 0 {
+        (1.0 - distance_sum / pairs as f64).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+
+    let durations: Vec<f64> = members
+        .iter()
+        .filter_map(|r| r.duration_sec)
+        .map(|d| d as f64)
+        .collect();
+    let consistency = if durations.is_empty() {
+        0.0
+    } else {
+        let mean = durations.iter().sum::<f64>() / durations.len() as f64;
+        let variance =
+            durations.iter().map(|d| (d - mean).powi(2)).sum::<f64>() / durations.len() as f64;
+        if mean > 0.0 {
+            1.0 / (1.0 + variance.sqrt() / mean)
+        } else {
+            0.0
+        }
+    };
+
+    (0.3 * size + 0.4 * cohesion + 0.3 * consistency).clamp(0.0, 1.0)
+}
+
+fn representative_title(titles: &[String], mode: CentroidTitleMode, max_titles: usize) -> String {
+    // Evenly spaced subsample keeps the O(n²) medoid bounded and deterministic
+    let sampled: Vec<String>;
+    let titles = if max_titles > 0 && titles.len() > max_titles {
+        let step = titles.len() as f64 / max_titles as f64;
+        sampled = (0..max_titles)
+            .map(|i| titles[(i as f64 * step) as usize].clone())
+            .collect();
+        &sampled
+    } else {
+        titles
+    };
+
+    match mode {
+        CentroidTitleMode::Medoid => compute_centroid_title(titles),
+        CentroidTitleMode::Template => compute_template_title(titles),
+    }
+}
+
+/// Tokens present in more than half of the titles, ordered by first
+/// appearance in member order. Falls back to the medoid when nothing is shared.
+fn compute_template_title(titles: &[String]) -> String {
+    let mut order: Vec<&str> = Vec::new();
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for title in titles {
+        let mut seen = HashSet::new();
+        for token in title.split_whitespace() {
+            if seen.insert(token) {
+                let count = counts.entry(token).or_insert(0);
+                if *count == 0 {
+                    order.push(token);
+                }
+                *count += 1;
+            }
+        }
+    }
+
+    let template: Vec<&str> = order
+        .into_iter()
+        .filter(|token| counts[token] * 2 > titles.len())
+        .collect();
+    if template.is_empty() {
+        compute_centroid_title(titles)
+    } else {
+        template.join(" ")
+    }
+}
+
 fn compute_centroid_title(titles: &[String]) -> String {
     if titles.is_empty() {
         return String::new();
@@ -1337,7 +2532,7 @@ fn compute_centroid_title(titles: &[String]) -> String {
     for candidate in titles {
         let sum_distance: usize = titles
             .iter()
-            .map(|other| levenshtein::levenshtein(candidate, other))
+            .map(|other| levenshtein::levenshtein(compared_title(candidate), compared_title(other)))
             .sum();
 
         let avg_distance = sum_distance as f64 / titles.len() as f64;
@@ -1367,18 +2562,192 @@ pub fn calculate_noise_ratio(clusters: &ClusterSet) -> f64 {
     }
 }
 
-pub fn calculate_average_cohesion(clusters: &ClusterSet) -> f64 {
-    // Simplified cohesion: ratio of single-member clusters
-    let singleton_count = clusters
-        .values()
-        .filter(|c| c.member_race_ids.len() <= 1)
-        .count();
+/// Distinct titles per cluster compared pairwise for cohesion; only the most common are kept
+const COHESION_SAMPLE_MEMBERS: usize = 20;
 
-    if clusters.is_empty() {
+/// Mean over multi-member clusters of `1 - mean pairwise custom_distance` between
+/// members (members sharing a title are at distance 0). The metadata term comes
+/// from the recent members' own metadata when at least two carry any, else the
+/// titles are compared under the representative metadata. Noise buckets are
+/// skipped; a set without any comparable pair is treated as fully cohesive.
+pub fn calculate_cohesion(
+    clusters: &ClusterSet,
+    config_for_source: impl Fn(&str) -> SourceConfig,
+) -> f64 {
+    let mut configs: HashMap<&str, SourceConfig> = HashMap::new();
+    let mut per_cluster = Vec::new();
+
+    for (id, cluster) in clusters {
+        if id.ends_with(":source_avg") || cluster.member_title_count() < 2 {
+            continue;
+        }
+        let config = configs
+            .entry(cluster.source.as_str())
+            .or_insert_with(|| config_for_source(&cluster.source));
+
+        let mut titles: Vec<(&String, u32)> =
+            cluster.member_titles.iter().map(|(t, &c)| (t, c)).collect();
+        titles.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        titles.truncate(COHESION_SAMPLE_MEMBERS);
+        let members: Vec<(Race, f64)> = titles
+            .into_iter()
+            .map(|(title, count)| {
+                let mut race = Race::new(cluster.source.clone(), title.clone());
+                race.metadata = Some(cluster.representative_metadata.clone());
+                (race, count as f64)
+            })
+            .collect();
+
+        // Each distinct-title pair stands for count_a * count_b member pairs
+        let normalized = NormalizedTitles::from_races(members.iter().map(|(race, _)| race));
+        let mut total = 0.0;
+        let mut title_total = 0.0;
+        let mut pairs = 0.0;
+        for (i, (a, count_a)) in members.iter().enumerate() {
+            pairs += count_a * (count_a - 1.0) / 2.0;
+            for (b, count_b) in &members[i + 1..] {
+                let breakdown = distance_breakdown_with(a, b, config, &normalized);
+                total += count_a * count_b * breakdown.distance;
+                title_total += count_a * count_b * breakdown.title_distance;
+                pairs += count_a * count_b;
+            }
+        }
+        if pairs > 0.0 {
+            let history = &cluster.member_metadata_history;
+            let distance = match member_metadata_distance(history, config) {
+                Some(metadata) => config.w_title * title_total / pairs + config.w_meta * metadata,
+                None => total / pairs,
+            };
+            per_cluster.push(1.0 - distance.clamp(0.0, 1.0));
+        }
+    }
+
+    if per_cluster.is_empty() {
         return 1.0;
     }
+    per_cluster.iter().sum::<f64>() / per_cluster.len() as f64
+}
 
-    1.0 - (singleton_count as f64 / clusters.len() as f64)
+/// Mean metadata distance between pairs of a cluster's recent members, sampled
+/// like the titles; None when fewer than two members carry relevant metadata
+fn member_metadata_distance(
+    history: &[HashMap<String, String>],
+    config: &SourceConfig,
+) -> Option<f64> {
+    let sets: Vec<HashSet<String>> = history
+        .iter()
+        .map(|metadata| relevant_metadata(Some(metadata)))
+        .filter(|set| !set.is_empty())
+        .collect();
+    if sets.len() < 2 {
+        return None;
+    }
+    let step = sets.len().div_ceil(COHESION_SAMPLE_MEMBERS);
+    let sample: Vec<&HashSet<String>> = sets.iter().step_by(step).collect();
+    let mut total = 0.0;
+    let mut pairs = 0usize;
+    for (i, a) in sample.iter().enumerate() {
+        for b in &sample[i + 1..] {
+            total += 1.0 - jaccard_similarity(a, b, &config.metadata_key_weights);
+            pairs += 1;
+        }
+    }
+    (pairs > 0).then(|| total / pairs as f64)
+}
+
+/// A cluster's representative title and metadata as a race, for distance checks
+fn centroid_race(id: &ClusterId, cluster: &RaceCluster) -> Race {
+    let mut race = Race::new(cluster.source.clone(), cluster.representative_title.clone());
+    race.id = id.clone();
+    race.metadata = Some(cluster.representative_metadata.clone());
+    race
+}
+
+/// Cluster whose centroid is closest to `race` among clusters of its source,
+/// ignoring membership; the source's noise bucket is used only when nothing else is
+pub fn nearest_cluster<'a>(
+    clusters: &'a ClusterSet,
+    race: &Race,
+    config: &SourceConfig,
+) -> Option<(&'a ClusterId, &'a RaceCluster)> {
+    clusters
+        .iter()
+        .filter(|(id, c)| c.source == race.source && !id.ends_with(":source_avg"))
+        .map(|(id, c)| (custom_distance(race, &centroid_race(id, c), config), id, c))
+        .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal))
+        .map(|(_, id, c)| (id, c))
+        .or_else(|| {
+            let noise_id = format!("{}:source_avg", race.source);
+            clusters.get_key_value(&noise_id)
+        })
+}
+
+/// Split completed races into (train, holdout), holding out roughly `ratio` of
+/// them. Membership is decided by a seeded hash of the race id, so it does not
+/// depend on race order. Races without a duration always train.
+pub fn split_holdout(races: Vec<Race>, ratio: f64, seed: u64) -> (Vec<Race>, Vec<Race>) {
+    if ratio <= 0.0 {
+        return (races, Vec::new());
+    }
+    let threshold = (ratio.min(1.0) * u64::MAX as f64) as u64;
+    races.into_iter().partition(|race| {
+        race.duration_sec.is_none()
+            || seahash::hash(format!("{}:{}", seed, race.id).as_bytes()) >= threshold
+    })
+}
+
+/// Centroids per source compared pairwise for separation; only the largest clusters are kept
+const SEPARATION_SAMPLE_CENTROIDS: usize = 200;
+
+/// Mean distance from each cluster centroid (representative title and metadata)
+/// to the nearest other centroid of the same source. Noise buckets are skipped;
+/// with no comparable pair the clusters are treated as fully separated.
+pub fn calculate_separation(
+    clusters: &ClusterSet,
+    config_for_source: impl Fn(&str) -> SourceConfig,
+) -> f64 {
+    let mut by_source: std::collections::BTreeMap<&str, Vec<(&ClusterId, &RaceCluster)>> =
+        std::collections::BTreeMap::new();
+    for (id, cluster) in clusters {
+        if !id.ends_with(":source_avg") {
+            by_source
+                .entry(cluster.source.as_str())
+                .or_default()
+                .push((id, cluster));
+        }
+    }
+
+    let mut nearest = Vec::new();
+    for (source, mut members) in by_source {
+        if members.len() < 2 {
+            continue;
+        }
+        members.sort_by(|a, b| {
+            b.1.member_race_ids
+                .len()
+                .cmp(&a.1.member_race_ids.len())
+                .then_with(|| a.0.cmp(b.0))
+        });
+        members.truncate(SEPARATION_SAMPLE_CENTROIDS);
+        let config = config_for_source(source);
+        let centroids: Vec<Race> = members.iter().map(|(id, c)| centroid_race(id, c)).collect();
+        let normalized = NormalizedTitles::from_races(&centroids);
+
+        let mut source_nearest = vec![f64::INFINITY; centroids.len()];
+        for (i, a) in centroids.iter().enumerate() {
+            for (j, b) in centroids.iter().enumerate().skip(i + 1) {
+                let distance = custom_distance_with(a, b, &config, &normalized);
+                source_nearest[i] = source_nearest[i].min(distance);
+                source_nearest[j] = source_nearest[j].min(distance);
+            }
+        }
+        nearest.extend(source_nearest);
+    }
+
+    if nearest.is_empty() {
+        return 1.0;
+    }
+    nearest.iter().sum::<f64>() / nearest.len() as f64
 }
 
 // Calculate silhouette coefficient for cluster quality
@@ -1574,6 +2943,1203 @@ impl RebuildState {
         }
     }
 }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cluster::test_utils::test_cluster;
+
+    fn valid_source_config() -> SourceConfig {
+        RebuildConfig::default().source_configs["cargo"].clone()
+    }
+
+    #[test]
+    fn test_default_rebuild_config_is_valid() {
+        assert!(RebuildConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_source_config_rejects_bad_weights() {
+        let mut config = valid_source_config();
+        config.w_title = 0.7;
+        config.w_meta = 0.4;
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("sum"), "{}", err);
+
+        config.w_title = 1.2;
+        config.w_meta = -0.2;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_source_config_rejects_inverted_eps_range() {
+        let mut config = valid_source_config();
+        config.eps_range = (0.4, 0.4);
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("eps_range"), "{}", err);
+    }
+
+    #[test]
+    fn test_source_config_rejects_zero_min_samples() {
+        let mut config = valid_source_config();
+        config.min_samples = 0;
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("min_samples"), "{}", err);
+    }
+
+    #[test]
+    fn test_source_config_rejects_prediction_floor_above_window() {
+        let mut config = valid_source_config();
+        config.stats_window = 5;
+        config.min_samples_for_prediction = 6;
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("min_samples_for_prediction"), "{}", err);
+
+        config.min_samples_for_prediction = 5;
+        assert!(config.validate().is_ok());
+    }
+
+    fn synthetic_races(n: usize) -> Vec<Race> {
+        (0..n)
+            .map(|i| {
+                let mut race = Race::new(
+                    "cargo".to_string(),
+                    format!("cargo build --package crate_{}", i % 7),
+                );
+                race.id = format!("race-{}", i);
+                race
+            })
+            .collect()
+    }
+
+    /// One `cargo` race per title, with ids `{prefix}-0`, `{prefix}-1`, ...
+    fn cargo_races<T: AsRef<str>>(prefix: &str, titles: &[T]) -> Vec<Race> {
+        titles
+            .iter()
+            .enumerate()
+            .map(|(i, title)| {
+                let mut race = Race::new("cargo".to_string(), title.as_ref().to_string());
+                race.id = format!("{}-{}", prefix, i);
+                race
+            })
+            .collect()
+    }
+
+    /// Rebuilt `cargo` cluster whose members are exactly `races`
+    fn cargo_cluster(cluster_id: &str, races: &[Race]) -> RaceCluster {
+        DoubleBufferClusters::new(0).create_race_cluster(
+            cluster_id.to_string(),
+            "cargo".to_string(),
+            races.iter().map(|r| r.id.clone()).collect(),
+            races,
+            &valid_source_config(),
+            &RebuildConfig::default(),
+        )
+    }
+
+    /// `train-{i}` races titled "cargo test shard {i}", each lasting `duration_sec`
+    fn shard_races(n: usize, duration_sec: i64) -> Vec<Race> {
+        let titles: Vec<String> = (0..n).map(|i| format!("cargo test shard {}", i)).collect();
+        let mut races = cargo_races("train", &titles);
+        for race in &mut races {
+            race.duration_sec = Some(duration_sec);
+        }
+        races
+    }
+
+    #[test]
+    fn test_rebuild_normalizes_each_title_once() {
+        let races = synthetic_races(60);
+        let config = RebuildConfig::default();
+        let source_config = config.config_for_source("cargo");
+
+        let mut dbscan = OptimizedDBSCAN::new(config.distance_cache_size);
+        detect_optimal_eps(
+            &races,
+            source_config.min_samples,
+            source_config.eps_range.0,
+            source_config.eps_range.1,
+            &source_config,
+            &config,
+            &mut dbscan,
+        );
+        let result = dbscan.run_dbscan(races.clone(), 0.2, 2, &source_config);
+        let calls = dbscan.titles.normalizations();
+
+        // Previously two normalizations per computed distance
+        let distances = dbscan.cache_misses();
+        assert!(distances > 1000, "only {} distances computed", distances);
+        assert_eq!(calls, races.len());
+        assert!(!result.clusters.is_empty());
+
+        let mut hnsw = HnswDBSCAN::new(source_config.clone(), config.distance_cache_size);
+        hnsw.build_index(races.clone()).unwrap();
+        hnsw.run_dbscan(0.2, 2);
+        assert_eq!(hnsw.normalized_titles().normalizations(), races.len());
+
+        // Cached and uncached distances agree
+        let titles = NormalizedTitles::from_races(&races);
+        assert_eq!(
+            custom_distance_with(&races[0], &races[1], &source_config, &titles),
+            custom_distance(&races[0], &races[1], &source_config)
+        );
+    }
+
+    #[test]
+    fn test_representative_metadata_uses_mode_per_key() {
+        let races: Vec<Race> = [
+            ("gpt-4", "rust"),
+            ("gpt-4", "python"),
+            ("gpt-3.5", "rust"),
+            ("gpt-4", "go"),
+        ]
+        .iter()
+        .enumerate()
+        .map(|(i, (model, language))| {
+            let mut race = Race::new("codex".to_string(), "Refactor module".to_string());
+            race.id = format!("codex-{}", i);
+            let mut metadata = HashMap::new();
+            metadata.insert("model".to_string(), model.to_string());
+            metadata.insert("language".to_string(), language.to_string());
+            metadata.insert("session".to_string(), format!("s{}", i));
+            race.metadata = Some(metadata);
+            race
+        })
+        .collect();
+        let member_ids: Vec<String> = races.iter().map(|r| r.id.clone()).collect();
+
+        let buffers = DoubleBufferClusters::new(0);
+        let cluster = buffers.create_race_cluster(
+            "codex:0".to_string(),
+            "codex".to_string(),
+            member_ids,
+            &races,
+            &RebuildConfig::default().config_for_source("codex"),
+            &RebuildConfig::default(),
+        );
+
+        let metadata = &cluster.representative_metadata;
+        assert_eq!(metadata.get("model").map(String::as_str), Some("gpt-4"));
+        // rust (2) beats go and python (1 each)
+        assert_eq!(metadata.get("language").map(String::as_str), Some("rust"));
+        assert!(!metadata.contains_key("session"));
+
+        // Ties break lexicographically
+        let tied: Vec<&Race> = races[1..=2].iter().collect();
+        let metadata = compute_representative_metadata(&tied);
+        assert_eq!(metadata.get("model").map(String::as_str), Some("gpt-3.5"));
+        assert_eq!(metadata.get("language").map(String::as_str), Some("python"));
+    }
+
+    #[test]
+    fn test_noise_policy_shapes_cluster_set() {
+        let mut races: Vec<Race> = (0..6)
+            .map(|i| {
+                let mut race = Race::new("cargo".to_string(), "cargo build --release".to_string());
+                race.id = format!("build-{}", i);
+                race.duration_sec = Some(100);
+                race
+            })
+            .collect();
+        for (i, title) in ["publish docs site", "nightly fuzz campaign", "bench report"]
+            .iter()
+            .enumerate()
+        {
+            let mut race = Race::new("cargo".to_string(), title.to_string());
+            race.id = format!("outlier-{}", i);
+            race.duration_sec = Some(1000 * (i as i64 + 1));
+            races.push(race);
+        }
+
+        let mut source_config = valid_source_config();
+        let result = OptimizedDBSCAN::new(1000).run_dbscan(races.clone(), 0.3, 3, &source_config);
+        assert_eq!(result.clusters.len(), 1);
+        assert_eq!(result.noise.len(), 3);
+
+        let buffers = DoubleBufferClusters::new(0);
+        let config = RebuildConfig::default();
+        let mut cluster_ids = |policy: NoisePolicy| {
+            source_config.noise_policy = policy;
+            let mut ids: Vec<ClusterId> = buffers
+                .noise_clusters("cargo", result.noise.clone(), &races, &source_config, &config)
+                .into_iter()
+                .map(|(id, _)| id)
+                .collect();
+            ids.sort();
+            ids
+        };
+
+        assert_eq!(cluster_ids(NoisePolicy::SourceAverage), vec!["cargo:source_avg"]);
+        assert!(cluster_ids(NoisePolicy::Discard).is_empty());
+        assert_eq!(
+            cluster_ids(NoisePolicy::PerPointSingleton),
+            vec![
+                "cargo:noise:outlier-0",
+                "cargo:noise:outlier-1",
+                "cargo:noise:outlier-2"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_template_title_vs_medoid() {
+        let titles: Vec<String> = [
+            "cargo build --package api",
+            "cargo build --package web",
+            "cargo build --package cli",
+            "cargo build --package api --release",
+        ]
+        .iter()
+        .map(|t| t.to_string())
+        .collect();
+
+        let medoid = representative_title(&titles, CentroidTitleMode::Medoid, 200);
+        assert!(titles.contains(&medoid));
+
+        let template = representative_title(&titles, CentroidTitleMode::Template, 200);
+        assert_eq!(template, "cargo build --package");
+
+        // Nothing shared by a majority: fall back to the medoid
+        let disjoint = vec!["alpha".to_string(), "beta".to_string(), "gamma".to_string()];
+        let fallback = representative_title(&disjoint, CentroidTitleMode::Template, 200);
+        assert!(disjoint.contains(&fallback));
+
+        // Large clusters are subsampled but still yield a member title
+        let many: Vec<String> = (0..1000).map(|i| format!("pytest tests/test_{}.py", i)).collect();
+        let sampled = representative_title(&many, CentroidTitleMode::Medoid, 50);
+        assert!(many.contains(&sampled));
+    }
+
+    #[test]
+    fn test_eps_subsample_defaults_match_previous_formula() {
+        let config = RebuildConfig::default();
+        for n in [0, 10, 50_000, 100_000, 1_000_000] {
+            let previous = ((n * 15) / 100).max(n.min(50_000)).min(n);
+            assert_eq!(eps_subsample_size(n, &config), previous);
+        }
+    }
+
+    #[test]
+    fn test_small_subsample_eps_within_range() {
+        let races = synthetic_races(200);
+        let source_config = valid_source_config();
+        let mut config = RebuildConfig::default();
+        config.eps_subsample_fraction = 0.05;
+        config.eps_subsample_size = 20;
+        config.eps_subsample_seed = 7;
+        assert_eq!(eps_subsample_size(races.len(), &config), 20);
+
+        let eps = detect_optimal_eps(
+            &races,
+            source_config.min_samples,
+            source_config.eps_range.0,
+            source_config.eps_range.1,
+            &source_config,
+            &config,
+            &mut OptimizedDBSCAN::new(config.distance_cache_size),
+        );
+        assert!(eps.eps >= source_config.eps_range.0 && eps.eps <= source_config.eps_range.1);
+    }
+
+    #[test]
+    fn test_stale_races_decay_out_of_cluster_density() {
+        let now = Utc::now();
+        let race = |id: &str, age_days: i64| {
+            let mut race = Race::new("cargo".to_string(), "cargo build --release".to_string());
+            race.id = id.to_string();
+            race.started_at = now - chrono::Duration::days(age_days);
+            race.completed_at = Some(race.started_at + chrono::Duration::minutes(5));
+            race.duration_sec = Some(300);
+            race
+        };
+        // One run this week; the rest of the history is a year old
+        let races = vec![
+            race("recent", 2),
+            race("old-1", 365),
+            race("old-2", 366),
+            race("old-3", 367),
+            race("old-4", 368),
+        ];
+        let mut source_config = valid_source_config();
+        source_config.min_samples = 2;
+
+        let unweighted =
+            OptimizedDBSCAN::new(100).run_dbscan(races.clone(), 0.1, 2, &source_config);
+        assert_eq!(unweighted.clusters.len(), 1);
+        assert!(unweighted.noise.is_empty());
+
+        source_config.recency_half_life_days = Some(7.0);
+        let weight = recency_weight(&races[0], &source_config, now);
+        assert!(weight > 0.75 && weight < 1.0, "{}", weight);
+        assert!(recency_weight(&races[1], &source_config, now) < 1e-9);
+
+        // The old runs alone no longer add up to min_samples of neighbor weight
+        let weighted = OptimizedDBSCAN::new(100).run_dbscan(races, 0.1, 2, &source_config);
+        assert!(weighted.clusters.is_empty());
+        assert_eq!(weighted.noise.len(), 5);
+    }
+
+    #[test]
+    fn test_hnsw_sums_weighted_density_over_whole_neighborhood() {
+        let now = Utc::now();
+        // Enough races for the HNSW index, each two half-lives old (weight 0.25)
+        let races: Vec<Race> = (0..12)
+            .map(|i| {
+                let mut race = Race::new("cargo".to_string(), "cargo build --release".to_string());
+                race.id = format!("cargo-{}", i);
+                race.started_at = now - chrono::Duration::days(14);
+                race.completed_at = Some(race.started_at + chrono::Duration::minutes(5));
+                race.duration_sec = Some(300);
+                race
+            })
+            .collect();
+        let mut source_config = valid_source_config();
+        source_config.min_samples = 2;
+        source_config.recency_half_life_days = Some(7.0);
+
+        // Eleven neighbors at 0.25 clear min_samples; the first k never would
+        let mut hnsw = HnswDBSCAN::new(source_config.clone(), 1000);
+        hnsw.build_index(races.clone()).unwrap();
+        let result = hnsw.run_dbscan(0.1, 2);
+        assert_eq!(result.clusters.len(), 1);
+        assert!(result.noise.is_empty());
+
+        let exact = OptimizedDBSCAN::new(100).run_dbscan(races, 0.1, 2, &source_config);
+        assert_eq!(exact.clusters.len(), 1);
+    }
+
+    #[test]
+    fn test_dbscan_reuses_distances_cached_during_eps_detection() {
+        let races = synthetic_races(30);
+        let source_config = valid_source_config();
+        let config = RebuildConfig::default();
+        let pairs = (races.len() * (races.len() - 1) / 2) as u64;
+
+        let mut dbscan = OptimizedDBSCAN::new(config.distance_cache_size);
+        let knee = detect_optimal_eps(
+            &races,
+            source_config.min_samples,
+            source_config.eps_range.0,
+            source_config.eps_range.1,
+            &source_config,
+            &config,
+            &mut dbscan,
+        );
+        // Every pair is computed once; the reverse lookups hit
+        assert_eq!(dbscan.cache_misses(), pairs);
+        let hits_after_detection = dbscan.cache_hits();
+
+        let shared = dbscan.run_dbscan(
+            races.clone(),
+            knee.eps,
+            source_config.min_samples,
+            &source_config,
+        );
+        assert_eq!(dbscan.cache_misses(), pairs);
+        assert!(dbscan.cache_hits() > hits_after_detection);
+
+        // A separate instance computes every pair again and clusters the same
+        let mut fresh = OptimizedDBSCAN::new(config.distance_cache_size);
+        let separate = fresh.run_dbscan(races, knee.eps, source_config.min_samples, &source_config);
+        assert_eq!(fresh.cache_misses(), pairs);
+        assert_eq!(shared.noise.len(), separate.noise.len());
+        assert_eq!(shared.clusters.len(), separate.clusters.len());
+    }
+
+    fn varied_races(n: usize) -> Vec<Race> {
+        let commands = [
+            "cargo build",
+            "cargo test",
+            "npm run build",
+            "make",
+            "docker build",
+        ];
+        (0..n)
+            .map(|i| {
+                let mut race = Race::new(
+                    "cmd".to_string(),
+                    format!("{} --target t{} step {}", commands[i % 5], i % 11, i % 3),
+                );
+                race.id = format!("race-{}", i);
+                race
+            })
+            .collect()
+    }
+
+    fn sorted_clusters(result: &DBSCANResult) -> Vec<Vec<RaceId>> {
+        let mut clusters: Vec<Vec<RaceId>> = result
+            .clusters
+            .values()
+            .map(|members| {
+                let mut members = members.clone();
+                members.sort();
+                members
+            })
+            .collect();
+        clusters.sort();
+        clusters
+    }
+
+    #[test]
+    fn test_parallel_dbscan_matches_serial() {
+        let races = varied_races(300);
+        let source_config = valid_source_config();
+        let config = RebuildConfig::default();
+
+        let run = |parallel: bool| {
+            let mut dbscan =
+                OptimizedDBSCAN::new(config.distance_cache_size).with_parallel(parallel);
+            let knee = detect_optimal_eps(
+                &races,
+                source_config.min_samples,
+                source_config.eps_range.0,
+                source_config.eps_range.1,
+                &source_config,
+                &config,
+                &mut dbscan,
+            );
+            let result = dbscan.run_dbscan(
+                races.clone(),
+                knee.eps,
+                source_config.min_samples,
+                &source_config,
+            );
+            (knee, result)
+        };
+        let (serial_knee, serial) = run(false);
+        let (parallel_knee, parallel) = run(true);
+
+        assert_eq!(serial_knee.eps, parallel_knee.eps);
+        assert_eq!(serial_knee.confidence, parallel_knee.confidence);
+        assert!(!serial.clusters.is_empty());
+        assert_eq!(sorted_clusters(&serial), sorted_clusters(&parallel));
+        let mut serial_noise = serial.noise.clone();
+        let mut parallel_noise = parallel.noise.clone();
+        serial_noise.sort();
+        parallel_noise.sort();
+        assert_eq!(serial_noise, parallel_noise);
+        assert_eq!(serial.border_points.len(), parallel.border_points.len());
+    }
+
+    /// Timing comparison; run with `cargo test --release -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn test_parallel_dbscan_benchmark() {
+        let races = varied_races(800);
+        let source_config = valid_source_config();
+        let config = RebuildConfig::default();
+
+        let time = |parallel: bool| {
+            let mut dbscan =
+                OptimizedDBSCAN::new(config.distance_cache_size).with_parallel(parallel);
+            let start = std::time::Instant::now();
+            dbscan.run_dbscan(
+                races.clone(),
+                0.3,
+                source_config.min_samples,
+                &source_config,
+            );
+            start.elapsed()
+        };
+        let serial = time(false);
+        let parallel = time(true);
+        println!(
+            "DBSCAN over {} races: serial {:?}, parallel {:?} on {} threads",
+            races.len(),
+            serial,
+            parallel,
+            rayon::current_num_threads()
+        );
+        if rayon::current_num_threads() > 1 {
+            assert!(parallel < serial);
+        }
+    }
+
+    #[test]
+    fn test_flat_curve_has_low_confidence() {
+        let flat = vec![0.3; 20];
+        let knee = detect_knee_kneedle(&flat, Curve::Concave, Direction::Decreasing, 1.0).unwrap();
+        assert_eq!(knee.confidence, 0.0);
+
+        // A pronounced knee scores well above the default threshold
+        let elbow: Vec<f64> = (0..20)
+            .map(|i| if i < 15 { 0.9 - i as f64 * 0.001 } else { 0.1 })
+            .collect();
+        let knee = detect_knee_kneedle(&elbow, Curve::Concave, Direction::Decreasing, 1.0).unwrap();
+        assert!(knee.confidence > RebuildConfig::default().min_knee_confidence);
+    }
+
+    #[test]
+    fn test_low_confidence_knee_prefers_last_eps() {
+        let source_config = valid_source_config();
+        let config = RebuildConfig::default();
+        let low = KneeResult {
+            eps: 0.2,
+            confidence: 0.0,
+        };
+
+        assert_eq!(choose_eps(&low, Some(0.3), &source_config, &config), 0.3);
+        // Without a previous eps the detected value is all we have
+        assert_eq!(choose_eps(&low, None, &source_config, &config), 0.2);
+
+        // Confident knees are blended with the previous eps
+        let confident = KneeResult {
+            eps: 0.2,
+            confidence: 0.8,
+        };
+        let blended = choose_eps(&confident, Some(0.3), &source_config, &config);
+        assert!(blended > 0.2 && blended < 0.3);
+    }
+
+    #[test]
+    fn test_rebuild_config_error_names_source() {
+        let mut config = RebuildConfig::default();
+        config.source_configs.get_mut("npm").unwrap().min_samples = 0;
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("'npm'"), "{}", err);
+    }
+
+    #[test]
+    fn test_embedding_b64_matches_json_embedding() {
+        let embedding: Vec<f32> = (0..EMBEDDING_DIM).map(|i| (i as f32).sin()).collect();
+        let packed: Vec<u8> = embedding.iter().flat_map(|f| f.to_le_bytes()).collect();
+
+        let mut json_race = Race::new("codex".to_string(), "Refactor module".to_string());
+        let mut metadata = HashMap::new();
+        metadata.insert("embedding".to_string(), serde_json::to_string(&embedding).unwrap());
+        json_race.metadata = Some(metadata);
+
+        let mut b64_race = json_race.clone();
+        let mut metadata = HashMap::new();
+        metadata.insert("embedding_b64".to_string(), general_purpose::STANDARD.encode(&packed));
+        // Binary form takes precedence over a stale JSON one
+        metadata.insert("embedding".to_string(), "[1.0, 2.0]".to_string());
+        b64_race.metadata = Some(metadata);
+
+        assert_eq!(race_to_vector(&json_race), embedding);
+        assert_eq!(race_to_vector(&b64_race), embedding);
+    }
+
+    #[test]
+    fn test_embedding_b64_length_mismatch_falls_back_to_ngrams() {
+        let packed: Vec<u8> = [0.5f32; 16].iter().flat_map(|f| f.to_le_bytes()).collect();
+        let mut race = Race::new("codex".to_string(), "Refactor module".to_string());
+        let hashed = race_to_vector(&race);
+
+        let mut metadata = HashMap::new();
+        metadata.insert("embedding_b64".to_string(), general_purpose::STANDARD.encode(&packed));
+        race.metadata = Some(metadata);
+
+        let vector = race_to_vector(&race);
+        assert_eq!(vector.len(), EMBEDDING_DIM);
+        assert_eq!(vector, hashed);
+    }
+
+    #[test]
+    fn test_invalid_embeddings_fall_back_to_ngrams() {
+        let race = Race::new("codex".to_string(), "Refactor module".to_string());
+        let hashed = race_to_vector(&race);
+        let with_metadata = |key: &str, value: String| {
+            let mut race = race.clone();
+            race.metadata = Some(HashMap::from([(key.to_string(), value)]));
+            race
+        };
+
+        let mut nan = vec![0.1f32; EMBEDDING_DIM];
+        nan[7] = f32::NAN;
+        let packed: Vec<u8> = nan.iter().flat_map(|f| f.to_le_bytes()).collect();
+        let nan_b64 = general_purpose::STANDARD.encode(&packed);
+
+        // JSON can't spell NaN, but out-of-range numbers overflow f32 to infinity
+        let mut overflow: Vec<String> = vec!["0.1".to_string(); EMBEDDING_DIM];
+        overflow[3] = "1e300".to_string();
+        let overflow_json = format!("[{}]", overflow.join(","));
+
+        let short_json = serde_json::to_string(&vec![0.5f32; 16]).unwrap();
+        let oversized_json = format!("[{}]", vec!["0.5"; EMBEDDING_DIM * 20].join(","));
+
+        for race in [
+            with_metadata("embedding_b64", nan_b64),
+            with_metadata("embedding", overflow_json),
+            with_metadata("embedding", short_json),
+            with_metadata("embedding", oversized_json),
+            with_metadata("embedding", "[0.5, \"x\"".to_string()),
+        ] {
+            let vector = race_to_vector(&race);
+            assert_eq!(vector.len(), EMBEDDING_DIM);
+            assert_eq!(vector, hashed);
+            assert!(vector.iter().all(|x| x.is_finite()));
+        }
+    }
+
+    #[test]
+    fn test_quality_score_prefers_tight_well_sampled_clusters() {
+        let config = valid_source_config();
+        let race = |i: usize, title: &str, duration: i64| {
+            let mut race = Race::new("cargo".to_string(), title.to_string());
+            race.id = format!("cargo-{}", i);
+            race.duration_sec = Some(duration);
+            race
+        };
+
+        let tight: Vec<Race> = (0..30)
+            .map(|i| race(i, "cargo build --release", 120 + (i as i64 % 3)))
+            .collect();
+        let sparse = [
+            race(0, "cargo build", 30),
+            race(1, "cargo test --workspace", 600),
+            race(2, "cargo doc", 5),
+        ];
+
+        let tight_refs: Vec<&Race> = tight.iter().collect();
+        let sparse_refs: Vec<&Race> = sparse.iter().collect();
+        let tight_score = cluster_quality_score(&tight_refs, &config);
+        let sparse_score = cluster_quality_score(&sparse_refs, &config);
+
+        assert!((0.0..=1.0).contains(&tight_score));
+        assert!((0.0..=1.0).contains(&sparse_score));
+        assert!(
+            tight_score > sparse_score + 0.3,
+            "tight={} sparse={}",
+            tight_score,
+            sparse_score
+        );
+        assert_eq!(cluster_quality_score(&[], &config), 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_rebuild_clears_source_that_yields_no_clusters() {
+        let mut config = RebuildConfig::default();
+        config
+            .source_configs
+            .get_mut("cargo")
+            .unwrap()
+            .noise_policy = NoisePolicy::Discard;
+        let buffers = DoubleBufferClusters::new(0);
+
+        let old_races = synthetic_races(6);
+        let mut codex_race = Race::new("codex".to_string(), "Refactor module".to_string());
+        codex_race.id = "codex-0".to_string();
+        {
+            let mut active = buffers.active.write().await;
+            for (id, source, races) in [
+                ("cargo:old", "cargo", &old_races),
+                ("codex:old", "codex", &vec![codex_race.clone()]),
+            ] {
+                let member_ids = races.iter().map(|r| r.id.clone()).collect();
+                let cluster = buffers.create_race_cluster(
+                    id.to_string(),
+                    source.to_string(),
+                    member_ids,
+                    races,
+                    &config.config_for_source(source),
+                    &config,
+                );
+                active.insert(id.to_string(), cluster);
+            }
+        }
+
+        // Every cargo race is unlike the others, so DBSCAN finds only noise
+        let races: Vec<Race> = ["publish docs site", "nightly fuzz campaign", "bench report"]
+            .iter()
+            .enumerate()
+            .map(|(i, title)| {
+                let mut race = Race::new("cargo".to_string(), title.to_string());
+                race.id = format!("outlier-{}", i);
+                race
+            })
+            .collect();
+        buffers
+            .rebuild_with_zero_downtime(races, &HashSet::from(["cargo".to_string()]), &config)
+            .await
+            .unwrap();
+
+        let active = buffers.active.read().await;
+        assert!(active.values().all(|c| c.source != "cargo"));
+        assert!(active.contains_key("codex:old"));
+    }
+
+    #[test]
+    fn test_unknown_source_uses_configured_default() {
+        let builtin = RebuildConfig::default();
+        assert_eq!(
+            builtin.config_for_source("buildkite").eps_range,
+            SourceConfig::default_for_unknown().eps_range
+        );
+
+        let configured: SourceConfig =
+            toml::from_str("eps_range = [0.1, 0.2]\nmin_samples = 4\nnoise_policy = \"discard\"")
+                .unwrap();
+        let config = RebuildConfig::default().with_default_source_config(Some(configured));
+        assert!(config.validate().is_ok());
+
+        let unknown = config.config_for_source("buildkite");
+        assert_eq!(unknown.eps_range, (0.1, 0.2));
+        assert_eq!(unknown.min_samples, 4);
+        assert_eq!(unknown.noise_policy, NoisePolicy::Discard);
+        // Unset fields keep the built-in catch-all values
+        assert_eq!(unknown.w_title, 0.6);
+        // Tuned sources are unaffected
+        assert_eq!(config.config_for_source("cargo").eps_range, (0.15, 0.35));
+    }
+
+    #[test]
+    fn test_max_clusters_folds_weakest_into_source_avg() {
+        let mut config = RebuildConfig::default();
+        config.max_clusters = 3;
+        let source_config = valid_source_config();
+        let buffers = DoubleBufferClusters::new(0);
+
+        // Five clusters of decreasing size plus a little noise
+        let mut races = Vec::new();
+        let mut clusters = Vec::new();
+        for (c, size) in [6usize, 5, 4, 3, 2].iter().enumerate() {
+            let member_ids: Vec<RaceId> = (0..*size).map(|i| format!("c{}-{}", c, i)).collect();
+            for id in &member_ids {
+                let mut race = Race::new("cargo".to_string(), format!("cargo job {}", c));
+                race.id = id.clone();
+                race.duration_sec = Some(100);
+                races.push(race);
+            }
+            clusters.push((format!("cluster-{}", c), member_ids));
+        }
+        let mut noise_race = Race::new("cargo".to_string(), "cargo noise".to_string());
+        noise_race.id = "noise-0".to_string();
+        races.push(noise_race);
+
+        let mut candidates: Vec<(ClusterId, RaceCluster)> = clusters
+            .into_iter()
+            .map(|(id, members)| {
+                let cluster = buffers.create_race_cluster(
+                    id.clone(),
+                    "cargo".to_string(),
+                    members,
+                    &races,
+                    &source_config,
+                    &config,
+                );
+                (id, cluster)
+            })
+            .collect();
+        candidates.extend(buffers.noise_clusters(
+            "cargo",
+            vec!["noise-0".to_string()],
+            &races,
+            &source_config,
+            &config,
+        ));
+
+        // Discarding sources keep the best clusters and no noise bucket
+        let discard_config = SourceConfig {
+            noise_policy: NoisePolicy::Discard,
+            ..source_config.clone()
+        };
+        let capped = buffers.cap_source_clusters(
+            "cargo",
+            candidates.clone(),
+            &races,
+            &discard_config,
+            &config,
+        );
+        let ids: HashSet<&str> = capped.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(ids, HashSet::from(["cluster-0", "cluster-1", "cluster-2"]));
+
+        let capped =
+            buffers.cap_source_clusters("cargo", candidates, &races, &source_config, &config);
+        assert_eq!(capped.len(), 3);
+        let ids: HashSet<&str> = capped.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(
+            ids,
+            HashSet::from(["cluster-0", "cluster-1", "cargo:source_avg"])
+        );
+
+        let (_, source_avg) = capped
+            .iter()
+            .find(|(id, _)| id == "cargo:source_avg")
+            .unwrap();
+        let mut folded = source_avg.member_race_ids.clone();
+        folded.sort();
+        let mut expected: Vec<RaceId> = (2..5)
+            .flat_map(|c| (0..[6, 5, 4, 3, 2][c]).map(move |i| format!("c{}-{}", c, i)))
+            .collect();
+        expected.push("noise-0".to_string());
+        expected.sort();
+        assert_eq!(folded, expected);
+    }
+
+    #[test]
+    fn test_metadata_less_races_cluster_on_title_alone() {
+        let source_config = SourceConfig::default_for_unknown();
+        let race = |id: &str, title: &str| {
+            let mut race = Race::new("shell".to_string(), title.to_string());
+            race.id = id.to_string();
+            race
+        };
+        let races = vec![
+            race("a", "deploy staging shard 1"),
+            race("b", "deploy staging shard 2"),
+            race("c", "deploy staging shard 3"),
+            race("d", "rotate database credentials"),
+        ];
+
+        let breakdown = distance_breakdown(&races[0], &races[1], &source_config);
+        assert_eq!(breakdown.metadata_jaccard, None);
+        assert_eq!((breakdown.w_title, breakdown.w_meta), (1.0, 0.0));
+        assert_eq!(breakdown.distance, breakdown.title_distance);
+
+        // Irrelevant keys alone don't count as metadata either
+        let mut tagged = races[1].clone();
+        tagged.metadata = Some(HashMap::from([("pid".to_string(), "42".to_string())]));
+        let tagged_distance = custom_distance(&races[0], &tagged, &source_config);
+        assert_eq!(tagged_distance, breakdown.title_distance);
+
+        let result = OptimizedDBSCAN::new(100).run_dbscan(
+            races,
+            source_config.eps_range.0,
+            2,
+            &source_config,
+        );
+        assert_eq!(result.clusters.len(), 1);
+        let mut members = result.clusters.values().next().unwrap().clone();
+        members.sort();
+        assert_eq!(members, vec!["a", "b", "c"]);
+        assert_eq!(result.noise, vec!["d"]);
+    }
+
+    #[test]
+    fn test_title_distance_compares_bounded_prefixes() {
+        let source_config = SourceConfig::default_for_unknown();
+        let prefix = "deploy staging ".repeat(MAX_COMPARED_TITLE_CHARS / 15 + 1);
+        let long = |tail: char| {
+            let title = format!("{}{}", prefix, tail.to_string().repeat(200_000));
+            Race::new("shell".to_string(), title)
+        };
+
+        // Two 200k-character titles would need 4e10 edit-distance cells; only
+        // the shared prefix is compared, so they are identical for clustering
+        let (a, b) = (long('a'), long('b'));
+        assert_eq!(
+            compared_title(&a.title).chars().count(),
+            MAX_COMPARED_TITLE_CHARS
+        );
+        assert_eq!(compared_title(&a.title), compared_title(&b.title));
+        let breakdown = distance_breakdown(&a, &b, &source_config);
+        assert_eq!(breakdown.title_distance, 0.0);
+
+        assert_eq!(
+            compared_title(&"é".repeat(1000)).chars().count(),
+            MAX_COMPARED_TITLE_CHARS
+        );
+        assert_eq!(compared_title("short"), "short");
+    }
+
+    #[test]
+    fn test_metadata_key_weights_change_clustering_outcome() {
+        let race = |id: &str, language: &str, tool: &str, extension: &str| {
+            let mut race = Race::new("claude-code".to_string(), "refactor parser".to_string());
+            race.id = id.to_string();
+            race.metadata = Some(HashMap::from([
+                ("model".to_string(), "gpt-4".to_string()),
+                ("language".to_string(), language.to_string()),
+                ("tool".to_string(), tool.to_string()),
+                ("file_extension".to_string(), extension.to_string()),
+            ]));
+            race
+        };
+        // Same model, everything else differs
+        let races = vec![race("a", "rust", "edit", "rs"), race("b", "go", "bash", "go")];
+        // A single neighbor within eps is enough to form a cluster
+        let eps = 0.2;
+
+        let mut source_config = SourceConfig::default_for_unknown();
+        let uniform = distance_breakdown(&races[0], &races[1], &source_config);
+        assert!((uniform.metadata_jaccard.unwrap() - 1.0 / 7.0).abs() < 1e-9);
+        let result = OptimizedDBSCAN::new(100).run_dbscan(races.clone(), eps, 1, &source_config);
+        assert!(result.clusters.is_empty());
+        assert_eq!(result.noise.len(), 2);
+
+        source_config.metadata_key_weights = HashMap::from([("model".to_string(), 10.0)]);
+        source_config.validate().unwrap();
+        let weighted = distance_breakdown(&races[0], &races[1], &source_config);
+        assert!((weighted.metadata_jaccard.unwrap() - 10.0 / 16.0).abs() < 1e-9);
+        assert!(weighted.distance < eps && uniform.distance > eps);
+        let result = OptimizedDBSCAN::new(100).run_dbscan(races, eps, 1, &source_config);
+        assert_eq!(result.clusters.len(), 1);
+        assert!(result.noise.is_empty());
+
+        source_config.metadata_key_weights = HashMap::from([("pid".to_string(), 1.0)]);
+        assert!(source_config.validate().is_err());
+        source_config.metadata_key_weights = HashMap::from([("model".to_string(), -1.0)]);
+        assert!(source_config.validate().is_err());
+    }
+
+    #[test]
+    fn test_separated_clusters_score_higher_than_overlapping() {
+        let config = RebuildConfig::default();
+        let cluster_set = |titles: &[&str]| -> ClusterSet {
+            cargo_races("race", titles)
+                .iter()
+                .enumerate()
+                .map(|(i, race)| {
+                    let id = format!("cluster-{}", i);
+                    let cluster = cargo_cluster(&id, std::slice::from_ref(race));
+                    (id, cluster)
+                })
+                .collect()
+        };
+
+        let separated = cluster_set(&[
+            "cargo build --release",
+            "npm install dependencies",
+            "pytest integration suite",
+        ]);
+        let overlapping =
+            cluster_set(&["cargo test shard 1", "cargo test shard 2", "cargo test shard 3"]);
+
+        let config_for = |source: &str| config.config_for_source(source);
+        let high = calculate_separation(&separated, config_for);
+        let low = calculate_separation(&overlapping, config_for);
+        assert!(high > low, "separated {} vs overlapping {}", high, low);
+        assert!(low < config.min_separation);
+
+        // A lone cluster has nothing to overlap with
+        let single = cluster_set(&["cargo build --release"]);
+        assert_eq!(calculate_separation(&single, config_for), 1.0);
+    }
+
+    #[test]
+    fn test_tight_cluster_more_cohesive_than_loose_one() {
+        let config = RebuildConfig::default();
+        let single_cluster = |titles: &[&str]| -> ClusterSet {
+            let cluster = cargo_cluster("cluster-0", &cargo_races("race", titles));
+            HashMap::from([("cluster-0".to_string(), cluster)])
+        };
+
+        let tight = single_cluster(&[
+            "cargo test shard 1",
+            "cargo test shard 2",
+            "cargo test shard 3",
+            "cargo test shard 4",
+        ]);
+        let loose = single_cluster(&[
+            "cargo build --release",
+            "npm install dependencies",
+            "pytest integration suite",
+            "docker compose up",
+        ]);
+
+        let config_for = |source: &str| config.config_for_source(source);
+        let high = calculate_cohesion(&tight, config_for);
+        let low = calculate_cohesion(&loose, config_for);
+        assert!(high > low, "tight {} vs loose {}", high, low);
+        assert!(high > 0.8);
+    }
+
+    #[test]
+    fn test_cohesion_uses_member_metadata_history() {
+        let config = RebuildConfig::default();
+        let config_for = |source: &str| config.config_for_source(source);
+        let with_history = |languages: &[&str]| -> ClusterSet {
+            let mut cluster = test_cluster("cargo:0", &["a", "b", "c"]);
+            cluster.member_titles = count_titles(vec!["cargo build".to_string(); 3]);
+            cluster.representative_metadata =
+                HashMap::from([("language".to_string(), "rust".to_string())]);
+            cluster.member_metadata_history = languages
+                .iter()
+                .map(|l| HashMap::from([("language".to_string(), l.to_string())]))
+                .collect();
+            HashMap::from([("cargo:0".to_string(), cluster)])
+        };
+
+        let uniform = calculate_cohesion(&with_history(&["rust", "rust", "rust"]), config_for);
+        let mixed = calculate_cohesion(&with_history(&["rust", "go", "python"]), config_for);
+        assert!((uniform - 1.0).abs() < 1e-9, "{}", uniform);
+        // The representative metadata alone would hide the disagreement
+        assert!(mixed < uniform, "mixed {} vs uniform {}", mixed, uniform);
+        assert_eq!(calculate_cohesion(&with_history(&[]), config_for), uniform);
+    }
+
+    #[test]
+    fn test_repeated_member_titles_stored_once_with_counts() {
+        let config = RebuildConfig::default();
+        let titles: Vec<&str> = (0..500)
+            .map(|i| {
+                if i % 100 == 0 {
+                    "cargo build --release"
+                } else {
+                    "cargo build"
+                }
+            })
+            .collect();
+        let cluster = cargo_cluster("cargo:cluster_0", &cargo_races("race", &titles));
+
+        assert_eq!(
+            cluster.member_titles,
+            HashMap::from([
+                ("cargo build".to_string(), 495),
+                ("cargo build --release".to_string(), 5),
+            ])
+        );
+        assert_eq!(cluster.member_title_count(), 500);
+        let clusters = HashMap::from([(cluster.cluster_id.clone(), cluster.clone())]);
+        let cohesion = calculate_cohesion(&clusters, |s| config.config_for_source(s));
+        assert!(cohesion > 0.99, "{}", cohesion);
+
+        // Clusters persisted before schema version 4 hold one entry per member
+        let mut legacy = serde_json::to_value(&cluster).unwrap();
+        legacy["member_titles"] = serde_json::json!(["cargo build", "cargo test", "cargo build"]);
+        let loaded: RaceCluster = serde_json::from_value(legacy).unwrap();
+        assert_eq!(loaded.member_titles["cargo build"], 2);
+        assert_eq!(loaded.member_titles["cargo test"], 1);
+        assert_eq!(loaded.member_title_count(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_validation_sample_uses_real_member_durations() {
+        let source_config = valid_source_config();
+        let titles: Vec<String> = (0..3).map(|i| format!("cargo test shard {}", i)).collect();
+        let mut races = cargo_races("race", &titles);
+        for (race, duration) in races.iter_mut().zip([100, 200, 300]) {
+            race.duration_sec = Some(duration);
+        }
+        let cluster = cargo_cluster("cluster-0", &races);
+        let clusters = HashMap::from([("cluster-0".to_string(), cluster)]);
+
+        let races_by_id: HashMap<&str, &Race> = races.iter().map(|r| (r.id.as_str(), r)).collect();
+        let sample = validation_sample(&clusters, &races_by_id);
+        let mut titles: Vec<&str> = sample.iter().map(|r| r.title.as_str()).collect();
+        titles.sort();
+        assert_eq!(
+            titles,
+            vec![
+                "cargo test shard 0",
+                "cargo test shard 1",
+                "cargo test shard 2"
+            ]
+        );
+
+        let result = validate_clusters_comprehensive(
+            &clusters,
+            &clusters,
+            &sample,
+            &[],
+            &ValidationCriteria::default(),
+            &source_config,
+        )
+        .await;
+        // Placeholders carried the cluster median as duration, which always scored 0
+        assert!((result.metrics.mae - 200.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_holdout_is_disjoint_and_scored_out_of_sample() {
+        let mut races = synthetic_races(200);
+        for race in races.iter_mut().skip(10) {
+            race.duration_sec = Some(100);
+        }
+        let all_ids: HashSet<String> = races.iter().map(|r| r.id.clone()).collect();
+        let (train, holdout) = split_holdout(races, 0.2, 42);
+
+        let train_ids: HashSet<&str> = train.iter().map(|r| r.id.as_str()).collect();
+        let holdout_ids: HashSet<&str> = holdout.iter().map(|r| r.id.as_str()).collect();
+        assert!(train_ids.is_disjoint(&holdout_ids));
+        assert_eq!(train_ids.len() + holdout_ids.len(), all_ids.len());
+        assert!((20..=60).contains(&holdout.len()), "{}", holdout.len());
+        // Races without a duration can't be scored, so they always train
+        assert!(holdout.iter().all(|r| r.duration_sec.is_some()));
+
+        // Cluster trained on 100s races; the held-out race of the same shape ran 160s
+        let source_config = valid_source_config();
+        let train = shard_races(4, 100);
+        let cluster = cargo_cluster("cluster-0", &train);
+        let clusters = HashMap::from([("cluster-0".to_string(), cluster)]);
+        let mut held_out = Race::new("cargo".to_string(), "cargo test shard 9".to_string());
+        held_out.id = "holdout-0".to_string();
+        held_out.duration_sec = Some(160);
+
+        let result = validate_clusters_comprehensive(
+            &clusters,
+            &clusters,
+            &train,
+            &[held_out],
+            &ValidationCriteria::default(),
+            &source_config,
+        )
+        .await;
+        // In-sample the MAE would be 0
+        assert!((result.metrics.mae - 60.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_holdout_outside_eps_joins_noise_bucket() {
+        let config = RebuildConfig::default();
+        let mut noise = cargo_races("noise", &["docker compose up"]);
+        noise[0].duration_sec = Some(900);
+        let mut clusters = HashMap::from([
+            (
+                "cargo:cluster_0".to_string(),
+                cargo_cluster("cargo:cluster_0", &shard_races(4, 100)),
+            ),
+            (
+                "cargo:source_avg".to_string(),
+                cargo_cluster("cargo:source_avg", &noise),
+            ),
+        ]);
+        assert_eq!(clusters["cargo:cluster_0"].member_metadata_history.len(), 4);
+
+        let mut near = Race::new("cargo".to_string(), "cargo test shard 9".to_string());
+        near.id = "near".to_string();
+        near.duration_sec = Some(110);
+        near.metadata = Some(HashMap::from([("shard".to_string(), "9".to_string())]));
+        let mut far = Race::new("cargo".to_string(), "terraform apply production".to_string());
+        far.id = "far".to_string();
+        far.duration_sec = Some(5000);
+        let eps = HashMap::from([("cargo".to_string(), 0.3)]);
+        absorb_holdout(&mut clusters, vec![near, far], &config, &eps);
+
+        let absorbed = &clusters["cargo:cluster_0"];
+        assert_eq!(
+            absorbed.member_race_ids.last().map(String::as_str),
+            Some("near")
+        );
+        assert!(!absorbed.member_race_ids.contains(&"far".to_string()));
+        assert_eq!(
+            absorbed.member_metadata_history.len(),
+            absorbed.member_race_ids.len()
+        );
+        assert_eq!(
+            absorbed.member_metadata_history.last().unwrap()["shard"],
+            "9"
+        );
+        let bucket = &clusters["cargo:source_avg"];
+        assert_eq!(bucket.member_race_ids, ["noise-0", "far"]);
+        assert_eq!(bucket.member_metadata_history.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_rebuild_rejected_over_memory_budget() {
+        let config = RebuildConfig::default();
+        let races = synthetic_races(50);
+        let sources = HashSet::from(["cargo".to_string()]);
+
+        let tiny = DoubleBufferClusters::new(1024);
+        let err = tiny
+            .rebuild_with_zero_downtime(races.clone(), &sources, &config)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("memory"), "{}", err);
+        assert!(tiny.active.read().await.is_empty());
+
+        let roomy = DoubleBufferClusters::new(100_000_000);
+        roomy
+            .rebuild_with_zero_downtime(races, &sources, &config)
+            .await
+            .unwrap();
+        let active = roomy.active.read().await;
+        assert!(!active.is_empty());
+        assert!(estimate_cluster_set_bytes(&active) > 1024);
+    }
+}
+
 // Cluster rebuild pipeline, thresholds, and rollout integration.
 //
 // Background and rationale: `docs/proposals/CLUSTER_REBUILDING_PROPOSAL.md` and
@@ -1,15 +1,59 @@
 use anyhow::Result;
 use chrono::{DateTime, Utc};
-use std::collections::HashMap;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tokio::time::{interval, Duration};
 
-use crate::cluster::ClusteringEngine;
+use crate::cluster::{ClusteringEngine, PredictionAccuracy};
 use crate::hnsw_dbscan::{ValidationMetrics, ValidationResult};
+use crate::monitoring::AlertSystem;
 use crate::persistence::{PersistenceLayer, RaceStore};
-use crate::phased_rollout::{PhasedRollout, RolloutConfig, RolloutMode, RolloutPhase};
-use crate::rebuild::{ClusterSet, DBSCANMetrics, DoubleBufferClusters, RebuildConfig};
+use crate::phased_rollout::{
+    ManualChange, PhasedRollout, RolloutConfig, RolloutMode, RolloutPhase,
+};
+use crate::rebuild::{
+    adjusted_rand_index, calculate_cohesion, calculate_separation, membership_stability,
+    ClusterSet, DBSCANMetrics, DoubleBufferClusters, MappingThresholds, RebuildConfig,
+};
+
+/// Progress of the rebuild in flight, if any, and how the last one ended
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RebuildStatus {
+    pub running: bool,
+    /// Id of the running job
+    pub job_id: Option<String>,
+    /// Source being clustered; `None` while races are still being loaded
+    pub current_source: Option<String>,
+    pub started_at: Option<DateTime<Utc>>,
+    /// Sources this rebuild covers, known once races are loaded
+    pub sources: Vec<String>,
+    pub sources_completed: usize,
+    /// Sources whose rebuild errored or failed validation so far
+    pub failed_sources: Vec<String>,
+    pub last_result: Option<RebuildResult>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RebuildResult {
+    pub job_id: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    /// False if the rebuild errored or any source failed validation
+    pub success: bool,
+    pub sources: Vec<String>,
+    pub failed_sources: Vec<String>,
+    pub error: Option<String>,
+}
+
+/// Handle returned when a rebuild is requested
+#[derive(Debug, Clone, Serialize)]
+pub struct RebuildJob {
+    pub job_id: String,
+    /// False if the request joined a rebuild that was already running
+    pub started: bool,
+}
 
 #[derive(Clone)]
 pub struct RebuildTrigger {
@@ -18,7 +62,13 @@ pub struct RebuildTrigger {
     store: Arc<PersistenceLayer>,
     clustering_engine: Arc<ClusteringEngine>,
     last_rebuild: Arc<RwLock<DateTime<Utc>>>,
+    /// When each source was last rebuilt by this process, pass or fail, so a
+    /// failing source waits out its interval before it is retried
+    source_attempts: Arc<RwLock<HashMap<String, DateTime<Utc>>>>,
+    started_at: DateTime<Utc>,
     last_metrics: Arc<RwLock<DBSCANMetrics>>,
+    status: Arc<RwLock<RebuildStatus>>,
+    alert_system: Option<Arc<AlertSystem>>,
     pub rollout_controller: Arc<RwLock<PhasedRollout>>,
 }
 
@@ -31,13 +81,16 @@ impl RebuildTrigger {
     ) -> Self {
         // Initialize rollout configuration
         let rollout_config = RolloutConfig {
-            pilot_source: "ci".to_string(), // Start with CI as pilot
+            pilot_source: config.pilot_source.clone(),
             shadow_duration: chrono::Duration::days(1),
             canary_duration: chrono::Duration::days(2),
             canary_percentage: 10,
-            success_threshold: 0.95,
-            min_rebuilds_for_promotion: 10,
+            success_threshold: config.promotion_success_rate,
+            shadow_promotion_successes: config.shadow_promotion_successes,
+            canary_promotion_successes: config.canary_promotion_successes,
             auto_rollback: true,
+            rollback_recovery_after: chrono::Duration::from_std(config.rollback_recovery_after)
+                .unwrap_or_else(|_| chrono::Duration::hours(1)),
             validation_criteria: crate::hnsw_dbscan::ValidationCriteria::default(),
         };
 
@@ -70,7 +123,25 @@ impl RebuildTrigger {
                     enabled_count
                 );
 
-                // Don't call start_phase_1() here - use the saved state as-is
+                // Don't call start_phase_1() here - use the saved state as-is,
+                // apart from the pilot source and promotion thresholds, which
+                // follow the current settings
+                let mut saved_rollout = saved_rollout;
+                if saved_rollout.config.pilot_source != config.pilot_source {
+                    log::info!(
+                        "  Pilot source changed from '{}' to '{}'",
+                        saved_rollout.config.pilot_source,
+                        config.pilot_source
+                    );
+                }
+                saved_rollout.config.pilot_source = rollout_config.pilot_source;
+                saved_rollout.config.success_threshold = rollout_config.success_threshold;
+                saved_rollout.config.shadow_promotion_successes =
+                    rollout_config.shadow_promotion_successes;
+                saved_rollout.config.canary_promotion_successes =
+                    rollout_config.canary_promotion_successes;
+                saved_rollout.config.rollback_recovery_after =
+                    rollout_config.rollback_recovery_after;
                 saved_rollout
             }
             Ok(None) => {
@@ -85,6 +156,12 @@ impl RebuildTrigger {
             }
         };
 
+        // Transitions continue the sequence of the full history store
+        match store.last_phase_transition_seq() {
+            Ok(stored_seq) => rollout.sequence_transitions(stored_seq),
+            Err(e) => log::error!("Failed to read the rollout phase history: {}", e),
+        }
+
         // We can't call async functions in new(), so we'll discover sources later
         // during the first rebuild or when enable_all_sources is called
 
@@ -94,6 +171,8 @@ impl RebuildTrigger {
             store,
             clustering_engine,
             last_rebuild: Arc::new(RwLock::new(Utc::now())),
+            source_attempts: Arc::new(RwLock::new(HashMap::new())),
+            started_at: Utc::now(),
             last_metrics: Arc::new(RwLock::new(DBSCANMetrics {
                 noise_ratio: 0.0,
                 cluster_count: 0,
@@ -105,17 +184,73 @@ impl RebuildTrigger {
                 separation: 0.0,
                 ari_score: 1.0,
             })),
+            status: Arc::new(RwLock::new(RebuildStatus::default())),
+            alert_system: None,
             rollout_controller: Arc::new(RwLock::new(rollout)),
         }
     }
 
+    /// Where rollback auto-recovery is reported; without one it is only logged
+    pub fn with_alert_system(mut self, alert_system: Arc<AlertSystem>) -> Self {
+        self.alert_system = Some(alert_system);
+        self
+    }
+
+    /// Recover a Rollback that outlived `rollback_recovery_after` (see
+    /// [`PhasedRollout::recover_stale_rollback`]), alert about it and start a
+    /// fresh rebuild. Returns whether it recovered.
+    pub async fn recover_stale_rollback(&self) -> bool {
+        let (sources, phase) = {
+            let mut rollout = self.rollout_controller.write().await;
+            let Some(sources) = rollout.recover_stale_rollback(Utc::now()) else {
+                return false;
+            };
+            if let Err(e) = self.store.persist_rollout_config(&rollout) {
+                log::error!("Failed to persist rollout configuration: {}", e);
+            }
+            (sources, rollout.current_phase)
+        };
+
+        let message = format!(
+            "Rollout auto-recovered from a stale rollback into {:?}; sources back in shadow mode: {:?}",
+            phase, sources
+        );
+        match &self.alert_system {
+            Some(alerts) => alerts.send_critical_alert(&message).await,
+            None => log::warn!("{}", message),
+        }
+        let job = self.start_rebuild().await;
+        log::info!("Rebuild {} requested after rollback recovery", job.job_id);
+        true
+    }
+
+    /// Metrics recorded after the last completed rebuild
+    pub async fn last_metrics(&self) -> DBSCANMetrics {
+        self.last_metrics.read().await.clone()
+    }
+
+    pub async fn status(&self) -> RebuildStatus {
+        self.status.read().await.clone()
+    }
+
     /// Discover all unique sources from the database
     async fn discover_sources(store: &Arc<PersistenceLayer>) -> Vec<String> {
-        let mut sources = std::collections::HashSet::new();
+        // BTreeMap keys are already sorted
+        Self::count_races_by_source(store).await.into_keys().collect()
+    }
+
+    /// Persisted race count per source
+    pub async fn race_counts_by_source(&self) -> BTreeMap<String, usize> {
+        Self::count_races_by_source(&self.store).await
+    }
+
+    async fn count_races_by_source(store: &Arc<PersistenceLayer>) -> BTreeMap<String, usize> {
+        let mut counts = BTreeMap::new();
 
         // Scan the database to find all unique sources
         let filter = crate::persistence::RaceScanFilter {
             source: None,
+            sources: None,
             from: None,
             to: None,
             include_events: false,
@@ -129,7 +264,7 @@ impl RebuildTrigger {
                         break;
                     }
                     for race in &batch.items {
-                        sources.insert(race.source.clone());
+                        *counts.entry(race.source.clone()).or_insert(0) += 1;
                     }
                     cursor = batch.next_cursor;
                     if cursor.is_none() {
@@ -143,20 +278,18 @@ impl RebuildTrigger {
             }
         }
 
-        let mut sources: Vec<String> = sources.into_iter().collect();
-        sources.sort();
-        sources
+        counts
     }
 
     /// Public helper to enable all sources with the specified mode
-    pub async fn enable_all_sources(&self, mode: crate::phased_rollout::RolloutMode) {
+    pub async fn enable_all_sources(&self, mode: RolloutMode, change: &ManualChange) {
         let mut rollout = self.rollout_controller.write().await;
 
         // Make sure all sources are registered before enabling
         let sources = Self::discover_sources(&self.store).await;
         rollout.register_sources(&sources);
 
-        rollout.enable_all_sources(mode);
+        rollout.enable_all_sources(mode, change);
 
         // Persist the configuration
         if let Err(e) = self.store.persist_rollout_config(&*rollout) {
@@ -164,12 +297,74 @@ impl RebuildTrigger {
         }
     }
 
-    pub async fn reset_to_phase_1(&self) {
+    /// Move `source` one rollout stage forward and persist it. Skips the
+    /// success-count thresholds, but the success-rate gate still applies.
+    pub async fn promote_source(&self, source: &str, change: &ManualChange) -> Result<RolloutMode> {
+        self.step_source_mode(source, change, |rollout, source| {
+            match rollout.source_status.get(source).map(|s| s.mode) {
+                Some(RolloutMode::Shadow) => rollout.promote_to_canary(source),
+                Some(RolloutMode::Canary { .. }) => rollout.promote_to_production(source),
+                Some(mode) => Err(anyhow::anyhow!(
+                    "Cannot promote a source in {:?} mode",
+                    mode
+                )),
+                None => Err(anyhow::anyhow!("Source not found: {}", source)),
+            }
+        })
+        .await
+    }
+
+    /// Move `source` one rollout stage back and persist it
+    pub async fn demote_source(&self, source: &str, change: &ManualChange) -> Result<RolloutMode> {
+        self.step_source_mode(source, change, PhasedRollout::demote)
+            .await
+    }
+
+    async fn step_source_mode(
+        &self,
+        source: &str,
+        change: &ManualChange,
+        step: impl FnOnce(&mut PhasedRollout, &str) -> Result<()>,
+    ) -> Result<RolloutMode> {
+        let mut rollout = self.rollout_controller.write().await;
+        step(&mut rollout, source)?;
+        let mode = rollout.source_status[source].mode;
+        log::info!("Manually moved rollout source '{}' to {:?}", source, mode);
+        let phase = rollout.current_phase;
+        rollout.add_manual_transition(
+            phase,
+            phase,
+            &format!("Manually moved source {} to {:?}", source, mode),
+            change,
+        );
+
+        if let Err(e) = self.store.persist_rollout_config(&rollout) {
+            log::error!("Failed to persist rollout configuration: {}", e);
+        }
+        Ok(mode)
+    }
+
+    pub fn config(&self) -> &RebuildConfig {
+        &self.config
+    }
+
+    /// Timestamp of the last completed rebuild (server start if none yet)
+    pub async fn last_rebuild_at(&self) -> DateTime<Utc> {
+        *self.last_rebuild.read().await
+    }
+
+    pub async fn reset_to_phase_1(&self, change: &ManualChange) {
         let mut rollout = self.rollout_controller.write().await;
 
-        // Reset to Phase 1 with fresh state
+        // Reset to Phase 1 with fresh state; the reset itself stays in the history
+        let from_phase = rollout.current_phase;
         rollout.current_phase = RolloutPhase::SingleSource;
-        rollout.phase_history.clear();
+        rollout.add_manual_transition(
+            from_phase,
+            RolloutPhase::SingleSource,
+            "Manual reset to Phase 1",
+            change,
+        );
 
         // Discover and register sources
         let sources = Self::discover_sources(&self.store).await;
@@ -177,6 +372,12 @@ impl RebuildTrigger {
 
         // Enable only the pilot source
         let pilot_source = rollout.config.pilot_source.clone();
+        if !sources.contains(&pilot_source) {
+            log::warn!(
+                "Rollout pilot source '{}' has no persisted races; no source is enabled",
+                pilot_source
+            );
+        }
         for (source, status) in rollout.source_status.iter_mut() {
             if source == &pilot_source {
                 status.enabled = true;
@@ -209,6 +410,14 @@ impl RebuildTrigger {
         let mut rollout = self.rollout_controller.write().await;
         let sources = Self::discover_sources(&self.store).await;
 
+        if !sources.contains(&rollout.config.pilot_source) {
+            log::warn!(
+                "Rollout pilot source '{}' has no persisted races; Phase 1 enables nothing until it does (known sources: {:?})",
+                rollout.config.pilot_source,
+                sources
+            );
+        }
+
         // Log the current state before any changes
         log::info!(
             "Initialize sources called. Current phase: {:?}, existing sources: {}",
@@ -243,7 +452,7 @@ impl RebuildTrigger {
                             status.enabled = true;
                             status.mode = RolloutMode::Production;
                             log::info!("Re-enabled pilot source '{}' in Phase 1", pilot_source);
-                        } else if sources.contains(&pilot_source) {
+                        } else {
                             log::warn!(
                                 "Pilot source '{}' not found in registered sources",
                                 pilot_source
@@ -275,14 +484,12 @@ impl RebuildTrigger {
                         );
                     }
                     RolloutPhase::Rollback => {
-                        log::warn!("System in rollback phase, sources remain disabled");
-                        // Optionally auto-recover from rollback if it persists too long
-                        if let Some(last_transition) = rollout.phase_history.last() {
-                            let age = chrono::Utc::now() - last_transition.timestamp;
-                            if age > chrono::Duration::hours(1) {
-                                log::warn!("Rollback phase has persisted for over 1 hour, consider resetting with /rollout/reset");
-                            }
-                        }
+                        // A stale rollback is recovered by the promotion loop
+                        log::warn!(
+                            "System in rollback phase, sources remain disabled (auto-recovery after {} minutes: {})",
+                            rollout.config.rollback_recovery_after.num_minutes(),
+                            rollout.config.auto_rollback
+                        );
                     }
                 }
 
@@ -334,10 +541,11 @@ impl RebuildTrigger {
             let mut interval = interval(Duration::from_secs(3600)); // Check hourly
             loop {
                 interval.tick().await;
-                if periodic_self.should_rebuild_periodic().await {
-                    eprintln!("Periodic rebuild triggered");
+                let due = periodic_self.sources_due_for_rebuild(Utc::now()).await;
+                if !due.is_empty() {
+                    log::info!("Periodic rebuild triggered for {:?}", due);
                     periodic_self.log_rollout_status().await;
-                    if let Err(e) = periodic_self.trigger_rebuild().await {
+                    if let Err(e) = periodic_self.trigger_rebuild_for(&due).await {
                         eprintln!("Periodic rebuild failed: {}", e);
                     }
                 }
@@ -401,28 +609,37 @@ impl RebuildTrigger {
     }
 
     async fn check_rollout_promotion(&self) {
+        if self.recover_stale_rollback().await {
+            return;
+        }
         let mut rollout = self.rollout_controller.write().await;
+        let shadow_successes = rollout.config.shadow_promotion_successes;
+        let canary_successes = rollout.config.canary_promotion_successes;
+        let mut changed = false;
 
-        // Check if we can promote sources to next stage
+        // Check if we can promote sources to next stage; the promote_* calls
+        // also require the source's success rate to meet success_threshold
         for (source, status) in rollout.source_status.clone() {
             match status.mode {
                 crate::phased_rollout::RolloutMode::Shadow => {
                     // Check if ready for canary
-                    if status.success_count >= 5 {
+                    if status.success_count >= shadow_successes {
                         if let Err(e) = rollout.promote_to_canary(&source) {
                             eprintln!("Failed to promote {} to canary: {}", source, e);
                         } else {
                             eprintln!("✅ Promoted {} to canary mode", source);
+                            changed = true;
                         }
                     }
                 }
                 crate::phased_rollout::RolloutMode::Canary { .. } => {
                     // Check if ready for production
-                    if status.success_count >= 10 {
+                    if status.success_count >= canary_successes {
                         if let Err(e) = rollout.promote_to_production(&source) {
                             eprintln!("Failed to promote {} to production: {}", source, e);
                         } else {
                             eprintln!("✅ Promoted {} to production mode", source);
+                            changed = true;
                         }
                     }
                 }
@@ -431,23 +648,49 @@ impl RebuildTrigger {
         }
 
         // Try to advance to next phase
-        if let Ok(advanced) = rollout.try_advance_phase() {
-            if advanced {
-                eprintln!("🎉 Advanced rollout to phase: {:?}", rollout.current_phase);
-                self.log_rollout_status().await;
+        let advanced = matches!(rollout.try_advance_phase(), Ok(true));
+        // Keep promotions and the phase history across restarts; most passes
+        // change nothing and skip the write
+        if changed || advanced {
+            if let Err(e) = self.store.persist_rollout_config(&rollout) {
+                log::error!("Failed to persist rollout configuration: {}", e);
             }
         }
+        if advanced {
+            eprintln!("🎉 Advanced rollout to phase: {:?}", rollout.current_phase);
+            // log_rollout_status takes the read lock
+            drop(rollout);
+            self.log_rollout_status().await;
+        }
     }
 
-    async fn should_rebuild_periodic(&self) -> bool {
-        let last = self.last_rebuild.read().await;
-        let elapsed = Utc::now().signed_duration_since(*last);
+    /// Enabled, unpaused sources whose own rebuild interval has passed since
+    /// they were last rebuilt (or since startup, if never)
+    async fn sources_due_for_rebuild(&self, now: DateTime<Utc>) -> Vec<String> {
+        let rollout = self.rollout_controller.read().await;
+        let attempts = self.source_attempts.read().await;
+        let paused = self.store.paused_sources();
 
-        elapsed.num_seconds() >= self.config.rebuild_interval.as_secs() as i64
+        let mut due: Vec<String> = rollout
+            .source_status
+            .iter()
+            .filter(|(source, status)| status.enabled && !paused.contains(*source))
+            .filter(|(source, status)| {
+                let last = status
+                    .last_rebuild
+                    .max(attempts.get(*source).copied())
+                    .unwrap_or(self.started_at);
+                let interval = self.config.rebuild_interval_for(source);
+                now.signed_duration_since(last).num_seconds() >= interval.as_secs() as i64
+            })
+            .map(|(source, _)| source.clone())
+            .collect();
+        due.sort();
+        due
     }
 
     async fn should_rebuild_metrics(&self) -> bool {
-        let metrics = self.calculate_current_metrics().await;
+        let metrics = self.calculate_current_metrics(None).await;
         let last_metrics = self.last_metrics.read().await;
 
         // Check if metrics exceed thresholds
@@ -458,9 +701,32 @@ impl RebuildTrigger {
         mae_degraded || noise_high || cohesion_low
     }
 
-    async fn calculate_current_metrics(&self) -> DBSCANMetrics {
-        let clusters = self.clusters.active.read().await;
+    /// Metrics of the active cluster set; `previous` is the set it replaced,
+    /// without one ARI and stability are reported as full agreement
+    async fn calculate_current_metrics(&self, previous: Option<&ClusterSet>) -> DBSCANMetrics {
+        // Score a snapshot off the runtime so writers are not held up by the
+        // pairwise distance work
+        let clusters = self.clusters.active.read().await.clone();
+        let previous = previous.cloned();
+        let config = self.config.clone();
+        let metrics = tokio::task::spawn_blocking(move || {
+            Self::cluster_set_metrics(&clusters, previous.as_ref(), &config)
+        })
+        .await;
+        match metrics {
+            Ok(metrics) => metrics,
+            Err(e) => {
+                log::error!("Cluster metrics computation failed: {}", e);
+                self.last_metrics.read().await.clone()
+            }
+        }
+    }
 
+    fn cluster_set_metrics(
+        clusters: &ClusterSet,
+        previous: Option<&ClusterSet>,
+        config: &RebuildConfig,
+    ) -> DBSCANMetrics {
         let total_races: usize = clusters.values().map(|c| c.member_race_ids.len()).sum();
 
         let noise_races = clusters
@@ -489,11 +755,17 @@ impl RebuildTrigger {
             cluster_count: clusters.len(),
             avg_cluster_size: avg_size,
             singleton_clusters: singleton_count,
-            stability_score: 1.0, // Would need historical comparison
-            cohesion: calculate_cohesion(&*clusters),
+            stability_score: previous
+                .map(|previous| {
+                    membership_stability(previous, clusters, MappingThresholds::default())
+                })
+                .unwrap_or(1.0),
+            cohesion: calculate_cohesion(clusters, |source| config.config_for_source(source)),
             silhouette: 0.0, // Expensive to calculate
-            separation: calculate_separation(&*clusters),
-            ari_score: 1.0, // Would need previous clusters for comparison
+            separation: calculate_separation(clusters, |source| config.config_for_source(source)),
+            ari_score: previous
+                .map(|previous| adjusted_rand_index(previous, clusters))
+                .unwrap_or(1.0),
         }
     }
 
@@ -560,7 +832,110 @@ impl RebuildTrigger {
         mae > overall_median * 0.2
     }
 
+    /// Start a rebuild in the background and return its job id. While one is
+    /// running, the running job is returned instead (`started == false`).
+    pub async fn start_rebuild(&self) -> RebuildJob {
+        let job_id = match self.claim_job().await {
+            Ok(job_id) => job_id,
+            Err(running) => {
+                return RebuildJob {
+                    job_id: running,
+                    started: false,
+                }
+            }
+        };
+
+        let this = self.clone();
+        let task_job_id = job_id.clone();
+        tokio::spawn(async move {
+            let result = this.run_rebuild_isolated(None).await;
+            if let Err(e) = &result {
+                log::error!("Rebuild {} failed: {}", task_job_id, e);
+            }
+            this.finish_job(&result).await;
+        });
+
+        RebuildJob {
+            job_id,
+            started: true,
+        }
+    }
+
+    /// Run a rebuild to completion, publishing progress through `status()`.
+    /// Skipped if another rebuild is already running.
     pub async fn trigger_rebuild(&self) -> Result<()> {
+        self.trigger(None).await
+    }
+
+    /// Like [`trigger_rebuild`](Self::trigger_rebuild), limited to `sources`
+    pub async fn trigger_rebuild_for(&self, sources: &[String]) -> Result<()> {
+        let only: HashSet<String> = sources.iter().cloned().collect();
+        self.trigger(Some(only)).await
+    }
+
+    async fn trigger(&self, only: Option<HashSet<String>>) -> Result<()> {
+        if let Err(running) = self.claim_job().await {
+            log::info!("Rebuild {} already running; skipping trigger", running);
+            return Ok(());
+        }
+        let result = self.run_rebuild_isolated(only.clone()).await;
+        if let Some(only) = &only {
+            // Sources with nothing to rebuild still wait out their interval
+            let now = Utc::now();
+            let mut attempts = self.source_attempts.write().await;
+            for source in only {
+                attempts.insert(source.clone(), now);
+            }
+        }
+        self.finish_job(&result).await;
+        result
+    }
+
+    /// Run the rebuild on its own task, so a panic ends the job as failed
+    /// instead of leaving `status.running` set and blocking every later trigger
+    async fn run_rebuild_isolated(&self, only: Option<HashSet<String>>) -> Result<()> {
+        let this = self.clone();
+        match tokio::spawn(async move { this.run_rebuild(only.as_ref()).await }).await {
+            Ok(result) => result,
+            Err(e) => Err(anyhow::anyhow!("rebuild task panicked: {}", e)),
+        }
+    }
+
+    /// Mark a new job as running, or return the id of the one already running
+    async fn claim_job(&self) -> std::result::Result<String, String> {
+        let mut status = self.status.write().await;
+        if status.running {
+            return Err(status.job_id.clone().unwrap_or_default());
+        }
+        let job_id = uuid::Uuid::new_v4().to_string();
+        *status = RebuildStatus {
+            running: true,
+            job_id: Some(job_id.clone()),
+            started_at: Some(Utc::now()),
+            last_result: status.last_result.take(),
+            ..RebuildStatus::default()
+        };
+        Ok(job_id)
+    }
+
+    async fn finish_job(&self, result: &Result<()>) {
+        let mut status = self.status.write().await;
+        let failed_sources = std::mem::take(&mut status.failed_sources);
+        status.last_result = Some(RebuildResult {
+            job_id: status.job_id.take().unwrap_or_default(),
+            started_at: status.started_at.unwrap_or_else(Utc::now),
+            finished_at: Utc::now(),
+            success: result.is_ok() && failed_sources.is_empty(),
+            sources: std::mem::take(&mut status.sources),
+            failed_sources,
+            error: result.as_ref().err().map(|e| e.to_string()),
+        });
+        status.running = false;
+        status.current_source = None;
+    }
+
+    /// Rebuild every source the rollout selects, or only those in `only`
+    async fn run_rebuild(&self, only: Option<&HashSet<String>>) -> Result<()> {
         eprintln!("Triggering cluster rebuild...");
 
         // First, discover and register any new sources
@@ -594,45 +969,18 @@ impl RebuildTrigger {
         let mut sources_to_rebuild: HashMap<String, Vec<crate::models::Race>> = HashMap::new();
         
         // First, load races from persistence layer (current races)
-        {
-            let batch_size = 10_000usize;
-            let mut cursor: Option<String> = None;
-            loop {
-                let filter = crate::persistence::RaceScanFilter {
-                    source: None,
-                    from: None,
-                    to: None,
-                    include_events: false,
-                };
-                let batch = self
-                    .store
-                    .scan_races(filter, batch_size, cursor.clone())
-                    .await?;
-                if batch.items.is_empty() {
-                    break;
-                }
-                for race in batch.items {
-                    let request_hash = seahash::hash(race.id.as_bytes());
-                    if rollout_read.should_use_source(&race.source, request_hash) {
-                        sources_to_rebuild
-                            .entry(race.source.clone())
-                            .or_insert_with(Vec::new)
-                            .push(race);
-                    }
-                }
-                if let Some(next) = batch.next_cursor {
-                    cursor = Some(next);
-                } else {
-                    break;
-                }
-            }
-        }
-        
+        let batches = self
+            .scan_persisted_races(&rollout_read, only, &mut sources_to_rebuild)
+            .await?;
+        log::info!(
+            "Scanned persisted races in {} batches of up to {}",
+            batches,
+            self.config.batch_size
+        );
+
         // IMPORTANT: Also load historic races from JSON file for clustering
         // This is where the bulk of historic data (like 1200 CI races) is stored
-        let mut historic_path = dirs::home_dir().unwrap_or_else(|| std::path::PathBuf::from("."));
-        historic_path.push(".raceboard");
-        historic_path.push("races.json");
+        let historic_path = self.store.data_dir().join("races.json");
         
         if historic_path.exists() {
             eprintln!("Loading historic races from {:?}", historic_path);
@@ -659,6 +1007,15 @@ impl RebuildTrigger {
         }
         drop(rollout_read);
 
+        let paused = self.store.paused_sources();
+        sources_to_rebuild.retain(|source, _| {
+            if paused.contains(source) {
+                log::info!("Skipping paused source: {}", source);
+                return false;
+            }
+            only.is_none_or(|only| only.contains(source))
+        });
+
         if sources_to_rebuild.is_empty() {
             eprintln!("No sources enabled for rebuild in current phase");
             return Ok(());
@@ -669,6 +1026,15 @@ impl RebuildTrigger {
             sources_to_rebuild.keys().collect::<Vec<_>>()
         );
 
+        {
+            let mut status = self.status.write().await;
+            status.sources = sources_to_rebuild.keys().cloned().collect();
+            status.sources.sort();
+        }
+
+        // Snapshot for comparing memberships once all sources are rebuilt
+        let previous_clusters = self.clusters.active.read().await.clone();
+
         // Perform rebuild for each enabled source
         let mut all_validation_passed = true;
 
@@ -676,11 +1042,17 @@ impl RebuildTrigger {
         let mut rollout = self.rollout_controller.write().await;
         for (source, races) in sources_to_rebuild {
             eprintln!("Rebuilding clusters for source: {}", source);
+            self.status.write().await.current_source = Some(source.clone());
+            let mut source_passed = false;
 
             // Perform rebuild
             match self
                 .clusters
-                .rebuild_with_zero_downtime(races, &self.config)
+                .rebuild_with_zero_downtime(
+                    races,
+                    &HashSet::from([source.clone()]),
+                    &self.config,
+                )
                 .await
             {
                 Ok(_) => {
@@ -690,6 +1062,7 @@ impl RebuildTrigger {
                     // Record result in rollout controller
                     rollout.record_rebuild_result(&source, validation_result.clone());
 
+                    source_passed = validation_result.passed;
                     if !validation_result.passed {
                         all_validation_passed = false;
                         eprintln!(
@@ -714,6 +1087,16 @@ impl RebuildTrigger {
                     all_validation_passed = false;
                 }
             }
+
+            self.source_attempts
+                .write()
+                .await
+                .insert(source.clone(), Utc::now());
+            let mut status = self.status.write().await;
+            status.sources_completed += 1;
+            if !source_passed {
+                status.failed_sources.push(source);
+            }
         }
 
         // Try to advance phase if all validations passed
@@ -732,7 +1115,14 @@ impl RebuildTrigger {
         *self.last_rebuild.write().await = Utc::now();
 
         // Update metrics
-        let new_metrics = self.calculate_current_metrics().await;
+        let new_metrics = self
+            .calculate_current_metrics(Some(&previous_clusters))
+            .await;
+        log::info!(
+            "Rebuild against previous clusters: ARI {:.3}, stability {:.3}",
+            new_metrics.ari_score,
+            new_metrics.stability_score
+        );
         *self.last_metrics.write().await = new_metrics;
 
         // Persist the updated rollout configuration after rebuild
@@ -754,6 +1144,50 @@ impl RebuildTrigger {
         Ok(())
     }
 
+    /// Page through the historical store `batch_size` races at a time,
+    /// keeping races the rollout selects (from `only`, when given); returns
+    /// the number of scan calls
+    async fn scan_persisted_races(
+        &self,
+        rollout: &PhasedRollout,
+        only: Option<&HashSet<String>>,
+        sources_to_rebuild: &mut HashMap<String, Vec<crate::models::Race>>,
+    ) -> Result<usize> {
+        let mut cursor: Option<String> = None;
+        let mut batches = 0;
+        loop {
+            let filter = crate::persistence::RaceScanFilter {
+                source: None,
+                sources: only.cloned(),
+                from: None,
+                to: None,
+                include_events: false,
+            };
+            let batch = self
+                .store
+                .scan_races(filter, self.config.batch_size, cursor.take())
+                .await?;
+            batches += 1;
+            if batch.items.is_empty() {
+                break;
+            }
+            for race in batch.items {
+                let request_hash = seahash::hash(race.id.as_bytes());
+                if rollout.should_use_source(&race.source, request_hash) {
+                    sources_to_rebuild
+                        .entry(race.source.clone())
+                        .or_default()
+                        .push(race);
+                }
+            }
+            match batch.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+        Ok(batches)
+    }
+
     fn get_enabled_sources(
         &self,
         rollout: &PhasedRollout,
@@ -793,7 +1227,11 @@ impl RebuildTrigger {
         }
 
         // Count clusters for this source
-        let source_clusters: Vec<_> = clusters.values().filter(|c| c.source == source).collect();
+        let source_clusters: ClusterSet = clusters
+            .iter()
+            .filter(|(_, c)| c.source == source)
+            .map(|(id, c)| (id.clone(), c.clone()))
+            .collect();
 
         if source_clusters.is_empty() {
             return ValidationResult {
@@ -806,7 +1244,9 @@ impl RebuildTrigger {
 
         // Calculate basic metrics
         let noise_ratio = calculate_noise_ratio_for_source(&*clusters, source);
-        let cohesion = calculate_cohesion_for_source(&*clusters, source);
+        let cohesion = calculate_cohesion(&source_clusters, |source| {
+            self.config.config_for_source(source)
+        });
 
         let mut failures = Vec::new();
 
@@ -829,7 +1269,9 @@ impl RebuildTrigger {
                 success_rate: if failures.is_empty() { 1.0 } else { 0.0 },
                 noise_ratio,
                 cohesion,
-                separation: 1.0 / source_clusters.len().max(1) as f64,
+                separation: calculate_separation(&source_clusters, |source| {
+                    self.config.config_for_source(source)
+                }),
                 silhouette: 0.0,
                 ari: if failures.is_empty() { 1.0 } else { 0.0 },
             },
@@ -851,45 +1293,35 @@ impl RebuildTrigger {
         // Copy to main clustering engine
         {
             let mut main_clusters = self.clustering_engine.clusters.write().await;
-            main_clusters.clear();
+            // Rebuilt clusters keep stable ids; carry their accuracy record across
+            let mut accuracy: HashMap<String, PredictionAccuracy> = main_clusters
+                .drain()
+                .map(|(id, cluster)| (id, cluster.prediction_accuracy))
+                .collect();
             for (id, cluster) in rebuild_clusters.iter() {
-                main_clusters.insert(id.clone(), cluster.clone());
+                let mut cluster = cluster.clone();
+                if let Some(previous) = accuracy.remove(id) {
+                    cluster.prediction_accuracy = previous;
+                }
+                main_clusters.insert(id.clone(), cluster);
             }
             log::info!("Synced {} clusters to main clustering engine", main_clusters.len());
         }
         
-        // Persist all clusters to disk
-        if let Err(e) = self.store.persist_all_clusters(&*rebuild_clusters) {
-            log::error!("Failed to persist clusters: {}", e);
-        } else {
-            log::info!("Persisted {} clusters to disk", rebuild_clusters.len());
+        // Persist changed clusters to disk
+        match self.store.persist_all_clusters(&rebuild_clusters) {
+            Ok(summary) => log::info!(
+                "Persisted {} clusters to disk ({} written, {} deleted, {} unchanged)",
+                rebuild_clusters.len(),
+                summary.written,
+                summary.deleted,
+                summary.unchanged
+            ),
+            Err(e) => log::error!("Failed to persist clusters: {}", e),
         }
     }
 }
 
-fn calculate_cohesion(clusters: &ClusterSet) -> f64 {
-    if clusters.is_empty() {
-        return 1.0;
-    }
-
-    // Simplified: ratio of non-singleton clusters
-    let non_singleton = clusters
-        .values()
-        .filter(|c| c.member_race_ids.len() > 1)
-        .count();
-
-    non_singleton as f64 / clusters.len() as f64
-}
-
-fn calculate_separation(clusters: &ClusterSet) -> f64 {
-    // Simplified: inverse of cluster count (more clusters = less separation)
-    if clusters.len() <= 1 {
-        return 1.0;
-    }
-
-    1.0 / clusters.len() as f64
-}
-
 fn calculate_noise_ratio_for_source(clusters: &ClusterSet, source: &str) -> f64 {
     let source_clusters: Vec<_> = clusters.values().filter(|c| c.source == source).collect();
 
@@ -914,18 +1346,504 @@ fn calculate_noise_ratio_for_source(clusters: &ClusterSet, source: &str) -> f64
     singleton_clusters as f64 / total_races as f64
 }
 
-fn calculate_cohesion_for_source(clusters: &ClusterSet, source: &str) -> f64 {
-    let source_clusters: Vec<_> = clusters.values().filter(|c| c.source == source).collect();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cluster::test_utils::test_cluster;
+    use crate::cluster::RaceCluster;
+    use crate::phased_rollout::MAX_PHASE_HISTORY;
 
-    if source_clusters.is_empty() {
-        return 0.0;
+    fn cluster(id: &str, members: &[&str]) -> (String, RaceCluster) {
+        (id.to_string(), test_cluster(id, members))
     }
 
-    // Ratio of non-singleton clusters
-    let non_singleton = source_clusters
-        .iter()
-        .filter(|c| c.member_race_ids.len() > 1)
-        .count();
+    #[tokio::test]
+    async fn test_metrics_report_ari_against_previous_clusters() {
+        let old: ClusterSet = [
+            cluster("ci:0", &["a", "b", "c"]),
+            cluster("ci:1", &["d", "e", "f"]),
+        ]
+        .into_iter()
+        .collect();
+        let new: ClusterSet = [
+            cluster("ci:0", &["a", "b"]),
+            cluster("ci:1", &["c", "d"]),
+            cluster("ci:2", &["e", "f"]),
+        ]
+        .into_iter()
+        .collect();
+
+        let buffers = Arc::new(DoubleBufferClusters::new(0));
+        *buffers.active.write().await = new.clone();
+        let trigger = RebuildTrigger::new(
+            RebuildConfig::default(),
+            buffers,
+            Arc::new(PersistenceLayer::new_in_memory().unwrap()),
+            Arc::new(ClusteringEngine::new(100)),
+        );
+
+        let expected = adjusted_rand_index(&old, &new);
+        assert!(expected < 0.9);
+        let metrics = trigger.calculate_current_metrics(Some(&old)).await;
+        assert!((metrics.ari_score - expected).abs() < 1e-12);
+
+        // Identical memberships under different ids still agree fully
+        let renamed: ClusterSet = [
+            cluster("ci:7", &["a", "b"]),
+            cluster("ci:8", &["c", "d"]),
+            cluster("ci:9", &["e", "f"]),
+        ]
+        .into_iter()
+        .collect();
+        let metrics = trigger.calculate_current_metrics(Some(&renamed)).await;
+        assert!((metrics.ari_score - 1.0).abs() < 1e-12);
+    }
+
+    #[tokio::test]
+    async fn test_rebuild_keeping_memberships_scores_high_stability() {
+        let old: ClusterSet = [
+            cluster("ci:build", &["a", "b", "c", "d", "e"]),
+            cluster("ci:test", &["f", "g", "h", "i", "j"]),
+        ]
+        .into_iter()
+        .collect();
+        // Rebuild renumbers clusters and moves a single race across
+        let new: ClusterSet = [
+            cluster("ci:0", &["a", "b", "c", "d"]),
+            cluster("ci:1", &["e", "f", "g", "h", "i", "j"]),
+        ]
+        .into_iter()
+        .collect();
+
+        let buffers = Arc::new(DoubleBufferClusters::new(0));
+        *buffers.active.write().await = new;
+        let trigger = RebuildTrigger::new(
+            RebuildConfig::default(),
+            buffers,
+            Arc::new(PersistenceLayer::new_in_memory().unwrap()),
+            Arc::new(ClusteringEngine::new(100)),
+        );
+
+        let metrics = trigger.calculate_current_metrics(Some(&old)).await;
+        assert!((metrics.stability_score - 0.9).abs() < 1e-12);
+        // Stability tracks id continuity, not pairwise agreement
+        assert!(metrics.ari_score < metrics.stability_score);
+
+        let metrics = trigger.calculate_current_metrics(None).await;
+        assert_eq!(metrics.stability_score, 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_status_reports_running_rebuild_then_idle_with_result() {
+        let trigger = Arc::new(RebuildTrigger::new(
+            RebuildConfig::default(),
+            Arc::new(DoubleBufferClusters::new(0)),
+            Arc::new(PersistenceLayer::new_in_memory().unwrap()),
+            Arc::new(ClusteringEngine::new(100)),
+        ));
+        let idle = trigger.status().await;
+        assert!(!idle.running);
+        assert!(idle.last_result.is_none());
+
+        // Holding the rollout lock parks the rebuild right after it starts
+        let rollout = trigger.rollout_controller.write().await;
+        let task = {
+            let trigger = trigger.clone();
+            tokio::spawn(async move { trigger.trigger_rebuild().await })
+        };
+        let running = loop {
+            let status = trigger.status().await;
+            if status.running {
+                break status;
+            }
+            tokio::task::yield_now().await;
+        };
+        assert!(running.started_at.is_some());
+        assert!(running.current_source.is_none());
+        assert!(running.last_result.is_none());
+
+        drop(rollout);
+        task.await.unwrap().unwrap();
+
+        let done = trigger.status().await;
+        assert!(!done.running);
+        assert!(done.current_source.is_none());
+        let result = done.last_result.unwrap();
+        assert!(result.success);
+        assert_eq!(Some(result.started_at), running.started_at);
+        assert!(result.finished_at >= result.started_at);
+        assert!(result.error.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_rapid_triggers_share_one_background_job() {
+        let trigger = RebuildTrigger::new(
+            RebuildConfig::default(),
+            Arc::new(DoubleBufferClusters::new(0)),
+            Arc::new(PersistenceLayer::new_in_memory().unwrap()),
+            Arc::new(ClusteringEngine::new(100)),
+        );
+
+        // Keep the first job from finishing before the second trigger arrives
+        let rollout = trigger.rollout_controller.write().await;
+        let first = trigger.start_rebuild().await;
+        let second = trigger.start_rebuild().await;
+        assert!(first.started);
+        assert!(!second.started);
+        assert_eq!(second.job_id, first.job_id);
+        // Scheduled rebuilds don't start a parallel run either
+        trigger.trigger_rebuild().await.unwrap();
+
+        let status = trigger.status().await;
+        assert!(status.running);
+        assert_eq!(status.job_id.as_deref(), Some(first.job_id.as_str()));
+
+        drop(rollout);
+        let result = loop {
+            let status = trigger.status().await;
+            if !status.running {
+                break status.last_result.unwrap();
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        };
+        assert_eq!(result.job_id, first.job_id);
+        assert!(result.success);
+
+        let next = trigger.start_rebuild().await;
+        assert!(next.started);
+        assert_ne!(next.job_id, first.job_id);
+    }
+
+    #[tokio::test]
+    async fn test_rebuild_scan_uses_configured_batch_size() {
+        let store = Arc::new(PersistenceLayer::new_in_memory().unwrap());
+        for i in 0..25 {
+            let mut race = crate::models::Race::new("ci".to_string(), format!("job {}", i));
+            race.id = format!("race-{:02}", i);
+            race.duration_sec = Some(60);
+            store.store_race(&race).await.unwrap();
+        }
+        let scan = |batch_size: usize| {
+            let store = store.clone();
+            async move {
+                let trigger = RebuildTrigger::new(
+                    RebuildConfig::default().with_batch_size(batch_size),
+                    Arc::new(DoubleBufferClusters::new(0)),
+                    store,
+                    Arc::new(ClusteringEngine::new(100)),
+                );
+                let mut rollout = trigger.rollout_controller.write().await;
+                rollout.register_source("ci");
+                let status = rollout.source_status.get_mut("ci").unwrap();
+                status.enabled = true;
+                status.mode = RolloutMode::Shadow;
+                let mut sources = HashMap::new();
+                let batches = trigger
+                    .scan_persisted_races(&rollout, None, &mut sources)
+                    .await
+                    .unwrap();
+                let races: usize = sources.values().map(Vec::len).sum();
+                (batches, races)
+            }
+        };
+
+        // Batch size changes the round-trips, not which races are loaded
+        for (batch_size, expected_batches) in [(4, 7), (10, 3), (25, 2), (10_000, 1)] {
+            assert_eq!(scan(batch_size).await, (expected_batches, 25));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rebuild_scan_loads_only_requested_sources() {
+        let store = Arc::new(PersistenceLayer::new_in_memory().unwrap());
+        for (i, source) in ["ci", "ci", "ci", "docs", "docs"].iter().enumerate() {
+            let mut race = crate::models::Race::new(source.to_string(), "build".to_string());
+            race.id = format!("race-{}", i);
+            store.store_race(&race).await.unwrap();
+        }
+        let trigger = RebuildTrigger::new(
+            RebuildConfig::default(),
+            Arc::new(DoubleBufferClusters::new(0)),
+            store,
+            Arc::new(ClusteringEngine::new(100)),
+        );
+        let mut rollout = trigger.rollout_controller.write().await;
+        for source in ["ci", "docs"] {
+            rollout.register_source(source);
+            let status = rollout.source_status.get_mut(source).unwrap();
+            status.enabled = true;
+            status.mode = RolloutMode::Production;
+        }
+
+        let only: HashSet<String> = ["docs".to_string()].into();
+        let mut sources = HashMap::new();
+        trigger
+            .scan_persisted_races(&rollout, Some(&only), &mut sources)
+            .await
+            .unwrap();
+        assert_eq!(sources.len(), 1);
+        assert_eq!(sources["docs"].len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_sources_rebuild_on_their_own_intervals() {
+        let mut config = RebuildConfig::default();
+        for (source, hours) in [("ci", 24), ("docs", 24 * 7)] {
+            config.source_configs.insert(
+                source.to_string(),
+                crate::rebuild::SourceConfig {
+                    rebuild_interval_hours: Some(hours),
+                    ..crate::rebuild::SourceConfig::default_for_unknown()
+                },
+            );
+        }
+        let trigger = RebuildTrigger::new(
+            config,
+            Arc::new(DoubleBufferClusters::new(0)),
+            Arc::new(PersistenceLayer::new_in_memory().unwrap()),
+            Arc::new(ClusteringEngine::new(100)),
+        );
+        let two_days_ago = Utc::now() - chrono::Duration::days(2);
+        {
+            let mut rollout = trigger.rollout_controller.write().await;
+            for source in ["ci", "docs"] {
+                rollout.register_source(source);
+                let status = rollout.source_status.get_mut(source).unwrap();
+                status.enabled = true;
+                status.mode = RolloutMode::Production;
+                status.last_rebuild = Some(two_days_ago);
+            }
+        }
+
+        let due = trigger.sources_due_for_rebuild(Utc::now()).await;
+        assert_eq!(due, vec!["ci"]);
+
+        // Once attempted, ci waits out its day again; docs is due after a week
+        trigger.trigger_rebuild_for(&due).await.unwrap();
+        assert!(trigger.sources_due_for_rebuild(Utc::now()).await.is_empty());
+        let later = Utc::now() + chrono::Duration::days(6);
+        assert_eq!(
+            trigger.sources_due_for_rebuild(later).await,
+            vec!["ci", "docs"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_phase_1_enables_configured_pilot_source() {
+        assert_eq!(RebuildConfig::default().pilot_source, "cargo");
+        assert!(RebuildConfig::default()
+            .with_pilot_source("  ")
+            .validate()
+            .is_err());
+
+        let store = Arc::new(PersistenceLayer::new_in_memory().unwrap());
+        for (i, source) in ["cargo", "npm", "npm"].iter().enumerate() {
+            let mut race = crate::models::Race::new(source.to_string(), "build".to_string());
+            race.id = format!("race-{}", i);
+            store.store_race(&race).await.unwrap();
+        }
+        let trigger = RebuildTrigger::new(
+            RebuildConfig::default().with_pilot_source("npm"),
+            Arc::new(DoubleBufferClusters::new(0)),
+            store,
+            Arc::new(ClusteringEngine::new(100)),
+        );
+        trigger.initialize_sources().await;
+        {
+            let rollout = trigger.rollout_controller.read().await;
+            let enabled: Vec<&String> = rollout
+                .source_status
+                .iter()
+                .filter(|(_, status)| status.enabled)
+                .map(|(source, _)| source)
+                .collect();
+            assert_eq!(enabled, vec!["npm"]);
+        }
+
+        trigger.reset_to_phase_1(&ManualChange::default()).await;
+        let rollout = trigger.rollout_controller.read().await;
+        assert_eq!(rollout.current_phase, RolloutPhase::SingleSource);
+        assert_eq!(rollout.config.pilot_source, "npm");
+        assert_eq!(rollout.source_status["npm"].mode, RolloutMode::Production);
+        assert!(!rollout.source_status["cargo"].enabled);
+    }
+
+    #[tokio::test]
+    async fn test_low_success_rate_blocks_promotion() {
+        let trigger = RebuildTrigger::new(
+            RebuildConfig::default().with_promotion_thresholds(5, 10, 0.9),
+            Arc::new(DoubleBufferClusters::new(0)),
+            Arc::new(PersistenceLayer::new_in_memory().unwrap()),
+            Arc::new(ClusteringEngine::new(100)),
+        );
+        {
+            let mut rollout = trigger.rollout_controller.write().await;
+            assert_eq!(rollout.config.shadow_promotion_successes, 5);
+            for (source, mode, successes, failures) in [
+                ("flaky", RolloutMode::Shadow, 6, 20),
+                ("steady", RolloutMode::Shadow, 6, 0),
+                ("early", RolloutMode::Shadow, 4, 0),
+                ("canary", RolloutMode::Canary { percentage: 10 }, 12, 3),
+            ] {
+                rollout.seed_source(source, mode, successes, failures);
+            }
+        }
 
-    non_singleton as f64 / source_clusters.len() as f64
+        trigger.check_rollout_promotion().await;
+
+        let rollout = trigger.rollout_controller.read().await;
+        let mode = |source: &str| rollout.source_status[source].mode;
+        // Enough raw successes, but 6 of 26 is far below the 90% rate
+        assert_eq!(mode("flaky"), RolloutMode::Shadow);
+        assert_eq!(mode("steady"), RolloutMode::Canary { percentage: 10 });
+        assert_eq!(mode("early"), RolloutMode::Shadow);
+        // 12 of 15 passed: past the count threshold, short of the rate
+        assert_eq!(mode("canary"), RolloutMode::Canary { percentage: 10 });
+    }
+
+    #[tokio::test]
+    async fn test_promotion_check_without_changes_skips_persist() {
+        let trigger = RebuildTrigger::new(
+            RebuildConfig::default().with_promotion_thresholds(5, 10, 0.9),
+            Arc::new(DoubleBufferClusters::new(0)),
+            Arc::new(PersistenceLayer::new_in_memory().unwrap()),
+            Arc::new(ClusteringEngine::new(100)),
+        );
+        {
+            let mut rollout = trigger.rollout_controller.write().await;
+            rollout.seed_source("early", RolloutMode::Shadow, 1, 0);
+        }
+
+        trigger.check_rollout_promotion().await;
+        assert!(trigger.store.load_rollout_config().unwrap().is_none());
+
+        trigger
+            .rollout_controller
+            .write()
+            .await
+            .source_status
+            .get_mut("early")
+            .unwrap()
+            .success_count = 5;
+        trigger.check_rollout_promotion().await;
+        let saved = trigger.store.load_rollout_config().unwrap().unwrap();
+        assert_eq!(
+            saved.source_status["early"].mode,
+            RolloutMode::Canary { percentage: 10 }
+        );
+    }
+
+    #[test]
+    fn test_phase_history_store_keeps_transitions_past_the_cap() {
+        let store = PersistenceLayer::new_in_memory().unwrap();
+        let mut rollout = PhasedRollout::new(RolloutConfig::default());
+        for i in 0..MAX_PHASE_HISTORY + 50 {
+            rollout.add_transition(
+                RolloutPhase::SingleSource,
+                RolloutPhase::SingleSource,
+                format!("change {}", i),
+            );
+            // Persisted now and then, as promotion checks and manual changes do
+            if i % 30 == 0 {
+                store.persist_rollout_config(&rollout).unwrap();
+            }
+        }
+        store.persist_rollout_config(&rollout).unwrap();
+        assert_eq!(rollout.phase_history.len(), MAX_PHASE_HISTORY);
+        assert_eq!(rollout.phase_history[0].reason, "change 50");
+
+        let stored = store.phase_transitions(None, None).unwrap();
+        assert_eq!(stored.len(), MAX_PHASE_HISTORY + 50);
+        for (i, transition) in stored.iter().enumerate() {
+            assert_eq!(transition.reason, format!("change {}", i));
+        }
+
+        // A rollout saved before transitions were numbered continues the sequence
+        let mut legacy = PhasedRollout::new(RolloutConfig::default());
+        legacy.trigger_rollback("High failure rate detected");
+        legacy.last_transition_seq = 0;
+        legacy.phase_history[0].seq = 0;
+        legacy.sequence_transitions(store.last_phase_transition_seq().unwrap());
+        store.persist_rollout_config(&legacy).unwrap();
+        let stored = store.phase_transitions(None, None).unwrap();
+        assert_eq!(stored.len(), MAX_PHASE_HISTORY + 51);
+        assert_eq!(stored.last().unwrap().to_phase, RolloutPhase::Rollback);
+    }
+
+    #[tokio::test]
+    async fn test_stale_rollback_auto_recovers_after_window() {
+        let trigger = RebuildTrigger::new(
+            RebuildConfig::default()
+                .with_rollback_recovery_after(std::time::Duration::from_secs(30 * 60)),
+            Arc::new(DoubleBufferClusters::new(0)),
+            Arc::new(PersistenceLayer::new_in_memory().unwrap()),
+            Arc::new(ClusteringEngine::new(100)),
+        );
+        {
+            let mut rollout = trigger.rollout_controller.write().await;
+            rollout.register_sources(&["cargo".to_string(), "npm".to_string()]);
+            rollout.current_phase = RolloutPhase::AllSourcesConservative;
+            rollout.enable_all_sources(RolloutMode::Production, &ManualChange::default());
+            rollout.trigger_rollback("High failure rate detected");
+        }
+
+        // Still inside the window: stays rolled back
+        assert!(!trigger.recover_stale_rollback().await);
+        assert_eq!(
+            trigger.rollout_controller.read().await.current_phase,
+            RolloutPhase::Rollback
+        );
+
+        {
+            let mut rollout = trigger.rollout_controller.write().await;
+            let rolled_back = rollout.phase_history.last_mut().unwrap();
+            rolled_back.timestamp = rolled_back.timestamp - chrono::Duration::minutes(31);
+        }
+        assert!(trigger.recover_stale_rollback().await);
+
+        let rollout = trigger.rollout_controller.read().await;
+        assert_eq!(rollout.current_phase, RolloutPhase::AllSourcesConservative);
+        for status in rollout.source_status.values() {
+            assert!(status.enabled);
+            assert_eq!(status.mode, RolloutMode::Shadow);
+        }
+        let last = rollout.phase_history.last().unwrap();
+        assert_eq!(last.from_phase, RolloutPhase::Rollback);
+        let saved = trigger.store.load_rollout_config().unwrap().unwrap();
+        assert_eq!(saved.current_phase, RolloutPhase::AllSourcesConservative);
+        drop(rollout);
+
+        // Nothing left to recover
+        assert!(!trigger.recover_stale_rollback().await);
+    }
+
+    #[tokio::test]
+    async fn test_manual_steps_during_rollback_keep_recovery_clock() {
+        let trigger = RebuildTrigger::new(
+            RebuildConfig::default()
+                .with_rollback_recovery_after(std::time::Duration::from_secs(30 * 60)),
+            Arc::new(DoubleBufferClusters::new(0)),
+            Arc::new(PersistenceLayer::new_in_memory().unwrap()),
+            Arc::new(ClusteringEngine::new(100)),
+        );
+        {
+            let mut rollout = trigger.rollout_controller.write().await;
+            rollout.register_sources(&["cargo".to_string(), "npm".to_string()]);
+            rollout.current_phase = RolloutPhase::AllSourcesConservative;
+            rollout.trigger_rollback("High failure rate detected");
+            let rolled_back = rollout.phase_history.last_mut().unwrap();
+            rolled_back.timestamp = rolled_back.timestamp - chrono::Duration::minutes(31);
+            rollout.enable_all_sources(RolloutMode::Shadow, &ManualChange::default());
+        }
+        trigger
+            .demote_source("npm", &ManualChange::default())
+            .await
+            .unwrap();
+
+        // The operator's changes neither restart the window nor lose the phase
+        assert!(trigger.recover_stale_rollback().await);
+        let rollout = trigger.rollout_controller.read().await;
+        assert_eq!(rollout.current_phase, RolloutPhase::AllSourcesConservative);
+        assert!(rollout.source_status["npm"].enabled);
+    }
 }
@@ -0,0 +1,90 @@
+use std::future::Future;
+use tokio::time::Instant;
+
+/// Await one subsystem's shutdown until the shared `deadline`, logging (and
+/// abandoning) it if it overruns. Returns the task's output when it finished.
+pub async fn join_by_deadline<F: Future>(
+    name: &str,
+    task: F,
+    deadline: Instant,
+) -> Option<F::Output> {
+    match tokio::time::timeout_at(deadline, task).await {
+        Ok(output) => Some(output),
+        Err(_) => {
+            log::warn!("{} did not finish within the shutdown timeout", name);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_overrunning_subsystem_is_abandoned_at_deadline() {
+        let timeout = Duration::from_millis(50);
+        let started = Instant::now();
+        let deadline = started + timeout;
+
+        let quick = join_by_deadline("quick", async { 7 }, deadline).await;
+        assert_eq!(quick, Some(7));
+
+        let slow = join_by_deadline(
+            "slow",
+            tokio::time::sleep(Duration::from_secs(30)),
+            deadline,
+        )
+        .await;
+        assert!(slow.is_none());
+        assert!(started.elapsed() >= timeout);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[actix_web::test]
+    async fn test_http_stop_waits_only_the_configured_timeout() {
+        use actix_web::{web, App, HttpResponse, HttpServer};
+        use std::sync::Arc;
+
+        let mut config = crate::config::Settings::default().server;
+        config.shutdown_timeout_secs = 1;
+        let entered = Arc::new(tokio::sync::Notify::new());
+        let handler_entered = entered.clone();
+        let server = HttpServer::new(move || {
+            let entered = handler_entered.clone();
+            App::new().route(
+                "/slow",
+                web::get().to(move || {
+                    let entered = entered.clone();
+                    async move {
+                        entered.notify_one();
+                        tokio::time::sleep(Duration::from_secs(60)).await;
+                        HttpResponse::Ok().finish()
+                    }
+                }),
+            )
+        })
+        .workers(1)
+        // As main applies server.shutdown_timeout_secs
+        .shutdown_timeout(config.shutdown_timeout().as_secs())
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+        let addr = server.addrs()[0];
+        let server = server.run();
+        let handle = server.handle();
+        actix_web::rt::spawn(server);
+
+        let request = actix_web::rt::spawn(reqwest::get(format!("http://{}/slow", addr)));
+        tokio::time::timeout(Duration::from_secs(10), entered.notified())
+            .await
+            .unwrap();
+
+        // Graceful stop abandons the 60s request after the configured second,
+        // instead of actix's 30s default
+        tokio::time::timeout(Duration::from_secs(10), handle.stop(true))
+            .await
+            .expect("stop waited past shutdown_timeout_secs");
+        request.abort();
+    }
+}
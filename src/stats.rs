@@ -2,9 +2,22 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 
+/// Number of recent durations retained when no per-source window is configured
+pub const DEFAULT_WINDOW_SIZE: usize = 20;
+
+fn default_window_size() -> usize {
+    DEFAULT_WINDOW_SIZE
+}
+
+/// Out-of-range durations in a row after which they are kept anyway: a lasting
+/// shift (e.g. builds got slower) rather than one-off hung or aborted runs
+pub const OUTLIER_SHIFT_STREAK: u32 = 5;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExecutionStats {
     pub recent_times: VecDeque<i64>,
+    #[serde(default = "default_window_size")]
+    pub window_size: usize, // Max entries kept in recent_times
     pub mean: f64,
     pub median: f64,
     pub std_dev: f64,
@@ -12,6 +25,9 @@ pub struct ExecutionStats {
     pub percentiles: Percentiles,
     pub trend: TrendAnalysis,
     pub last_updated: DateTime<Utc>,
+    /// Durations rejected as anomalies or outliers since the last kept one
+    #[serde(default)]
+    pub rejected_streak: u32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,13 +40,154 @@ pub struct Percentiles {
     pub p95: i64,
 }
 
+impl Percentiles {
+    /// Stored value for one of the standard levels (10, 25, 50, 75, 90, 95)
+    pub fn get(&self, level: f64) -> Option<i64> {
+        match PercentileSet::label(level).as_str() {
+            "p10" => Some(self.p10),
+            "p25" => Some(self.p25),
+            "p50" => Some(self.p50),
+            "p75" => Some(self.p75),
+            "p90" => Some(self.p90),
+            "p95" => Some(self.p95),
+            _ => None,
+        }
+    }
+}
+
+/// Percentiles reported on top of the standard p10..p95, and the two that
+/// bound the ETA range
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PercentileSet {
+    pub extra: Vec<f64>, // e.g. [99.0] for tail latency
+    pub lower_bound: f64,
+    pub upper_bound: f64,
+}
+
+impl Default for PercentileSet {
+    fn default() -> Self {
+        Self {
+            extra: Vec::new(),
+            lower_bound: 25.0,
+            upper_bound: 75.0,
+        }
+    }
+}
+
+impl PercentileSet {
+    pub const STANDARD: [f64; 6] = [10.0, 25.0, 50.0, 75.0, 90.0, 95.0];
+
+    /// Report key for a level: 99.0 -> "p99", 99.9 -> "p99.9"
+    pub fn label(level: f64) -> String {
+        format!("p{}", level)
+    }
+
+    /// Standard and extra levels, ascending and without duplicates
+    pub fn levels(&self) -> Vec<f64> {
+        let mut levels: Vec<f64> = Self::STANDARD
+            .iter()
+            .chain(&self.extra)
+            .copied()
+            .filter(|level| (0.0..=100.0).contains(level))
+            .collect();
+        levels.sort_by(f64::total_cmp);
+        levels.dedup();
+        levels
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TrendAnalysis {
     pub direction: TrendDirection,
-    pub rate: f64, // Change rate per execution
-    pub confidence: f64,
+    pub rate: f64, // Change rate per execution, relative to the mean
+    pub confidence: f64, // R² of the regression
+    /// Fit inputs, persisted so reloaded stats keep their trend and updates stay incremental
+    #[serde(default)]
+    pub regression: Option<TrendRegression>,
+}
+
+/// Running sums for a least-squares fit of duration over execution index
+/// (oldest retained execution is x = 0)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TrendRegression {
+    pub n: usize,
+    pub sum_x: f64,
+    pub sum_y: f64,
+    pub sum_xy: f64,
+    pub sum_xx: f64,
+    pub sum_yy: f64,
+}
+
+impl TrendRegression {
+    fn fit(times: &VecDeque<i64>) -> Self {
+        let mut regression = Self::default();
+        for &y in times {
+            regression.push(y);
+        }
+        regression
+    }
+
+    fn push(&mut self, duration: i64) {
+        let (x, y) = (self.n as f64, duration as f64);
+        self.n += 1;
+        self.sum_x += x;
+        self.sum_y += y;
+        self.sum_xy += x * y;
+        self.sum_xx += x * x;
+        self.sum_yy += y * y;
+    }
+
+    /// Drop the oldest point (x = 0) and shift the rest down one index
+    fn pop_front(&mut self, duration: i64) {
+        let y = duration as f64;
+        self.n -= 1;
+        self.sum_y -= y;
+        self.sum_yy -= y * y;
+        let n = self.n as f64;
+        self.sum_xx += n - 2.0 * self.sum_x;
+        self.sum_x -= n;
+        self.sum_xy -= self.sum_y;
+    }
+
+    /// Slope in seconds per execution and R²
+    fn slope_and_r_squared(&self) -> (f64, f64) {
+        let n = self.n as f64;
+        let sxx = n * self.sum_xx - self.sum_x * self.sum_x;
+        let syy = n * self.sum_yy - self.sum_y * self.sum_y;
+        let sxy = n * self.sum_xy - self.sum_x * self.sum_y;
+        if sxx <= 0.0 {
+            return (0.0, 0.0);
+        }
+        let slope = sxy / sxx;
+        let r_squared = if syy > 0.0 {
+            (sxy * sxy / (sxx * syy)).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        (slope, r_squared)
+    }
 }
 
+/// Percentile of an ascending slice by linear interpolation between the two
+/// closest ranks (Hyndman & Fan type 7, as in R and NumPy), rounded to whole
+/// seconds. `level` is 0-100; an empty slice yields 0.
+fn interpolated_percentile(sorted: &[i64], level: f64) -> i64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let h = (sorted.len() - 1) as f64 * level.clamp(0.0, 100.0) / 100.0;
+    let below = h.floor() as usize;
+    let above = h.ceil() as usize;
+    if below == above {
+        return sorted[below];
+    }
+    let (low, high) = (sorted[below] as f64, sorted[above] as f64);
+    (low + (h - below as f64) * (high - low)).round() as i64
+}
+
+/// Relative slope per execution below which a trend counts as stable
+const STABLE_TREND_RATE: f64 = 0.005;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum TrendDirection {
     Improving,
@@ -44,12 +201,91 @@ pub struct EtaPrediction {
     pub confidence: f64,
     pub lower_bound: i64,
     pub upper_bound: i64,
+    /// Fallback stage that produced the value
+    pub method: PredictionMethod,
+}
+
+/// Stages of the ETA prediction fallback chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PredictionMethod {
+    /// Stats of the best matching cluster
+    Cluster,
+    /// Stats over all completed races of the source
+    SourceAverage,
+    /// Bootstrap patterns, per-source `default_eta_sec`, or the built-in table
+    Bootstrap,
+    /// Flat default; always produces a value
+    GlobalDefault,
+}
+
+/// Chain used for sources without a configured one
+pub const DEFAULT_FALLBACK_CHAIN: [PredictionMethod; 4] = [
+    PredictionMethod::Cluster,
+    PredictionMethod::SourceAverage,
+    PredictionMethod::Bootstrap,
+    PredictionMethod::GlobalDefault,
+];
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct TrendAdjustment {
+    pub enabled: bool,
+    pub horizon: f64,      // Executions ahead to extrapolate
+    pub max_fraction: f64, // Max adjustment relative to the median
+}
+
+impl Default for TrendAdjustment {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            horizon: 1.0,
+            max_fraction: 0.2,
+        }
+    }
+}
+
+/// Rejects durations more than `mad_multiplier` MADs from the current median,
+/// on top of the built-in modified z-score check in `is_anomaly`. Off unless a
+/// multiplier is set.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct OutlierFilter {
+    pub mad_multiplier: Option<f64>,
+    /// Retained samples required before filtering, so cold-start stats can
+    /// still establish a median
+    pub min_samples: usize,
+}
+
+impl Default for OutlierFilter {
+    fn default() -> Self {
+        Self {
+            mad_multiplier: None,
+            min_samples: 5,
+        }
+    }
+}
+
+impl OutlierFilter {
+    pub fn rejects(&self, stats: &ExecutionStats, duration: i64) -> bool {
+        let Some(k) = self.mad_multiplier else {
+            return false;
+        };
+        // With a zero MAD every differing duration would count as an outlier
+        if stats.recent_times.len() < self.min_samples || stats.mad == 0.0 {
+            return false;
+        }
+        (duration as f64 - stats.median).abs() > k * stats.mad
+    }
 }
 
 impl ExecutionStats {
     pub fn new() -> Self {
+        Self::new_with_window(DEFAULT_WINDOW_SIZE)
+    }
+
+    pub fn new_with_window(window_size: usize) -> Self {
         Self {
             recent_times: VecDeque::new(),
+            window_size: window_size.max(1),
             mean: 0.0,
             median: 0.0,
             std_dev: 0.0,
@@ -66,8 +302,10 @@ impl ExecutionStats {
                 direction: TrendDirection::Stable,
                 rate: 0.0,
                 confidence: 0.0,
+                regression: None,
             },
             last_updated: Utc::now(),
+            rejected_streak: 0,
         }
     }
 
@@ -92,21 +330,60 @@ impl ExecutionStats {
     }
 
     pub fn update_with_duration(&mut self, duration: i64) {
-        if self.is_anomaly(duration) {
+        self.update_with_duration_filtered(duration, &OutlierFilter::default());
+    }
+
+    /// Add a duration unless it is anomalous or `filter` rejects it; returns
+    /// whether it was kept. After `OUTLIER_SHIFT_STREAK` rejections in a row,
+    /// out-of-range durations are kept so the window can follow a lasting shift.
+    pub fn update_with_duration_filtered(&mut self, duration: i64, filter: &OutlierFilter) -> bool {
+        let anomaly = self.is_anomaly(duration);
+        let outlier = !anomaly && filter.rejects(self, duration);
+        if anomaly || outlier {
+            self.rejected_streak = self.rejected_streak.saturating_add(1);
+        } else {
+            self.rejected_streak = 0;
+        }
+
+        if self.rejected_streak >= OUTLIER_SHIFT_STREAK {
+            log::info!(
+                "Keeping out-of-range execution {}s: {} in a row (median: {}s)",
+                duration,
+                self.rejected_streak,
+                self.median
+            );
+        } else if anomaly {
             eprintln!(
                 "Anomalous execution detected: {}s (median: {}s)",
                 duration, self.median
             );
-            return;
+            return false;
+        } else if outlier {
+            log::warn!(
+                "Outlier execution rejected: {}s (median: {}s, MAD: {}s, k: {:?})",
+                duration,
+                self.median,
+                self.mad,
+                filter.mad_multiplier
+            );
+            return false;
         }
 
         self.recent_times.push_back(duration);
-        if self.recent_times.len() > 20 {
-            self.recent_times.pop_front();
+        if let Some(regression) = self.trend.regression.as_mut() {
+            regression.push(duration);
+        }
+        while self.recent_times.len() > self.window_size {
+            if let Some(oldest) = self.recent_times.pop_front() {
+                if let Some(regression) = self.trend.regression.as_mut() {
+                    regression.pop_front(oldest);
+                }
+            }
         }
 
         self.recalculate();
         self.last_updated = Utc::now();
+        true
     }
 
     pub fn recalculate(&mut self) {
@@ -162,57 +439,113 @@ impl ExecutionStats {
     }
 
     fn calculate_percentiles(&mut self, sorted_times: &[i64]) {
-        let len = sorted_times.len();
-        if len == 0 {
+        if sorted_times.is_empty() {
             return;
         }
 
-        self.percentiles.p10 = sorted_times[(len as f64 * 0.1) as usize];
-        self.percentiles.p25 = sorted_times[(len as f64 * 0.25) as usize];
-        self.percentiles.p50 = sorted_times[len / 2];
-        self.percentiles.p75 = sorted_times[(len as f64 * 0.75) as usize];
-        self.percentiles.p90 = sorted_times[(len as f64 * 0.9) as usize];
-        self.percentiles.p95 = sorted_times[((len as f64 * 0.95) as usize).min(len - 1)];
+        self.percentiles.p10 = interpolated_percentile(sorted_times, 10.0);
+        self.percentiles.p25 = interpolated_percentile(sorted_times, 25.0);
+        self.percentiles.p50 = interpolated_percentile(sorted_times, 50.0);
+        self.percentiles.p75 = interpolated_percentile(sorted_times, 75.0);
+        self.percentiles.p90 = interpolated_percentile(sorted_times, 90.0);
+        self.percentiles.p95 = interpolated_percentile(sorted_times, 95.0);
+    }
+
+    /// Refit the trend if its persisted regression is missing or out of step
+    /// with `recent_times` (e.g. records written before it was stored)
+    pub fn ensure_trend(&mut self) {
+        let in_sync = self
+            .trend
+            .regression
+            .as_ref()
+            .is_some_and(|r| r.n == self.recent_times.len());
+        if !in_sync {
+            self.analyze_trend();
+        }
     }
 
+    /// Linear regression of duration over execution index; R² is the confidence
     fn analyze_trend(&mut self) {
-        if self.recent_times.len() < 5 {
+        if self
+            .trend
+            .regression
+            .as_ref()
+            .is_none_or(|r| r.n != self.recent_times.len())
+        {
+            self.trend.regression = Some(TrendRegression::fit(&self.recent_times));
+        }
+
+        if self.recent_times.len() < 5 || self.mean <= 0.0 {
+            self.trend.rate = 0.0;
             self.trend.confidence = 0.0;
             self.trend.direction = TrendDirection::Stable;
             return;
         }
 
-        // Simple trend analysis: compare first half with second half
-        let mid = self.recent_times.len() / 2;
-        let first_half_avg: f64 =
-            self.recent_times.iter().take(mid).sum::<i64>() as f64 / mid as f64;
-        let second_half_avg: f64 = self.recent_times.iter().skip(mid).sum::<i64>() as f64
-            / (self.recent_times.len() - mid) as f64;
-
-        let change_rate = (second_half_avg - first_half_avg).abs() / first_half_avg;
-        self.trend.rate = change_rate;
-
-        if change_rate < 0.05 {
-            self.trend.direction = TrendDirection::Stable;
-            self.trend.confidence = 0.8;
-        } else if second_half_avg < first_half_avg {
-            self.trend.direction = TrendDirection::Improving;
-            self.trend.confidence = change_rate.min(0.95);
+        let (slope, r_squared) = self
+            .trend
+            .regression
+            .as_ref()
+            .map(TrendRegression::slope_and_r_squared)
+            .unwrap_or_default();
+        let rate = slope / self.mean;
+        self.trend.rate = rate.abs();
+        self.trend.confidence = r_squared;
+        self.trend.direction = if rate.abs() < STABLE_TREND_RATE {
+            TrendDirection::Stable
+        } else if rate < 0.0 {
+            TrendDirection::Improving
         } else {
-            self.trend.direction = TrendDirection::Degrading;
-            self.trend.confidence = change_rate.min(0.95);
+            TrendDirection::Degrading
+        };
+    }
+
+    /// Value at `level` (0-100). Standard levels come from `percentiles` so
+    /// seeded bootstrap stats keep their spread; others are interpolated over
+    /// the retained window.
+    pub fn percentile(&self, level: f64) -> i64 {
+        if let Some(value) = self.percentiles.get(level) {
+            return value;
         }
+        let mut sorted_times: Vec<i64> = self.recent_times.iter().copied().collect();
+        sorted_times.sort_unstable();
+        interpolated_percentile(&sorted_times, level)
+    }
+
+    /// Every level in `set` with its value, ascending by level
+    pub fn percentile_report(&self, set: &PercentileSet) -> Vec<(String, i64)> {
+        set.levels()
+            .into_iter()
+            .map(|level| (PercentileSet::label(level), self.percentile(level)))
+            .collect()
     }
 
     pub fn calculate_eta(&self) -> EtaPrediction {
+        self.calculate_eta_with(&TrendAdjustment::default(), &PercentileSet::default())
+    }
+
+    /// ETA from the median, extrapolated along the trend:
+    /// `expected = median + rate * confidence * horizon`, bounded to
+    /// `max_fraction` of the median so a noisy trend can't run away.
+    /// Bounds are the `percentiles` lower/upper levels.
+    /// Labeled `Cluster`; source-level callers relabel the method.
+    pub fn calculate_eta_with(
+        &self,
+        adjustment: &TrendAdjustment,
+        percentiles: &PercentileSet,
+    ) -> EtaPrediction {
         let base = self.median;
 
-        let adjusted = if self.trend.confidence > 0.7 {
-            match self.trend.direction {
-                TrendDirection::Improving => base * (1.0 - self.trend.rate.min(0.2)),
-                TrendDirection::Degrading => base * (1.0 + self.trend.rate.min(0.2)),
-                TrendDirection::Stable => base,
-            }
+        let adjusted = if adjustment.enabled {
+            // rate is relative; convert to seconds per execution
+            let slope = match self.trend.direction {
+                TrendDirection::Improving => -self.trend.rate * base,
+                TrendDirection::Degrading => self.trend.rate * base,
+                TrendDirection::Stable => 0.0,
+            };
+            let bound = base * adjustment.max_fraction.max(0.0);
+            let delta = (slope * self.trend.confidence * adjustment.horizon).clamp(-bound, bound);
+            base + delta
         } else {
             base
         };
@@ -220,8 +553,9 @@ impl ExecutionStats {
         EtaPrediction {
             expected_seconds: adjusted as i64,
             confidence: self.calculate_confidence(),
-            lower_bound: self.percentiles.p25,
-            upper_bound: self.percentiles.p75,
+            lower_bound: self.percentile(percentiles.lower_bound),
+            upper_bound: self.percentile(percentiles.upper_bound),
+            method: PredictionMethod::Cluster,
         }
     }
 
@@ -230,7 +564,8 @@ impl ExecutionStats {
             return 0.0;
         }
 
-        let sample_factor = (self.recent_times.len() as f64 / 20.0).min(1.0);
+        let full_sample = self.window_size.min(DEFAULT_WINDOW_SIZE) as f64;
+        let sample_factor = (self.recent_times.len() as f64 / full_sample).min(1.0);
         let consistency_factor = if self.mean > 0.0 {
             1.0 / (1.0 + (self.std_dev / self.mean).min(1.0))
         } else {
@@ -274,4 +609,252 @@ mod tests {
         assert!(confidence > 0.8);
         assert!(confidence <= 0.95);
     }
+
+    #[test]
+    fn test_window_bounds_recent_times() {
+        let mut stats = ExecutionStats::new_with_window(5);
+        for d in [100, 100, 100, 100, 100, 10, 11, 12, 13, 14] {
+            stats.update_with_duration(d);
+        }
+
+        assert_eq!(stats.recent_times.len(), 5);
+        assert_eq!(
+            stats.recent_times.iter().copied().collect::<Vec<_>>(),
+            vec![10, 11, 12, 13, 14]
+        );
+        // Percentiles only see the retained window
+        assert_eq!(stats.percentiles.p95, 14);
+        assert_eq!(stats.percentiles.p10, 10);
+
+        let default_stats = ExecutionStats::new();
+        assert_eq!(default_stats.window_size, DEFAULT_WINDOW_SIZE);
+    }
+
+    fn trending_stats(direction: TrendDirection, rate: f64, confidence: f64) -> ExecutionStats {
+        let mut stats = ExecutionStats::new();
+        stats.median = 100.0;
+        stats.trend = TrendAnalysis {
+            direction,
+            rate,
+            confidence,
+            regression: None,
+        };
+        stats
+    }
+
+    #[test]
+    fn test_trend_eta_increasing() {
+        let stats = trending_stats(TrendDirection::Degrading, 0.1, 0.9);
+        assert_eq!(stats.calculate_eta().expected_seconds, 109);
+
+        // Steep trend is bounded by max_fraction
+        let stats = trending_stats(TrendDirection::Degrading, 5.0, 0.95);
+        assert_eq!(stats.calculate_eta().expected_seconds, 120);
+    }
+
+    #[test]
+    fn test_trend_eta_decreasing() {
+        let stats = trending_stats(TrendDirection::Improving, 0.1, 0.5);
+        assert_eq!(stats.calculate_eta().expected_seconds, 95);
+
+        let stats = trending_stats(TrendDirection::Improving, 5.0, 0.95);
+        assert_eq!(stats.calculate_eta().expected_seconds, 80);
+    }
+
+    #[test]
+    fn test_regression_trend_increasing_series() {
+        let mut stats = ExecutionStats::new();
+        for i in 0..30 {
+            stats.update_with_duration(100 + 5 * i);
+        }
+        assert!(matches!(stats.trend.direction, TrendDirection::Degrading));
+        assert!(stats.trend.confidence > 0.99, "{}", stats.trend.confidence);
+
+        // Incremental sums over the sliding window match a fresh fit
+        let fresh = TrendRegression::fit(&stats.recent_times);
+        let incremental = stats.trend.regression.clone().unwrap();
+        assert_eq!(incremental, fresh);
+    }
+
+    #[test]
+    fn test_regression_trend_noisy_series_has_low_confidence() {
+        let mut stats = ExecutionStats::new();
+        for d in [
+            100, 120, 95, 115, 90, 125, 105, 98, 118, 92, 110, 101, 97, 121, 94, 108, 113, 99,
+            104, 116,
+        ] {
+            stats.update_with_duration(d);
+        }
+        assert!(stats.trend.confidence < 0.2, "{}", stats.trend.confidence);
+    }
+
+    #[test]
+    fn test_trend_survives_serialization_and_legacy_records_refit() {
+        let mut stats = ExecutionStats::new();
+        for i in 0..10 {
+            stats.update_with_duration(200 - 10 * i);
+        }
+        let json = serde_json::to_value(&stats).unwrap();
+        let mut reloaded: ExecutionStats = serde_json::from_value(json.clone()).unwrap();
+        reloaded.ensure_trend();
+        assert!(matches!(reloaded.trend.direction, TrendDirection::Improving));
+        assert_eq!(reloaded.trend.regression, stats.trend.regression);
+
+        // Records written before the regression was stored get a trend on load
+        let mut legacy = json;
+        legacy["trend"] = serde_json::json!({
+            "direction": "Stable",
+            "rate": 0.0,
+            "confidence": 0.0
+        });
+        let mut reloaded: ExecutionStats = serde_json::from_value(legacy).unwrap();
+        reloaded.ensure_trend();
+        assert!(matches!(reloaded.trend.direction, TrendDirection::Improving));
+        assert!(reloaded.trend.confidence > 0.99);
+    }
+
+    #[test]
+    fn test_trend_eta_flat_and_disabled() {
+        let stats = trending_stats(TrendDirection::Stable, 0.03, 0.8);
+        assert_eq!(stats.calculate_eta().expected_seconds, 100);
+
+        let stats = trending_stats(TrendDirection::Degrading, 0.1, 0.9);
+        let disabled = TrendAdjustment {
+            enabled: false,
+            ..TrendAdjustment::default()
+        };
+        assert_eq!(
+            stats
+                .calculate_eta_with(&disabled, &PercentileSet::default())
+                .expected_seconds,
+            100
+        );
+    }
+
+    #[test]
+    fn test_custom_percentile_set_for_known_distribution() {
+        let mut stats = ExecutionStats::new_with_window(100);
+        // 1..=100 in an order that keeps every point inside the anomaly band
+        for i in 0..100 {
+            stats.update_with_duration(1 + (i * 37) % 100);
+        }
+        assert_eq!(stats.recent_times.len(), 100);
+
+        let set = PercentileSet {
+            extra: vec![99.0, 5.0, 99.9],
+            lower_bound: 10.0,
+            upper_bound: 99.0,
+        };
+        let report = stats.percentile_report(&set);
+        let labels: Vec<&str> = report.iter().map(|(label, _)| label.as_str()).collect();
+        assert_eq!(
+            labels,
+            vec!["p5", "p10", "p25", "p50", "p75", "p90", "p95", "p99", "p99.9"]
+        );
+        let values: Vec<i64> = report.iter().map(|(_, value)| *value).collect();
+        assert_eq!(values, vec![6, 11, 26, 51, 75, 90, 95, 99, 100]);
+
+        let eta = stats.calculate_eta_with(&TrendAdjustment::default(), &set);
+        assert_eq!((eta.lower_bound, eta.upper_bound), (11, 99));
+
+        // Defaults keep the p25..p75 range
+        let eta = stats.calculate_eta();
+        assert_eq!((eta.lower_bound, eta.upper_bound), (26, 75));
+    }
+
+    #[test]
+    fn test_mad_filter_rejects_outlier_once_history_exists() {
+        let filter = OutlierFilter {
+            mad_multiplier: Some(3.0),
+            min_samples: 5,
+        };
+
+        // Cold start: 130 is 5 MADs out but there are too few samples to judge
+        let mut stats = ExecutionStats::new();
+        assert!(stats.update_with_duration_filtered(100, &filter));
+        assert!(stats.update_with_duration_filtered(110, &filter));
+        assert!(stats.update_with_duration_filtered(130, &filter));
+        assert_eq!(stats.recent_times.len(), 3);
+
+        let mut stats = ExecutionStats::new();
+        for d in [100, 104, 96, 102, 98, 101, 99, 103, 97] {
+            assert!(stats.update_with_duration_filtered(d, &filter));
+        }
+        assert_eq!((stats.median, stats.mad), (100.0, 2.0));
+
+        // 4.5 MADs out: passes the built-in check, rejected at k = 3
+        let mut unfiltered = stats.clone();
+        assert!(unfiltered.update_with_duration_filtered(109, &OutlierFilter::default()));
+        assert!(!stats.update_with_duration_filtered(109, &filter));
+        // A hung job hours long never reaches the stats
+        assert!(!stats.update_with_duration_filtered(4 * 3600, &filter));
+        assert_eq!(stats.recent_times.len(), 9);
+        assert_eq!(stats.median, 100.0);
+        assert!(stats.update_with_duration_filtered(104, &filter));
+        assert_eq!(stats.rejected_streak, 0);
+    }
+
+    #[test]
+    fn test_outlier_filter_follows_sustained_shift() {
+        let filter = OutlierFilter {
+            mad_multiplier: Some(3.0),
+            min_samples: 5,
+        };
+        let mut stats = ExecutionStats::new();
+        for d in [100, 104, 96, 102, 98, 101, 99, 103, 97, 100] {
+            assert!(stats.update_with_duration_filtered(d, &filter));
+        }
+
+        // Builds got permanently slower: the first few are still dropped
+        let kept: Vec<bool> = (0..40)
+            .map(|i| stats.update_with_duration_filtered(200 + i % 3, &filter))
+            .collect();
+        let dropped = OUTLIER_SHIFT_STREAK as usize - 1;
+        assert!(kept[..dropped].iter().all(|k| !k));
+        assert!(kept[dropped..].iter().all(|k| *k));
+        assert!(stats.median >= 200.0, "{}", stats.median);
+        assert_eq!(stats.rejected_streak, 0);
+    }
+
+    /// The indexing the stats used before interpolation, for comparison
+    fn nearest_rank_percentile(sorted: &[i64], level: f64) -> i64 {
+        let index = (sorted.len() as f64 * level / 100.0) as usize;
+        sorted[index.min(sorted.len() - 1)]
+    }
+
+    #[test]
+    fn test_interpolated_percentiles_for_small_samples() {
+        // Three samples: nearest rank pins p90 (and even p75) to the maximum
+        let sorted = [100, 200, 400];
+        assert_eq!(nearest_rank_percentile(&sorted, 75.0), 400);
+        assert_eq!(nearest_rank_percentile(&sorted, 90.0), 400);
+        assert_eq!(interpolated_percentile(&sorted, 10.0), 120);
+        assert_eq!(interpolated_percentile(&sorted, 25.0), 150);
+        assert_eq!(interpolated_percentile(&sorted, 50.0), 200);
+        assert_eq!(interpolated_percentile(&sorted, 75.0), 300);
+        assert_eq!(interpolated_percentile(&sorted, 90.0), 360);
+
+        // Two samples: nearest rank reports the upper sample as the median
+        let sorted = [60, 90];
+        assert_eq!(nearest_rank_percentile(&sorted, 50.0), 90);
+        assert_eq!(interpolated_percentile(&sorted, 50.0), 75);
+
+        // Uniform 0..=100: interpolation recovers the level exactly
+        let sorted: Vec<i64> = (0..=100).collect();
+        for level in [10.0, 25.0, 50.0, 75.0, 90.0, 95.0] {
+            assert_eq!(interpolated_percentile(&sorted, level), level as i64);
+        }
+
+        // Degenerate inputs
+        assert_eq!(interpolated_percentile(&[], 50.0), 0);
+        assert_eq!(interpolated_percentile(&[42], 90.0), 42);
+        assert_eq!(ExecutionStats::new().percentile(99.0), 0);
+
+        let mut stats = ExecutionStats::new();
+        for d in [100, 200, 400] {
+            stats.update_with_duration(d);
+        }
+        let eta = stats.calculate_eta();
+        assert_eq!((eta.lower_bound, eta.upper_bound), (150, 300));
+    }
 }
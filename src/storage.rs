@@ -1,7 +1,8 @@
-use crate::models::{Event, Race, RaceUpdate};
+use crate::config::DEFAULT_MAX_RACES;
+use crate::models::{Event, Race, RaceState, RaceUpdate};
 use crate::monitoring::MonitoringSystem;
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use tokio::sync::{broadcast, RwLock};
 
 #[derive(Debug, Clone)]
@@ -9,6 +10,35 @@ pub enum StorageEvent {
     Created(Race),
     Updated(Race),
     Deleted(String),
+    /// Dropped by storage itself rather than deleted by a client
+    Evicted {
+        id: String,
+        reason: EvictionReason,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EvictionReason {
+    /// `max_races` was reached and the oldest race (completed ones first)
+    /// made room for a new one
+    Capacity,
+}
+
+impl EvictionReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EvictionReason::Capacity => "capacity",
+        }
+    }
+}
+
+/// Result of an idempotent race creation
+#[derive(Debug, Clone)]
+pub enum CreateOutcome {
+    /// The race was stored (new id, or an existing non-terminal race was overwritten)
+    Stored(Race),
+    /// A race with this id already completed; it was left untouched
+    AlreadyCompleted(Race),
 }
 
 #[derive(Debug)]
@@ -17,13 +47,15 @@ pub struct Storage {
     event_sender: broadcast::Sender<StorageEvent>,
     max_races: usize,
     max_events_per_race: usize,
+    /// Publish `StorageEvent::Evicted` instead of a plain `Deleted` on eviction
+    eviction_events: bool,
+    /// Evictions since startup, by reason
+    evictions: Mutex<HashMap<EvictionReason, u64>>,
 }
 
 impl Storage {
     pub fn new() -> Self {
-        // CRITICAL: Increased from 1000 to 100,000 to support cluster rebuilding
-        // Cluster rebuilding needs extensive historic data
-        Self::with_config(100_000, 1000)
+        Self::with_config(DEFAULT_MAX_RACES, 1000)
     }
 
     pub fn with_config(max_races: usize, max_events_per_race: usize) -> Self {
@@ -44,32 +76,83 @@ impl Storage {
             event_sender,
             max_races,
             max_events_per_race,
+            eviction_events: true,
+            evictions: Mutex::new(HashMap::new()),
         }
     }
 
+    pub fn with_eviction_events(mut self, enabled: bool) -> Self {
+        self.eviction_events = enabled;
+        self
+    }
+
+    /// Evictions since startup, labeled by reason
+    pub fn eviction_counts(&self) -> HashMap<&'static str, u64> {
+        self.evictions
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(reason, count)| (reason.as_str(), *count))
+            .collect()
+    }
+
+    fn evict_locked(&self, races: &mut HashMap<String, Race>, id: &str, reason: EvictionReason) {
+        races.remove(id);
+        *self.evictions.lock().unwrap().entry(reason).or_insert(0) += 1;
+        let event = if self.eviction_events {
+            StorageEvent::Evicted {
+                id: id.to_string(),
+                reason,
+            }
+        } else {
+            StorageEvent::Deleted(id.to_string())
+        };
+        let _ = self.event_sender.send(event);
+        log::warn!(
+            "Evicted race {} ({}) due to storage limit. Data loss occurred!",
+            id,
+            reason.as_str()
+        );
+    }
+
     pub fn subscribe(&self) -> broadcast::Receiver<StorageEvent> {
         self.event_sender.subscribe()
     }
 
-    pub async fn create_or_update_race(&self, mut race: Race) -> Race {
+    pub async fn create_or_update_race(&self, race: Race) -> Race {
+        let mut races = self.races.write().await;
+        self.upsert_locked(&mut races, race)
+    }
+
+    /// Create a race, treating its id as an idempotency key: replaying a
+    /// create for a race that already reached a terminal state returns the
+    /// stored race instead of overwriting its duration and stats.
+    pub async fn create_race_idempotent(&self, race: Race) -> CreateOutcome {
         let mut races = self.races.write().await;
+        if let Some(existing) = races.get(&race.id) {
+            if existing.state.is_terminal() {
+                log::debug!("race_create replay ignored race_id={}", race.id);
+                return CreateOutcome::AlreadyCompleted(existing.clone());
+            }
+        }
+        CreateOutcome::Stored(self.upsert_locked(&mut races, race))
+    }
+
+    fn upsert_locked(&self, races: &mut HashMap<String, Race>, mut race: Race) -> Race {
 
         // Check if we need to make room
         if races.len() >= self.max_races && !races.contains_key(&race.id) {
             // Capacity reached: this is a recoverable operational condition
             log::warn!("storage_capacity_reached current_races={} action=evict_oldest", races.len());
 
-            // Find and remove the oldest race by started_at timestamp
-            if let Some((oldest_id, _)) = races.iter().min_by_key(|(_, r)| r.started_at) {
+            // Completed races are already persisted, so they make room before
+            // any live race does; within each group the oldest goes first
+            if let Some((oldest_id, _)) = races
+                .iter()
+                .min_by_key(|(_, r)| (!r.state.is_terminal(), r.started_at))
+            {
                 let oldest_id = oldest_id.clone();
-                races.remove(&oldest_id);
-                let _ = self
-                    .event_sender
-                    .send(StorageEvent::Deleted(oldest_id.clone()));
-                log::warn!(
-                    "Evicted race {} due to storage limit. Data loss occurred!",
-                    oldest_id
-                );
+                self.evict_locked(races, &oldest_id, EvictionReason::Capacity);
             }
         }
 
@@ -117,9 +200,23 @@ impl Storage {
     }
 
     pub async fn update_race(&self, id: &str, update: RaceUpdate) -> Option<Race> {
+        self.update_race_with_previous(id, update)
+            .await
+            .map(|(_, race)| race)
+    }
+
+    /// Like `update_race`, also returning the state the race had before this
+    /// update, read under the same lock so concurrent updates can't both see
+    /// themselves as the transition
+    pub async fn update_race_with_previous(
+        &self,
+        id: &str,
+        update: RaceUpdate,
+    ) -> Option<(RaceState, Race)> {
         let mut races = self.races.write().await;
 
         if let Some(race) = races.get_mut(id) {
+            let previous = race.state.clone();
             race.apply_update(update);
             let updated = race.clone();
             let _ = self
@@ -131,7 +228,7 @@ impl Storage {
                 updated.state
             );
 
-            Some(updated)
+            Some((previous, updated))
         } else {
             None
         }
@@ -141,19 +238,32 @@ impl Storage {
         let mut races = self.races.write().await;
 
         if let Some(race) = races.get_mut(id) {
-            // Limit events
-            if let Some(ref events) = race.events {
-                if events.len() >= self.max_events_per_race {
-                    log::info!(
-                        "event_cap_reached race_id={} max_events={}",
-                        id,
-                        self.max_events_per_race
-                    );
-                    return Some(race.clone());
+            race.add_event(event);
+
+            // Limit events: evict oldest (FIFO) and record how many were dropped
+            let mut dropped = 0;
+            if let Some(ref mut events) = race.events {
+                if events.len() > self.max_events_per_race {
+                    dropped = events.len() - self.max_events_per_race;
+                    events.drain(..dropped);
                 }
             }
+            if dropped > 0 {
+                let metadata = race.metadata.get_or_insert_with(HashMap::new);
+                let total = metadata
+                    .get("events_truncated")
+                    .and_then(|v| v.parse::<usize>().ok())
+                    .unwrap_or(0)
+                    + dropped;
+                metadata.insert("events_truncated".to_string(), total.to_string());
+                log::debug!(
+                    "event_cap_reached race_id={} max_events={} events_truncated={}",
+                    id,
+                    self.max_events_per_race,
+                    total
+                );
+            }
 
-            race.add_event(event);
             let updated = race.clone();
             let _ = self
                 .event_sender
@@ -1,6 +1,6 @@
 #[cfg(test)]
 mod tests {
-    use crate::models::{Event, Race, RaceState};
+    use crate::models::{EtaSource, Event, Race, RaceState};
     use crate::storage::Storage;
 
     #[tokio::test]
@@ -68,6 +68,283 @@ mod tests {
         assert_eq!(updated_race.events.unwrap().len(), 1);
     }
 
+    #[tokio::test]
+    async fn test_storage_add_event_evicts_oldest_past_cap() {
+        let storage = Storage::with_config(100, 3);
+        let race = Race::new("test".to_string(), "Test Race".to_string());
+        let created = storage.create_or_update_race(race).await;
+
+        for i in 0..5 {
+            let event = Event::new(format!("event_{}", i), None);
+            storage.add_event_to_race(&created.id, event).await;
+        }
+
+        let race = storage.get_race(&created.id).await.unwrap();
+        let types: Vec<String> = race
+            .events
+            .unwrap()
+            .into_iter()
+            .map(|e| e.event_type)
+            .collect();
+        assert_eq!(types, vec!["event_2", "event_3", "event_4"]);
+        assert_eq!(
+            race.metadata.unwrap().get("events_truncated"),
+            Some(&"2".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_small_configured_race_cap_is_honored() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let file = temp_dir.path().join("config.toml");
+        std::fs::write(&file, "[storage]\nmax_races = 5\n").unwrap();
+        let settings = crate::config::Settings::load(file.to_str().unwrap()).unwrap();
+        assert_eq!(settings.storage.active_race_capacity(), 5);
+
+        let storage = Storage::with_config(settings.storage.active_race_capacity(), 10);
+        for i in 0..8 {
+            let mut race = Race::new("cargo".to_string(), format!("build {}", i));
+            race.id = format!("race-{}", i);
+            race.started_at = chrono::Utc::now() + chrono::Duration::seconds(i);
+            storage.create_or_update_race(race).await;
+        }
+        assert_eq!(storage.get_all_races().await.len(), 5);
+        assert!(storage.get_race("race-7").await.is_some());
+        assert!(storage.get_race("race-0").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_live_race_survives_cap_filled_with_completed_races() {
+        let storage = Storage::with_config(3, 10);
+        let start = chrono::Utc::now();
+        let mut live = Race::new("cargo".to_string(), "long build".to_string());
+        live.id = "live".to_string();
+        live.started_at = start;
+        storage.create_or_update_race(live).await;
+        for i in 1..6 {
+            let mut race = Race::new("cargo".to_string(), format!("build {}", i));
+            race.id = format!("done-{}", i);
+            race.state = RaceState::Passed;
+            race.started_at = start + chrono::Duration::seconds(i);
+            storage.create_or_update_race(race).await;
+        }
+
+        assert_eq!(storage.get_all_races().await.len(), 3);
+        assert!(storage.get_race("live").await.is_some());
+        assert!(storage.get_race("done-5").await.is_some());
+        assert!(storage.get_race("done-1").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_inserting_past_capacity_emits_evicted_events() {
+        use crate::storage::{EvictionReason, StorageEvent};
+
+        let storage = Storage::with_config(2, 10);
+        let mut events = storage.subscribe();
+        for i in 0..4 {
+            let mut race = Race::new("cargo".to_string(), format!("build {}", i));
+            race.id = format!("race-{}", i);
+            race.started_at = chrono::Utc::now() + chrono::Duration::seconds(i);
+            storage.create_or_update_race(race).await;
+        }
+
+        let mut evicted = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            if let StorageEvent::Evicted { id, reason } = event {
+                assert_eq!(reason, EvictionReason::Capacity);
+                evicted.push(id);
+            }
+        }
+        assert_eq!(evicted, vec!["race-0", "race-1"]);
+        assert_eq!(storage.eviction_counts()["capacity"], 2);
+        assert_eq!(storage.get_all_races().await.len(), 2);
+
+        // Disabled, clients see the eviction as a plain delete
+        let storage = Storage::with_config(1, 10).with_eviction_events(false);
+        let mut events = storage.subscribe();
+        storage
+            .create_or_update_race(Race::new("cargo".to_string(), "a".to_string()))
+            .await;
+        storage
+            .create_or_update_race(Race::new("cargo".to_string(), "b".to_string()))
+            .await;
+        let deleted = std::iter::from_fn(|| events.try_recv().ok())
+            .filter(|event| matches!(event, StorageEvent::Deleted(_)))
+            .count();
+        assert_eq!(deleted, 1);
+        assert_eq!(storage.eviction_counts()["capacity"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_replayed_create_does_not_overwrite_completed_race() {
+        use crate::storage::CreateOutcome;
+
+        let storage = Storage::new();
+        let mut race = Race::new("gitlab".to_string(), "Pipeline".to_string());
+        race.id = "gitlab-42".to_string();
+        storage.create_or_update_race(race.clone()).await;
+
+        let completion = crate::models::RaceUpdate {
+            source: None,
+            title: None,
+            state: Some(RaceState::Passed),
+            started_at: None,
+            eta_sec: None,
+            progress: Some(100),
+            deeplink: None,
+            metadata: None,
+            eta_source: None,
+            eta_confidence: None,
+            update_interval_hint: None,
+        };
+        let completed = storage.update_race("gitlab-42", completion).await.unwrap();
+        assert!(completed.duration_sec.is_some());
+
+        // Adapter restarts and replays its create with stale state
+        let mut replay = race.clone();
+        replay.title = "Pipeline (replayed)".to_string();
+        match storage.create_race_idempotent(replay).await {
+            CreateOutcome::AlreadyCompleted(existing) => {
+                assert_eq!(existing.title, "Pipeline");
+                assert_eq!(existing.duration_sec, completed.duration_sec);
+            }
+            CreateOutcome::Stored(_) => panic!("completed race was overwritten"),
+        }
+
+        let stored = storage.get_race("gitlab-42").await.unwrap();
+        assert!(matches!(stored.state, RaceState::Passed));
+        assert_eq!(stored.completed_at, completed.completed_at);
+    }
+
+    #[tokio::test]
+    async fn test_create_for_running_race_still_updates() {
+        use crate::storage::CreateOutcome;
+
+        let storage = Storage::new();
+        let mut race = Race::new("gitlab".to_string(), "Pipeline".to_string());
+        race.id = "gitlab-43".to_string();
+        storage.create_or_update_race(race.clone()).await;
+
+        race.progress = Some(40);
+        match storage.create_race_idempotent(race).await {
+            CreateOutcome::Stored(stored) => assert_eq!(stored.progress, Some(40)),
+            CreateOutcome::AlreadyCompleted(_) => panic!("running race treated as completed"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_grpc_replayed_create_returns_existing_race() {
+        use crate::adapter_status::AdapterRegistry;
+        use crate::grpc_service::raceboard::race_service_server::RaceService;
+        use crate::grpc_service::raceboard::{Race as ProtoRace, RaceState as ProtoRaceState};
+        use crate::grpc_service::RaceServiceImpl;
+        use crate::persistence::PersistenceLayer;
+        use std::collections::HashMap;
+        use std::sync::Arc;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let persistence =
+            Arc::new(PersistenceLayer::new(Some(temp_dir.path().join("test.db"))).unwrap());
+        let service = RaceServiceImpl::new(
+            Arc::new(Storage::new()),
+            persistence,
+            Arc::new(AdapterRegistry::new()),
+            false,
+            HashMap::new(),
+        );
+
+        let completed = ProtoRace {
+            id: "ci-7".to_string(),
+            source: "ci".to_string(),
+            title: "Build".to_string(),
+            state: ProtoRaceState::Passed as i32,
+            eta_sec: Some(120),
+            ..Default::default()
+        };
+        let first = service
+            .create_race(tonic::Request::new(completed.clone()))
+            .await
+            .unwrap();
+        assert!(first.metadata().get("x-raceboard-existing").is_none());
+
+        let replay = ProtoRace {
+            title: "Build (replayed)".to_string(),
+            eta_sec: Some(5),
+            ..completed
+        };
+        let second = service.create_race(tonic::Request::new(replay)).await.unwrap();
+        assert_eq!(
+            second.metadata().get("x-raceboard-existing").unwrap(),
+            "1"
+        );
+        let race = second.into_inner();
+        assert_eq!(race.title, "Build");
+        assert_eq!(race.eta_sec, Some(120));
+    }
+
+    #[tokio::test]
+    async fn test_grpc_completed_update_posts_webhook_once() {
+        use crate::adapter_status::AdapterRegistry;
+        use crate::grpc_service::raceboard::race_service_server::RaceService;
+        use crate::grpc_service::raceboard::{RaceState as ProtoRaceState, UpdateRaceRequest};
+        use crate::grpc_service::RaceServiceImpl;
+        use crate::persistence::PersistenceLayer;
+        use crate::webhook::WebhookDispatcher;
+        use std::collections::HashMap;
+        use std::sync::Arc;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let config = crate::config::WebhooksConfig {
+            url: Some(format!("{}/webhook/grpc-completed", mockito::server_url())),
+            ..Default::default()
+        };
+        let webhooks = Arc::new(WebhookDispatcher::from_config(&config, temp_dir.path()).unwrap());
+        let storage = Arc::new(Storage::new());
+        let service = RaceServiceImpl::new(
+            storage.clone(),
+            Arc::new(PersistenceLayer::new(Some(temp_dir.path().join("test.db"))).unwrap()),
+            Arc::new(AdapterRegistry::new()),
+            false,
+            HashMap::new(),
+        )
+        .with_webhooks(Some(webhooks.clone()));
+
+        let mut race = Race::new("cargo".to_string(), "cargo build".to_string());
+        race.id = "grpc-hooked".to_string();
+        storage.create_or_update_race(race).await;
+        // Completing twice announces the transition once
+        for _ in 0..2 {
+            let update = UpdateRaceRequest {
+                id: "grpc-hooked".to_string(),
+                state: Some(ProtoRaceState::Passed as i32),
+                ..Default::default()
+            };
+            service
+                .update_race(tonic::Request::new(update))
+                .await
+                .unwrap();
+        }
+
+        let receiver = mockito::mock("POST", "/webhook/grpc-completed")
+            .with_status(204)
+            .expect(1)
+            .create();
+        let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel();
+        let task = tokio::spawn(webhooks.clone().run(shutdown_rx));
+        for _ in 0..100 {
+            if webhooks.delivered_count() > 0 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        shutdown_tx.send(()).unwrap();
+        task.await.unwrap();
+
+        receiver.assert();
+        assert_eq!(webhooks.delivered_count(), 1);
+        assert_eq!(webhooks.dead_letter_count(), 0);
+    }
+
     #[tokio::test]
     async fn test_storage_get_all_races() {
         let storage = Storage::new();
@@ -94,4 +371,105 @@ mod tests {
         let retrieved = storage.get_race(&created.id).await;
         assert!(retrieved.is_none());
     }
+
+    #[tokio::test]
+    async fn test_source_normalization_shares_cluster_bucket() {
+        use crate::cluster::ClusteringEngine;
+        use crate::models::normalize_source;
+        use std::collections::HashMap;
+
+        let mut aliases = HashMap::new();
+        aliases.insert("GH".to_string(), "github".to_string());
+
+        assert_eq!(normalize_source("  GitHub ", &aliases), "github");
+        assert_eq!(normalize_source("github", &aliases), "github");
+        assert_eq!(normalize_source("gh", &aliases), "github");
+        assert_eq!(normalize_source("GitLab", &aliases), "gitlab");
+
+        let engine = ClusteringEngine::new(100);
+        let metadata = HashMap::new();
+        let first_source = normalize_source("GitHub", &aliases);
+        let second_source = normalize_source("github", &aliases);
+        let first = engine
+            .assign_race_to_cluster("r1", "CI pipeline", &first_source, &metadata)
+            .await;
+        let second = engine
+            .assign_race_to_cluster("r2", "CI pipeline", &second_source, &metadata)
+            .await;
+        assert_eq!(first, second);
+        assert_eq!(engine.clusters.read().await.len(), 1);
+    }
+
+    #[test]
+    fn test_eta_source_serializes_as_proto_integers() {
+        for (source, value) in [
+            (EtaSource::Unspecified, 0),
+            (EtaSource::Exact, 1),
+            (EtaSource::Adapter, 2),
+            (EtaSource::Cluster, 3),
+            (EtaSource::Bootstrap, 4),
+        ] {
+            assert_eq!(serde_json::to_value(source).unwrap(), serde_json::json!(value));
+            assert_eq!(
+                serde_json::from_value::<EtaSource>(serde_json::json!(value)).unwrap(),
+                source
+            );
+            assert_eq!(i32::from(source), value);
+        }
+
+        let mut race = Race::new("google-calendar".to_string(), "Standup".to_string());
+        race.eta_sec = Some(900);
+        race.infer_eta_source();
+        race.infer_eta_confidence();
+        let json = serde_json::to_value(&race).unwrap();
+        assert_eq!(json["eta_source"], serde_json::json!(1));
+        assert_eq!(json["eta_confidence"], serde_json::json!(1.0));
+    }
+
+    #[tokio::test]
+    async fn test_grpc_health_check_tracks_serving_status() {
+        use crate::grpc_service::{report_health, set_health_status};
+        use crate::monitoring::MonitoringSystem;
+        use tonic_health::pb::health_check_response::ServingStatus as ProtoStatus;
+        use tonic_health::pb::health_client::HealthClient;
+        use tonic_health::pb::HealthCheckRequest;
+        use tonic_health::ServingStatus;
+
+        let (mut reporter, health_service) = tonic_health::server::health_reporter();
+        report_health(&mut reporter, &MonitoringSystem::new(100)).await;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let incoming = Box::pin(futures::stream::unfold(listener, |listener| async move {
+            let conn = listener.accept().await.map(|(stream, _)| stream);
+            Some((conn, listener))
+        }));
+        let server = tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(health_service)
+                .serve_with_incoming(incoming),
+        );
+
+        let channel = tonic::transport::Endpoint::from_shared(format!("http://{}", addr))
+            .unwrap()
+            .connect()
+            .await
+            .unwrap();
+        let mut client = HealthClient::new(channel);
+        let check = |service: &str| HealthCheckRequest {
+            service: service.to_string(),
+        };
+
+        let status = client.check(check("raceboard.RaceService")).await.unwrap();
+        assert_eq!(status.into_inner().status, ProtoStatus::Serving as i32);
+        let status = client.check(check("")).await.unwrap();
+        assert_eq!(status.into_inner().status, ProtoStatus::Serving as i32);
+
+        // Shutdown flips both the server-wide and RaceService entries
+        set_health_status(&mut reporter, ServingStatus::NotServing).await;
+        let status = client.check(check("raceboard.RaceService")).await.unwrap();
+        assert_eq!(status.into_inner().status, ProtoStatus::NotServing as i32);
+
+        server.abort();
+    }
 }
@@ -0,0 +1,361 @@
+use crate::config::WebhooksConfig;
+use crate::models::Race;
+use anyhow::Result;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Event name sent for races that reached passed/failed/canceled
+pub const RACE_COMPLETED_EVENT: &str = "race.completed";
+
+/// Hex HMAC-SHA256 of the body, present when `webhooks.secret` is set
+pub const SIGNATURE_HEADER: &str = "X-Raceboard-Signature";
+pub const EVENT_HEADER: &str = "X-Raceboard-Event";
+/// Same for every attempt of one event, so receivers can drop retried duplicates
+pub const DELIVERY_HEADER: &str = "X-Raceboard-Delivery";
+
+/// An event waiting for delivery; serialized once so every retry sends (and
+/// signs) identical bytes
+#[derive(Debug, Clone)]
+struct Delivery {
+    id: String,
+    race_id: String,
+    body: Vec<u8>,
+}
+
+/// Delivers race lifecycle events to the configured outbound webhook.
+/// `notify_completed` only enqueues, so the update path never waits on the receiver;
+/// `run` POSTs up to `max_in_flight` events at once with exponential backoff and
+/// appends events it gives up on to a dead-letter log (`webhook_dead_letter.jsonl`)
+/// from the blocking pool.
+pub struct WebhookDispatcher {
+    url: String,
+    config: WebhooksConfig,
+    client: reqwest::Client,
+    queue: mpsc::Sender<Delivery>,
+    /// Taken by `run`
+    receiver: Mutex<Option<mpsc::Receiver<Delivery>>>,
+    dead_letter_path: PathBuf,
+    delivered: AtomicU64,
+    dead_lettered: AtomicU64,
+}
+
+impl WebhookDispatcher {
+    /// `None` when no `webhooks.url` is configured
+    pub fn from_config(config: &WebhooksConfig, data_dir: &Path) -> Option<Self> {
+        let url = config.url.clone().filter(|url| !url.trim().is_empty())?;
+        let (queue, receiver) = mpsc::channel(config.queue_size.max(1));
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs.max(1)))
+            .build()
+            .unwrap_or_default();
+        Some(Self {
+            url,
+            config: config.clone(),
+            client,
+            queue,
+            receiver: Mutex::new(Some(receiver)),
+            dead_letter_path: data_dir.join("webhook_dead_letter.jsonl"),
+            delivered: AtomicU64::new(0),
+            dead_lettered: AtomicU64::new(0),
+        })
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn delivered_count(&self) -> u64 {
+        self.delivered.load(Ordering::Relaxed)
+    }
+
+    pub fn dead_letter_count(&self) -> u64 {
+        self.dead_lettered.load(Ordering::Relaxed)
+    }
+
+    pub fn dead_letter_path(&self) -> &Path {
+        &self.dead_letter_path
+    }
+
+    /// JSON body POSTed for a completed race
+    pub fn payload(race: &Race) -> Vec<u8> {
+        serde_json::to_vec(&serde_json::json!({
+            "event": RACE_COMPLETED_EVENT,
+            "race": race,
+        }))
+        .unwrap_or_default()
+    }
+
+    /// Queue `race`'s completion unless its source is filtered out. Never
+    /// blocks: with the queue full the event is dead-lettered in the background
+    /// instead. Must be called from within the Tokio runtime.
+    pub fn notify_completed(&self, race: &Race) {
+        if !self.config.delivers(&race.source) {
+            return;
+        }
+        let delivery = Delivery {
+            id: uuid::Uuid::new_v4().to_string(),
+            race_id: race.id.clone(),
+            body: Self::payload(race),
+        };
+        if let Err(e) = self.queue.try_send(delivery) {
+            let (delivery, reason) = match e {
+                mpsc::error::TrySendError::Full(d) => (d, "delivery queue full"),
+                mpsc::error::TrySendError::Closed(d) => (d, "dispatcher stopped"),
+            };
+            // Detached: the write finishes on the blocking pool
+            drop(self.dead_letter(&delivery, 0, reason));
+        }
+    }
+
+    /// Deliver queued events until `shutdown` fires; events still queued or
+    /// in flight then are dead-lettered rather than dropped.
+    pub async fn run(self: Arc<Self>, mut shutdown: tokio::sync::oneshot::Receiver<()>) {
+        let Some(mut receiver) = self.receiver.lock().unwrap().take() else {
+            log::error!("Webhook dispatcher is already running");
+            return;
+        };
+        let max_in_flight = self.config.max_in_flight.max(1);
+        let mut in_flight = tokio::task::JoinSet::new();
+        let (cancel_tx, cancel_rx) = tokio::sync::watch::channel(false);
+        loop {
+            let delivery = tokio::select! {
+                next = receiver.recv(), if in_flight.len() < max_in_flight => match next {
+                    Some(delivery) => delivery,
+                    None => break,
+                },
+                Some(_) = in_flight.join_next() => continue,
+                _ = &mut shutdown => break,
+            };
+            let dispatcher = self.clone();
+            let mut cancel = cancel_rx.clone();
+            in_flight.spawn(async move {
+                tokio::select! {
+                    _ = dispatcher.deliver(&delivery) => {}
+                    _ = cancel.changed() => {
+                        let _ = dispatcher
+                            .dead_letter(&delivery, 0, "server shutting down")
+                            .await;
+                    }
+                }
+            });
+        }
+
+        let _ = cancel_tx.send(true);
+        while in_flight.join_next().await.is_some() {}
+        receiver.close();
+        while let Ok(delivery) = receiver.try_recv() {
+            let _ = self.dead_letter(&delivery, 0, "server shutting down").await;
+        }
+        log::info!(
+            "Webhook dispatcher stopped: {} delivered, {} dead-lettered",
+            self.delivered_count(),
+            self.dead_letter_count()
+        );
+    }
+
+    async fn deliver(&self, delivery: &Delivery) {
+        let max_attempts = self.config.max_attempts.max(1);
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let error = match self.post(delivery).await {
+                Ok(()) => {
+                    self.delivered.fetch_add(1, Ordering::Relaxed);
+                    return;
+                }
+                Err(e) => e,
+            };
+            if !error.retryable || attempt >= max_attempts {
+                let _ = self.dead_letter(delivery, attempt, &error.message).await;
+                return;
+            }
+            let delay = self.config.backoff(attempt - 1);
+            log::warn!(
+                "Webhook delivery for race {} failed: {}. Retrying in {:?} ({}/{})",
+                delivery.race_id,
+                error.message,
+                delay,
+                attempt,
+                max_attempts
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    async fn post(&self, delivery: &Delivery) -> std::result::Result<(), DeliveryError> {
+        let mut request = self
+            .client
+            .post(&self.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(EVENT_HEADER, RACE_COMPLETED_EVENT)
+            .header(DELIVERY_HEADER, &delivery.id)
+            .body(delivery.body.clone());
+        if let Some(secret) = self.config.secret.as_deref().filter(|s| !s.is_empty()) {
+            request = request.header(SIGNATURE_HEADER, sign(secret, &delivery.body));
+        }
+
+        match request.send().await {
+            Ok(response) if response.status().is_success() => Ok(()),
+            Ok(response) => {
+                let status = response.status();
+                Err(DeliveryError {
+                    message: format!("status {}", status),
+                    // Other 4xx mean the receiver rejected the event itself
+                    retryable: status.is_server_error()
+                        || status == reqwest::StatusCode::TOO_MANY_REQUESTS,
+                })
+            }
+            Err(e) => Err(DeliveryError {
+                message: e.to_string(),
+                retryable: true,
+            }),
+        }
+    }
+
+    /// Count and log the event, appending it to the dead-letter log on the
+    /// blocking pool; await the handle to know the line is written
+    fn dead_letter(
+        &self,
+        delivery: &Delivery,
+        attempts: u32,
+        reason: &str,
+    ) -> tokio::task::JoinHandle<()> {
+        self.dead_lettered.fetch_add(1, Ordering::Relaxed);
+        log::error!(
+            "Webhook delivery for race {} dead-lettered after {} attempts: {}",
+            delivery.race_id,
+            attempts,
+            reason
+        );
+        let path = self.dead_letter_path.clone();
+        let delivery = delivery.clone();
+        let reason = reason.to_string();
+        tokio::task::spawn_blocking(move || {
+            if let Err(e) = append_dead_letter(&path, &delivery, attempts, &reason) {
+                log::error!("Failed to write webhook dead letter to {:?}: {}", path, e);
+            }
+        })
+    }
+}
+
+struct DeliveryError {
+    message: String,
+    retryable: bool,
+}
+
+/// One line per event, written in a single call so concurrent appends
+/// never interleave
+fn append_dead_letter(path: &Path, delivery: &Delivery, attempts: u32, reason: &str) -> Result<()> {
+    let payload: serde_json::Value = serde_json::from_slice(&delivery.body)?;
+    let entry = serde_json::json!({
+        "delivery_id": delivery.id,
+        "race_id": delivery.race_id,
+        "failed_at": Utc::now(),
+        "attempts": attempts,
+        "reason": reason,
+        "payload": payload,
+    });
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    file.write_all(format!("{}\n", entry).as_bytes())?;
+    Ok(())
+}
+
+/// Hex HMAC-SHA256 of `body`, the scheme the inbound GitLab webhook verifies
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::RaceState;
+
+    #[tokio::test]
+    async fn test_full_queue_dead_letters_without_blocking() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let config = WebhooksConfig {
+            url: Some("http://127.0.0.1:9/unused".to_string()),
+            queue_size: 1,
+            ..WebhooksConfig::default()
+        };
+        // Not running, so the second event finds the queue full
+        let dispatcher = WebhookDispatcher::from_config(&config, temp_dir.path()).unwrap();
+        let first = Race::new("cargo".to_string(), "build".to_string());
+        let second = Race::new("cargo".to_string(), "test".to_string());
+        dispatcher.notify_completed(&first);
+        dispatcher.notify_completed(&second);
+        assert_eq!(dispatcher.dead_letter_count(), 1);
+
+        let mut log = String::new();
+        for _ in 0..100 {
+            log = std::fs::read_to_string(dispatcher.dead_letter_path()).unwrap_or_default();
+            if !log.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let entry: serde_json::Value = serde_json::from_str(log.trim()).unwrap();
+        assert_eq!(entry["race_id"], second.id.as_str());
+        assert_eq!(entry["reason"], "delivery queue full");
+    }
+
+    #[tokio::test]
+    async fn test_rejected_delivery_is_dead_lettered() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let _mock = mockito::mock("POST", "/webhook/rejecting")
+            .with_status(400)
+            .expect(1)
+            .create();
+        let config = WebhooksConfig {
+            url: Some(format!("{}/webhook/rejecting", mockito::server_url())),
+            sources: vec!["cargo".to_string()],
+            ..WebhooksConfig::default()
+        };
+        let dispatcher =
+            Arc::new(WebhookDispatcher::from_config(&config, temp_dir.path()).unwrap());
+        let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel();
+        let task = tokio::spawn(dispatcher.clone().run(shutdown_rx));
+
+        let mut filtered = Race::new("npm".to_string(), "install".to_string());
+        filtered.state = RaceState::Passed;
+        dispatcher.notify_completed(&filtered);
+        let mut race = Race::new("cargo".to_string(), "build".to_string());
+        race.state = RaceState::Failed;
+        dispatcher.notify_completed(&race);
+
+        for _ in 0..100 {
+            if dispatcher.dead_letter_count() > 0 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        shutdown_tx.send(()).unwrap();
+        task.await.unwrap();
+
+        // A 400 is not retried, and the filtered source never reached the queue
+        _mock.assert();
+        assert_eq!(dispatcher.delivered_count(), 0);
+        assert_eq!(dispatcher.dead_letter_count(), 1);
+        let log = std::fs::read_to_string(dispatcher.dead_letter_path()).unwrap();
+        let entry: serde_json::Value = serde_json::from_str(log.trim()).unwrap();
+        assert_eq!(entry["race_id"], race.id.as_str());
+        assert_eq!(entry["attempts"], 1);
+        assert_eq!(entry["payload"]["race"]["state"], "failed");
+    }
+}
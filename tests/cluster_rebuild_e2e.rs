@@ -1,15 +1,15 @@
 use chrono::{Duration, Utc};
 use std::collections::HashMap;
 use RaceboardServer::{
-    cluster::RaceCluster,
+    cluster::{count_titles, PredictionAccuracy, RaceCluster},
     hnsw_dbscan::{HnswDBSCAN, ValidationCriteria, ValidationMetrics, ValidationResult},
     models::{Race, RaceState},
     phased_rollout::{PhasedRollout, RolloutConfig, RolloutPhase},
     rebuild::{
-        adjusted_rand_index, assign_noise_as_border, calculate_average_cohesion,
-        calculate_noise_ratio, custom_distance, detect_knee_kneedle, map_stable_ids,
-        race_to_vector, silhouette_sampled, ClusterId, CorePointIndex, Curve, DBSCANResult,
-        Direction, MappingThresholds, RaceId, SourceConfig,
+        adjusted_rand_index, assign_noise_as_border, calculate_cohesion, calculate_noise_ratio,
+        custom_distance, detect_knee_kneedle, map_stable_ids, race_to_vector, silhouette_sampled,
+        ClusterId, CorePointIndex, Curve, DBSCANResult, Direction, MappingThresholds, NoisePolicy,
+        RaceId, SourceConfig,
     },
     stats::ExecutionStats,
 };
@@ -76,6 +76,13 @@ async fn test_hnsw_dbscan_clustering() {
         tau_merge_lo: 0.35,
         tau_merge_hi: 0.6,
         last_eps: None,
+        stats_window: 20,
+        default_eta_sec: None,
+        noise_policy: NoisePolicy::default(),
+        recency_half_life_days: None,
+        metadata_key_weights: HashMap::new(),
+        min_samples_for_prediction: 1,
+        rebuild_interval_hours: None,
     };
 
     let races = create_test_races();
@@ -133,6 +140,13 @@ async fn test_validation_metrics() {
         tau_merge_lo: 0.35,
         tau_merge_hi: 0.6,
         last_eps: None,
+        stats_window: 20,
+        default_eta_sec: None,
+        noise_policy: NoisePolicy::default(),
+        recency_half_life_days: None,
+        metadata_key_weights: HashMap::new(),
+        min_samples_for_prediction: 1,
+        rebuild_interval_hours: None,
     };
 
     let races = create_test_races();
@@ -155,12 +169,15 @@ async fn test_validation_metrics() {
             representative_metadata: HashMap::new(),
             stats: stats1,
             member_race_ids: vec!["r1".to_string(), "r2".to_string(), "r3".to_string()],
-            member_titles: vec![
+            member_titles: count_titles(vec![
                 "cargo build".to_string(),
                 "cargo build --release".to_string(),
                 "cargo build --features foo".to_string(),
-            ],
+            ]),
             member_metadata_history: vec![],
+            quality_score: None,
+            prediction_accuracy: PredictionAccuracy::default(),
+            latest_member_at: None,
             last_updated: Utc::now(),
             last_accessed: Utc::now(),
         },
@@ -181,12 +198,15 @@ async fn test_validation_metrics() {
             representative_metadata: HashMap::new(),
             stats: stats2,
             member_race_ids: vec!["r6".to_string(), "r7".to_string(), "r8".to_string()],
-            member_titles: vec![
+            member_titles: count_titles(vec![
                 "cargo test".to_string(),
                 "cargo test --all".to_string(),
                 "cargo test integration".to_string(),
-            ],
+            ]),
             member_metadata_history: vec![],
+            quality_score: None,
+            prediction_accuracy: PredictionAccuracy::default(),
+            latest_member_at: None,
             last_updated: Utc::now(),
             last_accessed: Utc::now(),
         },
@@ -198,7 +218,7 @@ async fn test_validation_metrics() {
     assert!(noise_ratio < 0.5, "Noise ratio too high");
 
     // Test average cohesion
-    let cohesion = calculate_average_cohesion(&clusters);
+    let cohesion = calculate_cohesion(&clusters, |_| config.clone());
     println!("Average Cohesion: {:.3}", cohesion);
     assert!(cohesion > 0.5, "Cohesion too low");
 
@@ -228,7 +248,7 @@ async fn test_knee_detection() {
     let knee = detect_knee_kneedle(&k_distances, Curve::Convex, Direction::Increasing, 1.0);
 
     assert!(knee.is_some(), "Failed to detect knee");
-    let knee_value = knee.unwrap();
+    let knee_value = knee.unwrap().eps;
     println!("Detected knee at: {:.2}", knee_value);
     assert!(
         knee_value > 0.2 && knee_value < 0.6,
@@ -251,12 +271,15 @@ async fn test_stable_id_mapping() {
             representative_metadata: HashMap::new(),
             stats: ExecutionStats::default(),
             member_race_ids: vec!["r1".to_string(), "r2".to_string(), "r3".to_string()],
-            member_titles: vec![
+            member_titles: count_titles(vec![
                 "cargo build".to_string(),
                 "cargo build --release".to_string(),
                 "cargo build --features foo".to_string(),
-            ],
+            ]),
             member_metadata_history: vec![],
+            quality_score: None,
+            prediction_accuracy: PredictionAccuracy::default(),
+            latest_member_at: None,
             last_updated: Utc::now(),
             last_accessed: Utc::now(),
         },
@@ -273,12 +296,15 @@ async fn test_stable_id_mapping() {
             representative_metadata: HashMap::new(),
             stats: ExecutionStats::default(),
             member_race_ids: vec!["r1".to_string(), "r2".to_string(), "r4".to_string()],
-            member_titles: vec![
+            member_titles: count_titles(vec![
                 "cargo build".to_string(),
                 "cargo build --release".to_string(),
                 "cargo build".to_string(),
-            ],
+            ]),
             member_metadata_history: vec![],
+            quality_score: None,
+            prediction_accuracy: PredictionAccuracy::default(),
+            latest_member_at: None,
             last_updated: Utc::now(),
             last_accessed: Utc::now(),
         },
@@ -316,8 +342,10 @@ async fn test_phased_rollout() {
         canary_duration: Duration::hours(2),
         canary_percentage: 10,
         success_threshold: 0.9,
-        min_rebuilds_for_promotion: 3,
+        shadow_promotion_successes: 3,
+        canary_promotion_successes: 3,
         auto_rollback: true,
+        rollback_recovery_after: Duration::hours(1),
         validation_criteria: ValidationCriteria::default(),
     };
 
@@ -399,6 +427,13 @@ async fn test_full_rebuild_pipeline() {
         tau_merge_lo: 0.35,
         tau_merge_hi: 0.6,
         last_eps: None,
+        stats_window: 20,
+        default_eta_sec: None,
+        noise_policy: NoisePolicy::default(),
+        recency_half_life_days: None,
+        metadata_key_weights: HashMap::new(),
+        min_samples_for_prediction: 1,
+        rebuild_interval_hours: None,
     };
 
     let races = create_test_races();
@@ -504,6 +539,13 @@ async fn test_custom_distance_metric() {
         tau_merge_lo: 0.35,
         tau_merge_hi: 0.6,
         last_eps: None,
+        stats_window: 20,
+        default_eta_sec: None,
+        noise_policy: NoisePolicy::default(),
+        recency_half_life_days: None,
+        metadata_key_weights: HashMap::new(),
+        min_samples_for_prediction: 1,
+        rebuild_interval_hours: None,
     };
 
     // Test similar races
@@ -41,6 +41,7 @@ async fn test_scan_races_time_order_and_cursor() {
 
     let filter = RaceScanFilter {
         source: None,
+        sources: None,
         from: None,
         to: None,
         include_events: false,
@@ -3,7 +3,7 @@ use std::collections::HashMap;
 use RaceboardServer::{
     hnsw_dbscan::HnswDBSCAN,
     models::{Race, RaceState},
-    rebuild::{custom_distance, race_to_vector, SourceConfig},
+    rebuild::{custom_distance, race_to_vector, NoisePolicy, SourceConfig},
 };
 
 #[test]
@@ -23,6 +23,13 @@ fn test_hnsw_clustering() {
         tau_merge_lo: 0.35,
         tau_merge_hi: 0.6,
         last_eps: None,
+        stats_window: 20,
+        default_eta_sec: None,
+        noise_policy: NoisePolicy::default(),
+        recency_half_life_days: None,
+        metadata_key_weights: HashMap::new(),
+        min_samples_for_prediction: 1,
+        rebuild_interval_hours: None,
     };
 
     // Create test races